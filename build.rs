@@ -0,0 +1,29 @@
+//! Build metadata for `wm info`
+//!
+//! Exposes the git commit, target triple and profile as compile-time env vars.
+//! Everything is optional: builds from a crates.io tarball have no .git.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    let sha = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|s| !s.is_empty());
+    if let Some(sha) = sha {
+        println!("cargo:rustc-env=WM_BUILD_GIT_SHA={}", sha);
+    }
+
+    if let Ok(target) = std::env::var("TARGET") {
+        println!("cargo:rustc-env=WM_BUILD_TARGET={}", target);
+    }
+    if let Ok(profile) = std::env::var("PROFILE") {
+        println!("cargo:rustc-env=WM_BUILD_PROFILE={}", profile);
+    }
+}
@@ -13,11 +13,6 @@ fi
 # Use CLAUDE_PROJECT_DIR if available, otherwise current directory
 PROJECT_DIR="${CLAUDE_PROJECT_DIR:-.}"
 
-# Skip if not initialized (no .wm directory)
-if [ ! -d "$PROJECT_DIR/.wm" ]; then
-    exit 0
-fi
-
 # Skip if wm binary not available
 if ! command -v wm &> /dev/null; then
     exit 0
@@ -35,6 +30,8 @@ if [ -z "$SESSION_ID" ]; then
 fi
 
 # Change to project directory for wm to find .wm/
+# (wm itself exits quietly when uninitialized; it also honors WM_DIR, so
+# don't second-guess it with a .wm/ check here)
 cd "$PROJECT_DIR" || exit 0
 
 # Run wm hook compile with session_id, piping stdin for intent
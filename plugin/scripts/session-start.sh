@@ -0,0 +1,36 @@
+#!/bin/bash
+# SessionStart hook for wm
+# Injects the latest working set so a new session is grounded before the first prompt
+#
+# AIDEV-NOTE: Same contract as compile.sh - never blocks, exits 0 on any failure.
+
+# Skip if wm is disabled
+if [ "${WM_DISABLED:-}" = "1" ]; then
+    exit 0
+fi
+
+# Use CLAUDE_PROJECT_DIR if available, otherwise current directory
+PROJECT_DIR="${CLAUDE_PROJECT_DIR:-.}"
+
+# Skip if wm binary not available
+if ! command -v wm &> /dev/null; then
+    exit 0
+fi
+
+# Capture stdin (JSON from Claude Code)
+INPUT=$(cat)
+
+# Extract session_id from hook input
+SESSION_ID=$(echo "$INPUT" | jq -r '.session_id // ""')
+
+# session_id is required
+if [ -z "$SESSION_ID" ]; then
+    exit 0
+fi
+
+# Change to project directory for wm to find .wm/
+# (wm itself exits quietly when uninitialized; it also honors WM_DIR, so
+# don't second-guess it with a .wm/ check here)
+cd "$PROJECT_DIR" || exit 0
+
+wm hook session-start --session-id "$SESSION_ID" 2>/dev/null || exit 0
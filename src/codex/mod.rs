@@ -7,5 +7,5 @@ pub mod reader;
 pub mod session;
 pub mod types;
 
-pub use reader::{format_context, read_codex_session};
+pub use reader::{format_context, get_entries_in_window, get_entries_since, read_codex_session};
 pub use session::discover_sessions;
@@ -2,12 +2,15 @@
 //!
 //! Reads and parses Codex JSONL session files, formats for LLM extraction.
 
+use chrono::{DateTime, Utc};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 
 use crate::codex::types::CodexEntry;
-use crate::types::{strip_xml_tags, ReadError};
+use crate::redact;
+use crate::text;
+use crate::types::{MessageRole, ReadError, strip_xml_tags};
 
 /// Read and parse a Codex session JSONL file
 ///
@@ -39,12 +42,53 @@ pub fn read_codex_session(path: &Path) -> Result<Vec<CodexEntry>, ReadError> {
     Ok(entries)
 }
 
+/// Parse an entry's RFC 3339 timestamp
+fn entry_time(entry: &CodexEntry) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&entry.timestamp)
+        .ok()
+        .map(|ts| ts.with_timezone(&Utc))
+}
+
+/// Get relevant entries with timestamps in [start, end)
+pub fn get_entries_in_window(
+    entries: &[CodexEntry],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<&CodexEntry> {
+    entries
+        .iter()
+        .filter(|e| e.is_relevant())
+        .filter(|e| entry_time(e).is_some_and(|ts| ts >= start && ts < end))
+        .collect()
+}
+
+/// Get relevant entries after a timestamp (all relevant entries when None)
+///
+/// Entries with unparseable timestamps are included, matching the Claude reader.
+pub fn get_entries_since(entries: &[CodexEntry], since: Option<DateTime<Utc>>) -> Vec<&CodexEntry> {
+    entries
+        .iter()
+        .filter(|e| e.is_relevant())
+        .filter(|e| match (since, entry_time(e)) {
+            (Some(cutoff), Some(ts)) => ts > cutoff,
+            _ => true,
+        })
+        .collect()
+}
+
 /// Format Codex entries for context extraction (for sending to extraction LLM)
 ///
 /// Formats relevant entries into a human-readable transcript similar to
 /// the Claude Code format_context function.
-pub fn format_context(entries: &[CodexEntry]) -> String {
+/// Only blocks whose kind is in `roles` are emitted. Tool output is cut to
+/// `tool_output_max_bytes` (0 = no limit).
+pub fn format_context(
+    entries: &[&CodexEntry],
+    roles: &[MessageRole],
+    tool_output_max_bytes: usize,
+) -> String {
     let mut output = String::new();
+    let include = |role: MessageRole| roles.contains(&role);
 
     for entry in entries {
         if !entry.is_relevant() {
@@ -52,6 +96,9 @@ pub fn format_context(entries: &[CodexEntry]) -> String {
         }
 
         if let Some(text) = entry.user_message_text() {
+            if !include(MessageRole::User) {
+                continue;
+            }
             let cleaned = strip_environment_context(text);
             if !cleaned.is_empty() {
                 output.push_str("USER: ");
@@ -59,18 +106,21 @@ pub fn format_context(entries: &[CodexEntry]) -> String {
                 output.push_str("\n\n");
             }
         } else if let Some(text) = entry.agent_message_text() {
-            if !text.is_empty() {
+            if include(MessageRole::Assistant) && !text.is_empty() {
                 output.push_str("ASSISTANT: ");
                 output.push_str(text);
                 output.push_str("\n\n");
             }
         } else if let Some(text) = entry.agent_reasoning_text() {
-            if !text.is_empty() {
+            if include(MessageRole::Thinking) && !text.is_empty() {
                 output.push_str("THINKING: ");
                 output.push_str(text);
                 output.push_str("\n\n");
             }
         } else if entry.is_function_call() {
+            if !include(MessageRole::Assistant) {
+                continue;
+            }
             if let Some(name) = entry.function_call_name() {
                 output.push_str("TOOL: ");
                 output.push_str(name);
@@ -87,27 +137,21 @@ pub fn format_context(entries: &[CodexEntry]) -> String {
                 output.push('\n');
             }
         } else if entry.is_function_call_output()
+            && include(MessageRole::ToolResult)
             && let Some(output_text) = entry.function_call_output()
         {
             // Truncate very long outputs (respecting UTF-8 boundaries)
-            let truncated = if output_text.len() > 500 {
-                let truncate_at = output_text
-                    .char_indices()
-                    .take_while(|(i, _)| *i < 500)
-                    .last()
-                    .map(|(i, c)| i + c.len_utf8())
-                    .unwrap_or(0);
-                format!("{}...[truncated]", &output_text[..truncate_at])
-            } else {
-                output_text
-            };
             output.push_str("TOOL_RESULT: ");
-            output.push_str(&truncated);
+            output.push_str(&text::truncate_tool_output(
+                &output_text,
+                tool_output_max_bytes,
+            ));
             output.push_str("\n\n");
         }
     }
 
-    output
+    // Mask API keys, tokens, etc. before the text leaves for the LLM
+    redact::scrub(&output)
 }
 
 /// Strip <environment_context>...</environment_context> blocks from user messages
@@ -182,7 +226,25 @@ mod tests {
 
     #[test]
     fn test_format_context_basic() {
-        let entries = vec![
+        let entries = [
+            serde_json::from_str::<CodexEntry>(
+                r#"{"timestamp":"t","type":"event_msg","payload":{"type":"user_message","message":"Hello"}}"#,
+            )
+            .unwrap(),
+            serde_json::from_str::<CodexEntry>(
+                r#"{"timestamp":"t","type":"event_msg","payload":{"type":"agent_message","message":"Hi there"}}"#,
+            )
+            .unwrap(),
+        ];
+
+        let formatted = format_context(&entries.iter().collect::<Vec<_>>(), &MessageRole::ALL, 500);
+        assert!(formatted.contains("USER: Hello"));
+        assert!(formatted.contains("ASSISTANT: Hi there"));
+    }
+
+    #[test]
+    fn test_format_context_role_filter() {
+        let entries = [
             serde_json::from_str::<CodexEntry>(
                 r#"{"timestamp":"t","type":"event_msg","payload":{"type":"user_message","message":"Hello"}}"#,
             )
@@ -191,10 +253,47 @@ mod tests {
                 r#"{"timestamp":"t","type":"event_msg","payload":{"type":"agent_message","message":"Hi there"}}"#,
             )
             .unwrap(),
+            serde_json::from_str::<CodexEntry>(
+                r#"{"timestamp":"t","type":"event_msg","payload":{"type":"agent_reasoning","text":"Pondering"}}"#,
+            )
+            .unwrap(),
         ];
 
-        let formatted = format_context(&entries);
+        let formatted = format_context(
+            &entries.iter().collect::<Vec<_>>(),
+            &[MessageRole::User],
+            500,
+        );
         assert!(formatted.contains("USER: Hello"));
+        assert!(!formatted.contains("ASSISTANT:"));
+        assert!(!formatted.contains("THINKING:"));
+
+        let formatted = format_context(
+            &entries.iter().collect::<Vec<_>>(),
+            &[MessageRole::Assistant],
+            500,
+        );
+        assert!(!formatted.contains("USER:"));
         assert!(formatted.contains("ASSISTANT: Hi there"));
     }
+
+    #[test]
+    fn test_format_context_tool_output_limit() {
+        let output = |text: &str| {
+            serde_json::from_str::<CodexEntry>(&format!(
+                r#"{{"timestamp":"t","type":"response_item","payload":{{"type":"function_call_output","call_id":"c1","output":"{text}"}}}}"#
+            ))
+            .unwrap()
+        };
+        let short = [output("ok")];
+        let long = [output("é".repeat(10).as_str())];
+        let format = |entries: &[CodexEntry], max| {
+            format_context(&entries.iter().collect::<Vec<_>>(), &MessageRole::ALL, max)
+        };
+
+        assert!(format(&short, 5).contains("TOOL_RESULT: ok\n"));
+        assert!(format(&long, 5).contains("TOOL_RESULT: éé...[truncated]"));
+        assert!(format(&long, 0).contains(&"é".repeat(10)));
+        assert!(!format(&long, 0).contains("[truncated]"));
+    }
 }
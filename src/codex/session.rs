@@ -9,11 +9,15 @@ use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 
 use crate::codex::types::CodexEntry;
-use crate::types::{system_time_to_datetime, CodexSessionInfo};
+use crate::session;
+use crate::types::{CodexSessionInfo, system_time_to_datetime};
 
-/// Get the Codex sessions root directory (~/.codex/sessions/)
+/// Env var that overrides the Codex sessions directory
+pub const CODEX_SESSIONS_DIR_VAR: &str = "WM_CODEX_SESSIONS_DIR";
+
+/// Get the Codex sessions root directory (~/.codex/sessions/, or WM_CODEX_SESSIONS_DIR)
 pub fn codex_sessions_dir() -> Option<PathBuf> {
-    dirs::home_dir().map(|home| home.join(".codex").join("sessions"))
+    session::resolve_user_dir(CODEX_SESSIONS_DIR_VAR, &[".codex", "sessions"])
 }
 
 /// Discover all Codex sessions, optionally filtered by project path
@@ -22,8 +26,15 @@ pub fn codex_sessions_dir() -> Option<PathBuf> {
 /// Returns sessions sorted by modification time (newest first).
 pub fn discover_sessions(project_filter: Option<&str>) -> Result<Vec<CodexSessionInfo>, String> {
     let sessions_dir = codex_sessions_dir()
-        .ok_or_else(|| "Could not determine Codex sessions directory".to_string())?;
+        .ok_or_else(|| session::home_unavailable_message(CODEX_SESSIONS_DIR_VAR))?;
+    discover_sessions_in(&sessions_dir, project_filter)
+}
 
+/// Discover Codex sessions under an explicit sessions root (YYYY/MM/DD/rollout-*.jsonl)
+pub fn discover_sessions_in(
+    sessions_dir: &Path,
+    project_filter: Option<&str>,
+) -> Result<Vec<CodexSessionInfo>, String> {
     if !sessions_dir.exists() {
         return Ok(Vec::new());
     }
@@ -31,7 +42,7 @@ pub fn discover_sessions(project_filter: Option<&str>) -> Result<Vec<CodexSessio
     let mut sessions = Vec::new();
 
     // Walk YYYY/MM/DD directory structure
-    for year_entry in read_dir_sorted(&sessions_dir)? {
+    for year_entry in read_dir_sorted(sessions_dir)? {
         let year_path = year_entry.path();
         if !year_path.is_dir() {
             continue;
@@ -78,7 +89,7 @@ pub fn discover_sessions(project_filter: Option<&str>) -> Result<Vec<CodexSessio
     }
 
     // Sort by modification time, newest first
-    sessions.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.modified_at));
 
     Ok(sessions)
 }
@@ -0,0 +1,74 @@
+//! Terminal colors for display commands (status, show sessions, dive list,
+//! state diff)
+//!
+//! AIDEV-NOTE: Color is used only when stdout is a terminal and NO_COLOR is
+//! unset or empty (https://no-color.org). `--no-color` sets NO_COLOR for the
+//! process, the same way --wm-dir sets WM_DIR. Plain ANSI SGR codes; a handful
+//! of markers doesn't warrant a dependency.
+
+use std::io::IsTerminal;
+
+/// Environment variable that turns color off when set to a non-empty value
+pub const NO_COLOR_ENV: &str = "NO_COLOR";
+
+/// SGR code for red (removed lines, stopped state)
+pub const RED: &str = "31";
+
+/// SGR code for green (added lines, good state)
+pub const GREEN: &str = "32";
+
+const DIM: &str = "2";
+
+/// Whether display commands should color their output
+pub fn enabled() -> bool {
+    should_color(
+        std::env::var_os(NO_COLOR_ENV).as_deref(),
+        std::io::stdout().is_terminal(),
+    )
+}
+
+fn should_color(no_color: Option<&std::ffi::OsStr>, is_terminal: bool) -> bool {
+    is_terminal && no_color.is_none_or(|value| value.is_empty())
+}
+
+/// Good state: `running`, the current dive marker, sessions with wm state
+pub fn green(text: &str) -> String {
+    paint(enabled(), GREEN, text)
+}
+
+/// Stopped state: `paused`
+pub fn red(text: &str) -> String {
+    paint(enabled(), RED, text)
+}
+
+/// De-emphasized: sessions wm hasn't processed
+pub fn dim(text: &str) -> String {
+    paint(enabled(), DIM, text)
+}
+
+/// Wrap `text` in SGR `code` when `enabled` (callers that decide once per output)
+pub fn paint(enabled: bool, code: &str, text: &str) -> String {
+    if enabled {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn test_no_color_disables_escape_codes() {
+        let enabled = should_color(Some(OsStr::new("1")), true);
+        assert!(!enabled);
+        assert_eq!(paint(enabled, GREEN, "running"), "running");
+
+        // Empty NO_COLOR doesn't count; non-terminals never get color
+        assert!(should_color(Some(OsStr::new("")), true));
+        assert!(!should_color(None, false));
+        assert_eq!(paint(true, RED, "paused"), "\x1b[31mpaused\x1b[0m");
+    }
+}
@@ -4,49 +4,270 @@
 //! then combines them into a working set for the current session.
 //! All content is pre-curated, no LLM filtering needed.
 
+use crate::dive;
+use crate::intent;
+use crate::provenance;
 use crate::state;
-use crate::types::{HookResponse, HookSpecificOutput};
+use crate::types::{HookResponse, HookSpecificOutput, LogLevel};
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+use std::path::Path;
 
 /// Distill directory constant (matches distill.rs)
 const DISTILL_DIR: &str = "distill";
 
+/// Hook event name for the per-prompt compile hook
+const USER_PROMPT_SUBMIT: &str = "UserPromptSubmit";
+
+/// Last prompt the hook saw for a session (replayed by compile --all-sessions)
+const LAST_INTENT_FILE: &str = "last_intent.txt";
+
 /// Run wm compile with optional intent (CLI entry point)
 /// AIDEV-NOTE: Returns Ok() instead of Err when not initialized. This is intentional:
 /// extract/compile can be triggered automatically by hooks, so they must not spam error
 /// logs in projects without .wm/. User-invoked commands like show/status still return
 /// Err to inform the user. See also: extract::run().
-/// AIDEV-NOTE: Intent parameter is now unused since we don't do LLM filtering.
-/// Kept for API compatibility.
-pub fn run(_intent: Option<String>) -> Result<(), String> {
+/// AIDEV-NOTE: There is no LLM filtering; the intent is only used for
+/// `[compile.routes]` section routing (see `route_sections`).
+/// AIDEV-NOTE: With a session_id, output goes to the per-session working set
+/// (same location run_hook writes), so hook behavior can be reproduced from the CLI.
+/// With all_sessions, every .wm/sessions/*/ working set is rewritten (e.g. after
+/// compress), each routed by that session's last prompt as recorded by the hook;
+/// `intent` only covers sessions with nothing recorded.
+/// With Output::Stdout (--print) nothing under .wm/ is written; status messages
+/// go to stderr so stdout carries only the working set.
+pub fn run(
+    intent: Option<String>,
+    no_dive: bool,
+    output: Output,
+    save_as: Option<String>,
+    load: Option<String>,
+    unload: bool,
+) -> Result<(), String> {
     if !state::is_initialized() {
         eprintln!("Not initialized. Run 'wm init' first.");
         return Ok(());
     }
 
+    if unload {
+        state::set_saved_working_set(None)
+            .map_err(|e| format!("Failed to update config: {}", e))?;
+        println!("Cleared saved working set. The hook compiles from distilled knowledge again.");
+        return Ok(());
+    }
+
     // Check if compile is paused
     if !state::is_compile_enabled() {
-        println!("Compile is paused. Use 'wm resume compile' to enable.");
+        output.notice("Compile is paused. Use 'wm resume compile' to enable.");
         return Ok(());
     }
 
-    // Read distilled knowledge (pre-curated, no filtering needed)
-    let guardrails = read_distilled_file("guardrails.md");
-    let metis = read_distilled_file("metis.md");
+    if let Some(name) = save_as.as_deref().or(load.as_deref()) {
+        validate_working_set_name(name)?;
+    }
+
+    if let Some(name) = load {
+        let content = std::fs::read_to_string(state::saved_working_set_path(&name))
+            .map_err(|_| saved_not_found_message(&name))?;
+        state::set_saved_working_set(Some(&name))
+            .map_err(|e| format!("Failed to update config: {}", e))?;
+        println!(
+            "Loaded saved working set '{}' (the hook now injects it without recompiling)",
+            name
+        );
+        return write_output(&state::wm_dir(), &content, output);
+    }
+
+    // Read distilled knowledge (pre-curated, narrowed by intent routes)
+    let (guardrails, metis) = read_routed_knowledge(intent.as_deref());
 
     // Check for dive context - try named prep first, then legacy fallback
-    let dive_context = read_dive_context();
+    let dive_context = if include_dive(no_dive) {
+        read_dive_context()
+    } else {
+        String::new()
+    };
 
     // Combine all sources
     let combined = combine_context(&dive_context, &guardrails, &metis);
+    let compile_for = |intent: Option<&str>| {
+        let (guardrails, metis) = read_routed_knowledge(intent);
+        combine_context(&dive_context, &guardrails, &metis)
+    };
 
     if combined.trim().is_empty() {
-        println!("No distilled knowledge found. Run 'wm distill' first.");
+        output.notice("No distilled knowledge found. Run 'wm distill' first.");
         return Ok(());
     }
 
-    state::write_working_set(&combined)
+    if let Some(name) = save_as {
+        std::fs::create_dir_all(state::working_sets_dir())
+            .map_err(|e| format!("Failed to create working_sets directory: {}", e))?;
+        std::fs::write(state::saved_working_set_path(&name), &combined)
+            .map_err(|e| format!("Failed to save working set: {}", e))?;
+        println!("Saved working set to .wm/working_sets/{}.md", name);
+    }
+
+    if output == Output::AllSessions {
+        let wm_dir = state::wm_dir();
+        return compile_all_sessions_in(&wm_dir, |session_id| {
+            let session_intent = last_intent_in(&wm_dir, session_id);
+            compile_for(session_intent.as_deref().or(intent.as_deref()))
+        });
+    }
+
+    write_output(&state::wm_dir(), &combined, output)
+}
+
+/// Where wm compile puts the working set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    /// .wm/working_set.md
+    Project,
+    /// .wm/sessions/<id>/working_set.md (as the hook does)
+    Session(String),
+    /// Every .wm/sessions/*/working_set.md
+    AllSessions,
+    /// stdout only (--print)
+    Stdout,
+}
+
+impl Output {
+    /// Print a status message without mixing it into --print output
+    fn notice(&self, message: &str) {
+        if *self == Output::Stdout {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+}
+
+/// Write a working set to stdout, the session, every session, or the project
+fn write_output(wm_dir: &Path, content: &str, output: Output) -> Result<(), String> {
+    let session_id = match output {
+        Output::Stdout => {
+            println!("{}", content.trim_end());
+            return Ok(());
+        }
+        Output::AllSessions => return compile_all_sessions_in(wm_dir, |_| content.to_string()),
+        Output::Session(session_id) => Some(session_id),
+        Output::Project => None,
+    };
+
+    state::write_working_set_in(wm_dir, session_id.as_deref(), content)
         .map_err(|e| format!("Failed to write working set: {}", e))?;
-    println!("Compiled working set to .wm/working_set.md");
+    match session_id {
+        Some(session_id) => {
+            state::gc_session_dirs(&session_id);
+            println!(
+                "Compiled working set to .wm/sessions/{}/working_set.md",
+                session_id
+            );
+        }
+        None => println!("Compiled working set to .wm/working_set.md"),
+    }
+    Ok(())
+}
+
+/// Saved working set names follow the dive prep rules (kebab-case)
+fn validate_working_set_name(name: &str) -> Result<(), String> {
+    if dive::is_valid_prep_name(name) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid working set name '{}'. Use lowercase letters, numbers, and hyphens only.",
+            name
+        ))
+    }
+}
+
+/// Error for --load of a name with no file, listing what is saved
+fn saved_not_found_message(name: &str) -> String {
+    let saved = list_saved_working_sets(&state::working_sets_dir());
+    if saved.is_empty() {
+        format!(
+            "Saved working set '{}' not found. Create one with 'wm compile --save-as <name>'.",
+            name
+        )
+    } else {
+        format!(
+            "Saved working set '{}' not found. Saved: {}",
+            name,
+            saved.join(", ")
+        )
+    }
+}
+
+/// Names of saved working sets in `dir` (without .md), sorted
+fn list_saved_working_sets(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Content of the saved working set selected via --load, if any
+/// A missing file is logged and ignored so the hook falls back to compiling.
+fn read_saved_working_set() -> Option<String> {
+    let name = state::read_config().compile.saved?;
+    match std::fs::read_to_string(state::saved_working_set_path(&name)) {
+        Ok(content) => {
+            state::log("compile", &format!("Using saved working set '{}'", name));
+            Some(content)
+        }
+        Err(_) => {
+            state::log_at(
+                LogLevel::Warn,
+                "compile",
+                &format!("Saved working set '{}' missing, compiling instead", name),
+            );
+            None
+        }
+    }
+}
+
+/// Write each session's working set (`content_for` builds it from the session id)
+/// A failure on one session is reported and the rest still get refreshed.
+fn compile_all_sessions_in(
+    wm_dir: &Path,
+    content_for: impl Fn(&str) -> String,
+) -> Result<(), String> {
+    let session_ids = state::list_session_ids_in(wm_dir)
+        .map_err(|e| format!("Failed to list session directories: {}", e))?;
+
+    if session_ids.is_empty() {
+        println!("No session directories in .wm/sessions/.");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for session_id in &session_ids {
+        match state::write_working_set_in(wm_dir, Some(session_id), &content_for(session_id)) {
+            Ok(_) => println!("  {} ✓", session_id),
+            Err(e) => {
+                eprintln!("  {} ✗ {}", session_id, e);
+                state::log_at(
+                    LogLevel::Warn,
+                    "compile",
+                    &format!("Failed to write working set for {}: {}", session_id, e),
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "Compiled {} of {} session working set(s)",
+        session_ids.len() - failed,
+        session_ids.len()
+    );
     Ok(())
 }
 
@@ -63,9 +284,7 @@ pub fn run_hook(session_id: &str) -> Result<(), String> {
     // Check if compile is paused
     if !state::is_compile_enabled() {
         state::log("compile", "Paused via config, returning empty");
-        let response = HookResponse {
-            hook_specific_output: None,
-        };
+        let response = hook_response(USER_PROMPT_SUBMIT, None);
         let json = serde_json::to_string(&response).map_err(|e| e.to_string())?;
         println!("{}", json);
         return Ok(());
@@ -73,44 +292,39 @@ pub fn run_hook(session_id: &str) -> Result<(), String> {
 
     state::log("compile", "Hook fired");
 
-    // Consume stdin (intent) but don't use it - distilled content is always relevant
-    let _ = read_hook_input();
+    // Intent (the user's prompt) only drives [compile.routes] section routing
+    let intent = read_hook_input();
     state::log("compile", &format!("Session: {}", session_id));
-
-    // Read distilled knowledge (pre-curated, no filtering needed)
-    let guardrails = read_distilled_file("guardrails.md");
-    let metis = read_distilled_file("metis.md");
-
-    // Check for dive context - try named prep first, then legacy fallback
-    let dive_context = read_dive_context();
-
-    // Log what we found
-    if !dive_context.trim().is_empty() {
+    if let Some(intent) = intent.as_deref() {
         state::log(
             "compile",
-            &format!("Dive context: {} bytes", dive_context.len()),
+            &format!("Intent type: {}", intent::classify(intent)),
         );
-    }
-    if !guardrails.trim().is_empty() {
-        state::log(
-            "compile",
-            &format!("Guardrails: {} bytes", guardrails.len()),
-        );
-    }
-    if !metis.trim().is_empty() {
-        state::log("compile", &format!("Metis: {} bytes", metis.len()));
+        if let Err(e) = record_intent_in(&state::wm_dir(), session_id, intent) {
+            state::log_at(
+                LogLevel::Warn,
+                "compile",
+                &format!("Failed to record intent: {}", e),
+            );
+        }
     }
 
-    // Combine all sources (no LLM filtering - all content is pre-curated)
-    let final_content = combine_context(&dive_context, &guardrails, &metis);
+    // A saved working set (wm compile --load) is injected as-is
+    let mut final_content = compile_working_set(intent.as_deref());
 
     let has_content = !final_content.trim().is_empty();
 
+    if has_content && state::read_config().compile.freshness_header {
+        final_content = format!(
+            "{}\n\n{}",
+            freshness_header(Local::now(), state_updated_at()),
+            final_content
+        );
+    }
+
     if !has_content {
         state::log("compile", "No distilled content found, returning empty");
-        let response = HookResponse {
-            hook_specific_output: None,
-        };
+        let response = hook_response(USER_PROMPT_SUBMIT, None);
         let json = serde_json::to_string(&response).map_err(|e| e.to_string())?;
         println!("{}", json);
         return Ok(());
@@ -118,14 +332,10 @@ pub fn run_hook(session_id: &str) -> Result<(), String> {
 
     // Write working_set for debugging/inspection
     let _ = state::write_working_set_for_session(session_id, &final_content);
+    state::gc_session_dirs(session_id);
 
     // Output hook response with proper Claude Code structure
-    let response = HookResponse {
-        hook_specific_output: Some(HookSpecificOutput {
-            hook_event_name: "UserPromptSubmit".to_string(),
-            additional_context: Some(final_content),
-        }),
-    };
+    let response = hook_response(USER_PROMPT_SUBMIT, Some(final_content));
 
     let json = serde_json::to_string(&response).map_err(|e| e.to_string())?;
     state::log("compile", "Complete");
@@ -134,6 +344,138 @@ pub fn run_hook(session_id: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Run the SessionStart hook: inject the latest working set before the first prompt
+/// AIDEV-NOTE: Same contract as run_hook - silent when uninitialized, an empty
+/// `{}` response when paused or there's nothing to inject. Reads what compile
+/// last wrote rather than recompiling; falls back to compiling if nothing exists.
+pub fn run_session_start(session_id: &str) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Ok(());
+    }
+
+    let content = if state::is_compile_enabled() {
+        state::log("compile", &format!("SessionStart: {}", session_id));
+        session_start_context(session_id)
+    } else {
+        state::log("compile", "Paused via config, returning empty");
+        String::new()
+    };
+
+    let content = (!content.trim().is_empty()).then_some(content);
+    let json = serde_json::to_string(&hook_response("SessionStart", content))
+        .map_err(|e| e.to_string())?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// Current dive context plus the most recent working set
+fn session_start_context(session_id: &str) -> String {
+    let Some(path) = state::latest_working_set(session_id) else {
+        state::log("compile", "No working set yet, compiling");
+        return compile_for_hook(None);
+    };
+    state::log("compile", &format!("Working set: {}", path.display()));
+    let working_set = std::fs::read_to_string(&path).unwrap_or_default();
+
+    let dive_context = if include_dive(false) {
+        read_dive_context()
+    } else {
+        String::new()
+    };
+    with_dive_context(&dive_context, &working_set)
+}
+
+/// Prepend the dive context unless the working set already carries it
+/// (compile embeds the dive that was current when it ran).
+fn with_dive_context(dive_context: &str, working_set: &str) -> String {
+    let dive = dive_context.trim();
+    if dive.is_empty() || working_set.contains(dive) || working_set.contains(&dive_section(dive)) {
+        working_set.to_string()
+    } else if working_set.trim().is_empty() {
+        dive.to_string()
+    } else {
+        format!("{}\n\n---\n\n{}", dive, working_set.trim())
+    }
+}
+
+/// Hook response carrying `content` (None = empty `{}`, inject nothing)
+/// AIDEV-NOTE: All hook output goes through here so the mandatory
+/// `hookSpecificOutput` wrapper (see HookResponse) can't be left out.
+fn hook_response(hook_event_name: &str, content: Option<String>) -> HookResponse {
+    HookResponse {
+        hook_specific_output: content.map(|content| HookSpecificOutput {
+            hook_event_name: hook_event_name.to_string(),
+            additional_context: Some(content),
+        }),
+    }
+}
+
+/// Working set the hook would inject for `intent`, without writing anything
+/// (a saved working set if one is loaded, otherwise a fresh compile)
+pub fn compile_working_set(intent: Option<&str>) -> String {
+    read_saved_working_set().unwrap_or_else(|| compile_for_hook(intent))
+}
+
+/// Compile distilled knowledge and dive context for the hook, logging sizes
+/// AIDEV-NOTE: Deliberately uncached. Compile makes no LLM call - it reads
+/// guardrails.md, metis.md and the dive prep and concatenates them - so a
+/// cache key would need those same reads and save nothing.
+fn compile_for_hook(intent: Option<&str>) -> String {
+    // Read distilled knowledge (pre-curated, narrowed by intent routes)
+    let (guardrails, metis) = read_routed_knowledge(intent);
+
+    // Check for dive context - try named prep first, then legacy fallback
+    let dive_context = if include_dive(false) {
+        read_dive_context()
+    } else {
+        state::log("compile", "Dive context excluded via config");
+        String::new()
+    };
+
+    // Log what we found
+    if !dive_context.trim().is_empty() {
+        state::log(
+            "compile",
+            &format!("Dive context: {} bytes", dive_context.len()),
+        );
+    }
+    if !guardrails.trim().is_empty() {
+        state::log(
+            "compile",
+            &format!("Guardrails: {} bytes", guardrails.len()),
+        );
+    }
+    if !metis.trim().is_empty() {
+        state::log("compile", &format!("Metis: {} bytes", metis.len()));
+    }
+
+    // Combine all sources (no LLM filtering - all content is pre-curated)
+    combine_context(&dive_context, &guardrails, &metis)
+}
+
+/// Header telling the assistant how current the injected memory is
+/// AIDEV-NOTE: Lets the model (and `wm show working`) judge staleness, e.g. when
+/// extraction has been paused for days. Toggle with `[compile] freshness_header`.
+fn freshness_header(now: DateTime<Local>, state_updated: Option<DateTime<Local>>) -> String {
+    const FORMAT: &str = "%Y-%m-%d %H:%M %:z";
+    match state_updated {
+        Some(updated) => format!(
+            "<!-- working memory compiled {}, state last updated {} -->",
+            now.format(FORMAT),
+            updated.format(FORMAT)
+        ),
+        None => format!("<!-- working memory compiled {} -->", now.format(FORMAT)),
+    }
+}
+
+/// Modification time of state.md, if it exists
+fn state_updated_at() -> Option<DateTime<Local>> {
+    std::fs::metadata(state::wm_path("state.md"))
+        .and_then(|m| m.modified())
+        .ok()
+        .map(DateTime::<Local>::from)
+}
+
 /// Read intent from hook input (stdin contains JSON with prompt field)
 fn read_hook_input() -> Option<String> {
     use std::io::{self, Read};
@@ -154,10 +496,121 @@ fn read_hook_input() -> Option<String> {
     }
 }
 
+/// Remember a session's latest prompt so compile --all-sessions can route by it
+fn record_intent_in(wm_dir: &Path, session_id: &str, intent: &str) -> std::io::Result<()> {
+    let dir = wm_dir.join("sessions").join(session_id);
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join(LAST_INTENT_FILE), intent)
+}
+
+/// A session's latest prompt as recorded by the hook
+fn last_intent_in(wm_dir: &Path, session_id: &str) -> Option<String> {
+    std::fs::read_to_string(
+        wm_dir
+            .join("sessions")
+            .join(session_id)
+            .join(LAST_INTENT_FILE),
+    )
+    .ok()
+    .filter(|intent| !intent.trim().is_empty())
+}
+
 /// Read a distilled file from .wm/distill/
 fn read_distilled_file(filename: &str) -> String {
     let path = state::wm_path(DISTILL_DIR).join(filename);
-    std::fs::read_to_string(path).unwrap_or_default()
+    let content = std::fs::read_to_string(path).unwrap_or_default();
+    // Provenance comments are bookkeeping, not context for the assistant
+    provenance::strip_all(&content)
+}
+
+/// Read guardrails and metis; metis is narrowed to the sections the intent routes to
+fn read_routed_knowledge(intent: Option<&str>) -> (String, String) {
+    route_knowledge(
+        read_distilled_file("guardrails.md"),
+        &read_distilled_file("metis.md"),
+        intent,
+        &state::read_config().compile.routes,
+    )
+}
+
+/// Apply `[compile.routes]` to the distilled files
+/// AIDEV-NOTE: Guardrails are never narrowed. They are binary constraints
+/// ("never commit .env") that must hold whatever the prompt is about, so only
+/// metis is subject to intent routing.
+fn route_knowledge(
+    guardrails: String,
+    metis: &str,
+    intent: Option<&str>,
+    routes: &BTreeMap<String, Vec<String>>,
+) -> (String, String) {
+    let sections = intent
+        .map(|intent| route_sections(intent, routes))
+        .unwrap_or_default();
+    if sections.is_empty() {
+        return (guardrails, metis.to_string());
+    }
+
+    state::log(
+        "compile",
+        &format!("Intent routed to sections: {}", sections.join(", ")),
+    );
+    (guardrails, narrow_to_sections(metis, &sections))
+}
+
+/// Section names routed to by keywords found in the intent (case-insensitive)
+/// AIDEV-NOTE: `[compile.routes]` maps keyword -> section names, e.g.
+/// `deploy = ["Infra", "Deployment"]`. No matching keyword = no narrowing.
+fn route_sections(intent: &str, routes: &BTreeMap<String, Vec<String>>) -> Vec<String> {
+    let intent = intent.to_lowercase();
+    let mut sections: Vec<String> = Vec::new();
+    for (keyword, targets) in routes {
+        if !keyword.trim().is_empty() && intent.contains(&keyword.trim().to_lowercase()) {
+            for target in targets {
+                if !sections.iter().any(|s| s.eq_ignore_ascii_case(target)) {
+                    sections.push(target.clone());
+                }
+            }
+        }
+    }
+    sections
+}
+
+/// Keep the preamble plus `## ` sections whose heading names one of `sections`
+/// A file with none of the routed sections is returned whole rather than
+/// dropping knowledge that simply isn't organized by section.
+fn narrow_to_sections(content: &str, sections: &[String]) -> String {
+    let heading_matches = |line: &str| {
+        let title = line.trim_start_matches("## ").trim();
+        sections
+            .iter()
+            .any(|s| s.trim().eq_ignore_ascii_case(title))
+    };
+
+    let mut output = String::new();
+    let mut keep = true; // preamble before the first section
+    let mut matched_any = false;
+    for line in content.lines() {
+        if line.starts_with("## ") {
+            keep = heading_matches(line);
+            matched_any |= keep;
+        }
+        if keep {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    if matched_any {
+        output
+    } else {
+        content.to_string()
+    }
+}
+
+/// Whether dive context should be part of this compile
+/// The --no-dive flag wins; otherwise `[compile] include_dive` decides (default: true).
+fn include_dive(no_dive: bool) -> bool {
+    !no_dive && state::read_config().compile.include_dive
 }
 
 /// Read dive context - tries named prep first, then legacy fallback
@@ -165,7 +618,7 @@ fn read_distilled_file(filename: &str) -> String {
 /// 1. Current named prep from .wm/dives/{name}.md (if config.dive.current is set)
 /// 2. Legacy .wm/dive_context.md
 /// 3. Legacy .wm/OH_context.md
-fn read_dive_context() -> String {
+pub fn read_dive_context() -> String {
     // Try current named prep first
     if let Some(current_name) = state::current_dive() {
         let path = state::dive_prep_path(&current_name);
@@ -181,25 +634,317 @@ fn read_dive_context() -> String {
         .unwrap_or_default()
 }
 
+/// Heading the guardrails are injected under
+const ALWAYS_GUARDRAILS_HEADING: &str = "## Guardrails (always)";
+
+/// Heading the dive context is injected under
+const DIVE_CONTEXT_HEADING: &str = "## Dive Context";
+
+/// Heading the metis is injected under
+const METIS_HEADING: &str = "## Patterns (metis)";
+
 /// Combine context sources into a single markdown document
-/// Order: dive_context (session-specific grounding) → guardrails → metis
+/// Order: guardrails (always) → dive_context (session-specific grounding) → metis
+/// AIDEV-NOTE: Each source gets its own `## ` heading so the model can tell
+/// hard constraints from soft advice. With no guardrails or metis there's
+/// nothing to tell apart, and the dive context is returned as-is.
 fn combine_context(dive_context: &str, guardrails: &str, metis: &str) -> String {
+    if guardrails.trim().is_empty() && metis.trim().is_empty() {
+        return dive_context.trim().to_string();
+    }
+
     let mut sections = Vec::new();
 
-    // Dive context first (session-specific grounding)
-    if !dive_context.trim().is_empty() {
-        sections.push(dive_context.trim().to_string());
+    // Guardrails first (hard constraints, never filtered)
+    if !guardrails.trim().is_empty() {
+        sections.push(under_heading(ALWAYS_GUARDRAILS_HEADING, guardrails, true));
     }
 
-    // Guardrails (hard constraints)
-    if !guardrails.trim().is_empty() {
-        sections.push(guardrails.trim().to_string());
+    // Dive context (session-specific grounding)
+    if !dive_context.trim().is_empty() {
+        sections.push(dive_section(dive_context));
     }
 
     // Metis (wisdom/patterns)
     if !metis.trim().is_empty() {
-        sections.push(metis.trim().to_string());
+        sections.push(under_heading(METIS_HEADING, metis, true));
     }
 
     sections.join("\n\n---\n\n")
 }
+
+/// Dive context as it appears in a compiled working set
+fn dive_section(dive_context: &str) -> String {
+    under_heading(DIVE_CONTEXT_HEADING, dive_context, false)
+}
+
+/// Put a knowledge file under `heading`
+/// With `drop_title`, the file's `# Title` line is dropped (the heading
+/// replaces it). Remaining headings move down so the shallowest is `###`,
+/// nesting under the new heading. `#` lines inside fenced code blocks are
+/// code (shell comments, say), not headings, and are left alone.
+fn under_heading(heading: &str, content: &str, drop_title: bool) -> String {
+    let mut lines = content.trim().lines().peekable();
+    if drop_title && lines.peek().is_some_and(|line| line.starts_with("# ")) {
+        lines.next();
+    }
+    let mut in_fence = false;
+    let lines: Vec<(&str, bool)> = lines
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+                return (line, false);
+            }
+            (line, !in_fence && line.starts_with('#'))
+        })
+        .collect();
+
+    let level = |line: &str| line.len() - line.trim_start_matches('#').len();
+    let shallowest = lines
+        .iter()
+        .filter(|(_, is_heading)| *is_heading)
+        .map(|(line, _)| level(line))
+        .min()
+        .unwrap_or(3);
+    let shift = "#".repeat(3usize.saturating_sub(shallowest));
+
+    let body: Vec<String> = lines
+        .iter()
+        .map(|(line, is_heading)| {
+            if *is_heading {
+                format!("{}{}", shift, line)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    format!("{}\n\n{}", heading, body.join("\n").trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_print_writes_no_file() {
+        let dir = tempfile::tempdir().unwrap();
+        write_output(dir.path(), "## Guardrails (always)\n- x\n", Output::Stdout).unwrap();
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        write_output(dir.path(), "## Guardrails (always)\n- x\n", Output::Project).unwrap();
+        assert!(dir.path().join(state::WORKING_SET_FILE).exists());
+    }
+
+    #[test]
+    fn test_session_scoped_compile_writes_under_sessions_dir() {
+        let wm = tempfile::tempdir().unwrap();
+
+        let path = state::write_working_set_in(wm.path(), Some("abc"), "session").unwrap();
+        assert_eq!(path, wm.path().join("sessions/abc/working_set.md"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "session");
+
+        // Without a session the global working set is written, not the session's
+        let path = state::write_working_set_in(wm.path(), None, "global").unwrap();
+        assert_eq!(path, wm.path().join("working_set.md"));
+        assert_eq!(
+            std::fs::read_to_string(wm.path().join("sessions/abc/working_set.md")).unwrap(),
+            "session"
+        );
+    }
+
+    #[test]
+    fn test_all_sessions_compile_per_session_intent() {
+        let wm = tempfile::tempdir().unwrap();
+        record_intent_in(wm.path(), "a", "fix the login bug").unwrap();
+        std::fs::create_dir_all(wm.path().join("sessions/b")).unwrap();
+
+        compile_all_sessions_in(wm.path(), |session_id| {
+            format!("routed by {:?}", last_intent_in(wm.path(), session_id))
+        })
+        .unwrap();
+
+        let read = |id: &str| {
+            std::fs::read_to_string(wm.path().join("sessions").join(id).join("working_set.md"))
+                .unwrap()
+        };
+        assert_eq!(read("a"), "routed by Some(\"fix the login bug\")");
+        assert_eq!(read("b"), "routed by None");
+    }
+
+    #[test]
+    fn test_prompt_submit_response_wraps_context() {
+        let json = serde_json::to_value(hook_response(
+            USER_PROMPT_SUBMIT,
+            Some("context".to_string()),
+        ))
+        .unwrap();
+        assert_eq!(
+            json["hookSpecificOutput"]["hookEventName"],
+            "UserPromptSubmit"
+        );
+        assert_eq!(json["hookSpecificOutput"]["additionalContext"], "context");
+        // The bare top-level form is ignored by Claude Code
+        assert!(json.get("additionalContext").is_none());
+    }
+
+    #[test]
+    fn test_session_start_response_shape() {
+        let json = serde_json::to_value(hook_response(
+            "SessionStart",
+            Some("## Guardrails".to_string()),
+        ))
+        .unwrap();
+        assert_eq!(json["hookSpecificOutput"]["hookEventName"], "SessionStart");
+        assert_eq!(
+            json["hookSpecificOutput"]["additionalContext"],
+            "## Guardrails"
+        );
+
+        let empty = serde_json::to_string(&hook_response("SessionStart", None)).unwrap();
+        assert_eq!(empty, "{}");
+    }
+
+    #[test]
+    fn test_with_dive_context_skips_embedded_dive() {
+        let dive = "# Dive: auth\n";
+        assert_eq!(
+            with_dive_context(dive, "# Dive: auth\n\n---\n\nrules"),
+            "# Dive: auth\n\n---\n\nrules"
+        );
+        assert_eq!(
+            with_dive_context(dive, "rules\n"),
+            "# Dive: auth\n\n---\n\nrules"
+        );
+        assert_eq!(with_dive_context("", "rules"), "rules");
+    }
+
+    #[test]
+    fn test_combine_context_separates_categories() {
+        let guardrails = "# Guardrails\n\n- Never force-push main\n";
+        let metis = "# Metis\n\n## Testing\n- Prefer table-driven tests\n";
+        let dive = "# Dive: auth\n\n## Focus\n- Token refresh\n";
+
+        let combined = combine_context(dive, guardrails, metis);
+        assert_eq!(
+            combined,
+            "## Guardrails (always)\n\n- Never force-push main\n\n---\n\n\
+             ## Dive Context\n\n### Dive: auth\n\n#### Focus\n- Token refresh\n\n---\n\n\
+             ## Patterns (metis)\n\n### Testing\n- Prefer table-driven tests"
+        );
+
+        // Session start recognizes the dive already inside a compiled working set
+        assert_eq!(with_dive_context(dive, &combined), combined);
+    }
+
+    #[test]
+    fn test_combine_context_without_categories_is_single_section() {
+        let dive = "# Dive: auth\n\n- Token refresh\n";
+        assert_eq!(combine_context(dive, "", ""), dive.trim());
+        assert_eq!(combine_context("", "", ""), "");
+    }
+
+    #[test]
+    fn test_under_heading_leaves_fenced_code_alone() {
+        let dive = "## Setup\n\n```sh\n# install deps\nnpm ci\n```\n\n## Notes\n- x";
+        assert_eq!(
+            dive_section(dive),
+            format!(
+                "{}\n\n### Setup\n\n```sh\n# install deps\nnpm ci\n```\n\n### Notes\n- x",
+                DIVE_CONTEXT_HEADING
+            )
+        );
+    }
+
+    #[test]
+    fn test_route_sections_matches_keywords() {
+        let routes = BTreeMap::from([
+            (
+                "deploy".to_string(),
+                vec!["Infra".to_string(), "Deployment".to_string()],
+            ),
+            ("test".to_string(), vec!["Testing".to_string()]),
+        ]);
+        assert_eq!(
+            route_sections("Fix the Deploy script", &routes),
+            vec!["Infra", "Deployment"]
+        );
+        assert!(route_sections("refactor parser", &routes).is_empty());
+    }
+
+    #[test]
+    fn test_guardrails_survive_unrelated_intent() {
+        let routes = BTreeMap::from([("deploy".to_string(), vec!["Infra".to_string()])]);
+        let guardrails = "# Guardrails\n\n## Secrets\n- Never commit .env files\n";
+        let metis = "# Metis\n\n## Infra\n- Blue/green deploys\n\n## Style\n- Small PRs\n";
+
+        let (guardrails, metis) = route_knowledge(
+            guardrails.to_string(),
+            metis,
+            Some("deploy the css fix"),
+            &routes,
+        );
+        let combined = combine_context("", &guardrails, &metis);
+
+        assert!(combined.starts_with("## Guardrails (always)\n\n### Secrets"));
+        assert!(combined.contains("- Never commit .env files"));
+        assert!(!combined.contains("# Guardrails\n"));
+        assert!(combined.contains("Blue/green"));
+        assert!(!combined.contains("Small PRs"));
+
+        // No guardrails file: nothing added
+        assert!(!combine_context("", "", &metis).contains("Guardrails (always)"));
+    }
+
+    #[test]
+    fn test_narrow_to_sections() {
+        let content = "# Guardrails\n\n## Infra\n- Never deploy on Friday\n\n## UI\n- Use tokens\n\n## deployment\n- Tag releases\n";
+        let narrowed =
+            narrow_to_sections(content, &["Infra".to_string(), "Deployment".to_string()]);
+        assert!(narrowed.starts_with("# Guardrails"));
+        assert!(narrowed.contains("Never deploy on Friday"));
+        assert!(narrowed.contains("Tag releases"));
+        assert!(!narrowed.contains("Use tokens"));
+
+        // No routed section present: keep everything
+        let flat = "# Metis\n\n- Prefer small PRs\n";
+        assert_eq!(narrow_to_sections(flat, &["Infra".to_string()]), flat);
+    }
+
+    #[test]
+    fn test_freshness_header() {
+        let now = DateTime::parse_from_rfc3339("2025-03-02T09:30:00+00:00")
+            .unwrap()
+            .with_timezone(&Local);
+        let updated = now - chrono::Duration::days(2);
+
+        let header = freshness_header(now, Some(updated));
+        assert!(header.starts_with("<!-- working memory compiled "));
+        assert!(header.contains(&updated.format("%Y-%m-%d %H:%M").to_string()));
+        assert!(header.ends_with(" -->"));
+
+        let no_state = freshness_header(now, None);
+        assert!(!no_state.contains("state last updated"));
+    }
+
+    #[test]
+    fn test_list_saved_working_sets() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("release-prep.md"), "x").unwrap();
+        std::fs::write(dir.path().join("auth.md"), "y").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "z").unwrap();
+
+        assert_eq!(
+            list_saved_working_sets(dir.path()),
+            vec!["auth".to_string(), "release-prep".to_string()]
+        );
+        assert!(list_saved_working_sets(&dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_working_set_names_follow_prep_rules() {
+        assert!(validate_working_set_name("release-prep").is_ok());
+        assert!(validate_working_set_name("../state").is_err());
+        assert!(validate_working_set_name("Auth").is_err());
+    }
+}
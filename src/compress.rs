@@ -5,12 +5,92 @@
 //! - Removing obsolete or superseded knowledge
 //! - Abstracting specific instances into general principles
 //! - Preserving critical constraints and preferences
-
+//!
+//! The pre-compression state is kept in state.md.backup; `wm state restore`
+//! swaps it back if compression dropped something important.
+
+use crate::history;
+use crate::llm::{self, LlmClient};
+use crate::pinned;
+use crate::prompts::{self, Prompt};
+use crate::provenance;
 use crate::state;
-use std::process::{Command, Stdio};
+use std::path::Path;
+
+pub const BACKUP_FILE: &str = "state.md.backup";
+
+/// Run wm state restore: put state.md.backup back in place
+pub fn restore() -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    history::snapshot_state();
+    let (before, after) =
+        restore_from_backup(&state::wm_path("state.md"), &state::wm_path(BACKUP_FILE))?;
+
+    state::log(
+        "compress",
+        &format!(
+            "Restored state.md from backup ({} → {} lines)",
+            before, after
+        ),
+    );
+    println!(
+        "Restored state.md from backup: {} → {} lines ({:+})",
+        before,
+        after,
+        after as i64 - before as i64
+    );
+    Ok(())
+}
+
+/// Back up `old_content`, then atomically replace state.md with `new_content`
+pub fn replace_with_backup(
+    state_path: &Path,
+    backup_path: &Path,
+    old_content: &str,
+    new_content: &str,
+) -> Result<(), String> {
+    // Backup old state before overwriting
+    std::fs::write(backup_path, old_content)
+        .map_err(|e| format!("Failed to write backup: {}", e))?;
+
+    // Write compressed state with atomic rename
+    let tmp_path = state_path.with_extension("md.tmp");
+    std::fs::write(&tmp_path, new_content)
+        .map_err(|e| format!("Failed to write temp file: {}", e))?;
+    std::fs::rename(&tmp_path, state_path)
+        .map_err(|e| format!("Failed to rename state file: {}", e))
+}
+
+/// Atomically move the backup over state.md
+///
+/// Returns (lines before, lines after). The backup is consumed, so a second
+/// restore errors instead of silently doing nothing.
+fn restore_from_backup(state_path: &Path, backup_path: &Path) -> Result<(usize, usize), String> {
+    let backup = std::fs::read_to_string(backup_path).map_err(|_| {
+        "No backup found at .wm/state.md.backup. A backup is written by 'wm compress'.".to_string()
+    })?;
+    let current = std::fs::read_to_string(state_path).unwrap_or_default();
+
+    std::fs::rename(backup_path, state_path)
+        .map_err(|e| format!("Failed to restore state file: {}", e))?;
+
+    Ok((current.lines().count(), backup.lines().count()))
+}
+
+/// Hard cap on compression passes (also the --until-stable limit)
+pub const MAX_PASSES: usize = 5;
+
+/// --until-stable stops once a pass removes less than this share of lines
+const STABLE_REDUCTION_PERCENT: usize = 5;
 
 /// Run wm compress
-pub fn run() -> Result<(), String> {
+/// Runs up to `passes` LLM passes, stopping early when one reports nothing
+/// left to compress. With `until_stable`, runs up to MAX_PASSES and also stops
+/// once a pass shrinks state.md by less than STABLE_REDUCTION_PERCENT.
+pub fn run(passes: usize, until_stable: bool) -> Result<(), String> {
     if !state::is_initialized() {
         return Err("Not initialized. Run 'wm init' first.".to_string());
     }
@@ -38,50 +118,124 @@ pub fn run() -> Result<(), String> {
 
     println!("Compressing state.md ({} lines)...", line_count);
 
-    // Call LLM to compress
-    let compressed = call_compression(&current_state)?;
-
-    if compressed.was_compressed {
-        // Backup old state before overwriting
-        let backup_path = state::wm_path("state.md.backup");
-        std::fs::write(&backup_path, &current_state)
-            .map_err(|e| format!("Failed to write backup: {}", e))?;
-
-        // Write compressed state with atomic rename
-        let tmp_path = state::wm_path("state.md.tmp");
-        std::fs::write(&tmp_path, &compressed.content)
-            .map_err(|e| format!("Failed to write temp file: {}", e))?;
-        std::fs::rename(&tmp_path, &state_path)
-            .map_err(|e| format!("Failed to rename state file: {}", e))?;
-
-        let new_line_count = compressed.content.lines().count();
-        let reduction = if line_count > 0 {
-            100 - (new_line_count * 100 / line_count)
-        } else {
-            0
-        };
-
+    let max_passes = if until_stable { MAX_PASSES } else { passes };
+    let client = llm::compress_client()?;
+    // Pinned blocks are never compressed; they go back in verbatim
+    let (pinned_blocks, compressible) = pinned::split(&current_state);
+    if compressible.trim().is_empty() {
+        println!("Nothing to compress - state.md only has pinned sections.");
+        return Ok(());
+    }
+    let (content, stats) = compress_passes(
+        client.as_ref(),
+        &compressible,
+        max_passes.clamp(1, MAX_PASSES),
+        until_stable,
+    )?;
+    let content = pinned::restore(&pinned_blocks, &content);
+
+    if stats.is_empty() {
         state::log(
             "compress",
-            &format!(
-                "Compressed {} → {} lines ({}% reduction)",
-                line_count, new_line_count, reduction
-            ),
+            "No compression possible - state already concise",
         );
-        println!(
-            "Compressed: {} → {} lines ({}% reduction)",
-            line_count, new_line_count, reduction
+        println!("State is already concise - no compression needed.");
+        return Ok(());
+    }
+
+    // One backup of the original, so restore undoes every pass
+    history::snapshot_state();
+    replace_with_backup(
+        &state_path,
+        &state::wm_path(BACKUP_FILE),
+        &current_state,
+        &content,
+    )?;
+
+    let new_line_count = content.lines().count();
+    let reduction = reduction_percent(line_count, new_line_count);
+    let summary = format!(
+        "{} → {} lines ({}% reduction)",
+        line_count, new_line_count, reduction
+    );
+    if stats.len() > 1 {
+        state::log(
+            "compress",
+            &format!("Compressed {} in {} passes", summary, stats.len()),
         );
-        println!("Backup saved to .wm/state.md.backup");
+        println!("Compressed: {} in {} passes", summary, stats.len());
     } else {
+        state::log("compress", &format!("Compressed {}", summary));
+        println!("Compressed: {}", summary);
+    }
+    println!("Backup saved to .wm/state.md.backup (undo: wm state restore)");
+
+    Ok(())
+}
+
+/// Line counts before and after one compression pass
+#[derive(Debug, PartialEq, Eq)]
+struct PassStats {
+    before: usize,
+    after: usize,
+}
+
+/// Compress repeatedly, returning the final content and each applied pass
+/// An empty pass list means the first pass found nothing to compress.
+/// AIDEV-NOTE: Each pass sees the previous pass's output with provenance
+/// tags stripped; carry_forward re-tags against the previous tagged content.
+fn compress_passes(
+    llm: &dyn LlmClient,
+    state: &str,
+    max_passes: usize,
+    until_stable: bool,
+) -> Result<(String, Vec<PassStats>), String> {
+    let mut current = state.to_string();
+    let mut stats = Vec::new();
+
+    for pass in 1..=max_passes {
+        // Tags are stripped for the LLM; merged bullets keep their newest source's tag
+        let compressed = call_compression(llm, &provenance::strip_all(&current))?;
+        if !compressed.was_compressed {
+            state::log("compress", &format!("Pass {}: nothing to compress", pass));
+            break;
+        }
+
+        let next = provenance::carry_forward(&current, &compressed.content);
+        let pass_stats = PassStats {
+            before: current.lines().count(),
+            after: next.lines().count(),
+        };
+        let reduction = reduction_percent(pass_stats.before, pass_stats.after);
         state::log(
             "compress",
-            "No compression possible - state already concise",
+            &format!(
+                "Pass {}: {} → {} lines ({}% reduction)",
+                pass, pass_stats.before, pass_stats.after, reduction
+            ),
         );
-        println!("State is already concise - no compression needed.");
+        if max_passes > 1 {
+            println!(
+                "  Pass {}: {} → {} lines ({}% reduction)",
+                pass, pass_stats.before, pass_stats.after, reduction
+            );
+        }
+
+        current = next;
+        stats.push(pass_stats);
+        if until_stable && reduction < STABLE_REDUCTION_PERCENT {
+            break;
+        }
     }
 
-    Ok(())
+    Ok((current, stats))
+}
+
+/// Percentage of lines removed going from `before` to `after` (0 if it grew)
+fn reduction_percent(before: usize, after: usize) -> usize {
+    (after * 100)
+        .checked_div(before)
+        .map_or(0, |ratio| 100usize.saturating_sub(ratio))
 }
 
 struct CompressionResult {
@@ -89,16 +243,11 @@ struct CompressionResult {
     content: String,
 }
 
-fn call_compression(current_state: &str) -> Result<CompressionResult, String> {
-    // Prevent recursion
-    unsafe { std::env::set_var("WM_DISABLED", "1") };
-
-    // AIDEV-NOTE: The compression prompt focuses on synthesis and abstraction,
-    // not just deduplication. It references the same tacit knowledge criteria
-    // from extract to ensure we preserve the right things.
-    let system_prompt = r#"You are compressing accumulated tacit knowledge into a more concise form.
-
-TACIT KNOWLEDGE REMINDER (what we're preserving):
+/// What to keep and how to synthesize it; shared by compress and `wm state merge`
+/// AIDEV-NOTE: The guide focuses on synthesis and abstraction, not just
+/// deduplication. It references the same tacit knowledge criteria from
+/// extract to ensure we preserve the right things.
+pub const SYNTHESIS_GUIDE: &str = r#"TACIT KNOWLEDGE REMINDER (what we're preserving):
 - Rationale behind decisions (WHY this approach)
 - Paths rejected and why (judgment in pruning)
 - Constraints discovered through friction
@@ -125,7 +274,13 @@ COMPRESSION STRATEGIES:
 5. CONSOLIDATE structure
    - Group related items under clear headings
    - Remove redundant phrasing
-   - Keep bullet points concise
+   - Keep bullet points concise"#;
+
+fn call_compression(llm: &dyn LlmClient, current_state: &str) -> Result<CompressionResult, String> {
+    let default_prompt = format!(
+        r#"You are compressing accumulated tacit knowledge into a more concise form.
+
+{}
 
 THE GOAL: A new Claude session 6 months from now should get the essential wisdom in fewer words. Compress aggressively but preserve meaning.
 
@@ -137,7 +292,10 @@ WAS_COMPRESSED: YES
 <compressed markdown content>
 
 If the state is already concise and no meaningful compression is possible, respond:
-WAS_COMPRESSED: NO"#;
+WAS_COMPRESSED: NO"#,
+        SYNTHESIS_GUIDE
+    );
+    let system_prompt = prompts::system_prompt(Prompt::Compress, &default_prompt);
 
     let message = format!("CURRENT STATE TO COMPRESS:\n\n{}\n\nOUTPUT:", current_state);
 
@@ -146,88 +304,110 @@ WAS_COMPRESSED: NO"#;
         &format!("Sending {} chars to LLM", message.len()),
     );
 
-    let mut cmd = Command::new("claude");
-    cmd.arg("-p")
-        .arg("--output-format")
-        .arg("json")
-        .arg("--no-session-persistence")
-        .arg("--system-prompt")
-        .arg(system_prompt)
-        .arg(&message)
-        .env("SUPEREGO_DISABLED", "1")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .stdin(Stdio::null());
-
-    let child = cmd
-        .spawn()
-        .map_err(|e| format!("Failed to spawn claude CLI: {}", e))?;
-
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for claude CLI: {}", e))?;
-
-    // Re-enable WM
-    unsafe { std::env::remove_var("WM_DISABLED") };
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        return Err(format!(
-            "Claude CLI failed (exit {:?}):\nstderr: {}\nstdout: {}",
-            output.status.code(),
-            stderr,
-            stdout
-        ));
+    // Shared LLM client handles recursion guards (WM_DISABLED, SUPEREGO_DISABLED)
+    let marker = state::read_config().markers.compress;
+    let response = llm::complete_with_marker(
+        llm,
+        &system_prompt,
+        &message,
+        llm::COMPRESSED_MARKER,
+        &marker,
+    )?;
+
+    Ok(CompressionResult {
+        was_compressed: response.is_positive,
+        content: response.content,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MockLlmClient;
+
+    #[test]
+    fn test_compression_with_mock_compressed() {
+        let mock = MockLlmClient::with_texts(&["WAS_COMPRESSED: YES\n# Principles\n- Be concise"]);
+        let result = call_compression(&mock, "- a\n- b\n- c").unwrap();
+
+        assert!(result.was_compressed);
+        assert_eq!(result.content, "# Principles\n- Be concise");
+        assert!(mock.calls.borrow()[0].1.contains("- a\n- b\n- c"));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    parse_compression_response(&stdout)
-}
+    #[test]
+    fn test_restore_after_compress() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("state.md");
+        let backup_path = dir.path().join(BACKUP_FILE);
+        let original = "- a\n- b\n- c\n";
+        std::fs::write(&state_path, original).unwrap();
 
-/// Strip markdown prefixes (same as extract.rs)
-fn strip_markdown_prefix(line: &str) -> &str {
-    line.trim().trim_start_matches(['#', '>', '*']).trim()
-}
+        replace_with_backup(&state_path, &backup_path, original, "- abc\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&state_path).unwrap(), "- abc\n");
 
-fn parse_compression_result(result_str: &str) -> CompressionResult {
-    let lines: Vec<&str> = result_str.lines().collect();
-
-    for (i, line) in lines.iter().enumerate() {
-        let stripped = strip_markdown_prefix(line);
-
-        if let Some(value) = stripped.strip_prefix("WAS_COMPRESSED:") {
-            let value = value.trim().to_uppercase();
-            if value == "YES" || value == "TRUE" {
-                let content = lines[i + 1..].join("\n").trim().to_string();
-                return CompressionResult {
-                    was_compressed: true,
-                    content,
-                };
-            }
-            return CompressionResult {
-                was_compressed: false,
-                content: String::new(),
-            };
-        }
+        let (before, after) = restore_from_backup(&state_path, &backup_path).unwrap();
+        assert_eq!((before, after), (1, 3));
+        assert_eq!(std::fs::read_to_string(&state_path).unwrap(), original);
+        assert!(!backup_path.exists());
+
+        let err = restore_from_backup(&state_path, &backup_path).unwrap_err();
+        assert!(err.contains("No backup found"));
     }
 
-    // Fallback: no marker found
-    state::log("compress", "No WAS_COMPRESSED marker found in response");
-    CompressionResult {
-        was_compressed: false,
-        content: String::new(),
+    #[test]
+    fn test_until_stable_stops_when_nothing_left() {
+        let mock = MockLlmClient::with_texts(&[
+            "WAS_COMPRESSED: YES\n- ab\n- c\n- d",
+            "WAS_COMPRESSED: YES\n- abcd",
+            "WAS_COMPRESSED: NO",
+        ]);
+        let (content, stats) = compress_passes(&mock, "- a\n- b\n- c\n- d\n", 5, true).unwrap();
+
+        assert_eq!(content, "- abcd");
+        assert_eq!(
+            stats,
+            vec![
+                PassStats {
+                    before: 4,
+                    after: 3
+                },
+                PassStats {
+                    before: 3,
+                    after: 1
+                }
+            ]
+        );
+        assert_eq!(mock.call_count(), 3);
+        // Each pass compresses the previous pass's output
+        assert!(mock.calls.borrow()[1].1.contains("- ab\n- c\n- d"));
     }
-}
 
-fn parse_compression_response(response: &str) -> Result<CompressionResult, String> {
-    let cli_response: serde_json::Value = serde_json::from_str(response)
-        .map_err(|e| format!("Failed to parse Claude CLI response: {}", e))?;
+    #[test]
+    fn test_passes_cap_and_stable_threshold() {
+        let twenty: String = (0..20).map(|i| format!("- item {}\n", i)).collect();
+        let nineteen: String = (0..19).map(|i| format!("- item {}\n", i)).collect();
+        let reply = format!("WAS_COMPRESSED: YES\n{}", nineteen);
+
+        // 20 → 19 lines is a 5% reduction: --until-stable keeps going
+        // only while passes remove at least that much
+        let mock = MockLlmClient::with_texts(&[&reply, &reply, &reply]);
+        let (_, stats) = compress_passes(&mock, &twenty, 5, true).unwrap();
+        assert_eq!(stats.len(), 2, "second pass removed nothing, so stop");
+
+        // Without --until-stable, exactly the requested passes run
+        let mock = MockLlmClient::with_texts(&[&reply, &reply, &reply]);
+        let (_, stats) = compress_passes(&mock, &twenty, 2, false).unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(mock.call_count(), 2);
+    }
 
-    let result_str = cli_response
-        .get("result")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| "Claude CLI response missing 'result' field".to_string())?;
+    #[test]
+    fn test_compression_with_mock_already_concise() {
+        let mock = MockLlmClient::with_texts(&["WAS_COMPRESSED: NO"]);
+        let result = call_compression(&mock, "- a").unwrap();
 
-    Ok(parse_compression_result(result_str))
+        assert!(!result.was_compressed);
+        assert!(result.content.is_empty());
+    }
 }
@@ -0,0 +1,364 @@
+//! `wm config get|set|list` - edit .wm/config.toml by dotted key
+//!
+//! Keys are `section.field` (e.g. `operations.extract`, `llm.model`). Values are
+//! parsed according to the key's type and the result is deserialized back into
+//! `Config`, so anything `read_config` would reject (bad enum names, negative
+//! numbers) is rejected here before it is written.
+//!
+//! AIDEV-NOTE: KEYS is the user-facing schema. When adding a config field, add
+//! it here too - test_keys_cover_config fails if a serialized field is missing.
+//! Map-valued fields (`compile.routes`) are edited in the file directly.
+
+use std::path::Path;
+
+use crate::state;
+use crate::types::Config;
+
+/// How a key's value is written on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Int,
+    Str,
+    /// String that can be cleared with `none` or an empty value
+    OptStr,
+    /// Integer that can be cleared with `none` or an empty value
+    OptInt,
+    /// Comma-separated list of strings
+    List,
+}
+
+/// Every settable key and its type
+const KEYS: &[(&str, Kind)] = &[
+    ("operations.extract", Kind::Bool),
+    ("operations.compile", Kind::Bool),
+    ("operations.extract_paused_until", Kind::OptStr),
+    ("operations.compile_paused_until", Kind::OptStr),
+    ("dive.current", Kind::OptStr),
+    ("compile.include_dive", Kind::Bool),
+    ("compile.saved", Kind::OptStr),
+    ("compile.freshness_header", Kind::Bool),
+    ("extract.flag_corrections", Kind::Bool),
+    ("extract.roles", Kind::List),
+    ("extract.carryover_window_minutes", Kind::Int),
+    ("extract.min_interval_secs", Kind::Int),
+    ("extract.max_context_bytes", Kind::Int),
+    ("extract.tool_output_max_bytes", Kind::Int),
+    ("extract.active_thread_only", Kind::Bool),
+    ("llm.backend", Kind::Str),
+    ("llm.model", Kind::OptStr),
+    ("llm.compress_model", Kind::OptStr),
+    ("llm.extra_args", Kind::List),
+    ("llm.max_retries", Kind::Int),
+    ("llm.timeout_secs", Kind::Int),
+    ("markers.extract", Kind::Str),
+    ("markers.distill", Kind::Str),
+    ("markers.compress", Kind::Str),
+    ("sessions.max_session_dirs", Kind::OptInt),
+    ("history.keep", Kind::Int),
+    ("codex.tool_output_max_bytes", Kind::Int),
+    ("oh.api_key", Kind::OptStr),
+    ("oh.api_url", Kind::OptStr),
+    ("log.level", Kind::Str),
+    ("log.max_bytes", Kind::Int),
+    ("log.keep", Kind::Int),
+];
+
+/// What each section is for (comments in the config.toml init writes)
+const SECTIONS: &[(&str, &str)] = &[
+    ("operations", "Pause/resume state (wm pause, wm resume)"),
+    ("dive", "Active dive prep (wm dive switch)"),
+    (
+        "compile",
+        "Working set compilation; [compile.routes] maps intent keywords to state.md sections",
+    ),
+    ("extract", "Knowledge extraction from transcripts"),
+    ("llm", "LLM backend for extract, distill and compress"),
+    (
+        "markers",
+        "Marker names the LLM answers with, per operation",
+    ),
+    (
+        "sessions",
+        "Retention of per-session directories under .wm/sessions/",
+    ),
+    ("history", "state.md snapshots under .wm/history/"),
+    ("codex", "Reading Codex sessions"),
+    ("oh", "Open Horizons credentials (wm distill --push-to-oh)"),
+    ("log", "hook.log verbosity and rotation"),
+];
+
+/// Contents of the config.toml `wm init` writes: every key at its default
+/// Keys without a default value (unset options) are listed commented out.
+pub fn default_file() -> String {
+    let defaults = toml::Value::try_from(Config::default()).expect("default config serializes");
+    let mut out = String::from(
+        "# wm project configuration\n\
+         # Values below are the defaults. Edit here or use `wm config set <key> <value>`.\n",
+    );
+    for (section, doc) in SECTIONS {
+        out.push_str(&format!("\n# {}\n[{}]\n", doc, section));
+        let prefix = format!("{}.", section);
+        for (key, _) in KEYS {
+            let Some(field) = key.strip_prefix(&prefix) else {
+                continue;
+            };
+            match defaults.get(section).and_then(|s| s.get(field)) {
+                Some(value) => out.push_str(&format!("{} = {}\n", field, value)),
+                None => out.push_str(&format!("# {} =\n", field)),
+            }
+        }
+    }
+    out
+}
+
+/// Run wm config get <key>
+pub fn get(key: &str) -> Result<(), String> {
+    let config = load()?;
+    println!("{}", get_value(&config, key)?);
+    Ok(())
+}
+
+/// Run wm config set <key> <value>
+pub fn set(key: &str, value: &str) -> Result<(), String> {
+    load()?;
+    let mut shown = String::new();
+    state::update_config(|config| {
+        *config = set_value(config, key, value)?;
+        shown = get_value(config, key)?;
+        Ok(())
+    })?;
+    println!("{} = {}", key, shown);
+    Ok(())
+}
+
+/// Run wm config list
+pub fn list() -> Result<(), String> {
+    let config = load()?;
+    for (key, _) in KEYS {
+        println!("{} = {}", key, get_value(&config, key)?);
+    }
+    Ok(())
+}
+
+/// Read config, refusing to continue if the file exists but doesn't parse
+/// AIDEV-NOTE: `state::read_config` falls back to defaults on a parse error;
+/// writing that back would silently wipe the user's settings.
+fn load() -> Result<Config, String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+    parse_config_file(&state::wm_path("config.toml"))
+}
+
+/// Parse a config file (missing = defaults)
+pub fn parse_config_file(path: &Path) -> Result<Config, String> {
+    state::parse_config_from(path).map_err(|e| format!("{}. Fix it before using wm config.", e))
+}
+
+/// Look up a key's type, or list the valid keys
+fn kind_of(key: &str) -> Result<Kind, String> {
+    KEYS.iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, kind)| *kind)
+        .ok_or_else(|| {
+            let valid: Vec<&str> = KEYS.iter().map(|(k, _)| *k).collect();
+            format!(
+                "Unknown key '{}'. Valid keys:\n  {}",
+                key,
+                valid.join("\n  ")
+            )
+        })
+}
+
+/// Split a dotted key into (section, field)
+fn split_key(key: &str) -> (&str, &str) {
+    key.split_once('.').expect("KEYS entries are section.field")
+}
+
+/// Current value of a key, formatted for display
+fn get_value(config: &Config, key: &str) -> Result<String, String> {
+    kind_of(key)?;
+    let (section, field) = split_key(key);
+    let root = toml::Value::try_from(config).map_err(|e| e.to_string())?;
+
+    Ok(match root.get(section).and_then(|s| s.get(field)) {
+        None => "(unset)".to_string(),
+        Some(toml::Value::String(s)) => s.clone(),
+        Some(toml::Value::Array(items)) => items
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(String::from)
+                    .unwrap_or_else(|| v.to_string())
+            })
+            .collect::<Vec<_>>()
+            .join(","),
+        Some(other) => other.to_string(),
+    })
+}
+
+/// Return a copy of `config` with `key` set from its command-line form
+fn set_value(config: &Config, key: &str, raw: &str) -> Result<Config, String> {
+    let kind = kind_of(key)?;
+    let (section, field) = split_key(key);
+    let mut root = toml::Value::try_from(config).map_err(|e| e.to_string())?;
+
+    let table = root
+        .as_table_mut()
+        .expect("Config serializes to a table")
+        .entry(section)
+        .or_insert_with(|| toml::Value::Table(toml::map::Map::new()))
+        .as_table_mut()
+        .ok_or_else(|| format!("[{}] is not a table", section))?;
+
+    match parse_value(kind, raw, key)? {
+        Some(value) => {
+            table.insert(field.to_string(), value);
+        }
+        None => {
+            table.remove(field);
+        }
+    }
+
+    root.try_into::<Config>()
+        .map_err(|e| format!("Invalid value '{}' for {}: {}", raw, key, e.message()))
+}
+
+/// Parse a raw value for a key type (None = clear an optional key)
+fn parse_value(kind: Kind, raw: &str, key: &str) -> Result<Option<toml::Value>, String> {
+    let raw = raw.trim();
+    let clears = raw.is_empty() || raw.eq_ignore_ascii_case("none");
+    let int = |raw: &str| {
+        raw.parse::<i64>()
+            .map(toml::Value::Integer)
+            .map_err(|_| format!("{} must be an integer, got '{}'", key, raw))
+    };
+
+    match kind {
+        Kind::Bool => match raw.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(Some(toml::Value::Boolean(true))),
+            "false" | "no" | "off" | "0" => Ok(Some(toml::Value::Boolean(false))),
+            _ => Err(format!(
+                "{} must be a boolean (true/false), got '{}'",
+                key, raw
+            )),
+        },
+        Kind::Int => int(raw).map(Some),
+        Kind::OptInt if clears => Ok(None),
+        Kind::OptInt => int(raw).map(Some),
+        Kind::Str => Ok(Some(toml::Value::String(raw.to_string()))),
+        Kind::OptStr if clears => Ok(None),
+        Kind::OptStr => Ok(Some(toml::Value::String(raw.to_string()))),
+        Kind::List => Ok(Some(toml::Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| toml::Value::String(s.to_string()))
+                .collect(),
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_then_get_round_trips() {
+        let config = Config::default();
+        let config = set_value(&config, "operations.extract", "false").unwrap();
+        let config = set_value(&config, "llm.model", "haiku").unwrap();
+        let config = set_value(&config, "dive.current", "auth-fix").unwrap();
+        let config = set_value(&config, "extract.roles", "user, assistant").unwrap();
+        let config = set_value(&config, "sessions.max_session_dirs", "20").unwrap();
+
+        assert!(!config.operations.extract);
+        assert_eq!(get_value(&config, "operations.extract").unwrap(), "false");
+        assert_eq!(get_value(&config, "llm.model").unwrap(), "haiku");
+        assert_eq!(get_value(&config, "dive.current").unwrap(), "auth-fix");
+        assert_eq!(
+            get_value(&config, "extract.roles").unwrap(),
+            "user,assistant"
+        );
+        assert_eq!(
+            get_value(&config, "sessions.max_session_dirs").unwrap(),
+            "20"
+        );
+
+        // Survives a write/read cycle through the file format
+        let reparsed: Config = toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+        assert_eq!(get_value(&reparsed, "llm.model").unwrap(), "haiku");
+
+        let cleared = set_value(&config, "llm.model", "none").unwrap();
+        assert_eq!(get_value(&cleared, "llm.model").unwrap(), "(unset)");
+    }
+
+    #[test]
+    fn test_set_validates_types() {
+        let config = Config::default();
+        let err = set_value(&config, "operations.extract", "maybe").unwrap_err();
+        assert!(err.contains("must be a boolean"));
+        assert!(set_value(&config, "history.keep", "ten").is_err());
+        assert!(set_value(&config, "history.keep", "-1").is_err());
+        assert!(set_value(&config, "llm.backend", "gpt").is_err());
+        assert!(set_value(&config, "extract.roles", "user,robot").is_err());
+    }
+
+    #[test]
+    fn test_unknown_key_lists_valid_keys() {
+        let err = get_value(&Config::default(), "operations.distill").unwrap_err();
+        assert!(err.contains("Unknown key"));
+        assert!(err.contains("operations.extract"));
+        assert!(err.contains("llm.model"));
+    }
+
+    #[test]
+    fn test_keys_cover_config() {
+        // Fill every optional field so it serializes
+        let mut config = Config::default();
+        config.operations.extract_paused_until = Some("x".to_string());
+        config.operations.compile_paused_until = Some("x".to_string());
+        config.dive.current = Some("x".to_string());
+        config.compile.saved = Some("x".to_string());
+        config.llm.model = Some("x".to_string());
+        config.llm.compress_model = Some("x".to_string());
+        config.sessions.max_session_dirs = Some(1);
+
+        let root = toml::Value::try_from(&config).unwrap();
+        for (section, fields) in root.as_table().unwrap() {
+            for (field, value) in fields.as_table().unwrap() {
+                if value.is_table() {
+                    continue;
+                }
+                let key = format!("{}.{}", section, field);
+                assert!(kind_of(&key).is_ok(), "{} missing from KEYS", key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_file_documents_every_section() {
+        let root = toml::Value::try_from(Config::default()).unwrap();
+        for section in root.as_table().unwrap().keys() {
+            assert!(
+                SECTIONS.iter().any(|(s, _)| s == section),
+                "{} missing from SECTIONS",
+                section
+            );
+        }
+
+        let file = default_file();
+        assert!(file.contains("[operations]\nextract = true\ncompile = true\n"));
+        assert!(file.contains("[dive]\n# current =\n"));
+    }
+
+    #[test]
+    fn test_invalid_config_file_is_not_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[operations\nextract = ").unwrap();
+        assert!(parse_config_file(&path).is_err());
+        assert!(parse_config_file(&dir.path().join("missing.toml")).is_ok());
+    }
+}
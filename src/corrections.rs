@@ -0,0 +1,128 @@
+//! Correction signal detection for extraction input
+//!
+//! Strong preferences often surface when the user pushes back on the assistant
+//! ("no, I said never do that"). This heuristic pre-pass runs over a formatted
+//! transcript and relabels such user turns from `USER:` to `CORRECTION:` so the
+//! extraction LLM weighs them more heavily.
+//!
+//! AIDEV-NOTE: Operates on the output of `format_context` (Claude and Codex share
+//! the same `USER:`/`ASSISTANT:`/... layout), so it works for every transcript source.
+//! Gated behind `[extract] flag_corrections` in config.toml.
+
+/// Line prefixes that start a new block in formatted transcripts
+const BLOCK_PREFIXES: &[&str] = &[
+    "USER: ",
+    "ASSISTANT: ",
+    "THINKING: ",
+    "TOOLS: ",
+    "TOOL: ",
+    "TOOL_RESULT: ",
+    "SUMMARY: ",
+];
+
+/// Phrases that indicate the user is correcting the assistant (lowercase)
+const CORRECTION_PHRASES: &[&str] = &[
+    "i said",
+    "i told you",
+    "i already said",
+    "i already told",
+    "as i said",
+    "not what i asked",
+    "that's not what",
+    "that is not what",
+    "that's wrong",
+    "that is wrong",
+    "don't do that",
+    "do not do that",
+    "stop doing",
+    "never do",
+    "why did you",
+    "i didn't ask",
+    "i did not ask",
+    "please don't",
+    "undo that",
+    "revert that",
+];
+
+/// Openers that indicate an immediate rejection of the previous turn (lowercase)
+const CORRECTION_OPENERS: &[&str] = &["no,", "no.", "no!", "no ", "nope", "wrong", "stop"];
+
+/// Check whether a user message reads like a correction or frustration signal
+pub fn is_correction(text: &str) -> bool {
+    let lower = text.trim().to_lowercase();
+    if lower.is_empty() {
+        return false;
+    }
+
+    CORRECTION_OPENERS.iter().any(|o| lower.starts_with(o))
+        || CORRECTION_PHRASES.iter().any(|p| lower.contains(p))
+}
+
+/// Relabel user turns that look like corrections as `CORRECTION:` blocks
+pub fn emphasize_corrections(formatted: &str) -> String {
+    let mut output = String::with_capacity(formatted.len());
+    let mut block = String::new();
+
+    for line in formatted.split_inclusive('\n') {
+        if BLOCK_PREFIXES.iter().any(|p| line.starts_with(p)) && !block.is_empty() {
+            output.push_str(&relabel_block(&block));
+            block.clear();
+        }
+        block.push_str(line);
+    }
+    output.push_str(&relabel_block(&block));
+
+    output
+}
+
+/// Relabel a single block if it is a user turn containing a correction
+fn relabel_block(block: &str) -> String {
+    match block.strip_prefix("USER: ") {
+        Some(text) if is_correction(text) => format!("CORRECTION: {}", text),
+        _ => block.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_correction_phrases() {
+        assert!(is_correction("No, I said never use unwrap here"));
+        assert!(is_correction("That's not what I asked for"));
+        assert!(is_correction("why did you delete the migration?"));
+        assert!(is_correction("nope"));
+    }
+
+    #[test]
+    fn test_is_correction_ignores_ordinary_messages() {
+        assert!(!is_correction("Please add a test for the parser"));
+        assert!(!is_correction("Notes are in docs/"));
+        assert!(!is_correction(""));
+    }
+
+    #[test]
+    fn test_emphasize_corrections_relabels_only_corrections() {
+        let formatted = "USER: add a cache\n\nASSISTANT: done\n\nUSER: no, I said never cache tokens\n\nASSISTANT: sorry\n\n";
+        let result = emphasize_corrections(formatted);
+
+        assert!(result.contains("USER: add a cache\n\n"));
+        assert!(result.contains("CORRECTION: no, I said never cache tokens\n\n"));
+        assert!(!result.contains("USER: no, I said"));
+        assert!(result.contains("ASSISTANT: sorry"));
+    }
+
+    #[test]
+    fn test_emphasize_corrections_multiline_user_block() {
+        let formatted = "USER: first line\nI told you to use tabs\n\nASSISTANT: ok\n\n";
+        let result = emphasize_corrections(formatted);
+        assert!(result.starts_with("CORRECTION: first line\nI told you to use tabs"));
+    }
+
+    #[test]
+    fn test_emphasize_corrections_preserves_text() {
+        let formatted = "SUMMARY: s\n\nTOOLS: Read(a.rs) \n\nUSER: thanks\n\n";
+        assert_eq!(emphasize_corrections(formatted), formatted);
+    }
+}
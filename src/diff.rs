@@ -0,0 +1,101 @@
+//! Line-based diff for comparing state.md versions
+//!
+//! Plain LCS over lines - state files are small (hundreds of lines), so the
+//! O(n*m) table is cheap and avoids a dependency.
+
+use crate::color;
+
+/// One line of diff output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Diff `old` against `new` line by line
+pub fn line_diff<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = length of the LCS of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            out.push(DiffLine::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push(DiffLine::Removed(a[i]));
+            i += 1;
+        } else {
+            out.push(DiffLine::Added(b[j]));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|l| DiffLine::Removed(l)));
+    out.extend(b[j..].iter().map(|l| DiffLine::Added(l)));
+    out
+}
+
+/// Render a diff with +/- markers (unchanged lines indented), optionally in color
+pub fn render(diff: &[DiffLine], color: bool) -> String {
+    let mut out = String::new();
+    for line in diff {
+        let rendered = match line {
+            DiffLine::Same(l) => format!("  {}", l),
+            DiffLine::Removed(l) => color::paint(color, color::RED, &format!("- {}", l)),
+            DiffLine::Added(l) => color::paint(color, color::GREEN, &format!("+ {}", l)),
+        };
+        out.push_str(&rendered);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff_known_strings() {
+        let old = "# State\n- Prefers tabs\n- Small commits\n";
+        let new = "# State\n- Small commits\n- Runs clippy\n";
+
+        assert_eq!(
+            line_diff(old, new),
+            vec![
+                DiffLine::Same("# State"),
+                DiffLine::Removed("- Prefers tabs"),
+                DiffLine::Same("- Small commits"),
+                DiffLine::Added("- Runs clippy"),
+            ]
+        );
+        assert_eq!(
+            render(&line_diff(old, new), false),
+            "  # State\n- - Prefers tabs\n  - Small commits\n+ - Runs clippy\n"
+        );
+    }
+
+    #[test]
+    fn test_line_diff_identical_and_empty() {
+        assert!(
+            line_diff("a\nb", "a\nb")
+                .iter()
+                .all(|l| matches!(l, DiffLine::Same(_)))
+        );
+        assert_eq!(line_diff("", "x"), vec![DiffLine::Added("x")]);
+    }
+}
@@ -10,19 +10,30 @@
 //! Each session's extraction is cached to support incremental runs (--force overrides).
 //! The raw extractions are written to .wm/distill/raw_extractions.md for Pass 2.
 
-use crate::codex;
-use crate::llm;
+use crate::corrections;
+use crate::llm::{self, LlmClient};
 use crate::oh;
-use crate::session;
+use crate::prompts::{self, Prompt};
+use crate::source::{
+    ClaudeSource, CodexSource, DistillSource, GeminiSource, SourceKind, TranscriptSource,
+};
 use crate::state;
-use crate::transcript::{format_context, read_transcript};
-use crate::types::{CodexSessionInfo, SessionInfo, SessionLike};
+use crate::text;
+use crate::types::{Config, ExtractConfig, LlmConfig, SessionLike};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 /// Directory for distillation output
-const DISTILL_DIR: &str = "distill";
+pub const DISTILL_DIR: &str = "distill";
+
+/// Hashes of items already pushed to OH, under DISTILL_DIR
+const PUSHED_FILE: &str = "pushed.json";
 
 /// Options for the distill command
 pub struct DistillOptions {
@@ -35,14 +46,84 @@ pub struct DistillOptions {
     /// Push distilled knowledge to Open Horizons via MCP
     pub push_to_oh: bool,
 
-    /// OH context ID to push to (required if push_to_oh is true)
+    /// OH context ID to push to (required if push_to_oh is true, unless both
+    /// per-category ids are given)
     pub context_id: Option<String>,
 
+    /// OH context ID for guardrail candidates (overrides context_id)
+    pub guardrails_context_id: Option<String>,
+
+    /// OH context ID for metis candidates (overrides context_id)
+    pub metis_context_id: Option<String>,
+
+    /// Push items even if pushed.json says they were sent before
+    pub force_push: bool,
+
     /// Filter to a specific project by name (substring match)
+    /// Gemini only stores a hash of the project path, so for Gemini this must
+    /// be the project's path.
     pub project: Option<String>,
 
-    /// Process Codex sessions instead of Claude Code sessions
-    pub codex: bool,
+    /// Exact project directory (takes precedence over `project`)
+    pub project_path: Option<PathBuf>,
+
+    /// Which agent's sessions to process (or all of them)
+    pub source: DistillSource,
+
+    /// Only sessions modified at or after this time
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only the newest N sessions (per agent)
+    pub limit: Option<usize>,
+
+    /// Give up on a session's extraction after this many seconds
+    pub timeout_secs: Option<u64>,
+
+    /// Re-extract only sessions recorded as failed in errors.log
+    pub retry_failed: bool,
+
+    /// Drop bullets already seen in an earlier session before Pass 2
+    pub dedup: bool,
+
+    /// Print the dry-run plan as JSON (requires dry_run)
+    pub json: bool,
+}
+
+/// Which sessions Pass 1 should (re-)extract
+enum Pass1Mode {
+    /// Extract new/changed sessions, reuse the cache for the rest
+    Incremental,
+    /// Re-extract every session regardless of cache
+    Force,
+    /// Re-extract only these sessions; others come from cache or are skipped
+    RetryOnly(HashSet<String>),
+}
+
+impl Pass1Mode {
+    /// Resolve the mode from CLI options (reads errors.log for --retry-failed)
+    fn from_options(options: &DistillOptions) -> Self {
+        if options.retry_failed {
+            Pass1Mode::RetryOnly(read_failed_session_ids())
+        } else if options.force {
+            Pass1Mode::Force
+        } else {
+            Pass1Mode::Incremental
+        }
+    }
+
+    /// True when --retry-failed found nothing to retry
+    fn has_nothing_to_retry(&self) -> bool {
+        matches!(self, Pass1Mode::RetryOnly(ids) if ids.is_empty())
+    }
+}
+
+/// Pass 1 settings, resolved once per `wm distill` run
+struct Pass1Settings<'a> {
+    /// config.toml as read at the start of the run
+    config: &'a Config,
+
+    /// Per-session budget from --timeout-secs
+    session_timeout: Option<Duration>,
 }
 
 /// Cached extraction result for a session
@@ -60,10 +141,21 @@ struct SessionExtraction {
     /// The extracted content (if has_knowledge is true)
     content: String,
 
-    /// File size at extraction time (to detect changes)
-    /// AIDEV-NOTE: Sessions are append-only JSONL, so size increase = new content.
-    /// This heuristic would break for editable files but works for transcripts.
+    /// File size at extraction time (cheap first check for changes)
     file_size_bytes: u64,
+
+    /// SHA-256 of the file bytes at extraction time (hex)
+    /// AIDEV-NOTE: Size alone misses edits that keep the length and re-extracts
+    /// files that were merely touched. Caches written before this field existed
+    /// deserialize to "" and are treated as stale.
+    #[serde(default)]
+    content_hash: String,
+
+    /// File modification time at extraction time
+    /// AIDEV-NOTE: While size and mtime both still match, the file isn't hashed
+    /// again; a changed mtime falls back to comparing `content_hash`.
+    #[serde(default)]
+    file_modified_at: Option<DateTime<Utc>>,
 }
 
 /// Run the distill command
@@ -73,117 +165,249 @@ pub fn run(options: DistillOptions) -> Result<(), String> {
     }
 
     // Validate options
-    if options.push_to_oh && options.context_id.is_none() {
-        return Err("--context-id is required when using --push-to-oh".to_string());
-    }
-
-    // Branch based on session source
-    if options.codex {
-        run_codex_distill(options)
-    } else {
-        run_claude_distill(options)
+    if options.push_to_oh {
+        push_contexts(&options)?;
     }
-}
 
-/// Run distill for Claude Code sessions
-fn run_claude_distill(options: DistillOptions) -> Result<(), String> {
-    // Discover sessions, optionally filtered by project
-    let sessions = if let Some(ref project_filter) = options.project {
-        discover_sessions_by_project_filter(project_filter)?
-    } else {
-        // Default: current project only
-        let project_path = session::current_project_path();
-        session::discover_sessions(&project_path)?
-    };
-
-    if sessions.is_empty() {
-        if let Some(ref filter) = options.project {
-            println!("No sessions found for projects matching '{}'.", filter);
-        } else {
-            println!("No sessions found for project.");
+    let kinds = options.source.kinds();
+    let mode = Pass1Mode::from_options(&options);
+    // Sessions from several agents are labelled by agent (see source_pass1)
+    let label_sources = kinds.len() > 1;
+
+    // Machine-readable plan: no progress chatter on stdout
+    if options.json {
+        let mut entries = Vec::new();
+        for kind in &kinds {
+            entries.extend(match kind {
+                SourceKind::Claude => {
+                    dry_run_entries::<ClaudeSource>(&options, &mode, label_sources)?
+                }
+                SourceKind::Codex => {
+                    dry_run_entries::<CodexSource>(&options, &mode, label_sources)?
+                }
+                SourceKind::Gemini => {
+                    dry_run_entries::<GeminiSource>(&options, &mode, label_sources)?
+                }
+            });
         }
+        let out = serde_json::to_string_pretty(&entries)
+            .map_err(|e| format!("Failed to serialize output: {}", e))?;
+        println!("{}", out);
         return Ok(());
     }
 
-    if let Some(ref filter) = options.project {
+    if mode.has_nothing_to_retry() {
         println!(
-            "Found {} Claude session(s) matching project filter '{}'",
-            sessions.len(),
-            filter
+            "No failed sessions recorded in .wm/{}/errors.log",
+            DISTILL_DIR
         );
-    } else {
-        println!("Found {} Claude session(s)", sessions.len());
+        return Ok(());
     }
 
-    if options.dry_run {
-        println!("\n[DRY RUN] Would process:");
-        let cache = load_cache("cache.json");
-        for session in &sessions {
-            let status = extraction_status(session, &cache, options.force);
-            println!("  {} [{}]", session.display_info(), status);
+    let config = state::read_config();
+    // Dry runs never call the LLM
+    let (client, pass1_client) = if options.dry_run {
+        (None, None)
+    } else {
+        let llm_config = &config.llm;
+        // A single Pass 1 call may not outlive the session budget either
+        let pass1_client = match options.timeout_secs {
+            Some(secs) => Some(llm::client_from_config(&LlmConfig {
+                timeout_secs: secs.min(llm_config.timeout_secs),
+                ..llm_config.clone()
+            })?),
+            None => None,
+        };
+        (Some(llm::client_from_config(llm_config)?), pass1_client)
+    };
+    let settings = Pass1Settings {
+        config: &config,
+        session_timeout: options.timeout_secs.map(Duration::from_secs),
+    };
+    let client = client.as_deref();
+    let pass1_client = pass1_client.as_deref().or(client);
+
+    let mut extractions = Vec::new();
+    let mut found_any = false;
+    for kind in &kinds {
+        let found = match kind {
+            SourceKind::Claude => source_pass1::<ClaudeSource>(
+                &options,
+                &settings,
+                &mode,
+                pass1_client,
+                label_sources,
+            )?,
+            SourceKind::Codex => source_pass1::<CodexSource>(
+                &options,
+                &settings,
+                &mode,
+                pass1_client,
+                label_sources,
+            )?,
+            SourceKind::Gemini => source_pass1::<GeminiSource>(
+                &options,
+                &settings,
+                &mode,
+                pass1_client,
+                label_sources,
+            )?,
+        };
+        if let Some(found) = found {
+            found_any = true;
+            extractions.extend(found);
         }
-        return Ok(());
     }
 
-    // Pass 1: Extract knowledge from each session
-    println!("\n=== Pass 1: Extracting knowledge from Claude sessions ===\n");
-    let extractions = run_pass1_generic(&sessions, options.force, "cache.json", extract_claude)?;
+    match client {
+        Some(client) if found_any => run_pass2_and_push(client, extractions, options),
+        _ => Ok(()),
+    }
+}
 
-    run_pass2_and_push(extractions, options)
+/// Sessions selected by --project-path, --project or the current project
+/// AIDEV-NOTE: --project is a substring match (`wm` also hits `swarm`), so an
+/// exact --project-path wins when both are given.
+fn discover<T: TranscriptSource>(options: &DistillOptions) -> Result<Vec<T::Session>, String> {
+    match options.project_path {
+        Some(ref path) => T::discover_path(path),
+        None => T::discover(options.project.as_deref()),
+    }
 }
 
-/// Run distill for Codex sessions
-fn run_codex_distill(options: DistillOptions) -> Result<(), String> {
-    // Discover Codex sessions, optionally filtered by project path
-    let sessions = codex::discover_sessions(options.project.as_deref())?;
+/// Pass 1 over one agent's sessions (or the dry-run listing when `llm` is None)
+/// Returns None when the source has no sessions.
+/// AIDEV-NOTE: Each agent has its own cache file, so ids never collide there.
+/// With `label_sources` (--source all) non-Claude ids are prefixed ("codex:<id>")
+/// before the extractions are pooled for Pass 2.
+fn source_pass1<T: TranscriptSource>(
+    options: &DistillOptions,
+    settings: &Pass1Settings,
+    mode: &Pass1Mode,
+    llm: Option<&dyn LlmClient>,
+    label_sources: bool,
+) -> Result<Option<Vec<SessionExtraction>>, String> {
+    let (sessions, filtered_out) =
+        filter_sessions(discover::<T>(options)?, options.since, options.limit);
+    let filtered_note = if filtered_out > 0 {
+        format!(" ({} older filtered out by --since/--limit)", filtered_out)
+    } else {
+        String::new()
+    };
 
     if sessions.is_empty() {
-        if let Some(ref filter) = options.project {
+        if filtered_out > 0 {
+            println!("No {} sessions left to process{}.", T::NAME, filtered_note);
+        } else if let Some(ref path) = options.project_path {
+            println!(
+                "No {} sessions found for project path {}.",
+                T::NAME,
+                path.display()
+            );
+        } else if let Some(ref filter) = options.project {
             println!(
-                "No Codex sessions found for projects matching '{}'.",
+                "No {} sessions found for projects matching '{}'.",
+                T::NAME,
                 filter
             );
         } else {
-            println!("No Codex sessions found.");
+            println!("No {} sessions found for project.", T::NAME);
         }
-        return Ok(());
+        return Ok(None);
     }
 
-    if let Some(ref filter) = options.project {
+    if let Some(ref path) = options.project_path {
+        println!(
+            "Found {} {} session(s) for project path {}{}",
+            sessions.len(),
+            T::NAME,
+            path.display(),
+            filtered_note
+        );
+    } else if let Some(ref filter) = options.project {
         println!(
-            "Found {} Codex session(s) matching project filter '{}'",
+            "Found {} {} session(s) matching project filter '{}'{}",
             sessions.len(),
-            filter
+            T::NAME,
+            filter,
+            filtered_note
         );
     } else {
-        println!("Found {} Codex session(s)", sessions.len());
+        println!(
+            "Found {} {} session(s){}",
+            sessions.len(),
+            T::NAME,
+            filtered_note
+        );
     }
 
-    if options.dry_run {
+    let Some(llm) = llm else {
         println!("\n[DRY RUN] Would process:");
-        let cache = load_cache(CODEX_CACHE_FILE);
+        let cache = load_cache(T::CACHE_FILE);
         for session in &sessions {
-            let status = extraction_status(session, &cache, options.force);
-            println!("  {} [{}]", session.display_info(), status);
+            let status = extraction_status(session, &cache, mode);
+            println!("  {} [{}]", session.display_info(), status.label());
+        }
+        return Ok(None);
+    };
+
+    // Pass 1: Extract knowledge from each session
+    println!(
+        "\n=== Pass 1: Extracting knowledge from {} sessions ===\n",
+        T::NAME
+    );
+    let mut extractions = run_pass1_generic(
+        llm,
+        &sessions,
+        mode,
+        &state::wm_path(DISTILL_DIR),
+        T::CACHE_FILE,
+        settings,
+        prepare_session::<T>,
+    )?;
+    if label_sources {
+        for extraction in &mut extractions {
+            extraction.session_id = labelled_id::<T>(&extraction.session_id);
         }
-        return Ok(());
     }
+    Ok(Some(extractions))
+}
 
-    // Pass 1: Extract knowledge from each Codex session
-    println!("\n=== Pass 1: Extracting knowledge from Codex sessions ===\n");
-    let extractions =
-        run_pass1_generic(&sessions, options.force, CODEX_CACHE_FILE, extract_codex)?;
+/// Apply --since and --limit to newest-first sessions; returns the kept
+/// sessions and how many were dropped
+fn filter_sessions<S: SessionLike>(
+    sessions: Vec<S>,
+    since: Option<DateTime<Utc>>,
+    limit: Option<usize>,
+) -> (Vec<S>, usize) {
+    let total = sessions.len();
+    let kept: Vec<S> = sessions
+        .into_iter()
+        .filter(|s| since.is_none_or(|since| s.modified_at() >= since))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    let dropped = total - kept.len();
+    (kept, dropped)
+}
 
-    run_pass2_and_push(extractions, options)
+/// Session id as shown when several agents are distilled together:
+/// Claude ids as-is, others prefixed with the agent ("codex:<id>")
+fn labelled_id<T: TranscriptSource>(session_id: &str) -> String {
+    if T::NAME == ClaudeSource::NAME {
+        session_id.to_string()
+    } else {
+        format!("{}:{}", T::NAME.to_lowercase(), session_id)
+    }
 }
 
-/// Run Pass 2 and optionally push to OH (shared by both Claude and Codex paths)
+/// Run Pass 2 and optionally push to OH (shared by Claude, Codex, and Gemini paths)
 fn run_pass2_and_push(
+    llm: &dyn LlmClient,
     extractions: Vec<SessionExtraction>,
     options: DistillOptions,
 ) -> Result<(), String> {
     // Accumulate raw extractions
-    let raw_content = accumulate_extractions(&extractions);
+    let raw_content = accumulate_extractions(&extractions, options.dedup);
 
     if raw_content.is_empty() {
         println!("\nNo knowledge extracted from any session.");
@@ -203,18 +427,24 @@ fn run_pass2_and_push(
 
     // Pass 2: Categorize into guardrails vs metis
     println!("\n=== Pass 2: Categorizing into guardrails vs metis ===\n");
-    let categorized = run_pass2(&raw_content)?;
+    let categorized = run_pass2(llm, &raw_content)?;
 
     // Push to Open Horizons if requested
     if options.push_to_oh {
-        let context_id = options.context_id.as_ref().unwrap(); // Already validated above
-        push_to_oh(context_id, &categorized)?;
+        let (guardrails_context, metis_context) = push_contexts(&options)?;
+        push_to_oh(
+            &guardrails_context,
+            &metis_context,
+            &categorized,
+            options.force_push,
+        )?;
     }
 
     Ok(())
 }
 
 /// Result of Pass 2 categorization
+#[derive(Debug)]
 pub struct CategorizationResult {
     pub guardrails: Vec<String>,
     pub metis: Vec<String>,
@@ -222,16 +452,22 @@ pub struct CategorizationResult {
 
 /// Run Pass 2: categorize raw extractions into guardrails vs metis
 /// Returns the categorization result for optional OH push.
-fn run_pass2(raw_extractions: &str) -> Result<CategorizationResult, String> {
-    let result = call_categorization_llm(raw_extractions)?;
+fn run_pass2(llm: &dyn LlmClient, raw_extractions: &str) -> Result<CategorizationResult, String> {
+    let result = call_categorization_llm(llm, raw_extractions)?;
 
     let guardrail_count = result.guardrails.len();
     let metis_count = result.metis.len();
 
+    let distill_dir = state::wm_path(DISTILL_DIR);
+
     // Write guardrails
     if !result.guardrails.is_empty() {
-        let content = format_categorized_output("Guardrails", &result.guardrails);
-        write_categorized_file("guardrails.md", &content)?;
+        write_pass2_file_in(
+            &distill_dir,
+            "guardrails.md",
+            "Guardrails",
+            &result.guardrails,
+        )?;
         println!(
             "  ✓ {} guardrail(s) written to .wm/{}/guardrails.md",
             guardrail_count, DISTILL_DIR
@@ -242,8 +478,7 @@ fn run_pass2(raw_extractions: &str) -> Result<CategorizationResult, String> {
 
     // Write metis
     if !result.metis.is_empty() {
-        let content = format_categorized_output("Metis", &result.metis);
-        write_categorized_file("metis.md", &content)?;
+        write_pass2_file_in(&distill_dir, "metis.md", "Metis", &result.metis)?;
         println!(
             "  ✓ {} metis item(s) written to .wm/{}/metis.md",
             metis_count, DISTILL_DIR
@@ -261,12 +496,17 @@ fn run_pass2(raw_extractions: &str) -> Result<CategorizationResult, String> {
 }
 
 /// Call LLM to categorize extractions into guardrails vs metis
-fn call_categorization_llm(raw_extractions: &str) -> Result<CategorizationResult, String> {
+fn call_categorization_llm(
+    llm: &dyn LlmClient,
+    raw_extractions: &str,
+) -> Result<CategorizationResult, String> {
     // AIDEV-NOTE: Categorization distinguishes between:
     // - Guardrails: Hard constraints that must NEVER be violated (binary enforcement)
     // - Metis: Wisdom/patterns about HOW to work effectively (contextual guidance)
     // The key difference: guardrails are rules, metis is advice.
-    let system_prompt = r#"You are categorizing tacit knowledge into two types:
+    let system_prompt = prompts::system_prompt(
+        Prompt::Categorize,
+        r#"You are categorizing tacit knowledge into two types:
 
 **GUARDRAILS** - Hard constraints that must NEVER be violated:
 - Prohibitions: "Never do X", "Always do Y before Z"
@@ -298,14 +538,15 @@ Rules:
 2. Preserve the original meaning but clarify if needed
 3. If an item could be both, choose based on severity (safety-critical = guardrail)
 4. It's OK to have empty sections if nothing fits that category
-5. Combine duplicates, but don't lose distinct nuances"#;
+5. Combine duplicates, but don't lose distinct nuances"#,
+    );
 
     let message = format!(
         "Categorize these extracted insights:\n\n{}\n\nOUTPUT:",
         raw_extractions
     );
 
-    let result_str = llm::call_claude(system_prompt, &message)?;
+    let result_str = llm.complete(&system_prompt, &message)?;
     parse_categorization_response(&result_str)
 }
 
@@ -378,20 +619,112 @@ fn format_categorized_output(title: &str, items: &[String]) -> String {
     output
 }
 
-/// Write a categorized output file
-fn write_categorized_file(filename: &str, content: &str) -> Result<(), String> {
-    let distill_dir = state::wm_path(DISTILL_DIR);
-    std::fs::create_dir_all(&distill_dir)
-        .map_err(|e| format!("Failed to create distill directory: {}", e))?;
+/// Items added from outside distill (wm oh pull, wm state merge), under DISTILL_DIR
+/// AIDEV-NOTE: Pass 2 rewrites guardrails.md/metis.md from this project's
+/// sessions alone. Imported items are recorded here as well and merged back
+/// after every rewrite, so the next `wm distill` doesn't silently drop them.
+const IMPORTED_DIR: &str = "imported";
+
+/// Write a Pass 2 categorized file, keeping previously imported items
+fn write_pass2_file_in(
+    distill_dir: &Path,
+    filename: &str,
+    title: &str,
+    items: &[String],
+) -> Result<(), String> {
+    let imported = std::fs::read_to_string(distill_dir.join(IMPORTED_DIR).join(filename))
+        .map(|content| self::items(&content))
+        .unwrap_or_default();
+    let (content, _) =
+        merge_categorized(&format_categorized_output(title, items), title, &imported);
+    write_file(&distill_dir.join(filename), &content)
+}
 
-    let path = distill_dir.join(filename);
-    std::fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", filename, e))?;
+/// Write a file under the distill directory, creating its parent
+fn write_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create distill directory: {}", e))?;
+    }
+    std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
 
-    Ok(())
+/// Add items from outside distill to a categorized file, skipping ones it
+/// already has; they survive later Pass 2 rewrites (see IMPORTED_DIR)
+///
+/// Returns how many were added. Existing content (including `## ` route
+/// sections) is left as-is; a missing file is created with `title`.
+pub fn import_items(filename: &str, title: &str, items: &[String]) -> Result<usize, String> {
+    import_items_in(&state::wm_path(DISTILL_DIR), filename, title, items)
+}
+
+fn import_items_in(
+    distill_dir: &Path,
+    filename: &str,
+    title: &str,
+    items: &[String],
+) -> Result<usize, String> {
+    merge_into_file(&distill_dir.join(IMPORTED_DIR).join(filename), title, items)?;
+    merge_into_file(&distill_dir.join(filename), title, items)
+}
+
+/// Append unseen items to `path`; returns how many were new
+fn merge_into_file(path: &Path, title: &str, items: &[String]) -> Result<usize, String> {
+    let existing = std::fs::read_to_string(path).unwrap_or_default();
+    let (content, added) = merge_categorized(&existing, title, items);
+    if added > 0 {
+        write_file(path, &content)?;
+    }
+    Ok(added)
+}
+
+/// `existing` with the unseen `items` appended as bullets, and how many were new
+fn merge_categorized(existing: &str, title: &str, items: &[String]) -> (String, usize) {
+    let mut seen: HashSet<String> = existing.lines().filter_map(normalize_bullet).collect();
+    let new: Vec<String> = items
+        .iter()
+        .filter(|item| normalize_bullet(&format!("- {}", item)).is_some_and(|key| seen.insert(key)))
+        .cloned()
+        .collect();
+
+    if existing.trim().is_empty() {
+        return (format_categorized_output(title, &new), new.len());
+    }
+    let mut content = existing.trim_end().to_string();
+    content.push('\n');
+    for item in &new {
+        content.push_str(&format!("- {}\n", item));
+    }
+    (content, new.len())
+}
+
+/// Resolve the OH contexts for (guardrails, metis)
+/// A per-category id wins; otherwise both fall back to --context-id.
+fn push_contexts(options: &DistillOptions) -> Result<(String, String), String> {
+    let resolve =
+        |specific: &Option<String>| specific.clone().or_else(|| options.context_id.clone());
+    match (
+        resolve(&options.guardrails_context_id),
+        resolve(&options.metis_context_id),
+    ) {
+        (Some(guardrails), Some(metis)) => Ok((guardrails, metis)),
+        _ => Err("--context-id is required when using --push-to-oh \
+             (or set both --guardrails-context-id and --metis-context-id)"
+            .to_string()),
+    }
 }
 
 /// Push categorized items to Open Horizons
-fn push_to_oh(context_id: &str, categorized: &CategorizationResult) -> Result<(), String> {
+/// AIDEV-NOTE: Items recorded in pushed.json (same context, category and
+/// normalized text) are skipped so repeated distill runs don't re-create
+/// candidates. Only successful pushes are recorded; `force_push` ignores the
+/// ledger but still records.
+fn push_to_oh(
+    guardrails_context_id: &str,
+    metis_context_id: &str,
+    categorized: &CategorizationResult,
+    force_push: bool,
+) -> Result<(), String> {
     if categorized.guardrails.is_empty() && categorized.metis.is_empty() {
         println!("\n=== Push to OH ===\n");
         println!("  ○ Nothing to push (no candidates)");
@@ -399,16 +732,58 @@ fn push_to_oh(context_id: &str, categorized: &CategorizationResult) -> Result<()
     }
 
     println!("\n=== Push to Open Horizons ===\n");
-    println!("  Context: {}", context_id);
+    if guardrails_context_id == metis_context_id {
+        println!("  Context: {}", guardrails_context_id);
+    } else {
+        println!("  Guardrails context: {}", guardrails_context_id);
+        println!("  Metis context: {}", metis_context_id);
+    }
 
-    let result = oh::push_candidates(context_id, &categorized.guardrails, &categorized.metis)?;
+    let ledger_path = state::wm_path(DISTILL_DIR).join(PUSHED_FILE);
+    let mut ledger = load_pushed(&ledger_path);
+    let (guardrails, skipped_guardrails) = unpushed(
+        &categorized.guardrails,
+        guardrails_context_id,
+        "guardrail",
+        &ledger,
+        force_push,
+    );
+    let (metis, skipped_metis) = unpushed(
+        &categorized.metis,
+        metis_context_id,
+        "metis",
+        &ledger,
+        force_push,
+    );
+    let skipped = skipped_guardrails + skipped_metis;
+
+    let result = oh::push_candidates(guardrails_context_id, &guardrails, metis_context_id, &metis)?;
+
+    ledger.extend(
+        result
+            .guardrails_pushed
+            .iter()
+            .map(|item| push_key(guardrails_context_id, "guardrail", item)),
+    );
+    ledger.extend(
+        result
+            .metis_pushed
+            .iter()
+            .map(|item| push_key(metis_context_id, "metis", item)),
+    );
+    if let Err(e) = save_pushed(&ledger_path, &ledger) {
+        eprintln!("Warning: {}", e);
+    }
 
     // Report results
-    if result.guardrails_pushed > 0 {
-        println!("  ✓ {} guardrail(s) pushed", result.guardrails_pushed);
+    if !result.guardrails_pushed.is_empty() {
+        println!("  ✓ {} guardrail(s) pushed", result.guardrails_pushed.len());
     }
-    if result.metis_pushed > 0 {
-        println!("  ✓ {} metis item(s) pushed", result.metis_pushed);
+    if !result.metis_pushed.is_empty() {
+        println!("  ✓ {} metis item(s) pushed", result.metis_pushed.len());
+    }
+    if skipped > 0 {
+        println!("  ○ {} skipped (already pushed)", skipped);
     }
 
     // Report errors
@@ -419,10 +794,11 @@ fn push_to_oh(context_id: &str, categorized: &CategorizationResult) -> Result<()
         }
     }
 
-    let total_pushed = result.guardrails_pushed + result.metis_pushed;
+    let total_pushed = result.guardrails_pushed.len() + result.metis_pushed.len();
     println!(
-        "\nOH push complete: {} item(s) pushed, {} error(s)",
+        "\nOH push complete: {} item(s) pushed, {} skipped (already pushed), {} error(s)",
         total_pushed,
+        skipped,
         result.errors.len()
     );
 
@@ -434,22 +810,162 @@ fn push_to_oh(context_id: &str, categorized: &CategorizationResult) -> Result<()
     Ok(())
 }
 
+/// Ledger key for an item pushed to a context
+/// Text is normalized like bullet dedup, so rewording-free repeats match.
+fn push_key(context_id: &str, kind: &str, content: &str) -> String {
+    let text = normalize_bullet(&format!("- {}", content)).unwrap_or_default();
+    let mut hasher = Sha256::new();
+    for part in [context_id, kind, &text] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Items not yet in the ledger (all of them with `force`), and how many were skipped
+fn unpushed(
+    items: &[String],
+    context_id: &str,
+    kind: &str,
+    ledger: &HashSet<String>,
+    force: bool,
+) -> (Vec<String>, usize) {
+    if force {
+        return (items.to_vec(), 0);
+    }
+    let fresh: Vec<String> = items
+        .iter()
+        .filter(|item| !ledger.contains(&push_key(context_id, kind, item)))
+        .cloned()
+        .collect();
+    let skipped = items.len() - fresh.len();
+    (fresh, skipped)
+}
+
+/// Read pushed.json (missing or unreadable = nothing pushed yet)
+fn load_pushed(path: &Path) -> HashSet<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Write pushed.json, sorted so diffs stay stable
+fn save_pushed(path: &Path, ledger: &HashSet<String>) -> Result<(), String> {
+    let mut hashes: Vec<&String> = ledger.iter().collect();
+    hashes.sort();
+    let json = serde_json::to_string_pretty(&hashes)
+        .map_err(|e| format!("Failed to serialize {}: {}", PUSHED_FILE, e))?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", PUSHED_FILE, e))
+}
+
 // =============================================================================
 // Generic Pass 1 Implementation
 // =============================================================================
 
+/// What Pass 1 would do with a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum ExtractionStatus {
+    /// --force: re-extract regardless of cache
+    Force,
+    /// --retry-failed: listed in errors.log
+    Retry,
+    /// Not cached, or transcript changed since it was cached
+    NewChanged,
+    /// Served from cache
+    Cached,
+    /// --retry-failed: neither failed nor cached, so left out
+    Ignored,
+}
+
+impl ExtractionStatus {
+    /// Label for human dry-run output
+    fn label(self) -> &'static str {
+        match self {
+            Self::Force => "force",
+            Self::Retry => "retry",
+            Self::NewChanged => "new/changed",
+            Self::Cached => "cached",
+            Self::Ignored => "skip",
+        }
+    }
+}
+
 /// Determine extraction status for a session
 fn extraction_status<S: SessionLike>(
     session: &S,
     cache: &HashMap<String, SessionExtraction>,
-    force: bool,
-) -> &'static str {
-    if force {
-        "force"
-    } else if needs_extraction(session, cache) {
-        "new/changed"
-    } else {
-        "cached"
+    mode: &Pass1Mode,
+) -> ExtractionStatus {
+    let session_id = session.session_id();
+    match mode {
+        Pass1Mode::Force => ExtractionStatus::Force,
+        Pass1Mode::RetryOnly(ids) if ids.contains(session_id) => ExtractionStatus::Retry,
+        Pass1Mode::RetryOnly(_) if !cache.contains_key(session_id) => ExtractionStatus::Ignored,
+        Pass1Mode::RetryOnly(_) => ExtractionStatus::Cached,
+        Pass1Mode::Incremental if needs_extraction(session, cache) => ExtractionStatus::NewChanged,
+        Pass1Mode::Incremental => ExtractionStatus::Cached,
+    }
+}
+
+/// One session in `wm distill --dry-run --json` output
+#[derive(Serialize)]
+struct DryRunEntry {
+    session_id: String,
+    size_bytes: u64,
+    modified_at: DateTime<Utc>,
+    status: ExtractionStatus,
+}
+
+/// Dry-run plan entries for one agent's sessions (ids labelled as in Pass 1)
+fn dry_run_entries<T: TranscriptSource>(
+    options: &DistillOptions,
+    mode: &Pass1Mode,
+    label_sources: bool,
+) -> Result<Vec<DryRunEntry>, String> {
+    let (sessions, _) = filter_sessions(discover::<T>(options)?, options.since, options.limit);
+    let cache = load_cache(T::CACHE_FILE);
+    Ok(sessions
+        .iter()
+        .map(|session| DryRunEntry {
+            session_id: if label_sources {
+                labelled_id::<T>(session.session_id())
+            } else {
+                session.session_id().to_string()
+            },
+            size_bytes: session.size_bytes(),
+            modified_at: session.modified_at(),
+            status: extraction_status(session, &cache, mode),
+        })
+        .collect())
+}
+
+/// Whether a session's cached extraction can be reused
+#[derive(Debug, PartialEq)]
+enum CacheCheck {
+    Fresh,
+    /// Needs extraction; carries the content hash if the check computed one
+    Stale(Option<String>),
+}
+
+/// Check a session against the cache: size first, then mtime, then content hash
+fn check_cache<S: SessionLike>(
+    session: &S,
+    cache: &HashMap<String, SessionExtraction>,
+) -> CacheCheck {
+    let Some(cached) = cache.get(session.session_id()) else {
+        return CacheCheck::Stale(None);
+    };
+    if cached.file_size_bytes != session.size_bytes() || cached.content_hash.is_empty() {
+        return CacheCheck::Stale(None);
+    }
+    if cached.file_modified_at == Some(session.modified_at()) {
+        return CacheCheck::Fresh;
+    }
+    match content_hash(session.path()) {
+        Some(hash) if hash == cached.content_hash => CacheCheck::Fresh,
+        hash => CacheCheck::Stale(hash),
     }
 }
 
@@ -458,63 +974,148 @@ fn needs_extraction<S: SessionLike>(
     session: &S,
     cache: &HashMap<String, SessionExtraction>,
 ) -> bool {
-    match cache.get(session.session_id()) {
-        Some(cached) => cached.file_size_bytes != session.size_bytes(),
-        None => true,
-    }
+    check_cache(session, cache) != CacheCheck::Fresh
+}
+
+/// SHA-256 of a file's bytes as lowercase hex (None if unreadable)
+fn content_hash(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    Some(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Sessions read and formatted together before their LLM calls
+/// Bounds how many formatted transcripts are held in memory at once.
+const PREPARE_BATCH: usize = 32;
+
+/// Upper bound on concurrent transcript reads
+const MAX_READ_THREADS: usize = 8;
+
+/// A session transcript read and formatted ahead of the LLM stage
+struct PreparedSession {
+    /// Hash taken before reading, so content appended meanwhile marks it stale
+    content_hash: String,
+    /// Formatted transcript (empty if nothing relevant), or the read error
+    formatted: Result<String, String>,
 }
 
 /// Generic Pass 1: extract knowledge from sessions
 ///
-/// Takes an extractor function that handles the specific session type.
-fn run_pass1_generic<S: SessionLike>(
+/// Takes a prepare function that reads and formats the specific session type.
+/// AIDEV-NOTE: IO-bound work (reading + formatting transcripts) runs on a small
+/// thread pool one batch at a time; the LLM calls that follow stay sequential.
+/// Only this thread touches the cache and the error log, and sessions are
+/// handled in their original order, so output and cache contents are unchanged.
+/// With a `settings.session_timeout`, a session that runs out of time is logged as
+/// failed like any other error and the batch moves on.
+fn run_pass1_generic<S: SessionLike + Sync>(
+    llm: &dyn LlmClient,
     sessions: &[S],
-    force: bool,
+    mode: &Pass1Mode,
+    distill_dir: &Path,
     cache_file: &str,
-    extractor: fn(&S) -> Result<SessionExtraction, String>,
+    settings: &Pass1Settings,
+    prepare: fn(&S, &Config) -> Result<String, String>,
 ) -> Result<Vec<SessionExtraction>, String> {
-    let mut cache = load_cache(cache_file);
+    let mut cache = load_cache_in(distill_dir, cache_file);
     let mut results = Vec::new();
+    let mut retried = HashSet::new();
     let mut processed = 0;
     let mut skipped = 0;
     let mut failed = 0;
 
-    for session in sessions {
-        // Check if we can use cached extraction
-        if !force
-            && !needs_extraction(session, &cache)
-            && let Some(cached) = cache.get(session.session_id())
-        {
-            println!("  {} [cached]", session.session_id());
-            results.push(cached.clone());
-            skipped += 1;
-            continue;
-        }
-
-        // Extract from this session
-        println!("  {} extracting...", session.session_id());
-        match extractor(session) {
-            Ok(extraction) => {
-                let status = if extraction.has_knowledge {
-                    "✓ knowledge found"
-                } else {
-                    "○ no knowledge"
-                };
-                println!("    {}", status);
-
-                cache.insert(session.session_id().to_string(), extraction.clone());
-                results.push(extraction);
-                processed += 1;
+    for batch in sessions.chunks(PREPARE_BATCH) {
+        // Check which sessions can use their cached extraction
+        // AIDEV-NOTE: In retry mode only failed sessions are extracted; everything
+        // else is served from cache (or skipped) so Pass 2 still sees the full set.
+        let checks: Vec<CacheCheck> = batch
+            .iter()
+            .map(|session| match mode {
+                Pass1Mode::Force => CacheCheck::Stale(None),
+                Pass1Mode::RetryOnly(ids) if ids.contains(session.session_id()) => {
+                    CacheCheck::Stale(None)
+                }
+                Pass1Mode::RetryOnly(_) => CacheCheck::Fresh,
+                Pass1Mode::Incremental => check_cache(session, &cache),
+            })
+            .collect();
+
+        let to_extract: Vec<(&S, Option<String>)> = batch
+            .iter()
+            .zip(&checks)
+            .filter_map(|(session, check)| match check {
+                CacheCheck::Fresh => None,
+                CacheCheck::Stale(hash) => Some((session, hash.clone())),
+            })
+            .collect();
+        let mut prepared = prepare_sessions(&to_extract, settings.config, prepare).into_iter();
+
+        for (session, check) in batch.iter().zip(checks) {
+            if check == CacheCheck::Fresh {
+                if let Some(cached) = cache.get(session.session_id()) {
+                    println!("  {} [cached]", session.session_id());
+                    results.push(cached.clone());
+                    skipped += 1;
+                }
+                continue;
             }
-            Err(e) => {
-                eprintln!("    ✗ error: {}", e);
-                log_extraction_error(session.session_id(), &e);
-                failed += 1;
+
+            let is_retry =
+                matches!(mode, Pass1Mode::RetryOnly(ids) if ids.contains(session.session_id()));
+            let PreparedSession {
+                content_hash,
+                formatted,
+            } = prepared
+                .next()
+                .expect("one prepared entry per extracted session");
+
+            // Extract from this session
+            println!("  {} extracting...", session.session_id());
+            let budget = settings
+                .session_timeout
+                .map(|timeout| SessionBudget::new(llm, timeout));
+            let session_llm = budget.as_ref().map_or(llm, |b| b as &dyn LlmClient);
+            let extraction = formatted.and_then(|formatted| {
+                extract_from_formatted(
+                    session_llm,
+                    session.session_id(),
+                    &formatted,
+                    session.size_bytes(),
+                    &settings.config.extract,
+                    &settings.config.markers.distill,
+                )
+            });
+            match extraction {
+                Ok(mut extraction) => {
+                    extraction.content_hash = content_hash;
+                    extraction.file_modified_at = Some(session.modified_at());
+                    let status = if extraction.has_knowledge {
+                        "✓ knowledge found"
+                    } else {
+                        "○ no knowledge"
+                    };
+                    println!("    {}", status);
+
+                    cache.insert(session.session_id().to_string(), extraction.clone());
+                    results.push(extraction);
+                    processed += 1;
+                    if is_retry {
+                        retried.insert(session.session_id().to_string());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("    ✗ error: {}", e);
+                    log_extraction_error_in(distill_dir, session.session_id(), &e);
+                    failed += 1;
+                }
             }
         }
     }
 
-    save_cache(&cache, cache_file)?;
+    save_cache(&cache, distill_dir, cache_file)?;
+
+    if !retried.is_empty() {
+        prune_error_log(distill_dir, &retried);
+    }
 
     let mut summary_parts = vec![format!("{} session(s) processed", processed)];
     if skipped > 0 {
@@ -526,61 +1127,90 @@ fn run_pass1_generic<S: SessionLike>(
     println!("\n{}", summary_parts.join(", "));
 
     if failed > 0 {
-        println!("See .wm/{}/errors.log for failure details", DISTILL_DIR);
+        println!(
+            "See .wm/{}/{} for failure details ({} for scripts)",
+            DISTILL_DIR, ERRORS_LOG, ERRORS_JSONL
+        );
     }
 
     Ok(results)
 }
 
-// =============================================================================
-// Claude Code Extraction
-// =============================================================================
-
-/// Extract knowledge from a Claude Code session
-fn extract_claude(session: &SessionInfo) -> Result<SessionExtraction, String> {
-    state::log(
-        "distill",
-        &format!("Extracting from session {}", session.session_id),
-    );
-
-    let entries = read_transcript(&session.transcript_path)
-        .map_err(|e| format!("Failed to read transcript: {}", e))?;
-
-    // AIDEV-NOTE: Use .as_str() for proper Option<&str> comparison
-    let session_messages: Vec<_> = entries
-        .iter()
-        .filter(|e| e.session_id() == Some(session.session_id.as_str()))
-        .filter(|e| e.is_message() || e.is_summary())
-        .collect();
-
-    if session_messages.is_empty() {
-        return Ok(empty_extraction(&session.session_id, session.size_bytes));
-    }
-
-    let formatted = format_context(&session_messages);
-    extract_from_formatted(&session.session_id, &formatted, session.size_bytes)
+/// Hash, read and format sessions on a bounded pool of threads
+///
+/// Results are returned in the same order as `sessions`. A hash the cache
+/// check already computed is reused instead of reading the file again.
+fn prepare_sessions<S: SessionLike + Sync>(
+    sessions: &[(&S, Option<String>)],
+    config: &Config,
+    prepare: fn(&S, &Config) -> Result<String, String>,
+) -> Vec<PreparedSession> {
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(MAX_READ_THREADS)
+        .min(sessions.len());
+    let next = AtomicUsize::new(0);
+    let slots: Vec<Mutex<Option<PreparedSession>>> =
+        sessions.iter().map(|_| Mutex::new(None)).collect();
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((session, known_hash)) = sessions.get(index) else {
+                        break;
+                    };
+                    let prepared = PreparedSession {
+                        content_hash: known_hash
+                            .clone()
+                            .or_else(|| content_hash(session.path()))
+                            .unwrap_or_default(),
+                        formatted: prepare(session, config),
+                    };
+                    *slots[index].lock().unwrap_or_else(|e| e.into_inner()) = Some(prepared);
+                }
+            });
+        }
+    });
+
+    slots
+        .into_iter()
+        .map(|slot| {
+            slot.into_inner()
+                .unwrap_or_else(|e| e.into_inner())
+                .expect("every session prepared")
+        })
+        .collect()
 }
 
 // =============================================================================
-// Codex Extraction
+// Session Extraction
 // =============================================================================
 
-/// Extract knowledge from a Codex session
-fn extract_codex(session: &CodexSessionInfo) -> Result<SessionExtraction, String> {
+/// Read a session of any transcript source and format it for extraction
+fn prepare_session<T: TranscriptSource>(
+    session: &T::Session,
+    config: &Config,
+) -> Result<String, String> {
     state::log(
         "distill",
-        &format!("Extracting from Codex session {}", session.session_id),
+        &format!(
+            "Extracting from {} session {}",
+            T::NAME,
+            session.session_id()
+        ),
     );
 
-    let entries = codex::read_codex_session(&session.session_path)
-        .map_err(|e| format!("Failed to read Codex session: {}", e))?;
+    let entries = T::read(session.path())
+        .map_err(|e| format!("Failed to read {} session: {}", T::NAME, e))?;
 
-    if !entries.iter().any(|e| e.is_relevant()) {
-        return Ok(empty_extraction(&session.session_id, session.size_bytes));
+    let relevant = T::relevant(session, &entries);
+    if relevant.is_empty() {
+        return Ok(String::new());
     }
 
-    let formatted = codex::format_context(&entries);
-    extract_from_formatted(&session.session_id, &formatted, session.size_bytes)
+    Ok(T::format_context(&relevant, config))
 }
 
 // =============================================================================
@@ -595,20 +1225,68 @@ fn empty_extraction(session_id: &str, file_size_bytes: u64) -> SessionExtraction
         has_knowledge: false,
         content: String::new(),
         file_size_bytes,
+        content_hash: String::new(),
+        file_modified_at: None,
+    }
+}
+
+/// LLM client that refuses further calls once a session's time is up
+/// AIDEV-NOTE: A call already running can't be interrupted from here; distill
+/// caps the per-call timeout at the same budget, so a session ends within
+/// about twice --timeout-secs even if its last call starts near the deadline.
+/// The budget covers the LLM calls only: reading and formatting happen earlier,
+/// batched across sessions on the thread pool, and aren't counted.
+struct SessionBudget<'a> {
+    inner: &'a dyn LlmClient,
+    timeout: Duration,
+    deadline: Instant,
+}
+
+impl<'a> SessionBudget<'a> {
+    fn new(inner: &'a dyn LlmClient, timeout: Duration) -> Self {
+        Self {
+            inner,
+            timeout,
+            deadline: Instant::now() + timeout,
+        }
+    }
+}
+
+impl LlmClient for SessionBudget<'_> {
+    fn complete(&self, system_prompt: &str, message: &str) -> Result<String, String> {
+        if Instant::now() >= self.deadline {
+            return Err(format!(
+                "Session extraction timed out after {} seconds",
+                self.timeout.as_secs()
+            ));
+        }
+        self.inner.complete(system_prompt, message)
     }
 }
 
 /// Extract from formatted transcript content
 fn extract_from_formatted(
+    llm: &dyn LlmClient,
     session_id: &str,
     formatted: &str,
     file_size_bytes: u64,
+    config: &ExtractConfig,
+    marker: &str,
 ) -> Result<SessionExtraction, String> {
     if formatted.trim().is_empty() {
         return Ok(empty_extraction(session_id, file_size_bytes));
     }
 
-    let result = call_extraction_llm(formatted)?;
+    let result = if config.flag_corrections {
+        extract_chunked(
+            llm,
+            &corrections::emphasize_corrections(formatted),
+            config.max_context_bytes,
+            marker,
+        )?
+    } else {
+        extract_chunked(llm, formatted, config.max_context_bytes, marker)?
+    };
 
     Ok(SessionExtraction {
         session_id: session_id.to_string(),
@@ -616,6 +1294,8 @@ fn extract_from_formatted(
         has_knowledge: result.has_knowledge,
         content: result.content,
         file_size_bytes,
+        content_hash: String::new(),
+        file_modified_at: None,
     })
 }
 
@@ -626,12 +1306,18 @@ struct ExtractionResult {
 }
 
 /// Call LLM to extract tacit knowledge from transcript
-fn call_extraction_llm(transcript: &str) -> Result<ExtractionResult, String> {
+fn call_extraction_llm(
+    llm: &dyn LlmClient,
+    transcript: &str,
+    marker: &str,
+) -> Result<ExtractionResult, String> {
     // AIDEV-NOTE: Distill extraction prompt differs from per-turn extract:
     // - We're looking at a complete session, not incremental updates
     // - Focus on extracting standalone insights that can be categorized later
     // - No existing state to merge with - each session is independent
-    let system_prompt = r#"You are extracting tacit knowledge from an AI coding session transcript.
+    let system_prompt = prompts::system_prompt(
+        Prompt::Distill,
+        r#"You are extracting tacit knowledge from an AI coding session transcript.
 
 Tacit knowledge is wisdom about HOW to work effectively, not WHAT was done. Look for:
 - User preferences revealed through corrections or choices
@@ -640,6 +1326,8 @@ Tacit knowledge is wisdom about HOW to work effectively, not WHAT was done. Look
 - Decisions and their rationale (WHY, not just WHAT)
 - Quality standards implicit in feedback
 
+Turns labeled CORRECTION: are the user pushing back on the assistant. These are the highest-value moments - weigh them heavily.
+
 OUTPUT FORMAT:
 
 If you found tacit knowledge worth capturing, respond:
@@ -658,12 +1346,100 @@ Each insight should be:
 If nothing worth capturing, respond:
 HAS_KNOWLEDGE: NO
 
-Most sessions have little or no tacit knowledge. That's normal."#;
+Most sessions have little or no tacit knowledge. That's normal."#,
+    );
 
     let message = format!("TRANSCRIPT:\n{}\n\nOUTPUT:", transcript);
 
-    let result_str = llm::call_claude(system_prompt, &message)?;
-    let response = llm::parse_marker_response(&result_str, "HAS_KNOWLEDGE");
+    let response =
+        llm::complete_with_marker(llm, &system_prompt, &message, llm::KNOWLEDGE_MARKER, marker)?;
+
+    Ok(ExtractionResult {
+        has_knowledge: response.is_positive,
+        content: response.content,
+    })
+}
+
+/// Extract from a transcript, map-reducing over chunks if it exceeds `max_bytes`
+///
+/// AIDEV-NOTE: Each chunk is extracted independently (map), then the positive
+/// results are merged and deduplicated in one more call (reduce). With zero or
+/// one positive chunk there is nothing to merge, so no reduce call is made.
+/// `max_bytes == 0` disables chunking.
+fn extract_chunked(
+    llm: &dyn LlmClient,
+    transcript: &str,
+    max_bytes: usize,
+    marker: &str,
+) -> Result<ExtractionResult, String> {
+    let chunks = text::chunk_messages(transcript, max_bytes);
+    if chunks.len() <= 1 {
+        return call_extraction_llm(llm, transcript, marker);
+    }
+
+    state::log(
+        "distill",
+        &format!(
+            "Transcript is {} bytes, extracting in {} chunks",
+            transcript.len(),
+            chunks.len()
+        ),
+    );
+
+    let mut insights = Vec::new();
+    for chunk in &chunks {
+        let result = call_extraction_llm(llm, chunk, marker)?;
+        if result.has_knowledge && !result.content.trim().is_empty() {
+            insights.push(result.content);
+        }
+    }
+
+    match insights.len() {
+        0 => Ok(ExtractionResult {
+            has_knowledge: false,
+            content: String::new(),
+        }),
+        1 => Ok(ExtractionResult {
+            has_knowledge: true,
+            content: insights.remove(0),
+        }),
+        _ => call_merge_llm(llm, &insights, marker),
+    }
+}
+
+/// Merge insights extracted from chunks of one session into a single list
+fn call_merge_llm(
+    llm: &dyn LlmClient,
+    insights: &[String],
+    marker: &str,
+) -> Result<ExtractionResult, String> {
+    let system_prompt = r#"You are merging tacit knowledge extracted from consecutive parts of one AI coding session.
+
+Combine the lists into one:
+- Remove duplicates and near-duplicates (keep the clearest wording)
+- If a later part refines or contradicts an earlier one, keep the later version
+- Do not add insights that are not in the input
+
+OUTPUT FORMAT:
+
+HAS_KNOWLEDGE: YES
+
+- Insight 1
+- Insight 2
+...
+
+If nothing remains worth keeping, respond:
+HAS_KNOWLEDGE: NO"#;
+
+    let parts: Vec<String> = insights
+        .iter()
+        .enumerate()
+        .map(|(i, content)| format!("PART {}:\n{}", i + 1, content.trim()))
+        .collect();
+    let message = format!("{}\n\nMERGED:", parts.join("\n\n"));
+
+    let response =
+        llm::complete_with_marker(llm, system_prompt, &message, llm::KNOWLEDGE_MARKER, marker)?;
 
     Ok(ExtractionResult {
         has_knowledge: response.is_positive,
@@ -672,24 +1448,88 @@ Most sessions have little or no tacit knowledge. That's normal."#;
 }
 
 /// Accumulate extractions into a single markdown document
-fn accumulate_extractions(extractions: &[SessionExtraction]) -> String {
+///
+/// With `dedup`, a bullet whose normalized text already appeared in an earlier
+/// session is dropped; the first occurrence keeps its original wording.
+fn accumulate_extractions(extractions: &[SessionExtraction], dedup: bool) -> String {
     let mut output = String::new();
+    let mut seen = HashSet::new();
 
     for extraction in extractions {
-        if extraction.has_knowledge && !extraction.content.trim().is_empty() {
-            output.push_str(&format!(
-                "## Session: {}\n\n{}\n\n",
-                extraction.session_id, extraction.content
-            ));
+        if !extraction.has_knowledge || extraction.content.trim().is_empty() {
+            continue;
         }
+
+        let content = if dedup {
+            drop_seen_bullets(&extraction.content, &mut seen)
+        } else {
+            extraction.content.clone()
+        };
+        if content.trim().is_empty() {
+            continue;
+        }
+
+        output.push_str(&format!(
+            "## Session: {}\n\n{}\n\n",
+            extraction.session_id,
+            content.trim()
+        ));
     }
 
     output.trim().to_string()
 }
 
+/// Remove bullet lines whose normalized text is already in `seen`
+/// Non-bullet lines pass through untouched.
+fn drop_seen_bullets(content: &str, seen: &mut HashSet<String>) -> String {
+    content
+        .lines()
+        .filter(|line| match normalize_bullet(line) {
+            Some(key) => seen.insert(key),
+            None => true,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Bullet items of a categorized file, marker stripped
+pub fn items(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter(|line| normalize_bullet(line).is_some())
+        .filter_map(parse_bullet_item)
+        .collect()
+}
+
+/// Number of bullet items in a categorized file (guardrails.md, metis.md)
+pub fn count_items(content: &str) -> usize {
+    content.lines().filter_map(normalize_bullet).count()
+}
+
+/// Comparison key for a bullet line: marker stripped, lowercased,
+/// whitespace collapsed, trailing punctuation dropped. None for non-bullets.
+fn normalize_bullet(line: &str) -> Option<String> {
+    let trimmed = line.trim();
+    let text = trimmed
+        .strip_prefix("- ")
+        .or_else(|| trimmed.strip_prefix("* "))?;
+    let key = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['.', ';', ','])
+        .to_lowercase();
+    if key.is_empty() { None } else { Some(key) }
+}
+
 /// Load extraction cache from disk
 fn load_cache(filename: &str) -> HashMap<String, SessionExtraction> {
-    let cache_path = state::wm_path(DISTILL_DIR).join(filename);
+    load_cache_in(&state::wm_path(DISTILL_DIR), filename)
+}
+
+/// Load an extraction cache from an explicit distill directory
+fn load_cache_in(distill_dir: &Path, filename: &str) -> HashMap<String, SessionExtraction> {
+    let cache_path = distill_dir.join(filename);
 
     std::fs::read_to_string(&cache_path)
         .ok()
@@ -697,10 +1537,31 @@ fn load_cache(filename: &str) -> HashMap<String, SessionExtraction> {
         .unwrap_or_default()
 }
 
+/// When the newest cached session extraction (any agent) was made
+pub fn last_extracted_at() -> Option<DateTime<Utc>> {
+    [
+        ClaudeSource::CACHE_FILE,
+        CodexSource::CACHE_FILE,
+        GeminiSource::CACHE_FILE,
+    ]
+    .into_iter()
+    .flat_map(|file| load_cache(file).into_values())
+    .map(|extraction| extraction.extracted_at)
+    .max()
+}
+
+/// Whether a Claude session is present in the distill extraction cache
+pub fn is_distilled(session_id: &str) -> bool {
+    load_cache(ClaudeSource::CACHE_FILE).contains_key(session_id)
+}
+
 /// Save extraction cache to disk
-fn save_cache(cache: &HashMap<String, SessionExtraction>, filename: &str) -> Result<(), String> {
-    let distill_dir = state::wm_path(DISTILL_DIR);
-    std::fs::create_dir_all(&distill_dir)
+fn save_cache(
+    cache: &HashMap<String, SessionExtraction>,
+    distill_dir: &Path,
+    filename: &str,
+) -> Result<(), String> {
+    std::fs::create_dir_all(distill_dir)
         .map_err(|e| format!("Failed to create distill directory: {}", e))?;
 
     let cache_path = distill_dir.join(filename);
@@ -725,20 +1586,60 @@ fn write_raw_extractions(content: &str) -> Result<(), String> {
     Ok(())
 }
 
-/// Log an extraction error to the errors log file
-fn log_extraction_error(session_id: &str, error: &str) {
+/// Human-readable failure log, under DISTILL_DIR
+const ERRORS_LOG: &str = "errors.log";
+
+/// Structured failure log (one ErrorRecord per line), under DISTILL_DIR
+const ERRORS_JSONL: &str = "errors.jsonl";
+
+/// Coarse failure classification for aggregating errors.jsonl
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ErrorCategory {
+    Timeout,
+    CliMissing,
+    Parse,
+    Other,
+}
+
+impl ErrorCategory {
+    /// Classify an extraction error by its message
+    /// AIDEV-NOTE: Errors are plain Strings, so this keys off the wording
+    /// llm.rs uses ("timed out", CLAUDE_NOT_FOUND, "parse"/"no output").
+    fn classify(error: &str) -> Self {
+        let lower = error.to_lowercase();
+        if lower.contains("timed out") {
+            ErrorCategory::Timeout
+        } else if llm::is_cli_missing(error) {
+            ErrorCategory::CliMissing
+        } else if lower.contains("parse") || lower.contains("no output") {
+            ErrorCategory::Parse
+        } else {
+            ErrorCategory::Other
+        }
+    }
+}
+
+/// One line of errors.jsonl
+#[derive(Debug, Serialize, Deserialize)]
+struct ErrorRecord {
+    session_id: String,
+    timestamp: DateTime<Utc>,
+    error: String,
+    category: ErrorCategory,
+}
+
+/// Append an extraction error to the logs in `distill_dir` (best-effort)
+fn log_extraction_error_in(distill_dir: &Path, session_id: &str, error: &str) {
     use chrono::Local;
     use std::fs::OpenOptions;
     use std::io::Write;
 
-    let distill_dir = state::wm_path(DISTILL_DIR);
-
     // Ensure directory exists
-    if std::fs::create_dir_all(&distill_dir).is_err() {
+    if std::fs::create_dir_all(distill_dir).is_err() {
         return; // Silently fail - this is best-effort logging
     }
 
-    let log_path = distill_dir.join("errors.log");
     let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
     // Collapse multi-line errors to single line for parseable log format
     let error_oneline = error.replace('\n', " | ");
@@ -747,63 +1648,85 @@ fn log_extraction_error(session_id: &str, error: &str) {
         timestamp, session_id, error_oneline
     );
 
-    // Append to log file, ignore errors (logging should never fail the operation)
-    let _ = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&log_path)
-        .and_then(|mut f| f.write_all(line.as_bytes()));
+    let record = ErrorRecord {
+        session_id: session_id.to_string(),
+        timestamp: Utc::now(),
+        error: error.to_string(),
+        category: ErrorCategory::classify(error),
+    };
+    let json_line = serde_json::to_string(&record).map(|json| json + "\n");
+
+    // Append to log files, ignore errors (logging should never fail the operation)
+    let append = |name: &str, text: &str| {
+        let _ = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(distill_dir.join(name))
+            .and_then(|mut f| f.write_all(text.as_bytes()));
+    };
+    append(ERRORS_LOG, &line);
+    if let Ok(json_line) = json_line {
+        append(ERRORS_JSONL, &json_line);
+    }
 }
 
-/// Separate cache file for Codex extractions
-const CODEX_CACHE_FILE: &str = "codex_cache.json";
-
-// =============================================================================
-// Claude session handling
-// =============================================================================
+/// Parse the session id from an errors.log line
+/// Format: `[YYYY-MM-DD HH:MM:SS] Session <id>: <error>`
+fn parse_error_log_session_id(line: &str) -> Option<&str> {
+    let rest = line.split_once("] Session ")?.1;
+    let (session_id, _) = rest.split_once(": ")?;
+    Some(session_id.trim()).filter(|id| !id.is_empty())
+}
 
-/// Discover sessions from projects matching a filter string
-fn discover_sessions_by_project_filter(filter: &str) -> Result<Vec<SessionInfo>, String> {
-    if filter.trim().is_empty() {
-        return Err("Project filter cannot be empty".to_string());
-    }
+/// Read the set of session ids recorded as failed in errors.log
+fn read_failed_session_ids() -> HashSet<String> {
+    let log_path = state::wm_path(DISTILL_DIR).join(ERRORS_LOG);
+    std::fs::read_to_string(log_path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(parse_error_log_session_id)
+        .map(String::from)
+        .collect()
+}
 
-    let matching_projects = session::find_projects_by_filter(filter)?;
+/// Remove errors.log lines for sessions that have since succeeded
+fn remove_error_lines(content: &str, succeeded: &HashSet<String>) -> String {
+    content
+        .lines()
+        .filter(|line| !parse_error_log_session_id(line).is_some_and(|id| succeeded.contains(id)))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
 
-    if matching_projects.is_empty() {
-        return Err(format!(
-            "No projects found matching '{}'. Use 'wm show sessions' to list available projects.",
-            filter
-        ));
-    }
+/// Remove errors.jsonl records for sessions that have since succeeded
+fn remove_error_records(content: &str, succeeded: &HashSet<String>) -> String {
+    content
+        .lines()
+        .filter(|line| {
+            !serde_json::from_str::<ErrorRecord>(line)
+                .is_ok_and(|record| succeeded.contains(&record.session_id))
+        })
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
 
-    // If multiple matches, show which projects we're processing
-    if matching_projects.len() > 1 {
-        println!("Matched {} projects:", matching_projects.len());
-        for p in &matching_projects {
-            println!("  {} ({} sessions)", p.project_id, p.session_count);
+/// Prune retried-and-succeeded sessions from both error logs (best-effort)
+fn prune_error_log(distill_dir: &Path, succeeded: &HashSet<String>) {
+    let prune = |name: &str, remove: fn(&str, &HashSet<String>) -> String| {
+        let log_path = distill_dir.join(name);
+        if let Ok(content) = std::fs::read_to_string(&log_path) {
+            let _ = std::fs::write(&log_path, remove(&content, succeeded));
         }
-        println!();
-    } else {
-        println!("Project: {}", matching_projects[0].project_id);
-    }
-
-    // Collect sessions from all matching projects
-    let mut all_sessions = Vec::new();
-    for project in matching_projects {
-        let sessions = session::discover_sessions_in_dir(&project.project_dir)?;
-        all_sessions.extend(sessions);
-    }
-
-    // Sort by modification time, newest first
-    all_sessions.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
-
-    Ok(all_sessions)
+    };
+    prune(ERRORS_LOG, remove_error_lines);
+    prune(ERRORS_JSONL, remove_error_records);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::llm::MockLlmClient;
+    use crate::types::SessionInfo;
 
     #[test]
     fn test_parse_categorization_response_basic() {
@@ -891,6 +1814,621 @@ METIS:
         assert_eq!(parse_bullet_item("-"), None);
     }
 
+    #[test]
+    fn test_extract_from_formatted_with_mock() {
+        let mock = MockLlmClient::with_texts(&["HAS_KNOWLEDGE: YES\n- Run tests first"]);
+        let extraction = extract_from_formatted(
+            &mock,
+            "sess-1",
+            "USER: test first",
+            42,
+            &ExtractConfig::default(),
+            llm::KNOWLEDGE_MARKER,
+        )
+        .unwrap();
+
+        assert!(extraction.has_knowledge);
+        assert_eq!(extraction.content, "- Run tests first");
+        assert_eq!(extraction.file_size_bytes, 42);
+    }
+
+    #[test]
+    fn test_extract_from_formatted_skips_llm_when_empty() {
+        let mock = MockLlmClient::with_texts(&[]);
+        let extraction = extract_from_formatted(
+            &mock,
+            "sess-1",
+            "   ",
+            0,
+            &ExtractConfig::default(),
+            llm::KNOWLEDGE_MARKER,
+        )
+        .unwrap();
+
+        assert!(!extraction.has_knowledge);
+        assert_eq!(mock.call_count(), 0);
+    }
+
+    #[test]
+    fn test_extract_chunked_merges_positive_chunks() {
+        let transcript = "USER: first part\n\nUSER: second part\n\nUSER: third part\n\n";
+        let mock = MockLlmClient::with_texts(&[
+            "HAS_KNOWLEDGE: YES\n- Run tests first",
+            "HAS_KNOWLEDGE: NO",
+            "HAS_KNOWLEDGE: YES\n- Run tests first\n- Keep PRs small",
+            "HAS_KNOWLEDGE: YES\n- Run tests first\n- Keep PRs small",
+        ]);
+        let result = extract_chunked(&mock, transcript, 25, llm::KNOWLEDGE_MARKER).unwrap();
+
+        assert_eq!(mock.call_count(), 4);
+        assert!(result.has_knowledge);
+        assert_eq!(result.content, "- Run tests first\n- Keep PRs small");
+    }
+
+    #[test]
+    fn test_extract_chunked_skips_merge_for_single_positive() {
+        let transcript = "USER: first part\n\nUSER: second part\n\n";
+        let mock =
+            MockLlmClient::with_texts(&["HAS_KNOWLEDGE: NO", "HAS_KNOWLEDGE: YES\n- Be terse"]);
+        let result = extract_chunked(&mock, transcript, 25, llm::KNOWLEDGE_MARKER).unwrap();
+
+        assert_eq!(mock.call_count(), 2);
+        assert_eq!(result.content, "- Be terse");
+
+        let mock = MockLlmClient::with_texts(&["HAS_KNOWLEDGE: NO"]);
+        extract_chunked(&mock, transcript, 0, llm::KNOWLEDGE_MARKER).unwrap();
+        assert_eq!(mock.call_count(), 1);
+    }
+
+    #[test]
+    fn test_categorization_with_mock() {
+        let mock = MockLlmClient::with_texts(&[
+            "GUARDRAILS:\n- Never push to main\n\nMETIS:\n- Prefer small PRs",
+        ]);
+        let result = call_categorization_llm(&mock, "## Session: a\n\n- stuff").unwrap();
+
+        assert_eq!(result.guardrails, vec!["Never push to main"]);
+        assert_eq!(result.metis, vec!["Prefer small PRs"]);
+    }
+
+    #[test]
+    fn test_parse_error_log_session_id() {
+        let line = "[2025-01-15 10:00:00] Session abc-123: Claude CLI failed | stderr: x";
+        assert_eq!(parse_error_log_session_id(line), Some("abc-123"));
+        assert_eq!(parse_error_log_session_id("garbage"), None);
+    }
+
+    #[test]
+    fn test_remove_error_lines_keeps_unretried() {
+        let content = "[t1] Session a: boom\n[t2] Session b: bang\n[t3] Session a: again\n";
+        let succeeded: HashSet<String> = ["a".to_string()].into_iter().collect();
+        assert_eq!(
+            remove_error_lines(content, &succeeded),
+            "[t2] Session b: bang\n"
+        );
+    }
+
+    #[test]
+    fn test_extraction_error_writes_categorized_json_line() {
+        let dir = tempfile::tempdir().unwrap();
+        log_extraction_error_in(dir.path(), "abc-123", "LLM call timed out after 60 seconds");
+        log_extraction_error_in(dir.path(), "def-456", llm::CLAUDE_NOT_FOUND);
+
+        let content = std::fs::read_to_string(dir.path().join(ERRORS_JSONL)).unwrap();
+        let records: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["session_id"], "abc-123");
+        assert_eq!(records[0]["category"], "timeout");
+        assert_eq!(records[0]["error"], "LLM call timed out after 60 seconds");
+        assert!(records[0]["timestamp"].as_str().is_some());
+        assert_eq!(records[1]["category"], "cli_missing");
+
+        // The human log is still written
+        let log = std::fs::read_to_string(dir.path().join(ERRORS_LOG)).unwrap();
+        assert_eq!(
+            parse_error_log_session_id(log.lines().next().unwrap()),
+            Some("abc-123")
+        );
+    }
+
+    #[test]
+    fn test_error_category_classify() {
+        assert_eq!(
+            ErrorCategory::classify("Failed to parse OpenAI API response: eof"),
+            ErrorCategory::Parse
+        );
+        assert_eq!(
+            ErrorCategory::classify("Claude CLI produced no output"),
+            ErrorCategory::Parse
+        );
+        assert_eq!(
+            ErrorCategory::classify("Claude CLI failed (exit Some(1)):\nstderr: x"),
+            ErrorCategory::Other
+        );
+    }
+
+    fn extraction(session_id: &str, content: &str) -> SessionExtraction {
+        SessionExtraction {
+            session_id: session_id.to_string(),
+            extracted_at: Utc::now(),
+            has_knowledge: true,
+            content: content.to_string(),
+            file_size_bytes: 0,
+            content_hash: String::new(),
+            file_modified_at: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_categorized_appends_unseen_items() {
+        let existing = "# Guardrails\n\n## Testing\n- Never skip CI.\n";
+        let items = vec![
+            "never skip CI".to_string(),
+            "Don't force-push main".to_string(),
+            "Don't force-push main".to_string(),
+        ];
+        let (content, added) = merge_categorized(existing, "Guardrails", &items);
+        assert_eq!(added, 1);
+        assert_eq!(
+            content,
+            "# Guardrails\n\n## Testing\n- Never skip CI.\n- Don't force-push main\n"
+        );
+
+        let (fresh, added) = merge_categorized("", "Metis", &["Prefer small PRs".to_string()]);
+        assert_eq!(added, 1);
+        assert_eq!(fresh, "# Metis\n\n- Prefer small PRs\n");
+    }
+
+    #[test]
+    fn test_imported_items_survive_pass2_rewrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let pulled = vec!["Never force-push main".to_string()];
+        assert_eq!(
+            import_items_in(dir.path(), "guardrails.md", "Guardrails", &pulled).unwrap(),
+            1
+        );
+
+        // The next distill rewrites guardrails.md from sessions only
+        let generated = vec!["Run tests before pushing".to_string()];
+        write_pass2_file_in(dir.path(), "guardrails.md", "Guardrails", &generated).unwrap();
+
+        let content = std::fs::read_to_string(dir.path().join("guardrails.md")).unwrap();
+        assert_eq!(
+            content,
+            "# Guardrails\n\n- Run tests before pushing\n- Never force-push main\n"
+        );
+    }
+
+    #[test]
+    fn test_unpushed_skips_ledger_items_unless_forced() {
+        let items = vec!["Never push to main".to_string(), "Run CI".to_string()];
+        let mut ledger = HashSet::new();
+        ledger.insert(push_key("ctx", "guardrail", "never push to main."));
+
+        let (fresh, skipped) = unpushed(&items, "ctx", "guardrail", &ledger, false);
+        assert_eq!(fresh, vec!["Run CI"]);
+        assert_eq!(skipped, 1);
+
+        // Different context or category isn't a repeat
+        assert_eq!(unpushed(&items, "other", "guardrail", &ledger, false).1, 0);
+        assert_eq!(unpushed(&items, "ctx", "metis", &ledger, false).1, 0);
+
+        let (forced, skipped) = unpushed(&items, "ctx", "guardrail", &ledger, true);
+        assert_eq!(forced, items);
+        assert_eq!(skipped, 0);
+    }
+
+    #[test]
+    fn test_pushed_ledger_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PUSHED_FILE);
+        assert!(load_pushed(&path).is_empty());
+
+        let ledger: HashSet<String> = [push_key("ctx", "metis", "Prefer small PRs")].into();
+        save_pushed(&path, &ledger).unwrap();
+        assert_eq!(load_pushed(&path), ledger);
+    }
+
+    #[test]
+    fn test_codex_fixture_extracts_with_labelled_id() {
+        let root = tempfile::tempdir().unwrap();
+        let day = root.path().join("2025/01/15");
+        std::fs::create_dir_all(&day).unwrap();
+        std::fs::write(
+            day.join("rollout-2025-01-15T10-00-00-abc.jsonl"),
+            [
+                r#"{"timestamp":"t","type":"session_meta","payload":{"cwd":"/work/api"}}"#,
+                r#"{"timestamp":"t","type":"event_msg","payload":{"type":"user_message","message":"Use sqlx, not diesel"}}"#,
+                r#"{"timestamp":"t","type":"event_msg","payload":{"type":"agent_message","message":"Switching to sqlx"}}"#,
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let sessions =
+            crate::codex::session::discover_sessions_in(root.path(), Some("api")).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert!(
+            crate::codex::session::discover_sessions_in(root.path(), Some("web"))
+                .unwrap()
+                .is_empty()
+        );
+
+        let session = &sessions[0];
+        let formatted = prepare_session::<CodexSource>(session, &Config::default()).unwrap();
+        assert!(formatted.contains("Use sqlx, not diesel"));
+
+        let mock = MockLlmClient::with_texts(&["HAS_KNOWLEDGE: YES\n- Uses sqlx over diesel"]);
+        let codex = extract_from_formatted(
+            &mock,
+            &labelled_id::<CodexSource>(session.session_id()),
+            &formatted,
+            session.size_bytes(),
+            &ExtractConfig::default(),
+            llm::KNOWLEDGE_MARKER,
+        )
+        .unwrap();
+        assert_eq!(codex.session_id, "codex:2025-01-15T10-00-00-abc");
+
+        // Pooled with a Claude session of the same raw id, both stay distinct
+        let claude = extraction("2025-01-15T10-00-00-abc", "- Prefers small commits");
+        let out = accumulate_extractions(&[claude, codex], true);
+        assert!(out.contains("## Session: 2025-01-15T10-00-00-abc\n"));
+        assert!(
+            out.contains("## Session: codex:2025-01-15T10-00-00-abc\n\n- Uses sqlx over diesel")
+        );
+        assert_eq!(labelled_id::<ClaudeSource>("x"), "x");
+    }
+
+    fn session_at(id: &str, days_ago: i64, now: DateTime<Utc>) -> SessionInfo {
+        SessionInfo {
+            session_id: id.to_string(),
+            transcript_path: std::path::PathBuf::from(format!("{}.jsonl", id)),
+            modified_at: now - chrono::Duration::days(days_ago),
+            size_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_filter_sessions_by_date_and_count() {
+        let now = Utc::now();
+        // Newest first, as discovery returns them
+        let sessions = || {
+            vec![
+                session_at("a", 1, now),
+                session_at("b", 3, now),
+                session_at("c", 10, now),
+                session_at("d", 30, now),
+            ]
+        };
+        let ids = |kept: &[SessionInfo]| {
+            kept.iter()
+                .map(|s| s.session_id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let (kept, dropped) = filter_sessions(sessions(), None, None);
+        assert_eq!(ids(&kept), ["a", "b", "c", "d"]);
+        assert_eq!(dropped, 0);
+
+        let week_ago = Some(now - chrono::Duration::days(7));
+        let (kept, dropped) = filter_sessions(sessions(), week_ago, None);
+        assert_eq!(ids(&kept), ["a", "b"]);
+        assert_eq!(dropped, 2);
+
+        let (kept, dropped) = filter_sessions(sessions(), None, Some(3));
+        assert_eq!(ids(&kept), ["a", "b", "c"]);
+        assert_eq!(dropped, 1);
+
+        // Combined: the tighter of the two wins
+        let (kept, dropped) = filter_sessions(sessions(), week_ago, Some(1));
+        assert_eq!(ids(&kept), ["a"]);
+        assert_eq!(dropped, 3);
+    }
+
+    #[test]
+    fn test_accumulate_dedups_across_sessions() {
+        let extractions = vec![
+            extraction(
+                "s1",
+                "- Prefers small commits\n- Runs clippy before pushing",
+            ),
+            extraction(
+                "s2",
+                "- prefers  small commits.\n- Avoids unwrap in library code",
+            ),
+            extraction("s3", "* Runs clippy before pushing"),
+        ];
+
+        let out = accumulate_extractions(&extractions, true);
+        assert_eq!(out.matches("mall commits").count(), 1);
+        assert!(out.contains("- Prefers small commits"));
+        assert_eq!(out.matches("Runs clippy").count(), 1);
+        assert!(out.contains("## Session: s2\n\n- Avoids unwrap in library code"));
+        // s3 had nothing new, so its header is dropped too
+        assert!(!out.contains("## Session: s3"));
+    }
+
+    #[test]
+    fn test_accumulate_without_dedup_keeps_everything() {
+        let extractions = vec![
+            extraction("s1", "- Prefers small commits"),
+            extraction("s2", "- Prefers small commits"),
+        ];
+
+        let out = accumulate_extractions(&extractions, false);
+        assert_eq!(out.matches("Prefers small commits").count(), 2);
+        assert!(out.contains("## Session: s2"));
+    }
+
+    #[test]
+    fn test_normalize_bullet() {
+        assert_eq!(
+            normalize_bullet("  -   Use  Tabs. "),
+            Some("use tabs".to_string())
+        );
+        assert_eq!(normalize_bullet("Plain prose line"), None);
+        assert_eq!(normalize_bullet("- "), None);
+    }
+
+    fn push_options(
+        context_id: Option<&str>,
+        guardrails: Option<&str>,
+        metis: Option<&str>,
+    ) -> DistillOptions {
+        DistillOptions {
+            dry_run: false,
+            force: false,
+            push_to_oh: true,
+            context_id: context_id.map(String::from),
+            guardrails_context_id: guardrails.map(String::from),
+            metis_context_id: metis.map(String::from),
+            force_push: false,
+            project: None,
+            project_path: None,
+            timeout_secs: None,
+            source: DistillSource::Claude,
+            since: None,
+            limit: None,
+            retry_failed: false,
+            dedup: true,
+            json: false,
+        }
+    }
+
+    #[test]
+    fn test_push_contexts_fall_back_to_context_id() {
+        let both = push_contexts(&push_options(Some("ctx"), None, Some("wisdom"))).unwrap();
+        assert_eq!(both, ("ctx".to_string(), "wisdom".to_string()));
+
+        let split = push_contexts(&push_options(None, Some("rules"), Some("wisdom"))).unwrap();
+        assert_eq!(split, ("rules".to_string(), "wisdom".to_string()));
+
+        assert!(push_contexts(&push_options(None, Some("rules"), None)).is_err());
+    }
+
+    #[test]
+    fn test_extraction_status_json_names() {
+        let names: Vec<String> = [
+            ExtractionStatus::Force,
+            ExtractionStatus::NewChanged,
+            ExtractionStatus::Cached,
+            ExtractionStatus::Ignored,
+        ]
+        .iter()
+        .map(|s| serde_json::to_string(s).unwrap())
+        .collect();
+        assert_eq!(
+            names,
+            [
+                r#""force""#,
+                r#""new-changed""#,
+                r#""cached""#,
+                r#""ignored""#
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extraction_status_retry_mode() {
+        let session = SessionInfo {
+            session_id: "failed-1".to_string(),
+            transcript_path: std::path::PathBuf::from("x.jsonl"),
+            modified_at: Utc::now(),
+            size_bytes: 10,
+        };
+        let cache = HashMap::new();
+        let ids: HashSet<String> = ["failed-1".to_string()].into_iter().collect();
+        assert_eq!(
+            extraction_status(&session, &cache, &Pass1Mode::RetryOnly(ids)).label(),
+            "retry"
+        );
+        assert_eq!(
+            extraction_status(&session, &cache, &Pass1Mode::RetryOnly(HashSet::new())).label(),
+            "skip"
+        );
+        assert_eq!(
+            extraction_status(&session, &cache, &Pass1Mode::Incremental).label(),
+            "new/changed"
+        );
+    }
+
+    #[test]
+    fn test_needs_extraction_detects_same_size_edit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sess-1.jsonl");
+        std::fs::write(&path, "aaaa").unwrap();
+
+        let session = SessionInfo {
+            session_id: "sess-1".to_string(),
+            transcript_path: path.clone(),
+            modified_at: Utc::now(),
+            size_bytes: 4,
+        };
+        let mut extraction = empty_extraction("sess-1", 4);
+        extraction.content_hash = content_hash(&path).unwrap();
+        let cache = HashMap::from([("sess-1".to_string(), extraction)]);
+        assert!(!needs_extraction(&session, &cache));
+
+        // Same size, different content
+        std::fs::write(&path, "bbbb").unwrap();
+        assert!(needs_extraction(&session, &cache));
+    }
+
+    #[test]
+    fn test_check_cache_skips_hash_while_mtime_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sess-1.jsonl");
+        std::fs::write(&path, "aaaa").unwrap();
+        let modified_at = Utc::now();
+
+        let mut session = SessionInfo {
+            session_id: "sess-1".to_string(),
+            transcript_path: path.clone(),
+            modified_at,
+            size_bytes: 4,
+        };
+        let mut extraction = empty_extraction("sess-1", 4);
+        extraction.content_hash = content_hash(&path).unwrap();
+        extraction.file_modified_at = Some(modified_at);
+        let cache = HashMap::from([("sess-1".to_string(), extraction)]);
+
+        // Same size and mtime: fresh without reading the file
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(check_cache(&session, &cache), CacheCheck::Fresh);
+
+        // Touched but unchanged: hashed, still fresh
+        std::fs::write(&path, "aaaa").unwrap();
+        session.modified_at = modified_at + chrono::Duration::seconds(5);
+        assert_eq!(check_cache(&session, &cache), CacheCheck::Fresh);
+
+        // Changed: the computed hash comes back for Pass 1 to reuse
+        std::fs::write(&path, "bbbb").unwrap();
+        assert_eq!(
+            check_cache(&session, &cache),
+            CacheCheck::Stale(content_hash(&path))
+        );
+    }
+
+    fn read_or_fail(session: &SessionInfo, _config: &Config) -> Result<String, String> {
+        std::fs::read_to_string(session.path()).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_pass1_continues_after_session_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let sessions: Vec<SessionInfo> = ["a", "b", "c"]
+            .iter()
+            .map(|id| {
+                let path = dir.path().join(format!("{}.jsonl", id));
+                std::fs::write(&path, format!("USER: session {}", id)).unwrap();
+                SessionInfo {
+                    session_id: id.to_string(),
+                    transcript_path: path,
+                    modified_at: Utc::now(),
+                    size_bytes: 0,
+                }
+            })
+            .collect();
+        let mock = MockLlmClient::new(vec![
+            Ok("HAS_KNOWLEDGE: YES\n- From a".to_string()),
+            Err("LLM call timed out after 60 seconds".to_string()),
+            Ok("HAS_KNOWLEDGE: YES\n- From c".to_string()),
+        ]);
+        let distill_dir = dir.path().join("distill");
+
+        let results = run_pass1_generic(
+            &mock,
+            &sessions,
+            &Pass1Mode::Force,
+            &distill_dir,
+            "cache.json",
+            &Pass1Settings {
+                config: &Config::default(),
+                session_timeout: Some(Duration::from_secs(60)),
+            },
+            read_or_fail,
+        )
+        .unwrap();
+
+        let ids: Vec<&str> = results.iter().map(|r| r.session_id.as_str()).collect();
+        assert_eq!(ids, ["a", "c"]);
+        let errors = std::fs::read_to_string(distill_dir.join(ERRORS_JSONL)).unwrap();
+        let record: ErrorRecord = serde_json::from_str(errors.trim()).unwrap();
+        assert_eq!(record.session_id, "b");
+        assert_eq!(record.category, ErrorCategory::Timeout);
+        // The timed-out session isn't cached, so the next run retries it
+        assert!(!load_cache_in(&distill_dir, "cache.json").contains_key("b"));
+    }
+
+    #[test]
+    fn test_session_budget_refuses_calls_after_deadline() {
+        let mock = MockLlmClient::with_texts(&["ok"]);
+        let budget = SessionBudget::new(&mock, Duration::ZERO);
+        let err = budget.complete("system", "message").unwrap_err();
+        assert_eq!(err, "Session extraction timed out after 0 seconds");
+        assert_eq!(mock.call_count(), 0);
+
+        let budget = SessionBudget::new(&mock, Duration::from_secs(60));
+        assert_eq!(budget.complete("system", "message").unwrap(), "ok");
+    }
+
+    #[test]
+    fn test_prepare_sessions_keeps_order_and_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let sessions: Vec<SessionInfo> = (0..20)
+            .map(|i| {
+                let path = dir.path().join(format!("s{}.jsonl", i));
+                // Every fifth session is missing on disk
+                if i % 5 != 0 {
+                    std::fs::write(&path, format!("session {}", i)).unwrap();
+                }
+                SessionInfo {
+                    session_id: format!("s{}", i),
+                    transcript_path: path,
+                    modified_at: Utc::now(),
+                    size_bytes: 0,
+                }
+            })
+            .collect();
+        let refs: Vec<(&SessionInfo, Option<String>)> =
+            sessions.iter().map(|session| (session, None)).collect();
+
+        let prepared = prepare_sessions(&refs, &Config::default(), read_or_fail);
+        assert_eq!(prepared.len(), 20);
+        for (i, p) in prepared.iter().enumerate() {
+            if i % 5 == 0 {
+                assert!(p.formatted.is_err());
+                assert!(p.content_hash.is_empty());
+            } else {
+                assert_eq!(p.formatted.as_deref().unwrap(), format!("session {}", i));
+                assert_eq!(p.content_hash.len(), 64);
+            }
+        }
+        assert!(prepare_sessions::<SessionInfo>(&[], &Config::default(), read_or_fail).is_empty());
+    }
+
+    #[test]
+    fn test_needs_extraction_legacy_cache_is_stale() {
+        let cached: SessionExtraction = serde_json::from_str(
+            r#"{"session_id":"sess-1","extracted_at":"2025-01-15T10:00:00Z","has_knowledge":false,"content":"","file_size_bytes":4}"#,
+        )
+        .unwrap();
+        assert!(cached.content_hash.is_empty());
+
+        let session = SessionInfo {
+            session_id: "sess-1".to_string(),
+            transcript_path: std::path::PathBuf::from("missing.jsonl"),
+            modified_at: Utc::now(),
+            size_bytes: 4,
+        };
+        let cache = HashMap::from([("sess-1".to_string(), cached)]);
+        assert!(needs_extraction(&session, &cache));
+    }
+
     #[test]
     fn test_format_categorized_output() {
         let items = vec!["First item".to_string(), "Second item".to_string()];
@@ -3,8 +3,14 @@
 //! Supports multiple named preps (like git branches) stored in .wm/dives/
 //! with a "current" prep tracked in config.
 
+use crate::color;
+use crate::editor;
+use crate::intent::{self, IntentType};
+use crate::oh;
+use crate::session;
 use crate::state;
 use std::fs;
+use std::path::{Path, PathBuf};
 
 // ============================================================================
 // Named prep management
@@ -25,19 +31,24 @@ pub fn list() -> Result<(), String> {
     }
 
     for prep in preps {
-        let marker = if current.as_ref() == Some(&prep) {
-            "* "
+        if current.as_ref() == Some(&prep) {
+            println!("{} {}", color::green("*"), prep);
         } else {
-            "  "
-        };
-        println!("{}{}", marker, prep);
+            println!("  {}", prep);
+        }
     }
 
     Ok(())
 }
 
+/// Project-level dive template, used by `dive new` when no --template is given
+const DIVE_TEMPLATE_FILE: &str = "dive_template.md";
+
 /// Create a new named dive prep
-pub fn new(name: &str, content: Option<&str>) -> Result<(), String> {
+///
+/// Content comes from `content` if given, else the `--template` file, else
+/// .wm/dive_template.md, else the built-in skeleton.
+pub fn new(name: &str, content: Option<&str>, template: Option<&str>) -> Result<(), String> {
     if !state::is_initialized() {
         return Err("Not initialized. Run 'wm init' first.".to_string());
     }
@@ -60,11 +71,16 @@ pub fn new(name: &str, content: Option<&str>) -> Result<(), String> {
 
     state::ensure_dive_dir().map_err(|e| format!("Failed to create dives directory: {}", e))?;
 
-    let default_content = format!(
-        "# Dive: {}\n\nIntent: \n\n## Focus\n\n## Constraints\n",
-        name
-    );
-    let initial_content = content.unwrap_or(&default_content);
+    let initial_content = match content {
+        Some(content) => content.to_string(),
+        None => match read_template(template)? {
+            Some(template) => render_template(&template, name),
+            None => format!(
+                "# Dive: {}\n\nIntent: \n\n## Focus\n\n## Constraints\n",
+                name
+            ),
+        },
+    };
     fs::write(&path, initial_content).map_err(|e| format!("Failed to create prep: {}", e))?;
 
     println!("✓ Created dive prep '{}' at .wm/dives/{}.md", name, name);
@@ -73,6 +89,27 @@ pub fn new(name: &str, content: Option<&str>) -> Result<(), String> {
     Ok(())
 }
 
+/// Read the dive template: the explicit path, else .wm/dive_template.md if present
+/// AIDEV-NOTE: An explicit --template that can't be read is an error; a missing
+/// project default silently falls back to the built-in skeleton.
+fn read_template(template: Option<&str>) -> Result<Option<String>, String> {
+    if let Some(path) = template {
+        return fs::read_to_string(path)
+            .map(Some)
+            .map_err(|e| format!("Failed to read template '{}': {}", path, e));
+    }
+
+    Ok(fs::read_to_string(state::wm_path(DIVE_TEMPLATE_FILE)).ok())
+}
+
+/// Substitute {{name}} and {{date}} (today, YYYY-MM-DD) in a template
+fn render_template(template: &str, name: &str) -> String {
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    template
+        .replace("{{name}}", name)
+        .replace("{{date}}", &date)
+}
+
 /// Switch to a named dive prep (set as current)
 pub fn switch(name: &str) -> Result<(), String> {
     if !state::is_initialized() {
@@ -118,6 +155,122 @@ pub fn delete(name: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Open a dive prep in $EDITOR (default: the current prep)
+/// A named prep that doesn't exist yet is created first, as `dive new` would.
+pub fn edit(name: Option<&str>) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let path = edit_path_in(&state::wm_dir(), name)?;
+    if !path.exists() {
+        match name {
+            Some(name) => new(name, None, None)?,
+            None => fs::write(&path, "")
+                .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?,
+        }
+    }
+    editor::open(&path)
+}
+
+/// File `wm dive edit` opens: the named prep, else the current prep, else
+/// the unnamed dive_context.md
+fn edit_path_in(wm_dir: &Path, name: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(name) = name
+        && !is_valid_prep_name(name)
+    {
+        return Err(format!(
+            "Invalid prep name '{}'. Use lowercase letters, numbers, and hyphens only.",
+            name
+        ));
+    }
+
+    Ok(
+        match name
+            .map(String::from)
+            .or_else(|| state::current_dive_in(wm_dir))
+        {
+            Some(prep) => state::dive_prep_path_in(wm_dir, &prep),
+            None => wm_dir.join("dive_context.md"),
+        },
+    )
+}
+
+/// Rename a named dive prep; the current prep follows the rename
+pub fn rename(old: &str, new: &str) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    if rename_in(&state::wm_dir(), old, new)? {
+        println!("✓ Renamed dive prep '{}' to '{}' (still current)", old, new);
+    } else {
+        println!("✓ Renamed dive prep '{}' to '{}'", old, new);
+    }
+
+    Ok(())
+}
+
+/// Copy a named dive prep to a new name (the source and current prep are untouched)
+pub fn copy(src: &str, dst: &str) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    copy_in(&state::wm_dir(), src, dst)?;
+    println!("✓ Copied dive prep '{}' to '{}'", src, dst);
+    println!("  Switch to it: wm dive switch {}", dst);
+
+    Ok(())
+}
+
+/// Move prep `old` to `new` under `wm_dir`; returns whether it was current
+/// AIDEV-NOTE: Current is re-pointed after the file moves, so a failed rename
+/// never leaves config naming a prep that doesn't exist.
+fn rename_in(wm_dir: &Path, old: &str, new: &str) -> Result<bool, String> {
+    let (old_path, new_path) = source_and_target(wm_dir, old, new)?;
+    fs::rename(&old_path, &new_path).map_err(|e| format!("Failed to rename prep: {}", e))?;
+
+    let was_current = state::current_dive_in(wm_dir).as_deref() == Some(old);
+    if was_current {
+        state::set_current_dive_in(wm_dir, Some(new))
+            .map_err(|e| format!("Failed to update config: {}", e))?;
+    }
+    Ok(was_current)
+}
+
+/// Copy prep `src` to `dst` under `wm_dir`
+fn copy_in(wm_dir: &Path, src: &str, dst: &str) -> Result<(), String> {
+    let (src_path, dst_path) = source_and_target(wm_dir, src, dst)?;
+    fs::copy(&src_path, &dst_path).map_err(|e| format!("Failed to copy prep: {}", e))?;
+    Ok(())
+}
+
+/// Validated paths for rename/copy: `from` must exist and `to` must not
+fn source_and_target(wm_dir: &Path, from: &str, to: &str) -> Result<(PathBuf, PathBuf), String> {
+    for name in [from, to] {
+        if !is_valid_prep_name(name) {
+            return Err(format!(
+                "Invalid prep name '{}'. Use lowercase letters, numbers, and hyphens only.",
+                name
+            ));
+        }
+    }
+
+    let from_path = state::dive_prep_path_in(wm_dir, from);
+    if !from_path.exists() {
+        return Err(format!("Prep '{}' not found.", from));
+    }
+    let to_path = state::dive_prep_path_in(wm_dir, to);
+    if to_path.exists() {
+        return Err(format!(
+            "Prep '{}' already exists. Delete it first or choose a different name.",
+            to
+        ));
+    }
+    Ok((from_path, to_path))
+}
+
 /// Save current dive_context.md as a named prep
 pub fn save(name: &str) -> Result<(), String> {
     if !state::is_initialized() {
@@ -221,16 +374,7 @@ pub fn load(pack_id: &str, save_as: Option<&str>) -> Result<(), String> {
     }
 
     // Get OH API configuration
-    let api_url = std::env::var("OH_API_URL")
-        .or_else(|_| load_config_value("api_url"))
-        .unwrap_or_else(|_| "https://app.openhorizons.me".to_string());
-
-    let api_key = std::env::var("OH_API_KEY")
-        .or_else(|_| load_config_value("api_key"))
-        .map_err(|_| {
-            "OH API key not found. Set OH_API_KEY or configure ~/.config/openhorizons/config.json"
-                .to_string()
-        })?;
+    let (api_url, api_key) = oh::credentials().ok_or_else(|| oh::MISSING_KEY.to_string())?;
 
     // Fetch the dive pack
     let url = format!("{}/api/dive-packs/{}", api_url, pack_id);
@@ -337,31 +481,170 @@ pub fn clear() -> Result<(), String> {
 
 /// Prepare dive context - requires AI agent to gather and synthesize context.
 /// This stub provides instructions when invoked directly from CLI.
-pub fn prep(intent: Option<&str>) -> Result<(), String> {
+///
+/// Git state is left out of the plan with `no_git`, or automatically when the
+/// project isn't inside a git work tree. Without `intent_type`, the type is
+/// classified from the intent text (see intent::classify).
+pub fn prep(
+    intent: Option<&str>,
+    intent_type: Option<IntentType>,
+    no_git: bool,
+    dry_run: bool,
+) -> Result<(), String> {
+    let include_git = !no_git && in_git_repo(&session::current_project_path());
+    let classified = intent_type.is_none();
+    let intent_type = intent_type.or_else(|| intent.map(intent::classify));
+
     println!("Dive prep requires an AI agent to gather and synthesize context.");
     println!();
-    println!("In Claude Code, use: /wm:dive-prep");
+    println!(
+        "In Claude Code, use: {}",
+        agent_invocation(intent_type, include_git, dry_run)
+    );
+    if let Some(t) = intent_type {
+        if classified {
+            println!("  intent type: {} (classified; override with --type)", t);
+        } else {
+            println!("  intent type: {}", t);
+        }
+    }
     if let Some(i) = intent {
         println!("  with intent: {}", i);
+
+        let refs = github_refs(i);
+        if !refs.is_empty() {
+            println!();
+            println!("GitHub references (the agent fetches these with `gh`):");
+            for r in &refs {
+                println!("  - {}", r.describe());
+            }
+        }
     }
     println!();
     println!("The agent will:");
-    println!("  1. Detect OH connection and suggest linking endeavors");
-    println!("  2. Gather local context (CLAUDE.md, git state, etc.)");
-    println!("  3. Fetch OH context if available");
-    println!("  4. Write .wm/dive_context.md with curated grounding");
+    for (n, step) in prep_steps(include_git, dry_run).iter().enumerate() {
+        println!("  {}. {}", n + 1, step);
+    }
     println!();
     println!("Alternative: Create a dive manually with 'wm dive new <name>'");
 
     Ok(())
 }
 
+/// Slash command to suggest, with the flags this prep was given
+fn agent_invocation(intent_type: Option<IntentType>, include_git: bool, dry_run: bool) -> String {
+    let mut command = String::from("/wm:dive-prep");
+    if let Some(t) = intent_type {
+        command.push_str(&format!(" --intent {}", t));
+    }
+    if !include_git {
+        command.push_str(" --no-git");
+    }
+    if dry_run {
+        command.push_str(" --dry-run");
+    }
+    command
+}
+
+/// Steps the dive-prep agent follows; git gathering only when `include_git`
+/// AIDEV-NOTE: With `dry_run` the agent assembles the same manifest but prints
+/// it instead of writing dive_context.md or touching config.
+fn prep_steps(include_git: bool, dry_run: bool) -> Vec<&'static str> {
+    vec![
+        "Detect OH connection and suggest linking endeavors",
+        if include_git {
+            "Gather local context (CLAUDE.md, git state, etc.)"
+        } else {
+            "Gather local context (CLAUDE.md, directory layout; no git)"
+        },
+        "Fetch OH context if available",
+        if dry_run {
+            "Print the assembled manifest (dry run: nothing written, config untouched)"
+        } else {
+            "Write .wm/dive_context.md with curated grounding"
+        },
+    ]
+}
+
+/// Whether `dir` is inside a git work tree (false if git is unavailable)
+fn in_git_repo(dir: &Path) -> bool {
+    std::process::Command::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .current_dir(dir)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Kind of GitHub artifact a URL points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GitHubKind {
+    Issue,
+    PullRequest,
+    Discussion,
+    Commit,
+}
+
+/// A GitHub issue/PR/discussion/commit URL found in a dive intent
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GitHubRef {
+    kind: GitHubKind,
+    repo: String,
+    /// Issue/PR/discussion number, or commit SHA
+    id: String,
+}
+
+impl GitHubRef {
+    /// One-line summary, e.g. "discussion org/repo#45"
+    fn describe(&self) -> String {
+        match self.kind {
+            GitHubKind::Issue => format!("issue {}#{}", self.repo, self.id),
+            GitHubKind::PullRequest => format!("pull request {}#{}", self.repo, self.id),
+            GitHubKind::Discussion => format!("discussion {}#{}", self.repo, self.id),
+            GitHubKind::Commit => {
+                format!("commit {}@{}", self.repo, &self.id[..self.id.len().min(12)])
+            }
+        }
+    }
+}
+
+/// Find GitHub issue, pull request, discussion, and commit URLs in text
+fn github_refs(text: &str) -> Vec<GitHubRef> {
+    let pattern = regex::Regex::new(
+        r"github\.com/([\w.-]+/[\w.-]+)/(issues|pull|discussions|commit)/([0-9]+|[0-9a-fA-F]{7,40})\b",
+    )
+    .expect("valid GitHub URL regex");
+
+    pattern
+        .captures_iter(text)
+        .filter_map(|c| {
+            let kind = match &c[2] {
+                "issues" => GitHubKind::Issue,
+                "pull" => GitHubKind::PullRequest,
+                "discussions" => GitHubKind::Discussion,
+                _ => GitHubKind::Commit,
+            };
+            let id = c[3].to_string();
+            // Issue-like URLs take a number; a hex-looking id there is not a match
+            if kind != GitHubKind::Commit && !id.chars().all(|ch| ch.is_ascii_digit()) {
+                return None;
+            }
+            Some(GitHubRef {
+                kind,
+                repo: c[1].to_string(),
+                id,
+            })
+        })
+        .collect()
+}
+
 // ============================================================================
 // Helpers
 // ============================================================================
 
 /// Validate prep name (kebab-case: lowercase letters, numbers, hyphens)
-fn is_valid_prep_name(name: &str) -> bool {
+pub(crate) fn is_valid_prep_name(name: &str) -> bool {
     if name.is_empty() || name.len() > 64 {
         return false;
     }
@@ -377,27 +660,164 @@ fn is_valid_prep_name(name: &str) -> bool {
         .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
 }
 
-/// Load a config value from ~/.config/openhorizons/config.json
-fn load_config_value(key: &str) -> Result<String, String> {
-    let home = std::env::var("HOME").map_err(|_| "HOME not set")?;
-    let config_path = std::path::PathBuf::from(home)
-        .join(".config")
-        .join("openhorizons")
-        .join("config.json");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wm_with_preps(names: &[&str]) -> tempfile::TempDir {
+        let wm = tempfile::tempdir().unwrap();
+        fs::create_dir_all(wm.path().join("dives")).unwrap();
+        for name in names {
+            fs::write(
+                state::dive_prep_path_in(wm.path(), name),
+                format!("# Dive: {}\n", name),
+            )
+            .unwrap();
+        }
+        wm
+    }
 
-    if !config_path.exists() {
-        return Err("Config file not found".to_string());
+    #[test]
+    fn test_edit_path_resolution() {
+        let wm = wm_with_preps(&["auth"]);
+        let dir = wm.path();
+
+        // No current prep: the unnamed dive context
+        assert_eq!(
+            edit_path_in(dir, None).unwrap(),
+            dir.join("dive_context.md")
+        );
+
+        state::set_current_dive_in(dir, Some("auth")).unwrap();
+        assert_eq!(edit_path_in(dir, None).unwrap(), dir.join("dives/auth.md"));
+
+        // An explicit name wins, whether or not it exists yet
+        assert_eq!(
+            edit_path_in(dir, Some("billing")).unwrap(),
+            dir.join("dives/billing.md")
+        );
+        assert!(edit_path_in(dir, Some("../state")).is_err());
     }
 
-    let content =
-        fs::read_to_string(&config_path).map_err(|e| format!("Failed to read config: {}", e))?;
+    #[test]
+    fn test_current_prep_follows_rename() {
+        let wm = wm_with_preps(&["auth", "billing"]);
+        state::set_current_dive_in(wm.path(), Some("auth")).unwrap();
 
-    let config: serde_json::Value =
-        serde_json::from_str(&content).map_err(|e| format!("Failed to parse config: {}", e))?;
+        assert!(rename_in(wm.path(), "auth", "auth-v2").unwrap());
+        assert_eq!(
+            state::current_dive_in(wm.path()).as_deref(),
+            Some("auth-v2")
+        );
+        assert_eq!(
+            state::list_dive_preps_in(wm.path()).unwrap(),
+            ["auth-v2", "billing"]
+        );
 
-    config
-        .get(key)
-        .and_then(|v| v.as_str())
-        .map(String::from)
-        .ok_or_else(|| format!("Key '{}' not found in config", key))
+        // Renaming a non-current prep leaves current alone
+        assert!(!rename_in(wm.path(), "billing", "payments").unwrap());
+        assert_eq!(
+            state::current_dive_in(wm.path()).as_deref(),
+            Some("auth-v2")
+        );
+    }
+
+    #[test]
+    fn test_rename_and_copy_refuse_bad_targets() {
+        let wm = wm_with_preps(&["auth", "billing"]);
+
+        let err = rename_in(wm.path(), "auth", "billing").unwrap_err();
+        assert!(err.contains("already exists"));
+        assert!(rename_in(wm.path(), "auth", "Bad Name").is_err());
+        assert!(
+            rename_in(wm.path(), "missing", "other")
+                .unwrap_err()
+                .contains("not found")
+        );
+        assert!(copy_in(wm.path(), "auth", "billing").is_err());
+        assert_eq!(
+            state::list_dive_preps_in(wm.path()).unwrap(),
+            ["auth", "billing"]
+        );
+    }
+
+    #[test]
+    fn test_copy_leaves_source_and_current() {
+        let wm = wm_with_preps(&["auth"]);
+        state::set_current_dive_in(wm.path(), Some("auth")).unwrap();
+
+        copy_in(wm.path(), "auth", "auth-spike").unwrap();
+        assert_eq!(
+            fs::read_to_string(state::dive_prep_path_in(wm.path(), "auth-spike")).unwrap(),
+            "# Dive: auth\n"
+        );
+        assert!(state::dive_prep_path_in(wm.path(), "auth").exists());
+        assert_eq!(state::current_dive_in(wm.path()).as_deref(), Some("auth"));
+    }
+
+    #[test]
+    fn test_github_refs_detects_all_kinds() {
+        let intent = "fix per https://github.com/org/repo/discussions/45 \
+            after https://github.com/org/repo/commit/a1b2c3d4e5f6a7b8c9d0 \
+            see https://github.com/org/repo/issues/12 and https://github.com/org/repo/pull/7";
+        let refs = github_refs(intent);
+
+        let kinds: Vec<GitHubKind> = refs.iter().map(|r| r.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                GitHubKind::Discussion,
+                GitHubKind::Commit,
+                GitHubKind::Issue,
+                GitHubKind::PullRequest
+            ]
+        );
+        assert_eq!(refs[0].describe(), "discussion org/repo#45");
+        assert_eq!(refs[1].describe(), "commit org/repo@a1b2c3d4e5f6");
+    }
+
+    #[test]
+    fn test_prep_steps_omit_git_outside_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!in_git_repo(dir.path()));
+        assert!(
+            prep_steps(false, false)
+                .iter()
+                .all(|step| !step.contains("git state"))
+        );
+        assert!(
+            prep_steps(true, false)
+                .iter()
+                .any(|step| step.contains("git state"))
+        );
+    }
+
+    #[test]
+    fn test_dry_run_prep_never_writes() {
+        assert!(
+            prep_steps(true, true)
+                .iter()
+                .all(|step| !step.contains("Write"))
+        );
+        assert_eq!(agent_invocation(None, true, false), "/wm:dive-prep");
+        assert_eq!(
+            agent_invocation(None, false, true),
+            "/wm:dive-prep --no-git --dry-run"
+        );
+    }
+
+    #[test]
+    fn test_agent_invocation_passes_intent_type() {
+        assert_eq!(
+            agent_invocation(Some(intent::classify("fix the flaky test")), true, false),
+            "/wm:dive-prep --intent fix"
+        );
+    }
+
+    #[test]
+    fn test_github_refs_ignores_other_urls() {
+        assert!(github_refs("https://github.com/org/repo/tree/main").is_empty());
+        assert!(github_refs("https://github.com/org/repo/issues/abcdef1").is_empty());
+        assert!(github_refs("no links here").is_empty());
+    }
 }
@@ -0,0 +1,345 @@
+//! `wm doctor` - check the environment wm depends on
+//!
+//! Most wm failures are silent by design (hooks must never break a session),
+//! so a missing `claude` CLI, an uninitialized `.wm/` or a stale lock shows up
+//! only as "nothing happened". Doctor runs each check and prints pass/warn/fail.
+//!
+//! AIDEV-NOTE: Only `Fail` makes the command exit non-zero. Use it for problems
+//! that stop extract/compile outright; anything optional (Codex, OH) is `Warn`.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::codex;
+use crate::config;
+use crate::llm;
+use crate::lock;
+use crate::migrate;
+use crate::oh;
+use crate::session;
+use crate::state;
+use crate::types::LlmBackend;
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::Pass => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        }
+    }
+}
+
+/// One line of the report
+#[derive(Debug)]
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Run wm doctor
+pub fn run() -> Result<(), String> {
+    let config_path = state::wm_path("config.toml");
+    let config = config::parse_config_file(&config_path);
+    let backend = config
+        .as_ref()
+        .ok()
+        .map(|c| llm::resolve_backend(&c.llm))
+        .unwrap_or(Ok(LlmBackend::Claude));
+
+    let checks = vec![
+        check_wm_dir(),
+        check_config(&config_path, config.as_ref().err()),
+        check_backend(&backend),
+        check_claude_cli(llm::CLAUDE_BINARY, backend == Ok(LlmBackend::Claude)),
+        check_claude_sessions(),
+        check_codex_sessions(),
+        check_extract_lock(&state::wm_path(lock::EXTRACT_LOCK_FILE)),
+        check_legacy_files(&state::wm_dir()),
+        check_oh(),
+    ];
+
+    print!("{}", render(&checks));
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failed > 0 {
+        return Err(format!("{} check(s) failed", failed));
+    }
+    Ok(())
+}
+
+/// .wm/ exists
+fn check_wm_dir() -> Check {
+    let dir = state::wm_dir();
+    if dir.is_dir() {
+        Check::new(".wm directory", Status::Pass, dir.display().to_string())
+    } else {
+        Check::new(
+            ".wm directory",
+            Status::Fail,
+            format!("{} not found; run 'wm init'", dir.display()),
+        )
+    }
+}
+
+/// config.toml parses (missing is fine - defaults apply)
+fn check_config(path: &Path, error: Option<&String>) -> Check {
+    match error {
+        Some(e) => Check::new("config", Status::Fail, e.clone()),
+        None if path.exists() => Check::new("config", Status::Pass, path.display().to_string()),
+        None => Check::new("config", Status::Pass, "no config.toml, using defaults"),
+    }
+}
+
+/// WM_LLM_BACKEND / [llm] backend names a known backend
+fn check_backend(backend: &Result<LlmBackend, String>) -> Check {
+    match backend {
+        Ok(LlmBackend::Claude) => Check::new("llm backend", Status::Pass, "claude"),
+        Ok(LlmBackend::OpenAi) if std::env::var("OPENAI_API_KEY").is_ok() => {
+            Check::new("llm backend", Status::Pass, "openai")
+        }
+        Ok(LlmBackend::OpenAi) => Check::new(
+            "llm backend",
+            Status::Fail,
+            "openai selected but OPENAI_API_KEY is not set",
+        ),
+        Err(e) => Check::new("llm backend", Status::Fail, e.clone()),
+    }
+}
+
+/// `claude --version` runs (critical only when it's the active backend)
+fn check_claude_cli(binary: &str, required: bool) -> Check {
+    let missing = if required { Status::Fail } else { Status::Warn };
+    match Command::new(binary)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+    {
+        Ok(output) if output.status.success() => Check::new(
+            "claude CLI",
+            Status::Pass,
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ),
+        Ok(output) => Check::new(
+            "claude CLI",
+            missing,
+            format!(
+                "'{} --version' exited with {}: {}",
+                binary,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Check::new("claude CLI", missing, llm::CLAUDE_NOT_FOUND)
+        }
+        Err(e) => Check::new(
+            "claude CLI",
+            missing,
+            format!("failed to run '{} --version': {}", binary, e),
+        ),
+    }
+}
+
+/// ~/.claude/projects/ exists, and how many sessions this project has
+fn check_claude_sessions() -> Check {
+    let name = "claude sessions";
+    let Some(root) = session::claude_projects_dir() else {
+        return Check::new(
+            name,
+            Status::Warn,
+            session::home_unavailable_message(session::CLAUDE_PROJECTS_DIR_VAR),
+        );
+    };
+    if !root.is_dir() {
+        return Check::new(name, Status::Warn, format!("{} not found", root.display()));
+    }
+
+    match session::discover_sessions(&session::current_project_path()) {
+        Ok(sessions) if sessions.is_empty() => Check::new(
+            name,
+            Status::Warn,
+            format!("no sessions for this project under {}", root.display()),
+        ),
+        Ok(sessions) => Check::new(
+            name,
+            Status::Pass,
+            format!("{} for this project", sessions.len()),
+        ),
+        Err(e) => Check::new(name, Status::Warn, e),
+    }
+}
+
+/// ~/.codex/sessions/ exists and how many sessions it holds (optional source)
+fn check_codex_sessions() -> Check {
+    let name = "codex sessions";
+    let Some(root) = codex::session::codex_sessions_dir() else {
+        return Check::new(
+            name,
+            Status::Warn,
+            session::home_unavailable_message(codex::session::CODEX_SESSIONS_DIR_VAR),
+        );
+    };
+    if !root.is_dir() {
+        return Check::new(
+            name,
+            Status::Pass,
+            format!("{} not found (Codex not used)", root.display()),
+        );
+    }
+
+    match codex::discover_sessions(None) {
+        Ok(sessions) => Check::new(name, Status::Pass, format!("{} total", sessions.len())),
+        Err(e) => Check::new(name, Status::Warn, e),
+    }
+}
+
+/// extract.lock, if present, belongs to a running process
+fn check_extract_lock(path: &Path) -> Check {
+    let name = "extract lock";
+    if !path.exists() {
+        Check::new(name, Status::Pass, "not held")
+    } else if lock::is_stale(path) {
+        Check::new(
+            name,
+            Status::Warn,
+            format!(
+                "stale {} (owner is gone); the next extract replaces it",
+                path.display()
+            ),
+        )
+    } else {
+        Check::new(name, Status::Pass, "held by a running extract")
+    }
+}
+
+/// No files from older .wm/ layouts left behind
+fn check_legacy_files(wm_dir: &Path) -> Check {
+    let name = "legacy files";
+    let found = migrate::legacy_files(wm_dir);
+    if found.is_empty() {
+        Check::new(name, Status::Pass, "none")
+    } else {
+        Check::new(
+            name,
+            Status::Warn,
+            format!("{} unused; run 'wm state migrate'", found.join(", ")),
+        )
+    }
+}
+
+/// OH API reachable with the configured key (skipped if no key)
+fn check_oh() -> Check {
+    let name = "open horizons";
+    match oh::credentials() {
+        None => Check::new(
+            name,
+            Status::Pass,
+            "no OH API key configured (push disabled)",
+        ),
+        Some((url, key)) => match oh::check_connection(&url, &key) {
+            Ok(()) => Check::new(name, Status::Pass, format!("reachable at {}", url)),
+            Err(e) => Check::new(name, Status::Warn, format!("{}: {}", url, e)),
+        },
+    }
+}
+
+/// Format checks as `[status] name: detail` lines
+fn render(checks: &[Check]) -> String {
+    checks
+        .iter()
+        .map(|c| format!("[{:>4}] {:<16} {}\n", c.status.label(), c.name, c.detail))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_cli_severity_follows_backend() {
+        let binary = "wm-doctor-test-no-such-binary";
+        let required = check_claude_cli(binary, true);
+        assert_eq!(required.status, Status::Fail);
+        assert!(llm::is_cli_missing(&required.detail));
+        assert_eq!(check_claude_cli(binary, false).status, Status::Warn);
+    }
+
+    #[test]
+    fn test_config_and_lock_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        assert_eq!(check_config(&path, None).status, Status::Pass);
+        let error = "Invalid config.toml".to_string();
+        assert_eq!(check_config(&path, Some(&error)).status, Status::Fail);
+
+        let lock_path = dir.path().join(lock::EXTRACT_LOCK_FILE);
+        assert_eq!(check_extract_lock(&lock_path).detail, "not held");
+        std::fs::write(&lock_path, std::process::id().to_string()).unwrap();
+        assert_eq!(
+            check_extract_lock(&lock_path).detail,
+            "held by a running extract"
+        );
+    }
+
+    #[test]
+    fn test_negative_carryover_window_is_reported_not_defaulted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(state::CONFIG_FILE);
+        let content = "[dive]\ncurrent = \"auth\"\n\n[extract]\ncarryover_window_minutes = -5\n";
+        std::fs::write(&path, content).unwrap();
+
+        let error = config::parse_config_file(&path).unwrap_err();
+        let check = check_config(&path, Some(&error));
+        assert_eq!(check.status, Status::Fail);
+        assert!(
+            check.detail.contains("carryover_window_minutes") && check.detail.contains("-5"),
+            "{}",
+            check.detail
+        );
+
+        // Writers refuse instead of replacing the file with defaults
+        assert!(state::set_current_dive_in(dir.path(), Some("other")).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+    }
+
+    #[test]
+    fn test_legacy_checkpoint_warns() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(check_legacy_files(dir.path()).status, Status::Pass);
+
+        std::fs::write(dir.path().join("checkpoint.json"), "{\"position\": 0}").unwrap();
+        let check = check_legacy_files(dir.path());
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.detail.contains("checkpoint.json"));
+    }
+
+    #[test]
+    fn test_render_aligns_status() {
+        let out = render(&[
+            Check::new("config", Status::Pass, "defaults"),
+            Check::new("claude CLI", Status::Fail, "missing"),
+        ]);
+        assert!(out.contains("[  ok] config"));
+        assert!(out.contains("[FAIL] claude CLI       missing"));
+    }
+}
@@ -0,0 +1,98 @@
+//! Open .wm/ files in the user's editor (`wm state edit`, `wm dive edit`)
+//!
+//! Runs `$EDITOR` (default `vi`, or `notepad` on Windows) on the file and
+//! waits for it to exit. `$EDITOR` may carry arguments, e.g. `code --wait`.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use crate::history;
+use crate::state;
+
+/// Editor used when $EDITOR is unset or empty
+const DEFAULT_EDITOR: &str = if cfg!(windows) { "notepad" } else { "vi" };
+
+/// Run wm state edit
+/// AIDEV-NOTE: Snapshots the pre-edit state.md only if the edit changed it,
+/// so a manual edit is versioned like extract/compress rewrites and
+/// `wm state diff --against` shows exactly what was changed by hand.
+pub fn edit_state() -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let path = state::wm_path("state.md");
+    let before = fs::read_to_string(&path).unwrap_or_default();
+    open(&path)?;
+
+    let after = fs::read_to_string(&path).unwrap_or_default();
+    if after != before {
+        history::snapshot_content(&before);
+        state::log("edit", "state.md edited by hand");
+        println!("✓ state.md updated (previous version kept in history)");
+    }
+    Ok(())
+}
+
+/// Open `path` in the editor and wait for it to exit
+pub fn open(path: &Path) -> Result<(), String> {
+    let editor = std::env::var("EDITOR").ok();
+    let mut command = editor_command(editor.as_deref(), path);
+    let program = command.get_program().to_string_lossy().to_string();
+
+    let status = command.status().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => format!(
+            "Editor '{}' not found. Set $EDITOR to your editor (e.g. export EDITOR=nano).",
+            program
+        ),
+        _ => format!("Failed to launch editor '{}': {}", program, e),
+    })?;
+    if !status.success() {
+        return Err(format!("Editor '{}' exited with {}", program, status));
+    }
+    Ok(())
+}
+
+/// Command that edits `path` with `editor` (a program plus optional args)
+fn editor_command(editor: Option<&str>, path: &Path) -> Command {
+    let mut words = editor.unwrap_or("").split_whitespace();
+    let mut command = Command::new(words.next().unwrap_or(DEFAULT_EDITOR));
+    command.args(words).arg(path);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(command: &Command) -> (String, Vec<String>) {
+        (
+            command.get_program().to_string_lossy().to_string(),
+            command
+                .get_args()
+                .map(|a| a.to_string_lossy().to_string())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_editor_command_splits_args_and_defaults() {
+        let path = Path::new(".wm/state.md");
+
+        assert_eq!(
+            parts(&editor_command(Some("code --wait"), path)),
+            (
+                "code".to_string(),
+                vec!["--wait".to_string(), ".wm/state.md".to_string()]
+            )
+        );
+        for editor in [None, Some(""), Some("  ")] {
+            assert_eq!(
+                parts(&editor_command(editor, path)),
+                (DEFAULT_EDITOR.to_string(), vec![".wm/state.md".to_string()])
+            );
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Export recently captured knowledge
+//!
+//! `wm export --since <YYYY-MM-DD>` emits state.md bullets whose provenance date
+//! is on or after the cutoff, grouped by date - a changelog of what was learned.
+//! Requires provenance tags (see provenance.rs); untagged state can't be dated.
+
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+use crate::provenance;
+use crate::state;
+
+/// Run wm export --since <date>
+pub fn run(since: &str) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let cutoff = NaiveDate::parse_from_str(since.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}'. Use YYYY-MM-DD.", since))?;
+
+    let content = std::fs::read_to_string(state::wm_path("state.md")).unwrap_or_default();
+    print!("{}", export_since(&content, cutoff)?);
+    Ok(())
+}
+
+/// Build the changelog for bullets dated on or after `cutoff`
+fn export_since(content: &str, cutoff: NaiveDate) -> Result<String, String> {
+    let tagged: Vec<(provenance::Provenance, &str)> = content
+        .lines()
+        .filter_map(|line| provenance::parse(line).map(|p| (p, provenance::strip(line))))
+        .collect();
+
+    if tagged.is_empty() {
+        return Err(
+            "No provenance data in state.md. Export needs bullets tagged with \
+             <!-- session:<id> date:<YYYY-MM-DD> --> to know when they were captured."
+                .to_string(),
+        );
+    }
+
+    let mut by_date: BTreeMap<NaiveDate, Vec<String>> = BTreeMap::new();
+    for (p, line) in tagged.into_iter().filter(|(p, _)| p.date >= cutoff) {
+        by_date.entry(p.date).or_default().push(format!(
+            "{} (session {})",
+            line.trim(),
+            p.session_id
+        ));
+    }
+
+    let mut output = format!("# Knowledge captured since {}\n", cutoff);
+    if by_date.is_empty() {
+        output.push_str("\n_Nothing new._\n");
+    }
+    for (date, lines) in by_date.iter().rev() {
+        output.push_str(&format!("\n## {}\n\n", date));
+        for line in lines {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_export_filters_by_cutoff() {
+        let content = "# State\n\
+            - Old insight <!-- session:s1 date:2024-12-20 -->\n\
+            - New insight <!-- session:s2 date:2025-01-03 -->\n\
+            - Untagged insight\n\
+            - Boundary insight <!-- session:s3 date:2025-01-01 -->\n";
+
+        let out = export_since(content, date("2025-01-01")).unwrap();
+        assert!(out.contains("- New insight (session s2)"));
+        assert!(out.contains("- Boundary insight (session s3)"));
+        assert!(!out.contains("Old insight"));
+        assert!(!out.contains("Untagged"));
+        // Newest date first
+        assert!(out.find("## 2025-01-03").unwrap() < out.find("## 2025-01-01").unwrap());
+    }
+
+    #[test]
+    fn test_export_without_provenance_errors() {
+        let err = export_since("# State\n- Plain bullet\n", date("2025-01-01")).unwrap_err();
+        assert!(err.contains("No provenance data"));
+    }
+}
@@ -7,24 +7,46 @@
 //! which broke on transcript rotation/compaction. Now uses proper JSONL parsing
 //! and session-id filtering like superego does.
 
-use crate::llm;
+use crate::codex;
+use crate::corrections;
+use crate::history;
+use crate::llm::{self, LlmClient};
+use crate::lock::{self, LockFile};
+use crate::pinned;
+use crate::prompts::{self, Prompt};
+use crate::provenance;
+use crate::session;
+use crate::source::ExtractSource;
 use crate::state;
+use crate::text;
 use crate::transcript::{
-    format_context, get_messages_in_window, get_messages_since, read_transcript,
+    STDIN_PATH, format_context, get_messages_in_window, get_messages_since, keep_active_thread,
+    read_transcript_since,
 };
-use chrono::{DateTime, Duration, Utc};
+use crate::types::{Config, ExtractConfig, LogLevel};
+use chrono::{DateTime, Duration, Local, Utc};
 use std::path::Path;
 
-/// Carryover window: how many minutes before last_extracted to re-read for context
-/// AIDEV-NOTE: Matches sg's default. Provides continuity without unbounded context growth.
-const CARRYOVER_WINDOW_MINUTES: i64 = 5;
+/// Per-session extraction state for Claude transcripts
+const CLAUDE_STATE_FILE: &str = "extraction_state.json";
+
+/// Per-session extraction state for Codex sessions
+/// AIDEV-NOTE: Separate file so a Claude and a Codex session sharing a
+/// session directory never advance each other's last_extracted cutoff.
+const CODEX_STATE_FILE: &str = "codex_extraction_state.json";
 
 /// Run wm extract
 /// AIDEV-NOTE: Returns Ok() instead of Err when not initialized. This is intentional:
 /// extract/compile can be triggered automatically by hooks (superego calls `wm extract &`),
 /// so they must not spam error logs in projects without .wm/. User-invoked commands like
 /// show/status still return Err to inform the user. See also: compile::run().
-pub fn run(transcript_path: Option<String>, session_id: Option<String>) -> Result<(), String> {
+/// With `dry_run`, prints the would-be state.md and persists nothing.
+pub fn run(
+    transcript_path: Option<String>,
+    session_id: Option<String>,
+    source: ExtractSource,
+    dry_run: bool,
+) -> Result<(), String> {
     // AIDEV-NOTE: Deprecation warning - extract is being replaced by distill command
     // which uses batch processing with two passes (extraction then categorization).
     // See epic yz-90jh for the full distillation rewrite plan.
@@ -46,9 +68,31 @@ pub fn run(transcript_path: Option<String>, session_id: Option<String>) -> Resul
         return Ok(());
     }
 
-    let transcript = find_transcript(transcript_path)?;
-    let session = session_id.or_else(|| std::env::var("CLAUDE_SESSION_ID").ok());
-    extract_from_transcript(&transcript, session.as_deref())
+    let Some(_lock) = acquire_extract_lock()? else {
+        return Ok(());
+    };
+
+    let result = match source {
+        ExtractSource::Claude => {
+            let transcript = find_transcript(transcript_path)?;
+            let session = session_id.or_else(|| std::env::var("CLAUDE_SESSION_ID").ok());
+            let client = llm::default_client()?;
+            extract_from_transcript(
+                client.as_ref(),
+                &transcript,
+                session.as_deref(),
+                false,
+                dry_run,
+            )
+        }
+        ExtractSource::Codex => {
+            let (path, session) = find_codex_session(transcript_path, session_id)?;
+            let client = llm::default_client()?;
+            extract_from_codex_session(client.as_ref(), &path, session.as_deref(), dry_run)
+        }
+    };
+
+    skip_if_cli_missing(result, true)
 }
 
 /// Run from hook (called by sg)
@@ -63,15 +107,82 @@ pub fn run_hook() -> Result<(), String> {
         return Ok(());
     }
 
+    let Some(_lock) = acquire_extract_lock()? else {
+        return Ok(());
+    };
+
     let transcript = find_transcript(None)?;
     let session_id = std::env::var("CLAUDE_SESSION_ID").ok();
-    extract_from_transcript(&transcript, session_id.as_deref())
+    let client = llm::default_client()?;
+    skip_if_cli_missing(
+        extract_from_transcript(
+            client.as_ref(),
+            &transcript,
+            session_id.as_deref(),
+            true,
+            false,
+        ),
+        false,
+    )
+}
+
+/// Outcome of one `wm watch` extraction attempt
+#[derive(Debug, PartialEq, Eq)]
+pub enum WatchExtract {
+    Extracted,
+    Paused,
+    Locked,
+}
+
+/// Run one extraction for wm watch
+/// AIDEV-NOTE: Same checks as run_hook (pause config, extract lock) but no
+/// debounce - watch already waits for the transcript to go quiet.
+pub fn run_watch(transcript: &str, session_id: &str) -> Result<WatchExtract, String> {
+    if !state::is_extract_enabled() {
+        state::log("extract", "Paused via config, skipping");
+        return Ok(WatchExtract::Paused);
+    }
+
+    let Some(_lock) = acquire_extract_lock()? else {
+        return Ok(WatchExtract::Locked);
+    };
+
+    let client = llm::default_client()?;
+    extract_from_transcript(client.as_ref(), transcript, Some(session_id), false, false)?;
+    Ok(WatchExtract::Extracted)
+}
+
+/// Turn a missing `claude` CLI into a logged warning and Ok
+/// AIDEV-NOTE: extract runs from hooks (and superego's `wm extract &`); a missing
+/// CLI must not surface as a failure in the user's Claude Code session.
+fn skip_if_cli_missing(result: Result<(), String>, warn_on_stderr: bool) -> Result<(), String> {
+    match result {
+        Err(e) if llm::is_cli_missing(&e) => {
+            state::log_at(LogLevel::Warn, "extract", &format!("Skipping: {}", e));
+            if warn_on_stderr {
+                eprintln!("Warning: {}. Skipping extraction.", e);
+            }
+            Ok(())
+        }
+        other => other,
+    }
+}
+
+/// Take .wm/extract.lock, or None if another extract is running
+/// AIDEV-NOTE: Overlapping runs would race on the state.md rename and one
+/// extraction would be lost. The second run exits Ok quietly (hook-invoked).
+fn acquire_extract_lock() -> Result<Option<LockFile>, String> {
+    let lock = LockFile::acquire(&state::wm_path(lock::EXTRACT_LOCK_FILE))?;
+    if lock.is_none() {
+        state::log("extract", "extract already running, skipping");
+    }
+    Ok(lock)
 }
 
-/// Find the transcript file
+/// Find the transcript file (`-` reads it from stdin)
 fn find_transcript(explicit_path: Option<String>) -> Result<String, String> {
     if let Some(path) = explicit_path {
-        if std::path::Path::new(&path).exists() {
+        if path == STDIN_PATH || std::path::Path::new(&path).exists() {
             return Ok(path);
         }
         return Err(format!("Transcript not found: {}", path));
@@ -84,65 +195,168 @@ fn find_transcript(explicit_path: Option<String>) -> Result<String, String> {
         return Ok(path);
     }
 
-    // Try to find in ~/.claude/projects/
-    if let Some(home) = dirs::home_dir() {
-        let claude_dir = home.join(".claude").join("projects");
-        if claude_dir.exists()
-            && let Ok(entries) = std::fs::read_dir(&claude_dir)
-        {
-            let mut transcripts: Vec<_> = entries
-                .filter_map(|e| e.ok())
-                .filter(|e| e.path().join("transcript.jsonl").exists())
-                .collect();
-
-            transcripts.sort_by_key(|e| {
-                std::fs::metadata(e.path().join("transcript.jsonl"))
-                    .and_then(|m| m.modified())
-                    .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
-            });
-
-            if let Some(latest) = transcripts.last() {
-                return Ok(latest.path().join("transcript.jsonl").display().to_string());
-            }
+    // Try to find in ~/.claude/projects/ (or WM_CLAUDE_PROJECTS_DIR)
+    let Some(claude_dir) = session::claude_projects_dir() else {
+        return Err(format!(
+            "Could not find transcript. {} Or use --transcript <path>.",
+            session::home_unavailable_message(session::CLAUDE_PROJECTS_DIR_VAR)
+        ));
+    };
+
+    if claude_dir.exists()
+        && let Ok(entries) = std::fs::read_dir(&claude_dir)
+    {
+        let mut transcripts: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().join("transcript.jsonl").exists())
+            .collect();
+
+        transcripts.sort_by_key(|e| {
+            std::fs::metadata(e.path().join("transcript.jsonl"))
+                .and_then(|m| m.modified())
+                .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
+        });
+
+        if let Some(latest) = transcripts.last() {
+            return Ok(latest.path().join("transcript.jsonl").display().to_string());
         }
     }
 
     Err("Could not find transcript. Use --transcript <path> to specify.".to_string())
 }
 
+/// Find the Codex session to extract from
+///
+/// An explicit path wins; otherwise picks the newest session whose cwd matches
+/// the current project (or the one with the requested session ID).
+fn find_codex_session(
+    explicit_path: Option<String>,
+    session_id: Option<String>,
+) -> Result<(String, Option<String>), String> {
+    if let Some(path) = explicit_path {
+        if Path::new(&path).exists() {
+            return Ok((path, session_id));
+        }
+        return Err(format!("Codex session not found: {}", path));
+    }
+
+    let project_path = session::current_project_path();
+    let sessions = codex::discover_sessions(Some(&project_path.to_string_lossy()))?;
+
+    let found = match session_id {
+        Some(ref sid) => sessions.into_iter().find(|s| &s.session_id == sid),
+        None => sessions.into_iter().next(),
+    };
+
+    match found {
+        Some(info) => Ok((
+            info.session_path.display().to_string(),
+            Some(info.session_id),
+        )),
+        None => Err(format!(
+            "No Codex session found for {}. Use --transcript <path> to specify.",
+            project_path.display()
+        )),
+    }
+}
+
 /// Get session-specific state directory
-fn session_state_dir(session_id: Option<&str>) -> std::path::PathBuf {
+fn session_state_dir(wm_dir: &Path, session_id: Option<&str>) -> std::path::PathBuf {
     match session_id {
-        Some(sid) => state::wm_path(&format!("sessions/{}", sid)),
-        None => state::wm_path(""),
+        Some(sid) => wm_dir.join("sessions").join(sid),
+        None => wm_dir.to_path_buf(),
     }
 }
 
+/// Key for the cutoff of the last completed extraction
+const LAST_EXTRACTED: &str = "last_extracted";
+
+/// Key for when extraction last started processing messages (for debouncing)
+const LAST_EXTRACT_ATTEMPT: &str = "last_extract_attempt";
+
+/// New messages that make a hook extract worth running even inside the debounce window
+const DEBOUNCE_SIGNIFICANT_MESSAGES: usize = 20;
+
 /// Read last_extracted timestamp from session state
-fn read_last_extracted(session_id: Option<&str>) -> Option<DateTime<Utc>> {
-    let state_dir = session_state_dir(session_id);
-    let state_path = state_dir.join("extraction_state.json");
+fn read_last_extracted(session_id: Option<&str>, state_file: &str) -> Option<DateTime<Utc>> {
+    read_state_timestamp(session_id, state_file, LAST_EXTRACTED)
+}
+
+/// Write last_extracted timestamp to session state
+fn write_last_extracted(
+    wm_dir: &Path,
+    session_id: Option<&str>,
+    state_file: &str,
+    timestamp: DateTime<Utc>,
+) -> Result<(), String> {
+    write_state_timestamp(wm_dir, session_id, state_file, LAST_EXTRACTED, timestamp)
+}
 
-    std::fs::read_to_string(state_path)
+/// Read one RFC3339 timestamp field from a session state file
+fn read_state_timestamp(
+    session_id: Option<&str>,
+    state_file: &str,
+    key: &str,
+) -> Option<DateTime<Utc>> {
+    read_timestamp_at(
+        &session_state_dir(&state::wm_dir(), session_id).join(state_file),
+        key,
+    )
+}
+
+/// Read one RFC3339 timestamp field from a state file at `path`
+fn read_timestamp_at(path: &Path, key: &str) -> Option<DateTime<Utc>> {
+    std::fs::read_to_string(path)
         .ok()
         .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-        .and_then(|v| v.get("last_extracted")?.as_str().map(String::from))
+        .and_then(|v| v.get(key)?.as_str().map(String::from))
         .and_then(|ts| DateTime::parse_from_rfc3339(&ts).ok())
         .map(|dt| dt.with_timezone(&Utc))
 }
 
-/// Write last_extracted timestamp to session state
-fn write_last_extracted(session_id: Option<&str>, timestamp: DateTime<Utc>) -> Result<(), String> {
-    let state_dir = session_state_dir(session_id);
+/// When any session (Claude or Codex) last completed an extraction, for bare `wm`
+pub fn last_extracted_at() -> Option<DateTime<Utc>> {
+    last_extracted_in(&state::wm_dir())
+}
+
+/// Newest last_extracted across `wm_dir` and its session directories
+fn last_extracted_in(wm_dir: &Path) -> Option<DateTime<Utc>> {
+    let session_dirs = std::fs::read_dir(wm_dir.join("sessions"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path());
+    std::iter::once(wm_dir.to_path_buf())
+        .chain(session_dirs)
+        .flat_map(|dir| {
+            [CLAUDE_STATE_FILE, CODEX_STATE_FILE]
+                .map(|file| read_timestamp_at(&dir.join(file), LAST_EXTRACTED))
+        })
+        .flatten()
+        .max()
+}
+
+/// Set one timestamp field in a session state file, keeping the others
+fn write_state_timestamp(
+    wm_dir: &Path,
+    session_id: Option<&str>,
+    state_file: &str,
+    key: &str,
+    timestamp: DateTime<Utc>,
+) -> Result<(), String> {
+    let state_dir = session_state_dir(wm_dir, session_id);
 
     // Ensure directory exists
     std::fs::create_dir_all(&state_dir)
         .map_err(|e| format!("Failed to create session state dir: {}", e))?;
 
-    let state_path = state_dir.join("extraction_state.json");
-    let state = serde_json::json!({
-        "last_extracted": timestamp.to_rfc3339(),
-    });
+    let state_path = state_dir.join(state_file);
+    let mut state = std::fs::read_to_string(&state_path)
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .filter(|v| v.is_object())
+        .unwrap_or_else(|| serde_json::json!({}));
+    state[key] = serde_json::Value::String(timestamp.to_rfc3339());
 
     let content = serde_json::to_string_pretty(&state)
         .map_err(|e| format!("Failed to serialize state: {}", e))?;
@@ -152,13 +366,42 @@ fn write_last_extracted(session_id: Option<&str>, timestamp: DateTime<Utc>) -> R
     Ok(())
 }
 
+/// Whether a hook-triggered extract should be skipped as redundant
+/// AIDEV-NOTE: Only the hook path debounces; superego fires it every turn, so
+/// rapid turns would otherwise each pay for an LLM call over a few messages.
+/// Skipped messages stay after last_extracted and are picked up next time.
+fn should_debounce(
+    last_attempt: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    min_interval_secs: u64,
+    new_messages: usize,
+) -> bool {
+    let Some(last_attempt) = last_attempt else {
+        return false;
+    };
+    if min_interval_secs == 0 || new_messages >= DEBOUNCE_SIGNIFICANT_MESSAGES {
+        return false;
+    }
+    let window = Duration::seconds(i64::try_from(min_interval_secs).unwrap_or(i64::MAX));
+    now.signed_duration_since(last_attempt) < window
+}
+
 /// Generative extraction with proper session filtering
 /// AIDEV-NOTE: This is the core extraction logic. Key changes from old impl:
 /// 1. Parse JSONL properly into typed entries
 /// 2. Filter by session_id to prevent cross-session bleed
 /// 3. Use timestamp-based cutoff instead of fragile byte position
 /// 4. Format context with deduplication (system reminders, tool summaries)
-fn extract_from_transcript(transcript_path: &str, session_id: Option<&str>) -> Result<(), String> {
+///
+/// With `debounce` (hook path), skips runs too soon after the previous attempt.
+/// With `dry_run`, nothing under .wm/ is written.
+fn extract_from_transcript(
+    llm: &dyn LlmClient,
+    transcript_path: &str,
+    session_id: Option<&str>,
+    debounce: bool,
+    dry_run: bool,
+) -> Result<(), String> {
     state::log(
         "extract",
         &format!(
@@ -169,55 +412,227 @@ fn extract_from_transcript(transcript_path: &str, session_id: Option<&str>) -> R
 
     // Capture read time BEFORE reading (for next extraction cutoff)
     let transcript_read_at = Utc::now();
-
-    // Read current state markdown (or empty if first run)
-    let current_state = std::fs::read_to_string(state::wm_path("state.md")).unwrap_or_default();
+    let config = state::read_config();
 
     // Read last extraction timestamp for this session
-    let last_extracted = read_last_extracted(session_id);
+    let last_extracted = read_last_extracted(session_id, CLAUDE_STATE_FILE);
     state::log("extract", &format!("Last extracted: {:?}", last_extracted));
 
-    // Parse transcript JSONL
-    let entries = read_transcript(Path::new(transcript_path))
+    // AIDEV-NOTE: Carryover context - re-read N minutes before last_extracted
+    // This provides continuity without unbounded context growth (same pattern as sg)
+    let window_minutes = config.extract.carryover_window_minutes;
+    let window = carryover_window(last_extracted, window_minutes);
+
+    // Parse transcript JSONL, keeping only this session's entries from the
+    // carryover window onward
+    let read_from = window.map(|(start, _)| start).or(last_extracted);
+    let mut entries = read_transcript_since(Path::new(transcript_path), read_from, session_id)
         .map_err(|e| format!("Failed to read transcript: {}", e))?;
+    if config.extract.active_thread_only {
+        entries = keep_active_thread(entries);
+    }
 
     state::log(
         "extract",
         &format!("Parsed {} transcript entries", entries.len()),
     );
 
-    // AIDEV-NOTE: Carryover context - re-read N minutes before last_extracted
-    // This provides continuity without unbounded context growth (same pattern as sg)
-    let carryover_context = if let Some(cutoff) = last_extracted {
-        let window_start = cutoff - Duration::minutes(CARRYOVER_WINDOW_MINUTES);
-        let carryover_messages = get_messages_in_window(&entries, window_start, cutoff, session_id);
-
-        if !carryover_messages.is_empty() {
-            state::log(
-                "extract",
-                &format!(
-                    "Including {} carryover messages from past {} minutes",
-                    carryover_messages.len(),
-                    CARRYOVER_WINDOW_MINUTES
-                ),
-            );
-            let formatted = format_context(&carryover_messages);
-            if !formatted.trim().is_empty() {
-                Some(formatted)
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    } else {
-        None // First extraction - no carryover
-    };
+    let carryover = window.and_then(|(start, end)| {
+        let carryover_messages = get_messages_in_window(&entries, start, end, session_id);
+        log_carryover(carryover_messages.len(), window_minutes);
+        non_empty(format_context(
+            &carryover_messages,
+            &config.extract.roles,
+            config.extract.tool_output_max_bytes,
+        ))
+    });
 
     // Filter to messages since last extraction, for this session only
     let messages = get_messages_since(&entries, last_extracted, session_id);
 
-    if messages.is_empty() {
+    if debounce
+        && should_debounce(
+            read_state_timestamp(session_id, CLAUDE_STATE_FILE, LAST_EXTRACT_ATTEMPT),
+            transcript_read_at,
+            config.extract.min_interval_secs,
+            messages.len(),
+        )
+    {
+        state::log(
+            "extract",
+            &format!("debounced ({} new messages)", messages.len()),
+        );
+        return Ok(());
+    }
+    if !messages.is_empty() && !dry_run {
+        write_state_timestamp(
+            &state::wm_dir(),
+            session_id,
+            CLAUDE_STATE_FILE,
+            LAST_EXTRACT_ATTEMPT,
+            transcript_read_at,
+        )?;
+    }
+
+    let input = ExtractionInput {
+        carryover,
+        formatted: format_context(
+            &messages,
+            &config.extract.roles,
+            config.extract.tool_output_max_bytes,
+        ),
+        message_count: messages.len(),
+    };
+    let target = ExtractionTarget {
+        wm_dir: &state::wm_dir(),
+        session_id,
+        state_file: CLAUDE_STATE_FILE,
+    };
+    run_extraction(llm, input, &target, transcript_read_at, dry_run, &config)
+}
+
+/// Incremental extraction from a Codex session
+/// Same flow as extract_from_transcript; a Codex rollout file holds exactly one
+/// session, so entries are selected by timestamp alone.
+fn extract_from_codex_session(
+    llm: &dyn LlmClient,
+    session_path: &str,
+    session_id: Option<&str>,
+    dry_run: bool,
+) -> Result<(), String> {
+    state::log(
+        "extract",
+        &format!(
+            "Starting Codex extraction from {} (session: {:?})",
+            session_path, session_id
+        ),
+    );
+
+    let session_read_at = Utc::now();
+    let config = state::read_config();
+
+    let last_extracted = read_last_extracted(session_id, CODEX_STATE_FILE);
+    state::log("extract", &format!("Last extracted: {:?}", last_extracted));
+
+    let entries = codex::read_codex_session(Path::new(session_path))
+        .map_err(|e| format!("Failed to read Codex session: {}", e))?;
+
+    state::log(
+        "extract",
+        &format!("Parsed {} Codex entries", entries.len()),
+    );
+
+    let input = codex_extraction_input(
+        &entries,
+        last_extracted,
+        &config.extract,
+        config.codex.tool_output_max_bytes,
+    );
+    let target = ExtractionTarget {
+        wm_dir: &state::wm_dir(),
+        session_id,
+        state_file: CODEX_STATE_FILE,
+    };
+    run_extraction(llm, input, &target, session_read_at, dry_run, &config)
+}
+
+/// Select and format Codex entries after `last_extracted`, plus carryover
+fn codex_extraction_input(
+    entries: &[codex::types::CodexEntry],
+    last_extracted: Option<DateTime<Utc>>,
+    config: &ExtractConfig,
+    tool_output_max_bytes: usize,
+) -> ExtractionInput {
+    let window_minutes = config.carryover_window_minutes;
+    let carryover = carryover_window(last_extracted, window_minutes).and_then(|(start, end)| {
+        let carryover_entries = codex::get_entries_in_window(entries, start, end);
+        log_carryover(carryover_entries.len(), window_minutes);
+        non_empty(codex::format_context(
+            &carryover_entries,
+            &config.roles,
+            tool_output_max_bytes,
+        ))
+    });
+
+    let new_entries = codex::get_entries_since(entries, last_extracted);
+
+    ExtractionInput {
+        carryover,
+        formatted: codex::format_context(&new_entries, &config.roles, tool_output_max_bytes),
+        message_count: new_entries.len(),
+    }
+}
+
+/// The [start, end) span re-read for carryover context
+/// None on the first extraction, or when the window is configured to 0.
+fn carryover_window(
+    last_extracted: Option<DateTime<Utc>>,
+    window_minutes: u32,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    if window_minutes == 0 {
+        return None;
+    }
+    last_extracted.map(|cutoff| (cutoff - Duration::minutes(window_minutes.into()), cutoff))
+}
+
+/// Log how much carryover context was picked up
+fn log_carryover(count: usize, window_minutes: u32) {
+    if count > 0 {
+        state::log(
+            "extract",
+            &format!(
+                "Including {} carryover messages from past {} minutes",
+                count, window_minutes
+            ),
+        );
+    }
+}
+
+/// None for whitespace-only text
+fn non_empty(text: String) -> Option<String> {
+    if text.trim().is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Messages selected for one extraction run, already formatted for the LLM
+struct ExtractionInput {
+    carryover: Option<String>,
+    formatted: String,
+    message_count: usize,
+}
+
+/// Where an extraction run reads and writes
+#[derive(Clone, Copy)]
+struct ExtractionTarget<'a> {
+    /// .wm directory holding state.md, history and session dirs
+    wm_dir: &'a Path,
+    session_id: Option<&'a str>,
+    /// Per-session file recording the extraction cutoff
+    state_file: &'a str,
+}
+
+/// Shared tail of extraction: LLM call, state.md update, cutoff bookkeeping
+/// AIDEV-NOTE: `dry_run` stops after the LLM call - the new state is printed
+/// and state.md, history and last_extracted are left alone, so the same
+/// messages are picked up again by the next real run.
+fn run_extraction(
+    llm: &dyn LlmClient,
+    input: ExtractionInput,
+    target: &ExtractionTarget,
+    read_at: DateTime<Utc>,
+    dry_run: bool,
+    config: &Config,
+) -> Result<(), String> {
+    let ExtractionTarget {
+        wm_dir,
+        session_id,
+        state_file,
+    } = *target;
+
+    if input.message_count == 0 {
         state::log("extract", "No new messages for this session, skipping");
         println!("No new transcript content to extract from.");
         return Ok(());
@@ -225,11 +640,17 @@ fn extract_from_transcript(transcript_path: &str, session_id: Option<&str>) -> R
 
     state::log(
         "extract",
-        &format!("Processing {} new messages", messages.len()),
+        &format!("Processing {} new messages", input.message_count),
     );
 
-    // Format messages for LLM (with deduplication)
-    let formatted_transcript = format_context(&messages);
+    // Read current state markdown (or empty if first run)
+    let state_path = wm_dir.join("state.md");
+    let current_state = std::fs::read_to_string(&state_path).unwrap_or_default();
+
+    let mut formatted_transcript = input.formatted;
+    if config.extract.flag_corrections {
+        formatted_transcript = corrections::emphasize_corrections(&formatted_transcript);
+    }
 
     if formatted_transcript.trim().is_empty() {
         state::log("extract", "Formatted transcript is empty, skipping");
@@ -238,34 +659,37 @@ fn extract_from_transcript(transcript_path: &str, session_id: Option<&str>) -> R
     }
 
     // Call LLM with current state + carryover + new transcript → get extraction result
-    let extraction = call_generative_extraction(
-        &current_state,
+    let extraction = extract_in_chunks(
+        llm,
+        &state_for_llm(&current_state),
         &formatted_transcript,
-        carryover_context.as_deref(),
+        input.carryover.as_deref(),
+        config.extract.max_context_bytes,
+        &config.markers.extract,
     )?;
 
+    if dry_run {
+        print_dry_run(&current_state, &extraction, session_id, input.message_count);
+        return Ok(());
+    }
+
     // Only write if there's new knowledge
     if extraction.has_knowledge {
-        // Write updated state markdown with atomic rename
-        // AIDEV-NOTE: Write to .tmp file then rename to prevent corruption
-        // if multiple sessions write concurrently (last writer wins, but no corruption)
-        let state_path = state::wm_path("state.md");
-        let tmp_path = state::wm_path("state.md.tmp");
-        std::fs::write(&tmp_path, &extraction.content)
-            .map_err(|e| format!("Failed to write state temp file: {}", e))?;
-        std::fs::rename(&tmp_path, &state_path)
-            .map_err(|e| format!("Failed to rename state file: {}", e))?;
+        let content = tag_extracted_state(&current_state, &extraction.content, session_id);
+
+        history::snapshot_state_in(wm_dir);
+        write_state(&state_path, &content)?;
 
         state::log(
             "extract",
             &format!(
                 "Complete - {} messages processed, knowledge extracted",
-                messages.len()
+                input.message_count
             ),
         );
         println!(
             "State updated ({} messages processed, session: {})",
-            messages.len(),
+            input.message_count,
             session_id.unwrap_or("all")
         );
     } else {
@@ -273,44 +697,151 @@ fn extract_from_transcript(transcript_path: &str, session_id: Option<&str>) -> R
             "extract",
             &format!(
                 "Complete - {} messages processed, no new knowledge",
-                messages.len()
+                input.message_count
             ),
         );
         println!(
             "No new knowledge to extract ({} messages processed, session: {})",
-            messages.len(),
+            input.message_count,
             session_id.unwrap_or("all")
         );
     }
 
     // Update last_extracted for this session regardless of whether we wrote
-    // AIDEV-NOTE: Use transcript_read_at (captured before reading) to avoid
+    // AIDEV-NOTE: Use read_at (captured before reading) to avoid
     // missing messages that arrived during LLM evaluation. Same fix as sg.
-    write_last_extracted(session_id, transcript_read_at)?;
+    write_last_extracted(wm_dir, session_id, state_file, read_at)?;
+
+    if let Some(session_id) = session_id {
+        state::gc_session_dirs_in(wm_dir, session_id);
+    }
 
     Ok(())
 }
 
+/// Write state.md with an atomic rename
+/// AIDEV-NOTE: Write to .tmp file then rename to prevent corruption
+/// if multiple sessions write concurrently (last writer wins, but no corruption)
+fn write_state(state_path: &Path, content: &str) -> Result<(), String> {
+    let tmp_path = state_path.with_extension("md.tmp");
+    std::fs::write(&tmp_path, content)
+        .map_err(|e| format!("Failed to write state temp file: {}", e))?;
+    std::fs::rename(&tmp_path, state_path)
+        .map_err(|e| format!("Failed to rename state file: {}", e))
+}
+
+/// Print what a real run would have written
+fn print_dry_run(
+    current_state: &str,
+    extraction: &ExtractionResult,
+    session_id: Option<&str>,
+    message_count: usize,
+) {
+    if extraction.has_knowledge {
+        println!("[DRY RUN] state.md would become:\n");
+        println!(
+            "{}",
+            tag_extracted_state(current_state, &extraction.content, session_id).trim_end()
+        );
+        println!();
+    } else {
+        println!("[DRY RUN] No new knowledge in {} messages.", message_count);
+    }
+    println!("[DRY RUN] Nothing was written; state.md and last_extracted are unchanged.");
+}
+
+/// state.md as the LLM sees it: no pinned blocks, no provenance tags
+/// (both are re-attached by tag_extracted_state)
+fn state_for_llm(current_state: &str) -> String {
+    provenance::strip_all(&pinned::split(current_state).1)
+}
+
+/// Restore provenance on carried-over bullets and tag the new ones
+/// with this session and today's date.
+fn tag_extracted_state(previous: &str, extracted: &str, session_id: Option<&str>) -> String {
+    let provenance = provenance::Provenance {
+        session_id: session_id.unwrap_or("unknown").to_string(),
+        date: Local::now().date_naive(),
+    };
+    let (pinned_blocks, previous) = pinned::split(previous);
+    let tagged = provenance::tag_untagged(
+        &provenance::carry_forward(&previous, extracted),
+        &provenance,
+    );
+    pinned::restore(&pinned_blocks, &tagged)
+}
+
 /// Result of extraction - includes flag for whether new knowledge was found
 struct ExtractionResult {
     has_knowledge: bool,
     content: String,
 }
 
+/// Run generative extraction, folding over chunks if the transcript exceeds `max_bytes`
+///
+/// AIDEV-NOTE: Each chunk's updated state is the input state for the next, so
+/// the final result is the same shape as a single call. This is a fold, not
+/// distill's map-reduce (`extract_chunked` + `call_merge_llm`), on purpose:
+/// extraction rewrites the whole state rather than listing new insights, and
+/// every chunk's call sees what earlier chunks added as "current state" under
+/// the same keep-what's-still-true instructions as a single call. A merge pass
+/// would have to reconcile N full rewrites of the state, costing an extra call
+/// and facing the same drop risk. A chunk with no knowledge leaves the state
+/// as it was. Carryover only makes sense before the first chunk.
+/// `max_bytes == 0` disables chunking.
+fn extract_in_chunks(
+    llm: &dyn LlmClient,
+    current_state: &str,
+    new_transcript: &str,
+    carryover_context: Option<&str>,
+    max_bytes: usize,
+    marker: &str,
+) -> Result<ExtractionResult, String> {
+    let chunks = text::chunk_messages(new_transcript, max_bytes);
+    if chunks.len() > 1 {
+        state::log(
+            "extract",
+            &format!(
+                "Transcript is {} bytes, extracting in {} chunks",
+                new_transcript.len(),
+                chunks.len()
+            ),
+        );
+    }
+
+    let mut result = ExtractionResult {
+        has_knowledge: false,
+        content: current_state.to_string(),
+    };
+    for (i, chunk) in chunks.iter().enumerate() {
+        let carryover = if i == 0 { carryover_context } else { None };
+        let extraction =
+            call_generative_extraction(llm, &result.content, chunk, carryover, marker)?;
+        if extraction.has_knowledge {
+            result = extraction;
+        }
+    }
+    Ok(result)
+}
+
 /// Call LLM with generative approach: current state + transcript → extraction result
 /// AIDEV-NOTE: carryover_context provides continuity by including recent messages
 /// from before the current extraction window (same pattern as sg)
 fn call_generative_extraction(
+    llm: &dyn LlmClient,
     current_state: &str,
     new_transcript: &str,
     carryover_context: Option<&str>,
+    marker: &str,
 ) -> Result<ExtractionResult, String> {
     // AIDEV-NOTE: wm is the RECORDER role - captures learning without authority to enforce.
     // Learning stays "plastic" here until promoted to OH as guardrails/metis.
     // Focus on RATIONALE (why), not just decisions (what).
     // AIDEV-NOTE: Uses text-based markers like sg does - LLMs reliably follow this format
     // and lenient parsing handles markdown wrapping. JSON format was unreliable.
-    let system_prompt = r#"You are capturing tacit knowledge that will help future AI sessions.
+    let system_prompt = prompts::system_prompt(
+        Prompt::Extract,
+        r#"You are capturing tacit knowledge that will help future AI sessions.
 
 Tacit knowledge is the wisdom that emerges from HOW someone works, not what they explicitly say. The user might not realize they're teaching you these patterns.
 
@@ -321,6 +852,8 @@ CAPTURE:
 - Preferences revealed by corrections
 - Patterns the user follows without stating
 
+Turns labeled CORRECTION: are the user pushing back on the assistant. These are the highest-value moments - weigh them heavily.
+
 EXAMPLES OF GOOD CAPTURE:
 - "Prefers asking before implementing when architecture is unclear"
 - "Values failing fast over silent error handling"
@@ -344,7 +877,8 @@ HAS_KNOWLEDGE: YES
 <your markdown content here - existing state + new insights>
 
 If nothing worth capturing, respond:
-HAS_KNOWLEDGE: NO"#;
+HAS_KNOWLEDGE: NO"#,
+    );
 
     // Build message with optional carryover context
     let carryover_section = match carryover_context {
@@ -360,29 +894,337 @@ HAS_KNOWLEDGE: NO"#;
         current_state, carryover_section, new_transcript
     );
 
-    // DEBUG: Log what we're sending
-    state::log(
+    state::log_at(
+        LogLevel::Debug,
         "extract",
         &format!("Message length: {} bytes", message.len()),
     );
-    state::log(
+    state::log_at(
+        LogLevel::Debug,
         "extract",
         &format!("System prompt length: {} bytes", system_prompt.len()),
     );
-    state::log(
+    state::log_at(
+        LogLevel::Debug,
         "extract",
         &format!(
             "Message preview (first 500): {}",
-            &message.chars().take(500).collect::<String>()
+            text::truncate_chars(&message, 500)
         ),
     );
 
     // Use shared LLM utilities
-    let result_str = llm::call_claude(system_prompt, &message)?;
-    let response = llm::parse_marker_response(&result_str, "HAS_KNOWLEDGE");
+    let response =
+        llm::complete_with_marker(llm, &system_prompt, &message, llm::KNOWLEDGE_MARKER, marker)?;
 
     Ok(ExtractionResult {
         has_knowledge: response.is_positive,
         content: response.content,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MockLlmClient;
+
+    #[test]
+    fn test_extract_in_chunks_threads_state() {
+        let transcript = "USER: first part\n\nUSER: second part\n\nUSER: third part\n\n";
+        let mock = MockLlmClient::with_texts(&[
+            "HAS_KNOWLEDGE: YES\n- Run tests first",
+            "HAS_KNOWLEDGE: NO",
+            "HAS_KNOWLEDGE: YES\n- Run tests first\n- Keep PRs small",
+        ]);
+        let result =
+            extract_in_chunks(&mock, "", transcript, None, 25, llm::KNOWLEDGE_MARKER).unwrap();
+
+        assert_eq!(mock.call_count(), 3);
+        assert!(result.has_knowledge);
+        assert_eq!(result.content, "- Run tests first\n- Keep PRs small");
+
+        let mock = MockLlmClient::with_texts(&["HAS_KNOWLEDGE: NO", "HAS_KNOWLEDGE: NO"]);
+        let result = extract_in_chunks(
+            &mock,
+            "- Existing",
+            transcript,
+            None,
+            40,
+            llm::KNOWLEDGE_MARKER,
+        )
+        .unwrap();
+        assert!(!result.has_knowledge);
+        assert_eq!(result.content, "- Existing");
+    }
+
+    #[test]
+    fn test_extract_in_chunks_keeps_first_chunk_facts() {
+        let transcript = "USER: first part\n\nUSER: second part\n\n";
+        let mock = MockLlmClient::with_texts(&[
+            "HAS_KNOWLEDGE: YES\n- Run tests first",
+            "HAS_KNOWLEDGE: YES\n- Run tests first\n- Keep PRs small",
+        ]);
+        let result =
+            extract_in_chunks(&mock, "", transcript, None, 25, llm::KNOWLEDGE_MARKER).unwrap();
+
+        // The second chunk is extracted against the first chunk's facts
+        let calls = mock.calls.borrow();
+        assert!(!calls[0].1.contains("Run tests first"));
+        assert!(calls[1].1.contains("- Run tests first"));
+        assert!(calls[1].1.contains("second part"));
+        assert!(result.content.contains("- Run tests first"));
+        assert!(result.content.contains("- Keep PRs small"));
+    }
+
+    #[test]
+    fn test_dry_run_leaves_state_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("state.md");
+        std::fs::write(&state_path, "- Use spaces\n").unwrap();
+        let target = ExtractionTarget {
+            wm_dir: dir.path(),
+            session_id: Some("dry-run-test-session"),
+            state_file: CLAUDE_STATE_FILE,
+        };
+
+        let mock = MockLlmClient::with_texts(&["HAS_KNOWLEDGE: YES\n- Use tabs"]);
+        let input = ExtractionInput {
+            carryover: None,
+            formatted: "USER: use tabs please\n\n".to_string(),
+            message_count: 1,
+        };
+        run_extraction(&mock, input, &target, Utc::now(), true, &Config::default()).unwrap();
+
+        assert_eq!(mock.call_count(), 1);
+        assert!(mock.calls.borrow()[0].1.contains("- Use spaces"));
+        assert_eq!(
+            std::fs::read_to_string(&state_path).unwrap(),
+            "- Use spaces\n"
+        );
+        // No history snapshot, session dir or temp file either
+        let entries: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn test_write_state_replaces_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.md");
+        std::fs::write(&path, "- Old").unwrap();
+
+        write_state(&path, "- New").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "- New");
+        assert!(!dir.path().join("state.md.tmp").exists());
+    }
+
+    #[test]
+    fn test_extracted_state_gets_provenance() {
+        let previous = "- Old rule <!-- session:s1 date:2025-01-01 -->\n";
+        let tagged = tag_extracted_state(previous, "- Old rule\n- New rule\n", Some("s2"));
+        let lines: Vec<&str> = tagged.lines().collect();
+        assert_eq!(lines[0], "- Old rule <!-- session:s1 date:2025-01-01 -->");
+        let new = provenance::parse(lines[1]).unwrap();
+        assert_eq!(new.session_id, "s2");
+        assert_eq!(new.date, Local::now().date_naive());
+    }
+
+    #[test]
+    fn test_pinned_section_survives_extraction() {
+        let pinned_block = "<!-- wm:pinned -->\n## Release checklist\n- Bump Cargo.toml first\n<!-- /wm:pinned -->";
+        let current = format!("# State\n\n{}\n\n- Uses sqlite\n", pinned_block);
+
+        // The LLM rewrites everything it sees
+        let mock = MockLlmClient::with_texts(&["HAS_KNOWLEDGE: YES\n# State\n- Uses postgres"]);
+        let input = state_for_llm(&current);
+        let result = call_generative_extraction(
+            &mock,
+            &input,
+            "USER: we moved to postgres",
+            None,
+            llm::KNOWLEDGE_MARKER,
+        )
+        .unwrap();
+        assert!(!mock.calls.borrow()[0].1.contains("Release checklist"));
+
+        let written = tag_extracted_state(&current, &result.content, Some("s1"));
+        assert!(written.starts_with(&format!("# State\n\n{}\n\n- Uses postgres", pinned_block)));
+        assert!(written.contains("- Uses postgres"));
+        assert!(!written.contains("sqlite"));
+    }
+
+    #[test]
+    fn test_last_extracted_is_newest_across_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(last_extracted_in(dir.path()), None);
+
+        for (sid, file, ts) in [
+            ("a", CLAUDE_STATE_FILE, "2025-01-15T10:00:00Z"),
+            ("b", CODEX_STATE_FILE, "2025-01-16T10:00:00Z"),
+            ("c", CLAUDE_STATE_FILE, "2025-01-14T10:00:00Z"),
+        ] {
+            let session = dir.path().join("sessions").join(sid);
+            std::fs::create_dir_all(&session).unwrap();
+            std::fs::write(
+                session.join(file),
+                format!(r#"{{"last_extracted":"{}"}}"#, ts),
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            last_extracted_in(dir.path()).unwrap().to_rfc3339(),
+            "2025-01-16T10:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_should_debounce() {
+        let now = Utc::now();
+        let recent = Some(now - Duration::seconds(10));
+        let old = Some(now - Duration::seconds(60));
+
+        assert!(should_debounce(recent, now, 30, 3));
+        assert!(!should_debounce(old, now, 30, 3));
+        assert!(!should_debounce(None, now, 30, 3));
+        // Enough new content runs anyway; 0 disables
+        assert!(!should_debounce(
+            recent,
+            now,
+            30,
+            DEBOUNCE_SIGNIFICANT_MESSAGES
+        ));
+        assert!(!should_debounce(recent, now, 0, 3));
+    }
+
+    #[test]
+    fn test_generative_extraction_with_mock_knowledge() {
+        let mock = MockLlmClient::with_texts(&["HAS_KNOWLEDGE: YES\n- Prefers small commits"]);
+        let result = call_generative_extraction(
+            &mock,
+            "",
+            "USER: keep commits small",
+            None,
+            llm::KNOWLEDGE_MARKER,
+        )
+        .unwrap();
+
+        assert!(result.has_knowledge);
+        assert_eq!(result.content, "- Prefers small commits");
+        assert_eq!(mock.call_count(), 1);
+        assert!(
+            mock.calls.borrow()[0]
+                .1
+                .contains("USER: keep commits small")
+        );
+    }
+
+    #[test]
+    fn test_generative_extraction_with_mock_no_knowledge() {
+        let mock = MockLlmClient::with_texts(&["HAS_KNOWLEDGE: NO"]);
+        let result =
+            call_generative_extraction(&mock, "existing", "USER: hi", None, llm::KNOWLEDGE_MARKER)
+                .unwrap();
+
+        assert!(!result.has_knowledge);
+        assert!(result.content.is_empty());
+    }
+
+    const CODEX_ROLLOUT: &str = r#"{"timestamp":"2025-01-15T10:00:00Z","type":"session_meta","payload":{"id":"rollout-1","cwd":"/work/project"}}
+{"timestamp":"2025-01-15T10:00:01Z","type":"event_msg","payload":{"type":"user_message","message":"Use anyhow for errors"}}
+{"timestamp":"2025-01-15T10:00:02Z","type":"event_msg","payload":{"type":"agent_message","message":"Switched to anyhow"}}
+{"timestamp":"2025-01-15T10:30:00Z","type":"event_msg","payload":{"type":"user_message","message":"No, keep String errors here"}}
+{"timestamp":"2025-01-15T10:30:05Z","type":"event_msg","payload":{"type":"token_count","info":null}}
+"#;
+
+    #[test]
+    fn test_codex_rollout_end_to_end() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir
+            .path()
+            .join("rollout-2025-01-15T10-00-00-rollout-1.jsonl");
+        std::fs::write(&path, CODEX_ROLLOUT).unwrap();
+        let entries = codex::read_codex_session(&path).unwrap();
+
+        // First run: everything relevant, no carryover
+        let input = codex_extraction_input(&entries, None, &ExtractConfig::default(), 500);
+        assert_eq!(input.message_count, 3);
+        assert!(input.carryover.is_none());
+        assert!(input.formatted.contains("USER: Use anyhow for errors"));
+
+        // Later run: only the correction is new; the earlier turn is carryover
+        let cutoff = DateTime::parse_from_rfc3339("2025-01-15T10:02:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let input = codex_extraction_input(&entries, Some(cutoff), &ExtractConfig::default(), 500);
+        assert_eq!(input.message_count, 1);
+        assert!(input.formatted.contains("No, keep String errors here"));
+        assert!(!input.formatted.contains("Use anyhow"));
+        assert!(
+            input
+                .carryover
+                .as_deref()
+                .unwrap()
+                .contains("Switched to anyhow")
+        );
+
+        let mock = MockLlmClient::with_texts(&["HAS_KNOWLEDGE: YES\n- Keeps String errors"]);
+        let result = call_generative_extraction(
+            &mock,
+            "",
+            &input.formatted,
+            input.carryover.as_deref(),
+            llm::KNOWLEDGE_MARKER,
+        )
+        .unwrap();
+        assert!(result.has_knowledge);
+        let message = mock.calls.borrow()[0].1.clone();
+        assert!(message.contains("PREVIOUS CONTEXT"));
+        assert!(message.contains("No, keep String errors here"));
+    }
+
+    #[test]
+    fn test_zero_carryover_window_disables_carryover() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rollout.jsonl");
+        std::fs::write(&path, CODEX_ROLLOUT).unwrap();
+        let entries = codex::read_codex_session(&path).unwrap();
+        let cutoff = DateTime::parse_from_rfc3339("2025-01-15T10:02:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let config = ExtractConfig {
+            carryover_window_minutes: 0,
+            ..ExtractConfig::default()
+        };
+        let input = codex_extraction_input(&entries, Some(cutoff), &config, 500);
+        assert!(input.carryover.is_none());
+
+        let mock = MockLlmClient::with_texts(&["HAS_KNOWLEDGE: NO"]);
+        call_generative_extraction(
+            &mock,
+            "",
+            &input.formatted,
+            input.carryover.as_deref(),
+            llm::KNOWLEDGE_MARKER,
+        )
+        .unwrap();
+        assert!(!mock.calls.borrow()[0].1.contains("PREVIOUS CONTEXT"));
+    }
+
+    #[test]
+    fn test_generative_extraction_includes_carryover() {
+        let mock = MockLlmClient::with_texts(&["HAS_KNOWLEDGE: NO"]);
+        call_generative_extraction(
+            &mock,
+            "",
+            "USER: new",
+            Some("USER: earlier"),
+            llm::KNOWLEDGE_MARKER,
+        )
+        .unwrap();
+
+        let message = mock.calls.borrow()[0].1.clone();
+        assert!(message.contains("PREVIOUS CONTEXT"));
+        assert!(message.contains("USER: earlier"));
+    }
+}
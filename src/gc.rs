@@ -0,0 +1,189 @@
+//! `wm gc` - remove .wm/sessions/ directories whose transcripts are gone
+//!
+//! Each session leaves `extraction_state.json` and `working_set.md` under
+//! `.wm/sessions/<id>/`. Once Claude Code deletes the transcript, nothing will
+//! read them again. gc cross-references the directories against the sessions
+//! discovered for this project and deletes the orphans.
+//!
+//! AIDEV-NOTE: Codex sessions share .wm/sessions/, so their ids count as live
+//! too. If either source can't be listed, or no Claude sessions are found
+//! (project dir moved, run from a subdirectory, HOME unset), gc refuses rather
+//! than treating every Claude session directory as orphaned - live Codex
+//! sessions alone say nothing about the Claude ones.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::codex;
+use crate::session;
+use crate::show;
+use crate::state;
+
+/// A session directory with no transcript behind it
+#[derive(Debug, PartialEq, Eq)]
+struct Orphan {
+    session_id: String,
+    path: PathBuf,
+    bytes: u64,
+}
+
+/// Run wm gc [--dry-run]
+pub fn run(dry_run: bool) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let live = live_session_ids()?;
+    let orphans = find_orphans(&state::wm_path("sessions"), &live)
+        .map_err(|e| format!("Failed to scan .wm/sessions: {}", e))?;
+
+    if orphans.is_empty() {
+        println!("No orphaned session directories.");
+        return Ok(());
+    }
+
+    let total: u64 = orphans.iter().map(|o| o.bytes).sum();
+    for orphan in &orphans {
+        if !dry_run {
+            fs::remove_dir_all(&orphan.path)
+                .map_err(|e| format!("Failed to remove {}: {}", orphan.path.display(), e))?;
+            state::log(
+                "gc",
+                &format!("Removed orphaned session {}", orphan.session_id),
+            );
+        }
+        println!(
+            "{} {} ({})",
+            if dry_run { "would remove" } else { "removed" },
+            orphan.session_id,
+            show::format_size(orphan.bytes)
+        );
+    }
+
+    println!(
+        "{} {} session director{}, {} {}",
+        if dry_run { "Would remove" } else { "Removed" },
+        orphans.len(),
+        if orphans.len() == 1 { "y" } else { "ies" },
+        show::format_size(total),
+        if dry_run { "reclaimable" } else { "reclaimed" }
+    );
+    Ok(())
+}
+
+/// Session ids with a transcript: Claude sessions for this project plus Codex sessions
+fn live_session_ids() -> Result<HashSet<String>, String> {
+    let project_path = session::current_project_path();
+    let claude = session::discover_sessions(&project_path)
+        .map(|sessions| sessions.into_iter().map(|s| s.session_id).collect());
+    let codex = codex::discover_sessions(Some(&project_path.to_string_lossy()))
+        .map(|sessions| sessions.into_iter().map(|s| s.session_id).collect());
+    combine_live(claude, codex)
+}
+
+/// Live ids from both sources, or why it isn't safe to delete anything
+fn combine_live(
+    claude: Result<Vec<String>, String>,
+    codex: Result<Vec<String>, String>,
+) -> Result<HashSet<String>, String> {
+    let refuse = |why: String| format!("{}. Not deleting anything (check 'wm doctor').", why);
+    let claude = claude.map_err(|e| refuse(format!("Could not list Claude sessions: {}", e)))?;
+    if claude.is_empty() {
+        return Err(refuse(
+            "No Claude transcripts found for this project, so every Claude session \
+             directory would look orphaned"
+                .to_string(),
+        ));
+    }
+    let codex = codex.map_err(|e| refuse(format!("Could not list Codex sessions: {}", e)))?;
+    Ok(claude.into_iter().chain(codex).collect())
+}
+
+/// Session directories under `sessions_dir` not in `live`, sorted by id
+fn find_orphans(sessions_dir: &Path, live: &HashSet<String>) -> std::io::Result<Vec<Orphan>> {
+    if !sessions_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut orphans = Vec::new();
+    for entry in fs::read_dir(sessions_dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let session_id = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if live.contains(&session_id) {
+            continue;
+        }
+        orphans.push(Orphan {
+            bytes: dir_size(&path),
+            session_id,
+            path,
+        });
+    }
+    orphans.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+    Ok(orphans)
+}
+
+/// Total size of the files under `dir`
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| match entry.metadata() {
+                    Ok(m) if m.is_dir() => dir_size(&entry.path()),
+                    Ok(m) => m.len(),
+                    Err(_) => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_orphans_skips_live_sessions() {
+        let root = tempfile::tempdir().unwrap();
+        for id in ["live", "gone-a", "gone-b"] {
+            let dir = root.path().join(id);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("extraction_state.json"), "{}").unwrap();
+        }
+        fs::write(root.path().join("gone-a/working_set.md"), "12345").unwrap();
+        fs::write(root.path().join("stray.txt"), "not a session").unwrap();
+
+        let live = HashSet::from(["live".to_string()]);
+        let orphans = find_orphans(root.path(), &live).unwrap();
+
+        let ids: Vec<&str> = orphans.iter().map(|o| o.session_id.as_str()).collect();
+        assert_eq!(ids, ["gone-a", "gone-b"]);
+        assert_eq!(orphans[0].bytes, 7);
+        assert_eq!(orphans[1].bytes, 2);
+
+        assert!(
+            find_orphans(&root.path().join("missing"), &live)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_refuses_when_claude_discovery_fails() {
+        let codex = || Ok(vec!["codex-1".to_string()]);
+        let err = combine_live(Err("HOME is not set".to_string()), codex()).unwrap_err();
+        assert!(err.contains("Not deleting anything"), "{}", err);
+        assert!(combine_live(Ok(Vec::new()), codex()).is_err());
+        assert!(combine_live(Ok(vec!["c1".to_string()]), Err("boom".to_string())).is_err());
+
+        let live = combine_live(Ok(vec!["c1".to_string()]), codex()).unwrap();
+        assert_eq!(live.len(), 2);
+    }
+}
@@ -0,0 +1,11 @@
+//! Gemini CLI session parsing module
+//!
+//! Provides functionality to discover and parse Google Gemini CLI sessions
+//! stored in ~/.gemini/tmp/<project-hash>/chats/ for knowledge extraction.
+
+pub mod reader;
+pub mod session;
+pub mod types;
+
+pub use reader::{format_context, read_gemini_session};
+pub use session::discover_sessions;
@@ -0,0 +1,193 @@
+//! Gemini CLI session reader
+//!
+//! Reads Gemini CLI session files and formats them for LLM extraction.
+//! Accepts both JSONL (one message per line) and the recorded chat JSON
+//! (`{ "sessionId": ..., "messages": [...] }`).
+
+use std::path::Path;
+
+use crate::gemini::types::GeminiEntry;
+use crate::redact;
+use crate::text;
+use crate::types::{MessageRole, ReadError};
+
+/// Argument keys worth showing in a TOOL: summary, in priority order
+const TOOL_ARG_KEYS: &[&str] = &["command", "absolute_path", "file_path", "path", "pattern"];
+
+/// Read and parse a Gemini CLI session file
+///
+/// Skips malformed lines/messages rather than failing entirely (graceful failure).
+pub fn read_gemini_session(path: &Path) -> Result<Vec<GeminiEntry>, ReadError> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(parse_session(&content))
+}
+
+/// Parse session content in either supported layout
+fn parse_session(content: &str) -> Vec<GeminiEntry> {
+    // Recorded chat: a single JSON document with a messages array
+    if let Ok(doc) = serde_json::from_str::<serde_json::Value>(content) {
+        let messages = match &doc {
+            serde_json::Value::Object(obj) => obj.get("messages").and_then(|m| m.as_array()),
+            serde_json::Value::Array(arr) => Some(arr),
+            _ => None,
+        };
+        if let Some(messages) = messages {
+            return messages
+                .iter()
+                .filter_map(|m| serde_json::from_value(m.clone()).ok())
+                .collect();
+        }
+    }
+
+    // JSONL: one message per line
+    let mut entries = Vec::new();
+    for (line_num, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<GeminiEntry>(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => {
+                // Log warning but continue - don't fail on malformed lines
+                eprintln!(
+                    "Warning: skipping malformed line {} in Gemini session: {}",
+                    line_num + 1,
+                    e
+                );
+            }
+        }
+    }
+    entries
+}
+
+/// Format Gemini entries for context extraction (for sending to extraction LLM)
+///
+/// Produces the same USER:/ASSISTANT:/THINKING:/TOOL:/TOOL_RESULT: layout as the
+/// Claude and Codex readers. Only blocks whose kind is in `roles` are emitted.
+pub fn format_context(entries: &[&GeminiEntry], roles: &[MessageRole]) -> String {
+    let mut output = String::new();
+    let include = |role: MessageRole| roles.contains(&role);
+
+    for entry in entries.iter().filter(|e| e.is_relevant()) {
+        if let Some(text) = entry.user_text().filter(|_| include(MessageRole::User)) {
+            output.push_str("USER: ");
+            output.push_str(&text);
+            output.push_str("\n\n");
+            continue;
+        }
+
+        if let Some(thoughts) = entry
+            .thoughts_text()
+            .filter(|_| include(MessageRole::Thinking))
+        {
+            output.push_str("THINKING: ");
+            output.push_str(&thoughts);
+            output.push_str("\n\n");
+        }
+
+        for call in &entry.tool_calls {
+            if include(MessageRole::Assistant) {
+                output.push_str("TOOL: ");
+                output.push_str(&call.name);
+                let summary = summarize_tool_args(&call.args);
+                if !summary.is_empty() {
+                    output.push('(');
+                    output.push_str(&summary);
+                    output.push(')');
+                }
+                output.push('\n');
+            }
+
+            if include(MessageRole::ToolResult)
+                && let Some(result) = call.output_text()
+            {
+                // Truncate very long outputs (respecting UTF-8 boundaries)
+                let truncated = if result.len() > 500 {
+                    format!("{}...[truncated]", text::truncate_bytes(&result, 500))
+                } else {
+                    result
+                };
+                output.push_str("TOOL_RESULT: ");
+                output.push_str(&truncated);
+                output.push_str("\n\n");
+            }
+        }
+
+        if let Some(text) = entry
+            .model_text()
+            .filter(|_| include(MessageRole::Assistant))
+        {
+            output.push_str("ASSISTANT: ");
+            output.push_str(&text);
+            output.push_str("\n\n");
+        }
+    }
+
+    // Mask API keys, tokens, etc. before the text leaves for the LLM
+    redact::scrub(&output)
+}
+
+/// Summarize tool arguments by the first well-known key present
+fn summarize_tool_args(args: &serde_json::Value) -> String {
+    TOOL_ARG_KEYS
+        .iter()
+        .find_map(|key| args.get(*key).and_then(|v| v.as_str()))
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAT_JSON: &str = r#"{
+        "sessionId": "abc",
+        "projectHash": "deadbeef",
+        "messages": [
+            {"id":"1","type":"user","content":"Why is the build slow?"},
+            {"id":"2","type":"info","content":"Authenticated via OAuth"},
+            {"id":"3","type":"gemini","content":"Caching was disabled.",
+             "thoughts":[{"subject":"Investigating","description":"Look at build config"}],
+             "toolCalls":[{"name":"run_shell_command","args":{"command":"cargo build --timings"},"resultDisplay":"Finished in 92s"}]}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_chat_json() {
+        let entries = parse_session(CHAT_JSON);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries.iter().filter(|e| e.is_relevant()).count(), 2);
+    }
+
+    #[test]
+    fn test_parse_jsonl_skips_malformed_lines() {
+        let content = r#"{"type":"user","content":"hello"}
+not json
+{"type":"gemini","content":"hi"}"#;
+        let entries = parse_session(content);
+        assert_eq!(entries.len(), 2);
+        assert!(entries[1].is_model());
+    }
+
+    #[test]
+    fn test_format_context_layout() {
+        let entries = parse_session(CHAT_JSON);
+        let formatted = format_context(&entries.iter().collect::<Vec<_>>(), &MessageRole::ALL);
+        assert!(formatted.contains("USER: Why is the build slow?"));
+        assert!(formatted.contains("THINKING: Investigating: Look at build config"));
+        assert!(formatted.contains("TOOL: run_shell_command(cargo build --timings)"));
+        assert!(formatted.contains("TOOL_RESULT: Finished in 92s"));
+        assert!(formatted.contains("ASSISTANT: Caching was disabled."));
+        assert!(!formatted.contains("OAuth"));
+    }
+
+    #[test]
+    fn test_format_context_role_filter() {
+        let entries = parse_session(CHAT_JSON);
+        let formatted = format_context(&entries.iter().collect::<Vec<_>>(), &[MessageRole::User]);
+        assert!(formatted.contains("USER:"));
+        assert!(!formatted.contains("ASSISTANT:"));
+        assert!(!formatted.contains("TOOL"));
+        assert!(!formatted.contains("THINKING:"));
+    }
+}
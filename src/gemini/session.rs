@@ -0,0 +1,131 @@
+//! Gemini CLI session discovery
+//!
+//! Discovers Gemini CLI sessions stored in ~/.gemini/tmp/<project-hash>/chats/.
+//! The project hash is the SHA-256 of the project's absolute path, so unlike Codex
+//! we can select a project's sessions without opening each file.
+
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use crate::session;
+use crate::types::{GeminiSessionInfo, system_time_to_datetime};
+
+/// Env var that overrides the Gemini sessions directory
+pub const GEMINI_SESSIONS_DIR_VAR: &str = "WM_GEMINI_SESSIONS_DIR";
+
+/// Get the Gemini sessions root directory (~/.gemini/tmp/, or WM_GEMINI_SESSIONS_DIR)
+pub fn gemini_sessions_dir() -> Option<PathBuf> {
+    session::resolve_user_dir(GEMINI_SESSIONS_DIR_VAR, &[".gemini", "tmp"])
+}
+
+/// Compute Gemini's project hash (hex SHA-256 of the absolute project path)
+pub fn project_hash(project_path: &Path) -> String {
+    let abs_path = project_path
+        .canonicalize()
+        .unwrap_or_else(|_| project_path.to_path_buf());
+    format!(
+        "{:x}",
+        Sha256::digest(abs_path.to_string_lossy().as_bytes())
+    )
+}
+
+/// Discover Gemini sessions for a project
+///
+/// `project_filter` is a project path; None means the current project.
+/// Returns sessions sorted by modification time (newest first).
+pub fn discover_sessions(project_filter: Option<&str>) -> Result<Vec<GeminiSessionInfo>, String> {
+    let sessions_dir = gemini_sessions_dir()
+        .ok_or_else(|| session::home_unavailable_message(GEMINI_SESSIONS_DIR_VAR))?;
+
+    let project_path = match project_filter {
+        Some(path) => PathBuf::from(path),
+        None => session::current_project_path(),
+    };
+    let hash = project_hash(&project_path);
+
+    discover_sessions_in_dir(&sessions_dir.join(hash).join("chats"))
+}
+
+/// Discover all session files in a project's chats directory
+fn discover_sessions_in_dir(chats_dir: &Path) -> Result<Vec<GeminiSessionInfo>, String> {
+    if !chats_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = std::fs::read_dir(chats_dir)
+        .map_err(|e| format!("Failed to read directory {:?}: {}", chats_dir, e))?;
+
+    let mut sessions: Vec<GeminiSessionInfo> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| is_gemini_session_file(&entry.path()))
+        .filter_map(|entry| get_session_info(&entry.path()))
+        .collect();
+
+    // Sort by modification time, newest first
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.modified_at));
+
+    Ok(sessions)
+}
+
+/// Check if a path is a Gemini session file (session-*.json or session-*.jsonl)
+fn is_gemini_session_file(path: &Path) -> bool {
+    let filename = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+
+    filename.starts_with("session-")
+        && (filename.ends_with(".json") || filename.ends_with(".jsonl"))
+}
+
+/// Build session info from file metadata
+fn get_session_info(path: &Path) -> Option<GeminiSessionInfo> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified_at = system_time_to_datetime(metadata.modified().ok()?)?;
+
+    let filename = path.file_stem()?.to_str()?;
+    let session_id = filename
+        .strip_prefix("session-")
+        .unwrap_or(filename)
+        .to_string();
+
+    Some(GeminiSessionInfo {
+        session_id,
+        session_path: path.to_path_buf(),
+        modified_at,
+        size_bytes: metadata.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_gemini_session_file() {
+        assert!(is_gemini_session_file(Path::new(
+            "session-2025-09-01T10-00-abc.json"
+        )));
+        assert!(is_gemini_session_file(Path::new("session-abc.jsonl")));
+        assert!(!is_gemini_session_file(Path::new("logs.json")));
+        assert!(!is_gemini_session_file(Path::new("session-abc.txt")));
+    }
+
+    #[test]
+    fn test_project_hash_is_hex_sha256() {
+        let hash = project_hash(Path::new("/nonexistent/project"));
+        assert_eq!(hash.len(), 64);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_discover_sessions_in_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("session-one.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("logs.json"), "[]").unwrap();
+
+        let sessions = discover_sessions_in_dir(dir.path()).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, "one");
+    }
+}
@@ -0,0 +1,185 @@
+//! Gemini CLI session entry types
+//!
+//! Gemini CLI records each chat message as an object with a `type`:
+//! - user: A user prompt
+//! - gemini: A model turn (text content, optional thoughts and toolCalls)
+//! - info / error / warning: CLI status and telemetry (not relevant for extraction)
+//!
+//! Content is either a plain string or an array of `{ "text": ... }` parts.
+
+use serde::{Deserialize, Serialize};
+
+/// A single message in a Gemini CLI session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiEntry {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    #[serde(rename = "type")]
+    pub entry_type: String,
+    #[serde(default)]
+    pub content: serde_json::Value,
+    #[serde(default)]
+    pub thoughts: Vec<GeminiThought>,
+    #[serde(default)]
+    pub tool_calls: Vec<GeminiToolCall>,
+}
+
+/// A reasoning summary attached to a model turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiThought {
+    #[serde(default)]
+    pub subject: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// A tool invocation attached to a model turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub args: serde_json::Value,
+    /// Human-readable result shown in the CLI
+    #[serde(default)]
+    pub result_display: Option<serde_json::Value>,
+    /// Raw functionResponse parts
+    #[serde(default)]
+    pub result: serde_json::Value,
+}
+
+impl GeminiEntry {
+    /// Check if this is a user prompt
+    pub fn is_user(&self) -> bool {
+        self.entry_type == "user"
+    }
+
+    /// Check if this is a model turn
+    pub fn is_model(&self) -> bool {
+        self.entry_type == "gemini" || self.entry_type == "model"
+    }
+
+    /// Check if this entry is relevant for knowledge extraction
+    /// AIDEV-NOTE: info/error/warning entries are CLI telemetry (auth notices,
+    /// quota messages, token stats) and carry no conversational content.
+    pub fn is_relevant(&self) -> bool {
+        self.is_user() || self.is_model()
+    }
+
+    /// Flatten string-or-parts content into text
+    fn content_text(&self) -> Option<String> {
+        let text = match &self.content {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Array(parts) => parts
+                .iter()
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => String::new(),
+        };
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed.to_string())
+        }
+    }
+
+    /// Extract user prompt text
+    pub fn user_text(&self) -> Option<String> {
+        if self.is_user() {
+            self.content_text()
+        } else {
+            None
+        }
+    }
+
+    /// Extract model response text (excludes thoughts)
+    pub fn model_text(&self) -> Option<String> {
+        if self.is_model() {
+            self.content_text()
+        } else {
+            None
+        }
+    }
+
+    /// Extract model thoughts as "subject: description" lines
+    pub fn thoughts_text(&self) -> Option<String> {
+        if !self.is_model() || self.thoughts.is_empty() {
+            return None;
+        }
+        let lines: Vec<String> = self
+            .thoughts
+            .iter()
+            .map(|t| match (t.subject.is_empty(), t.description.is_empty()) {
+                (false, false) => format!("{}: {}", t.subject, t.description),
+                (false, true) => t.subject.clone(),
+                _ => t.description.clone(),
+            })
+            .filter(|l| !l.is_empty())
+            .collect();
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+}
+
+impl GeminiToolCall {
+    /// Tool output as text, preferring the display form
+    pub fn output_text(&self) -> Option<String> {
+        match self.result_display.as_ref() {
+            Some(serde_json::Value::String(s)) => Some(s.clone()),
+            Some(serde_json::Value::Null) | None if self.result.is_null() => None,
+            Some(serde_json::Value::Null) | None => Some(self.result.to_string()),
+            Some(other) => Some(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_user_entry() {
+        let json = r#"{"id":"m1","timestamp":"2025-09-01T10:00:00Z","type":"user","content":"Add a flag"}"#;
+        let entry: GeminiEntry = serde_json::from_str(json).unwrap();
+        assert!(entry.is_user());
+        assert!(entry.is_relevant());
+        assert_eq!(entry.user_text(), Some("Add a flag".to_string()));
+        assert_eq!(entry.model_text(), None);
+    }
+
+    #[test]
+    fn test_parse_model_entry_with_parts_thoughts_and_tools() {
+        let json = r#"{"id":"m2","type":"gemini","content":[{"text":"Done."}],
+            "thoughts":[{"subject":"Planning","description":"Check the CLI parser"}],
+            "toolCalls":[{"name":"read_file","args":{"absolute_path":"/src/main.rs"},"resultDisplay":"fn main() {}"}]}"#;
+        let entry: GeminiEntry = serde_json::from_str(json).unwrap();
+        assert!(entry.is_model());
+        assert_eq!(entry.model_text(), Some("Done.".to_string()));
+        assert_eq!(
+            entry.thoughts_text(),
+            Some("Planning: Check the CLI parser".to_string())
+        );
+        assert_eq!(entry.tool_calls[0].name, "read_file");
+        assert_eq!(
+            entry.tool_calls[0].output_text(),
+            Some("fn main() {}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_telemetry_entries_not_relevant() {
+        for kind in ["info", "error", "warning"] {
+            let json = format!(r#"{{"type":"{}","content":"Quota exceeded"}}"#, kind);
+            let entry: GeminiEntry = serde_json::from_str(&json).unwrap();
+            assert!(!entry.is_relevant());
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Rotating history of state.md versions
+//!
+//! Before extract or compress overwrites state.md, the current version is
+//! copied to .wm/history/state-YYYYMMDD-HHMMSS.md. Only the newest
+//! `history.keep` snapshots are kept. `wm state history` lists them and
+//! `wm state show <snapshot>` prints one, and `wm state diff` compares
+//! state.md against the compress backup or a snapshot.
+
+use chrono::Local;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::color;
+use crate::diff;
+use crate::state;
+use crate::types::LogLevel;
+
+const HISTORY_DIR: &str = "history";
+const SNAPSHOT_PREFIX: &str = "state-";
+const SNAPSHOT_EXT: &str = ".md";
+
+/// Snapshot state.md before it is overwritten
+/// AIDEV-NOTE: Best-effort - a failed snapshot is logged and never blocks the
+/// write it protects. Empty or missing state.md has nothing worth keeping.
+pub fn snapshot_state() {
+    snapshot_state_in(&state::wm_dir());
+}
+
+/// snapshot_state for the .wm directory at `wm_dir`
+pub fn snapshot_state_in(wm_dir: &Path) {
+    let content = fs::read_to_string(wm_dir.join("state.md")).unwrap_or_default();
+    snapshot_content_in(wm_dir, &content);
+}
+
+/// Snapshot a version of state.md that is no longer on disk
+/// (`wm state edit` keeps the pre-edit text and records it once the editor exits)
+pub fn snapshot_content(content: &str) {
+    snapshot_content_in(&state::wm_dir(), content);
+}
+
+fn snapshot_content_in(wm_dir: &Path, content: &str) {
+    let keep = state::read_config().history.keep;
+    if keep == 0 {
+        return;
+    }
+
+    if content.trim().is_empty() {
+        return;
+    }
+
+    let name = format!(
+        "{}{}{}",
+        SNAPSHOT_PREFIX,
+        Local::now().format("%Y%m%d-%H%M%S"),
+        SNAPSHOT_EXT
+    );
+    if let Err(e) = write_snapshot(&wm_dir.join(HISTORY_DIR), &name, content, keep) {
+        state::log_at(
+            LogLevel::Warn,
+            "history",
+            &format!("Failed to snapshot state.md: {}", e),
+        );
+    }
+}
+
+/// Run wm state history
+pub fn list() -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let snapshots = list_snapshots(&history_dir())
+        .map_err(|e| format!("Failed to read .wm/{}: {}", HISTORY_DIR, e))?;
+
+    if snapshots.is_empty() {
+        println!(
+            "No state.md snapshots yet. They are taken before extract or compress rewrites state.md."
+        );
+        return Ok(());
+    }
+
+    // Newest first
+    for name in snapshots.iter().rev() {
+        let lines = fs::read_to_string(history_dir().join(name))
+            .map(|c| c.lines().count())
+            .unwrap_or(0);
+        println!("{}  ({} lines)", snapshot_id(name), lines);
+    }
+    Ok(())
+}
+
+/// Run wm state show <snapshot>
+pub fn show(snapshot: &str) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let path = snapshot_path(&history_dir(), snapshot)?;
+    let content = fs::read_to_string(&path).map_err(|_| {
+        format!(
+            "Snapshot '{}' not found. List snapshots with 'wm state history'.",
+            snapshot
+        )
+    })?;
+    print!("{}", content);
+    Ok(())
+}
+
+/// Run wm state diff [--against <snapshot>]
+///
+/// Compares state.md.backup (or the snapshot) as "before" with state.md as "after".
+/// Color is used when stdout is a terminal and NO_COLOR is unset.
+pub fn diff(against: Option<&str>) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let (label, before) = match against {
+        Some(snapshot) => {
+            let path = snapshot_path(&history_dir(), snapshot)?;
+            let content = fs::read_to_string(&path).map_err(|_| {
+                format!(
+                    "Snapshot '{}' not found. List snapshots with 'wm state history'.",
+                    snapshot
+                )
+            })?;
+            (format!("snapshot {}", snapshot_id(snapshot)), content)
+        }
+        None => {
+            let content = fs::read_to_string(state::wm_path("state.md.backup")).map_err(|_| {
+                "No backup found at .wm/state.md.backup. Use --against <snapshot> to compare with history."
+                    .to_string()
+            })?;
+            ("state.md.backup".to_string(), content)
+        }
+    };
+    let after = fs::read_to_string(state::wm_path("state.md")).unwrap_or_default();
+
+    let lines = diff::line_diff(&before, &after);
+    let added = lines
+        .iter()
+        .filter(|l| matches!(l, diff::DiffLine::Added(_)))
+        .count();
+    let removed = lines
+        .iter()
+        .filter(|l| matches!(l, diff::DiffLine::Removed(_)))
+        .count();
+
+    println!("--- {}", label);
+    println!("+++ state.md");
+    if added == 0 && removed == 0 {
+        println!("(no changes)");
+        return Ok(());
+    }
+
+    print!("{}", diff::render(&lines, color::enabled()));
+    println!("\n{} added, {} removed", added, removed);
+    Ok(())
+}
+
+fn history_dir() -> PathBuf {
+    state::wm_path(HISTORY_DIR)
+}
+
+/// Write a snapshot and prune the directory down to `keep` entries
+///
+/// An existing snapshot with the same name (same second) is left alone so the
+/// older version wins.
+fn write_snapshot(dir: &Path, name: &str, content: &str, keep: usize) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(name);
+    if !path.exists() {
+        fs::write(path, content)?;
+    }
+
+    let snapshots = list_snapshots(dir)?;
+    let excess = snapshots.len().saturating_sub(keep);
+    for old in &snapshots[..excess] {
+        fs::remove_file(dir.join(old))?;
+    }
+    Ok(())
+}
+
+/// Snapshot filenames, oldest first (the timestamp format sorts lexically)
+fn list_snapshots(dir: &Path) -> io::Result<Vec<String>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut names: Vec<String> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|n| n.starts_with(SNAPSHOT_PREFIX) && n.ends_with(SNAPSHOT_EXT))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Display id for a snapshot file: the timestamp part
+fn snapshot_id(name: &str) -> &str {
+    name.strip_prefix(SNAPSHOT_PREFIX)
+        .and_then(|n| n.strip_suffix(SNAPSHOT_EXT))
+        .unwrap_or(name)
+}
+
+/// Resolve a user-supplied snapshot (id or filename) to a path in `dir`
+fn snapshot_path(dir: &Path, snapshot: &str) -> Result<PathBuf, String> {
+    if snapshot.contains('/') || snapshot.contains('\\') || snapshot.contains("..") {
+        return Err(format!("Invalid snapshot name '{}'", snapshot));
+    }
+
+    let id = snapshot_id(snapshot);
+    Ok(dir.join(format!("{}{}{}", SNAPSHOT_PREFIX, id, SNAPSHOT_EXT)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_snapshot_prunes_oldest() {
+        let dir = tempfile::tempdir().unwrap();
+        for (i, ts) in ["20250101-000000", "20250102-000000", "20250103-000000"]
+            .iter()
+            .enumerate()
+        {
+            let name = format!("state-{}.md", ts);
+            write_snapshot(dir.path(), &name, &format!("v{}", i), 2).unwrap();
+        }
+
+        let snapshots = list_snapshots(dir.path()).unwrap();
+        assert_eq!(
+            snapshots,
+            ["state-20250102-000000.md", "state-20250103-000000.md"]
+        );
+    }
+
+    #[test]
+    fn test_write_snapshot_keeps_first_in_same_second() {
+        let dir = tempfile::tempdir().unwrap();
+        write_snapshot(dir.path(), "state-20250101-000000.md", "first", 10).unwrap();
+        write_snapshot(dir.path(), "state-20250101-000000.md", "second", 10).unwrap();
+
+        let content = fs::read_to_string(dir.path().join("state-20250101-000000.md")).unwrap();
+        assert_eq!(content, "first");
+    }
+
+    #[test]
+    fn test_snapshot_path_accepts_id_or_filename() {
+        let dir = Path::new("/h");
+        let expected = dir.join("state-20250101-120000.md");
+        assert_eq!(snapshot_path(dir, "20250101-120000").unwrap(), expected);
+        assert_eq!(
+            snapshot_path(dir, "state-20250101-120000.md").unwrap(),
+            expected
+        );
+        assert!(snapshot_path(dir, "../state.md").is_err());
+    }
+}
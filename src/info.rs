@@ -0,0 +1,159 @@
+//! Environment report for diagnosing version and path mismatches
+//!
+//! `wm info` prints which binary ran, its version and build metadata, and the
+//! paths wm resolved (.wm/, config, Claude projects dir). When a user reports
+//! "unrecognized subcommand", this is usually an older wm earlier on PATH.
+//!
+//! AIDEV-NOTE: Read-only and offline. Paths come from the same helpers the
+//! other commands use, so what this prints is what they act on.
+
+use std::path::{Path, PathBuf};
+
+use crate::session;
+use crate::state;
+
+/// Resolved environment facts
+struct Info {
+    binary: Option<PathBuf>,
+    version: &'static str,
+    git_sha: Option<&'static str>,
+    target: Option<&'static str>,
+    profile: Option<&'static str>,
+    wm_dir: PathBuf,
+    initialized: bool,
+    config_path: PathBuf,
+    project_dir_env: Option<String>,
+    wm_dir_env: Option<String>,
+    claude_projects_dir: Option<PathBuf>,
+    claude_project_dir: Option<PathBuf>,
+}
+
+/// Run wm info
+pub fn run() -> Result<(), String> {
+    let wm_dir = state::wm_dir();
+    let info = Info {
+        binary: std::env::current_exe().ok(),
+        version: env!("CARGO_PKG_VERSION"),
+        git_sha: option_env!("WM_BUILD_GIT_SHA"),
+        target: option_env!("WM_BUILD_TARGET"),
+        profile: option_env!("WM_BUILD_PROFILE"),
+        initialized: wm_dir.exists(),
+        wm_dir: absolute(&wm_dir),
+        config_path: absolute(&state::wm_path("config.toml")),
+        project_dir_env: std::env::var("CLAUDE_PROJECT_DIR").ok(),
+        wm_dir_env: std::env::var(state::WM_DIR_ENV).ok(),
+        claude_projects_dir: session::claude_projects_dir(),
+        claude_project_dir: session::get_project_dir(&session::current_project_path()),
+    };
+
+    print!("{}", render(&info));
+    Ok(())
+}
+
+/// Absolute form of a path (canonical if it exists)
+fn absolute(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    })
+}
+
+/// Format the report, one `label: value` per line
+fn render(info: &Info) -> String {
+    let show = |p: &Option<PathBuf>| {
+        p.as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "(unknown)".to_string())
+    };
+    let build = [info.git_sha, info.target, info.profile]
+        .iter()
+        .flatten()
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut lines = vec![
+        format!("binary:          {}", show(&info.binary)),
+        format!("version:         {}", info.version),
+        format!(
+            "build:           {}",
+            if build.is_empty() {
+                "(unknown)"
+            } else {
+                &build
+            }
+        ),
+        format!(
+            ".wm directory:   {}{}",
+            info.wm_dir.display(),
+            if info.initialized {
+                ""
+            } else {
+                " (not initialized)"
+            }
+        ),
+        format!(
+            "config:          {}{}",
+            info.config_path.display(),
+            if info.config_path.exists() {
+                ""
+            } else {
+                " (missing, using defaults)"
+            }
+        ),
+    ];
+    if let Some(dir) = &info.project_dir_env {
+        lines.push(format!("CLAUDE_PROJECT_DIR: {}", dir));
+    }
+    if let Some(dir) = &info.wm_dir_env {
+        lines.push(format!("WM_DIR:          {}", dir));
+    }
+    lines.push(format!(
+        "claude projects: {}",
+        show(&info.claude_projects_dir)
+    ));
+    lines.push(format!(
+        "this project:    {}",
+        info.claude_project_dir
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "(no Claude sessions found)".to_string())
+    ));
+
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_reports_missing_pieces() {
+        let dir = tempfile::tempdir().unwrap();
+        let info = Info {
+            binary: Some(PathBuf::from("/usr/local/bin/wm")),
+            version: "1.2.3",
+            git_sha: Some("abc123"),
+            target: None,
+            profile: Some("release"),
+            wm_dir: dir.path().join(".wm"),
+            initialized: false,
+            config_path: dir.path().join(".wm/config.toml"),
+            project_dir_env: None,
+            wm_dir_env: None,
+            claude_projects_dir: None,
+            claude_project_dir: None,
+        };
+
+        let out = render(&info);
+        assert!(out.contains("binary:          /usr/local/bin/wm"));
+        assert!(out.contains("version:         1.2.3"));
+        assert!(out.contains("build:           abc123 release"));
+        assert!(out.contains("(not initialized)"));
+        assert!(out.contains("(missing, using defaults)"));
+        assert!(out.contains("claude projects: (unknown)"));
+        assert!(out.contains("(no Claude sessions found)"));
+        assert!(!out.contains("CLAUDE_PROJECT_DIR"));
+    }
+}
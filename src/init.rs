@@ -1,28 +1,170 @@
 //! Initialize .wm/ in current project
 
-use crate::state::{self, wm_dir, wm_path};
+use crate::config;
+use crate::migrate;
+use crate::state::{self, wm_dir};
 use std::fs;
+use std::path::{Path, PathBuf};
+
+type InitialContent = fn() -> String;
+
+/// Files every .wm/ starts with, and what generates their initial content
+/// AIDEV-NOTE: config.toml lists every setting at its default so they can be
+/// discovered without reading the docs. Written values stay pinned if a later
+/// wm changes a default; that's the trade for discoverability.
+const SCAFFOLD: &[(&str, InitialContent)] = &[
+    // Freeform markdown for tacit knowledge
+    ("state.md", String::new),
+    ("working_set.md", String::new),
+    ("config.toml", config::default_file),
+];
+
+/// Suffix of the sibling directory --reset moves the old .wm/ to
+const BACKUP_SUFFIX: &str = ".bak";
 
 /// Run wm init
-pub fn run() -> Result<(), String> {
+/// With `force`, an existing .wm/ gets any missing scaffold files (existing
+/// ones are left alone). With `reset`, the existing .wm/ is moved to .wm.bak/
+/// and a fresh one created.
+pub fn run(force: bool, reset: bool) -> Result<(), String> {
+    let dir = wm_dir();
+
     if state::is_initialized() {
-        return Err("Already initialized: .wm/ exists".to_string());
+        if reset {
+            let backup = archive(&dir)?;
+            println!("Moved existing .wm/ to {}", backup.display());
+        } else if force {
+            println!("Repairing existing .wm/");
+            report(&scaffold(&dir)?);
+            if migrate::remove_legacy_checkpoint(&dir)? {
+                println!("  removed   .wm/checkpoint.json (obsolete extraction cursor)");
+            }
+            return Ok(());
+        } else {
+            return Err(
+                "Already initialized: .wm/ exists (--force restores missing files, --reset starts over)"
+                    .to_string(),
+            );
+        }
     }
 
-    // Create .wm/ directory
-    fs::create_dir_all(wm_dir()).map_err(|e| format!("Failed to create .wm/: {}", e))?;
+    scaffold(&dir)?;
+    println!("Initialized {}/", dir.display());
 
-    // Create empty state.md (freeform markdown for tacit knowledge)
-    fs::write(wm_path("state.md"), "").map_err(|e| format!("Failed to write state.md: {}", e))?;
+    Ok(())
+}
 
-    // Create checkpoint.json for tracking extraction progress
-    fs::write(wm_path("checkpoint.json"), "{\"position\": 0}")
-        .map_err(|e| format!("Failed to write checkpoint.json: {}", e))?;
+/// Create `dir` and any missing scaffold files, never overwriting
+/// Returns each scaffold file with whether it was created.
+fn scaffold(dir: &Path) -> Result<Vec<(&'static str, bool)>, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create .wm/: {}", e))?;
 
-    // Create empty working set
-    state::write_working_set("").map_err(|e| format!("Failed to write working_set.md: {}", e))?;
+    SCAFFOLD
+        .iter()
+        .map(|(name, content)| {
+            let path = dir.join(name);
+            if path.exists() {
+                return Ok((*name, false));
+            }
+            fs::write(&path, content()).map_err(|e| format!("Failed to write {}: {}", name, e))?;
+            Ok((*name, true))
+        })
+        .collect()
+}
 
-    println!("Initialized .wm/ in current directory");
+/// Print what --force created and preserved
+fn report(files: &[(&str, bool)]) {
+    for (name, created) in files {
+        if *created {
+            println!("  created   .wm/{}", name);
+        } else {
+            println!("  preserved .wm/{}", name);
+        }
+    }
+}
 
-    Ok(())
+/// Move `dir` to a sibling .wm.bak/, refusing to replace an existing backup
+/// (a WM_DIR override like `wm-data` backs up to `wm-data.bak`)
+fn archive(dir: &Path) -> Result<PathBuf, String> {
+    let mut name = dir.file_name().unwrap_or(dir.as_os_str()).to_os_string();
+    name.push(BACKUP_SUFFIX);
+    let backup = dir.with_file_name(name);
+    if backup.exists() {
+        return Err(format!(
+            "{} already exists; remove it before --reset",
+            backup.display()
+        ));
+    }
+    fs::rename(dir, &backup).map_err(|e| format!("Failed to move .wm/: {}", e))?;
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaffold_restores_missing_files_only() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join(".wm");
+        scaffold(&dir).unwrap();
+
+        fs::write(dir.join("state.md"), "- keep me\n").unwrap();
+        fs::remove_file(dir.join("config.toml")).unwrap();
+
+        let files = scaffold(&dir).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                ("state.md", false),
+                ("working_set.md", false),
+                ("config.toml", true)
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("state.md")).unwrap(),
+            "- keep me\n"
+        );
+        // The generated config parses back to exactly the defaults
+        let config = fs::read_to_string(dir.join("config.toml")).unwrap();
+        let parsed: crate::types::Config = toml::from_str(&config).unwrap();
+        assert_eq!(
+            toml::Value::try_from(parsed).unwrap(),
+            toml::Value::try_from(crate::types::Config::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fresh_scaffold_has_no_checkpoint() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join(".wm");
+        scaffold(&dir).unwrap();
+
+        let mut files: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["config.toml", "state.md", "working_set.md"]);
+    }
+
+    #[test]
+    fn test_archive_moves_wm_dir_once() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join(".wm");
+        scaffold(&dir).unwrap();
+        fs::write(dir.join("state.md"), "- old\n").unwrap();
+
+        let backup = archive(&dir).unwrap();
+        assert_eq!(backup, root.path().join(".wm.bak"));
+        assert!(!dir.exists());
+        assert_eq!(
+            fs::read_to_string(backup.join("state.md")).unwrap(),
+            "- old\n"
+        );
+
+        // A second reset would clobber the first backup: refuse
+        scaffold(&dir).unwrap();
+        assert!(archive(&dir).unwrap_err().contains("already exists"));
+    }
 }
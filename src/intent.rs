@@ -0,0 +1,166 @@
+//! Intent classification for dive prep workflow selection
+//!
+//! The dive-prep agent picks a workflow from an intent type (fix, plan,
+//! review, explore, ship). `classify` maps a free-text intent such as
+//! "fix the flaky test" to one of those types so users don't have to name it.
+//!
+//! AIDEV-NOTE: Keyword tier only, deterministic and instant. It runs on every
+//! prompt in the compile hook (for logging), where an LLM call would add
+//! seconds of latency; the dive-prep agent can still override the type.
+
+use clap::ValueEnum;
+use std::fmt;
+
+/// Kind of work a dive is for (selects the dive-prep workflow)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum IntentType {
+    Fix,
+    Plan,
+    Review,
+    #[default]
+    Explore,
+    Ship,
+}
+
+impl IntentType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IntentType::Fix => "fix",
+            IntentType::Plan => "plan",
+            IntentType::Review => "review",
+            IntentType::Explore => "explore",
+            IntentType::Ship => "ship",
+        }
+    }
+}
+
+impl fmt::Display for IntentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Word prefixes that signal each intent type
+/// Prefixes so "fixing", "crashes", "flaky" and "deployment" match; two-letter
+/// keywords ("vs") only match whole words, and short stems that start unrelated
+/// words are limited to their INFLECTIONS.
+const KEYWORDS: &[(IntentType, &[&str])] = &[
+    (
+        IntentType::Fix,
+        &[
+            "fix", "bug", "broke", "crash", "flak", "fail", "error", "regress", "debug", "repair",
+            "panic",
+        ],
+    ),
+    (
+        IntentType::Plan,
+        &[
+            "plan",
+            "design",
+            "should",
+            "decide",
+            "choose",
+            "compare",
+            "versus",
+            "vs",
+            "architect",
+            "propos",
+            "tradeoff",
+            "roadmap",
+        ],
+    ),
+    (
+        IntentType::Review,
+        &["review", "audit", "critique", "feedback"],
+    ),
+    (
+        IntentType::Ship,
+        &["ship", "release", "deploy", "publish", "launch", "rollout"],
+    ),
+];
+
+/// Endings allowed after short keywords that open unrelated words
+/// ("fixture", "planet", "shoulder")
+const INFLECTIONS: &[(&str, &[&str])] = &[
+    ("fix", &["", "es", "ed", "ing", "er", "ers"]),
+    ("plan", &["", "s", "ned", "ning", "ner", "ners"]),
+    ("should", &["", "n"]),
+];
+
+/// Whether `word` is `keyword`, or starts with it in a way that keeps its meaning
+fn matches_keyword(word: &str, keyword: &str) -> bool {
+    let Some(rest) = word.strip_prefix(keyword) else {
+        return false;
+    };
+    match INFLECTIONS.iter().find(|(stem, _)| *stem == keyword) {
+        Some((_, endings)) => endings.contains(&rest),
+        None => keyword.len() > 2 || rest.is_empty(),
+    }
+}
+
+/// Classify a free-text intent, defaulting to Explore when nothing matches
+pub fn classify(message: &str) -> IntentType {
+    classify_keywords(message).unwrap_or_default()
+}
+
+/// Intent type of the earliest keyword in the message, if any
+/// AIDEV-NOTE: Earliest wins so the leading verb decides: "review the fix"
+/// is a review, "should we fix X or Y" is a plan.
+fn classify_keywords(message: &str) -> Option<IntentType> {
+    message
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .find_map(|word| {
+            KEYWORDS.iter().find_map(|(kind, prefixes)| {
+                prefixes
+                    .iter()
+                    .any(|prefix| matches_keyword(&word, prefix))
+                    .then_some(*kind)
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_representative_phrasings() {
+        let cases = [
+            ("fix the flaky test", IntentType::Fix),
+            ("Login crashes on empty password", IntentType::Fix),
+            ("debugging the CI failure", IntentType::Fix),
+            ("should we use X or Y", IntentType::Plan),
+            ("design the cache layer", IntentType::Plan),
+            ("postgres vs sqlite for local state", IntentType::Plan),
+            ("review PR #42", IntentType::Review),
+            ("release 0.4 to crates.io", IntentType::Ship),
+            ("deploy the docs site", IntentType::Ship),
+            ("how does compile routing work?", IntentType::Explore),
+            ("", IntentType::Explore),
+        ];
+        for (message, expected) in cases {
+            assert_eq!(classify(message), expected, "{:?}", message);
+        }
+    }
+
+    #[test]
+    fn test_classify_earliest_keyword_wins() {
+        assert_eq!(classify("review the fix for #12"), IntentType::Review);
+        assert_eq!(classify("should we fix this now"), IntentType::Plan);
+        assert_eq!(classify("fix the release script"), IntentType::Fix);
+    }
+
+    #[test]
+    fn test_classify_matches_whole_word_prefixes_only() {
+        // "prefix" contains "fix" but doesn't start with it
+        assert_eq!(classify("rename the prefix helper"), IntentType::Explore);
+        assert_eq!(classify("vscode extension layout"), IntentType::Explore);
+        // Short stems only take their inflections
+        assert_eq!(classify("update the fixtures"), IntentType::Explore);
+        assert_eq!(classify("planet-scale shoulder check"), IntentType::Explore);
+        assert_eq!(classify("fixes for the planned release"), IntentType::Fix);
+        assert_eq!(classify("shouldn't this be cached"), IntentType::Plan);
+    }
+}
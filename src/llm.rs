@@ -5,7 +5,16 @@
 //! parse the response using text-based markers (HAS_KNOWLEDGE, HAS_RELEVANT, etc).
 
 use crate::state;
-use std::process::{Command, Stdio};
+use crate::types::{LlmBackend, LlmConfig, LogLevel};
+use std::io::Read;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+/// Default base URL for the OpenAI-compatible backend (override with OPENAI_BASE_URL)
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com";
+
+/// Default model for the OpenAI-compatible backend (override with OPENAI_MODEL)
+const DEFAULT_OPENAI_MODEL: &str = "gpt-4o-mini";
 
 /// Result of calling the LLM with a marker-based response format
 #[derive(Debug)]
@@ -49,44 +58,404 @@ impl Drop for EnvGuard {
     }
 }
 
-/// Call Claude CLI with a system prompt and message
-///
-/// Returns the raw result string from the Claude CLI JSON response.
-/// Sets WM_DISABLED and SUPEREGO_DISABLED to prevent recursion.
-pub fn call_claude(system_prompt: &str, message: &str) -> Result<String, String> {
-    // Prevent recursion using drop guards - env vars are restored even on panic/early return
-    let _wm_guard = EnvGuard::new("WM_DISABLED", "1");
-    let _sg_guard = EnvGuard::new("SUPEREGO_DISABLED", "1");
+/// A backend capable of answering a system prompt + message with raw text
+/// AIDEV-NOTE: extract, distill, and compress take `&dyn LlmClient` instead of
+/// calling the CLI directly, so the backend is swappable and tests can inject
+/// `MockLlmClient` with canned marker responses.
+pub trait LlmClient {
+    /// Send the prompt pair and return the model's raw response text
+    fn complete(&self, system_prompt: &str, message: &str) -> Result<String, String>;
+}
+
+/// LLM client that shells out to the `claude` CLI
+#[derive(Debug)]
+pub struct ClaudeCliClient {
+    /// Model passed via --model (None = CLI default)
+    model: Option<String>,
+    /// Extra CLI arguments appended before the prompt
+    extra_args: Vec<String>,
+    /// Kill the CLI if it runs longer than this
+    timeout: Duration,
+    /// Executable to run (resolved via PATH)
+    binary: String,
+}
+
+impl Default for ClaudeCliClient {
+    fn default() -> Self {
+        Self::from_config(&LlmConfig::default())
+    }
+}
+
+impl ClaudeCliClient {
+    /// Build a client from the `[llm]` config section
+    pub fn from_config(config: &LlmConfig) -> Self {
+        Self {
+            model: config.model.clone(),
+            extra_args: config.extra_args.clone(),
+            timeout: Duration::from_secs(config.timeout_secs),
+            binary: CLAUDE_BINARY.to_string(),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_binary(mut self, binary: &str) -> Self {
+        self.binary = binary.to_string();
+        self
+    }
 
-    call_claude_inner(system_prompt, message)
+    /// Full argv (excluding the binary) for a call
+    fn args(&self, system_prompt: &str, message: &str) -> Vec<String> {
+        let mut args: Vec<String> = vec![
+            "-p".into(),
+            "--output-format".into(),
+            "json".into(),
+            "--no-session-persistence".into(),
+        ];
+        if let Some(model) = &self.model {
+            args.push("--model".into());
+            args.push(model.clone());
+        }
+        args.extend(self.extra_args.iter().cloned());
+        args.push("--system-prompt".into());
+        args.push(system_prompt.to_string());
+        args.push(message.to_string());
+        args
+    }
 }
 
-/// Inner implementation of call_claude (without env var management)
-fn call_claude_inner(system_prompt: &str, message: &str) -> Result<String, String> {
+impl LlmClient for ClaudeCliClient {
+    fn complete(&self, system_prompt: &str, message: &str) -> Result<String, String> {
+        // Prevent recursion using drop guards - env vars are restored even on panic/early return
+        let _wm_guard = EnvGuard::new("WM_DISABLED", "1");
+        let _sg_guard = EnvGuard::new("SUPEREGO_DISABLED", "1");
+
+        call_claude_inner(
+            &self.binary,
+            &self.args(system_prompt, message),
+            message.len(),
+            self.timeout,
+        )
+    }
+}
+
+/// LLM client for OpenAI-compatible chat completion APIs
+/// AIDEV-NOTE: For environments without the `claude` CLI. Returns the raw message
+/// content, so `parse_marker_response` works unchanged on its output.
+#[derive(Debug)]
+pub struct OpenAiClient {
+    api_key: String,
+    base_url: String,
+    model: String,
+    timeout: Duration,
+}
+
+impl OpenAiClient {
+    /// Build a client from OPENAI_API_KEY, OPENAI_BASE_URL, and OPENAI_MODEL
+    /// (OPENAI_MODEL wins over `[llm] model`)
+    pub fn from_env(config: &LlmConfig) -> Result<Self, String> {
+        let api_key = std::env::var("OPENAI_API_KEY")
+            .map_err(|_| "OPENAI_API_KEY environment variable not set".to_string())?;
+        let base_url = std::env::var("OPENAI_BASE_URL")
+            .unwrap_or_else(|_| DEFAULT_OPENAI_BASE_URL.to_string());
+        let model = std::env::var("OPENAI_MODEL")
+            .ok()
+            .or_else(|| config.model.clone())
+            .unwrap_or_else(|| DEFAULT_OPENAI_MODEL.to_string());
+
+        Ok(Self {
+            api_key,
+            base_url,
+            model,
+            timeout: Duration::from_secs(config.timeout_secs),
+        })
+    }
+
+    /// Build the chat completion request body
+    fn request_body(&self, system_prompt: &str, message: &str) -> serde_json::Value {
+        serde_json::json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": system_prompt },
+                { "role": "user", "content": message },
+            ],
+        })
+    }
+}
+
+impl LlmClient for OpenAiClient {
+    fn complete(&self, system_prompt: &str, message: &str) -> Result<String, String> {
+        let url = format!(
+            "{}/v1/chat/completions",
+            self.base_url.trim_end_matches('/')
+        );
+
+        state::log(
+            "llm",
+            &format!(
+                "Calling OpenAI-compatible API {} (model: {}, message: {} bytes)",
+                url,
+                self.model,
+                message.len()
+            ),
+        );
+
+        let response = ureq::post(&url)
+            .timeout(self.timeout)
+            .set("Authorization", &format!("Bearer {}", self.api_key))
+            .set("Content-Type", "application/json")
+            .send_json(self.request_body(system_prompt, message))
+            .map_err(|e| match e {
+                ureq::Error::Status(code, response) => {
+                    let body = response.into_string().unwrap_or_default();
+                    format!("OpenAI API failed: HTTP {} - {}", code, body)
+                }
+                ureq::Error::Transport(t) if is_timeout(&t) => {
+                    format!(
+                        "LLM call timed out after {} seconds",
+                        self.timeout.as_secs()
+                    )
+                }
+                other => format!("OpenAI API request failed: {}", other),
+            })?;
+
+        let body: serde_json::Value = response
+            .into_json()
+            .map_err(|e| format!("Failed to parse OpenAI API response: {}", e))?;
+
+        parse_chat_completion(&body)
+    }
+}
+
+/// Whether a transport error is a timeout rather than some other I/O failure
+/// (ureq reports both as ErrorKind::Io; the io::Error underneath tells them apart)
+fn is_timeout(t: &ureq::Transport) -> bool {
+    use std::error::Error;
+    t.kind() == ureq::ErrorKind::Io
+        && t.source()
+            .and_then(|e| e.downcast_ref::<std::io::Error>())
+            .is_some_and(|e| {
+                matches!(
+                    e.kind(),
+                    std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+                )
+            })
+}
+
+/// Extract `choices[0].message.content` from a chat completion response
+fn parse_chat_completion(body: &serde_json::Value) -> Result<String, String> {
+    body.get("choices")
+        .and_then(|c| c.get(0))
+        .and_then(|c| c.get("message"))
+        .and_then(|m| m.get("content"))
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .ok_or_else(|| {
+            format!(
+                "OpenAI API response missing choices[0].message.content: {}",
+                body
+            )
+        })
+}
+
+/// Env var with extra claude CLI arguments (whitespace-separated)
+pub const CLAUDE_EXTRA_ARGS_VAR: &str = "WM_CLAUDE_EXTRA_ARGS";
+
+/// Flags wm sets itself; passing them again would break response parsing
+/// or the recursion/prompt setup
+const RESERVED_CLAUDE_FLAGS: &[&str] = &[
+    "-p",
+    "--print",
+    "--output-format",
+    "--no-session-persistence",
+    "--system-prompt",
+    "--model",
+];
+
+/// Merge `[llm] extra_args` with WM_CLAUDE_EXTRA_ARGS, rejecting reserved flags
+/// AIDEV-NOTE: Config args come first, env args after, so a one-off env value
+/// can follow (and for repeatable flags, override) the project's settings.
+/// Env values are split on whitespace only - no shell quoting.
+fn claude_extra_args(
+    config_args: &[String],
+    env_value: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let mut args = config_args.to_vec();
+    if let Some(value) = env_value {
+        args.extend(value.split_whitespace().map(String::from));
+    }
+
+    for arg in &args {
+        let reserved = RESERVED_CLAUDE_FLAGS
+            .iter()
+            .find(|flag| arg == *flag || arg.starts_with(&format!("{}=", flag)));
+        if let Some(flag) = reserved {
+            let hint = if *flag == "--model" {
+                " Use `[llm] model` in .wm/config.toml instead."
+            } else {
+                ""
+            };
+            return Err(format!(
+                "Extra claude argument '{}' conflicts with {}, which wm sets itself.{}",
+                arg, flag, hint
+            ));
+        }
+    }
+
+    Ok(args)
+}
+
+/// Resolve the backend: WM_LLM_BACKEND env var wins over `[llm] backend` in config
+pub fn resolve_backend(config: &LlmConfig) -> Result<LlmBackend, String> {
+    match std::env::var("WM_LLM_BACKEND") {
+        Ok(value) if !value.trim().is_empty() => value.parse(),
+        _ => Ok(config.backend),
+    }
+}
+
+/// Delay before the first retry; doubles on each subsequent retry (1s, 2s, 4s, ...)
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Error fragments that indicate a transient failure worth retrying
+/// AIDEV-NOTE: Matched case-insensitively against the full error string, which
+/// includes CLI stderr/stdout and HTTP bodies. Auth and prompt errors don't match
+/// any of these, so they fail fast.
+const TRANSIENT_ERROR_PATTERNS: &[&str] = &[
+    "failed to spawn",
+    "rate limit",
+    "rate_limit",
+    "overloaded",
+    "http 429",
+    "http 529",
+    "http 503",
+    "request failed",
+];
+
+/// Whether an LLM error looks transient (rate limit, overload, spawn/network blip)
+pub fn is_transient_error(error: &str) -> bool {
+    let lower = error.to_lowercase();
+    TRANSIENT_ERROR_PATTERNS.iter().any(|p| lower.contains(p))
+}
+
+/// Wraps another client, retrying transient failures with exponential backoff
+pub struct RetryClient<C: LlmClient> {
+    inner: C,
+    max_retries: u32,
+    base_delay: Duration,
+}
+
+impl<C: LlmClient> RetryClient<C> {
+    pub fn new(inner: C, max_retries: u32) -> Self {
+        Self {
+            inner,
+            max_retries,
+            base_delay: RETRY_BASE_DELAY,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+}
+
+impl<C: LlmClient> LlmClient for RetryClient<C> {
+    fn complete(&self, system_prompt: &str, message: &str) -> Result<String, String> {
+        let mut attempt = 0;
+        loop {
+            match self.inner.complete(system_prompt, message) {
+                Ok(text) => return Ok(text),
+                Err(e) if attempt < self.max_retries && is_transient_error(&e) => {
+                    let delay = self.base_delay * 2u32.pow(attempt);
+                    attempt += 1;
+                    state::log_at(
+                        LogLevel::Warn,
+                        "llm",
+                        &format!(
+                            "Transient error, retry {}/{} in {:?}: {}",
+                            attempt, self.max_retries, delay, e
+                        ),
+                    );
+                    std::thread::sleep(delay);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Build an LLM client for the given config
+pub fn client_from_config(config: &LlmConfig) -> Result<Box<dyn LlmClient>, String> {
+    match resolve_backend(config)? {
+        LlmBackend::Claude => {
+            let mut config = config.clone();
+            config.extra_args = claude_extra_args(
+                &config.extra_args,
+                std::env::var(CLAUDE_EXTRA_ARGS_VAR).ok().as_deref(),
+            )?;
+            Ok(Box::new(RetryClient::new(
+                ClaudeCliClient::from_config(&config),
+                config.max_retries,
+            )))
+        }
+        LlmBackend::OpenAi => Ok(Box::new(RetryClient::new(
+            OpenAiClient::from_env(config)?,
+            config.max_retries,
+        ))),
+    }
+}
+
+/// Get the default LLM client for this invocation (reads config once)
+pub fn default_client() -> Result<Box<dyn LlmClient>, String> {
+    client_from_config(&state::read_config().llm)
+}
+
+/// Get the LLM client for compress, honoring `[llm] compress_model`
+pub fn compress_client() -> Result<Box<dyn LlmClient>, String> {
+    let mut config = state::read_config().llm;
+    if let Some(model) = config.compress_model.take() {
+        config.model = Some(model);
+    }
+    client_from_config(&config)
+}
+
+/// Executable name of the Claude CLI
+pub const CLAUDE_BINARY: &str = "claude";
+
+/// Error returned when the Claude CLI isn't installed
+/// AIDEV-NOTE: Not transient (no retries). Hook-invoked extract matches it via
+/// `is_cli_missing` and exits Ok so a missing CLI never breaks the session.
+pub const CLAUDE_NOT_FOUND: &str = "claude CLI not found on PATH; install it or set WM_LLM_BACKEND";
+
+/// Whether an error means the Claude CLI isn't installed
+pub fn is_cli_missing(error: &str) -> bool {
+    error.contains(CLAUDE_NOT_FOUND)
+}
+
+/// Run the Claude CLI and parse its JSON result (without env var management)
+fn call_claude_inner(
+    binary: &str,
+    args: &[String],
+    message_len: usize,
+    timeout: Duration,
+) -> Result<String, String> {
     state::log(
         "llm",
-        &format!("Calling Claude CLI (message: {} bytes)", message.len()),
+        &format!("Calling Claude CLI (message: {} bytes)", message_len),
     );
 
-    let mut cmd = Command::new("claude");
-    cmd.arg("-p")
-        .arg("--output-format")
-        .arg("json")
-        .arg("--no-session-persistence")
-        .arg("--system-prompt")
-        .arg(system_prompt)
-        .arg(message)
+    let mut cmd = Command::new(binary);
+    cmd.args(args)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .stdin(Stdio::null());
 
-    let child = cmd
-        .spawn()
-        .map_err(|e| format!("Failed to spawn claude CLI: {}", e))?;
+    let child = cmd.spawn().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => CLAUDE_NOT_FOUND.to_string(),
+        _ => format!("Failed to spawn claude CLI: {}", e),
+    })?;
 
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for claude CLI: {}", e))?;
+    let output = wait_with_timeout(child, timeout)?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -100,16 +469,120 @@ fn call_claude_inner(system_prompt: &str, message: &str) -> Result<String, Strin
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
+    let (result, parse_path) = parse_cli_output(&stdout)?;
+    state::log_at(
+        LogLevel::Debug,
+        "llm",
+        &format!("Parsed Claude CLI output as {}", parse_path),
+    );
+    Ok(result)
+}
 
-    // Parse Claude CLI JSON wrapper to extract result field
-    let cli_response: serde_json::Value = serde_json::from_str(&stdout)
-        .map_err(|e| format!("Failed to parse Claude CLI response: {}", e))?;
+/// Extract the `result` string from Claude CLI stdout
+///
+/// Returns the result and which parse path succeeded (for logging).
+/// AIDEV-NOTE: `--output-format json` should print a single JSON object, but
+/// some CLI versions print warning lines first or plain text. Try, in order:
+/// the whole stdout as JSON, the first `{` through the last `}`, then the raw
+/// text as the result itself.
+fn parse_cli_output(stdout: &str) -> Result<(String, &'static str), String> {
+    let result_field = |value: serde_json::Value| {
+        value
+            .get("result")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .ok_or_else(|| "Claude CLI response missing 'result' field".to_string())
+    };
 
-    cli_response
-        .get("result")
-        .and_then(|v| v.as_str())
-        .map(String::from)
-        .ok_or_else(|| "Claude CLI response missing 'result' field".to_string())
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(stdout) {
+        return result_field(value).map(|r| (r, "json"));
+    }
+
+    if let (Some(start), Some(end)) = (stdout.find('{'), stdout.rfind('}'))
+        && start < end
+        && let Ok(value) = serde_json::from_str::<serde_json::Value>(&stdout[start..=end])
+    {
+        return result_field(value).map(|r| (r, "embedded json"));
+    }
+
+    let text = stdout.trim();
+    if text.is_empty() {
+        return Err("Claude CLI produced no output".to_string());
+    }
+    Ok((text.to_string(), "plain text"))
+}
+
+/// How often to poll a running child for exit
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Wait for a child process, killing it if it outlives the timeout
+/// AIDEV-NOTE: stdout/stderr are drained on reader threads so a chatty child
+/// can't block on a full pipe while we poll `try_wait`. Hook-invoked extract
+/// relies on this to never hang the user's session.
+fn wait_with_timeout(mut child: std::process::Child, timeout: Duration) -> Result<Output, String> {
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let stdout_reader = std::thread::spawn(move || read_all(stdout));
+    let stderr_reader = std::thread::spawn(move || read_all(stderr));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "LLM call timed out after {} seconds",
+                    timeout.as_secs()
+                ));
+            }
+            Ok(None) => std::thread::sleep(WAIT_POLL_INTERVAL),
+            Err(e) => return Err(format!("Failed to wait for claude CLI: {}", e)),
+        }
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+/// Read a child pipe to the end (empty if the pipe wasn't captured)
+fn read_all(pipe: Option<impl Read>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buf);
+    }
+    buf
+}
+
+/// Marker the built-in extract/distill prompts ask for
+pub const KNOWLEDGE_MARKER: &str = "HAS_KNOWLEDGE";
+
+/// Marker the built-in compress prompt asks for
+pub const COMPRESSED_MARKER: &str = "WAS_COMPRESSED";
+
+/// Call the LLM and parse its marker response, honoring a configured marker name
+///
+/// `default_marker` is the marker the built-in `system_prompt` is written with;
+/// when `marker` differs, the prompt is rewritten to ask for `marker` instead.
+pub fn complete_with_marker(
+    llm: &dyn LlmClient,
+    system_prompt: &str,
+    message: &str,
+    default_marker: &str,
+    marker: &str,
+) -> Result<MarkerResponse, String> {
+    let result_str = if marker == default_marker {
+        llm.complete(system_prompt, message)?
+    } else {
+        let prompt =
+            system_prompt.replace(&format!("{}:", default_marker), &format!("{}:", marker));
+        llm.complete(&prompt, message)?
+    };
+    Ok(parse_marker_response(&result_str, marker))
 }
 
 /// Parse a marker-based response (e.g., "HAS_KNOWLEDGE: YES\n<content>")
@@ -142,7 +615,8 @@ pub fn parse_marker_response(text: &str, marker_name: &str) -> MarkerResponse {
     }
 
     // Fallback: no marker found
-    state::log(
+    state::log_at(
+        LogLevel::Warn,
         "llm",
         &format!(
             "No {} marker found in response, treating as negative",
@@ -161,10 +635,290 @@ fn strip_markdown_prefix(line: &str) -> &str {
     line.trim().trim_start_matches(['#', '>', '*']).trim()
 }
 
+/// Test double that returns queued responses and records every call
+#[cfg(test)]
+pub struct MockLlmClient {
+    responses: std::cell::RefCell<std::collections::VecDeque<Result<String, String>>>,
+    pub calls: std::cell::RefCell<Vec<(String, String)>>,
+}
+
+#[cfg(test)]
+impl MockLlmClient {
+    /// Create a mock that answers calls in order with the given results
+    pub fn new(responses: Vec<Result<String, String>>) -> Self {
+        Self {
+            responses: std::cell::RefCell::new(responses.into()),
+            calls: std::cell::RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Create a mock that answers every call in order with the given texts
+    pub fn with_texts(texts: &[&str]) -> Self {
+        Self::new(texts.iter().map(|t| Ok(t.to_string())).collect())
+    }
+
+    /// Number of calls made so far
+    pub fn call_count(&self) -> usize {
+        self.calls.borrow().len()
+    }
+}
+
+#[cfg(test)]
+impl LlmClient for MockLlmClient {
+    fn complete(&self, system_prompt: &str, message: &str) -> Result<String, String> {
+        self.calls
+            .borrow_mut()
+            .push((system_prompt.to_string(), message.to_string()));
+        self.responses
+            .borrow_mut()
+            .pop_front()
+            .unwrap_or_else(|| Err("MockLlmClient: no more responses".to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_mock_client_returns_responses_in_order() {
+        let mock = MockLlmClient::with_texts(&["first", "second"]);
+        assert_eq!(mock.complete("sys", "a").unwrap(), "first");
+        assert_eq!(mock.complete("sys", "b").unwrap(), "second");
+        assert!(mock.complete("sys", "c").is_err());
+        assert_eq!(mock.call_count(), 3);
+        assert_eq!(mock.calls.borrow()[1].1, "b");
+    }
+
+    #[test]
+    fn test_retry_succeeds_after_transient_failures() {
+        let mock = MockLlmClient::new(vec![
+            Err("Claude CLI failed (exit Some(1)):\nstderr: Rate limit exceeded".to_string()),
+            Err("API Error: Overloaded".to_string()),
+            Ok("done".to_string()),
+        ]);
+        let client = RetryClient::new(mock, 3).with_base_delay(Duration::ZERO);
+        assert_eq!(client.complete("sys", "msg").unwrap(), "done");
+        assert_eq!(client.inner.call_count(), 3);
+    }
+
+    #[test]
+    fn test_retry_gives_up_after_max_retries() {
+        let mock = MockLlmClient::new(vec![
+            Err("rate limit".to_string()),
+            Err("rate limit".to_string()),
+            Err("rate limit".to_string()),
+        ]);
+        let client = RetryClient::new(mock, 2).with_base_delay(Duration::ZERO);
+        assert!(client.complete("sys", "msg").is_err());
+        assert_eq!(client.inner.call_count(), 3);
+    }
+
+    #[test]
+    fn test_retry_fails_fast_on_non_transient_error() {
+        let mock = MockLlmClient::new(vec![
+            Err("Claude CLI failed (exit Some(1)):\nstderr: Invalid API key".to_string()),
+            Ok("never reached".to_string()),
+        ]);
+        let client = RetryClient::new(mock, 3).with_base_delay(Duration::ZERO);
+        assert!(client.complete("sys", "msg").is_err());
+        assert_eq!(client.inner.call_count(), 1);
+    }
+
+    #[test]
+    fn test_wait_with_timeout_kills_slow_child() {
+        let child = Command::new("sleep")
+            .arg("10")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let started = Instant::now();
+        let err = wait_with_timeout(child, Duration::from_secs(1)).unwrap_err();
+        assert_eq!(err, "LLM call timed out after 1 seconds");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(!is_transient_error(&err));
+    }
+
+    #[test]
+    fn test_wait_with_timeout_collects_output() {
+        let child = Command::new("echo")
+            .arg("hello")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let output = wait_with_timeout(child, Duration::from_secs(5)).unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "hello");
+    }
+
+    #[test]
+    fn test_missing_cli_binary_is_clear_and_not_retried() {
+        let client = RetryClient::new(
+            ClaudeCliClient::default().with_binary("wm-test-no-such-claude-binary"),
+            3,
+        )
+        .with_base_delay(Duration::from_secs(60));
+
+        let err = client.complete("system", "message").unwrap_err();
+        assert_eq!(err, CLAUDE_NOT_FOUND);
+        assert!(is_cli_missing(&err));
+        assert!(!is_transient_error(&err));
+    }
+
+    #[test]
+    fn test_parse_cli_output_json() {
+        let (result, path) =
+            parse_cli_output(r#"{"type":"result","result":"HAS_KNOWLEDGE: NO"}"#).unwrap();
+        assert_eq!(result, "HAS_KNOWLEDGE: NO");
+        assert_eq!(path, "json");
+        assert!(parse_cli_output(r#"{"type":"result"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_cli_output_prefixed_json() {
+        let stdout =
+            "Warning: config file is deprecated\n{\"result\":\"- Use {braces} carefully\"}\n";
+        let (result, path) = parse_cli_output(stdout).unwrap();
+        assert_eq!(result, "- Use {braces} carefully");
+        assert_eq!(path, "embedded json");
+    }
+
+    #[test]
+    fn test_parse_cli_output_plain_text() {
+        let (result, path) = parse_cli_output("HAS_KNOWLEDGE: YES\n- Prefer tabs\n").unwrap();
+        assert_eq!(result, "HAS_KNOWLEDGE: YES\n- Prefer tabs");
+        assert_eq!(path, "plain text");
+        assert!(parse_cli_output("  \n").is_err());
+    }
+
+    #[test]
+    fn test_is_transient_error() {
+        assert!(is_transient_error(
+            "Failed to spawn claude CLI: No such file"
+        ));
+        assert!(is_transient_error(
+            "OpenAI API failed: HTTP 429 - slow down"
+        ));
+        assert!(!is_transient_error("OpenAI API failed: HTTP 401 - bad key"));
+        assert!(!is_transient_error(
+            "Claude CLI response missing 'result' field"
+        ));
+    }
+
+    #[test]
+    fn test_complete_with_custom_marker() {
+        let mock = MockLlmClient::with_texts(&["FOUND_IT: YES\n- insight"]);
+        let prompt = "Respond:\nHAS_KNOWLEDGE: YES\nor\nHAS_KNOWLEDGE: NO";
+        let response =
+            complete_with_marker(&mock, prompt, "msg", KNOWLEDGE_MARKER, "FOUND_IT").unwrap();
+
+        assert!(response.is_positive);
+        assert_eq!(response.content, "- insight");
+        let sent_prompt = &mock.calls.borrow()[0].0;
+        assert!(sent_prompt.contains("FOUND_IT: YES"));
+        assert!(!sent_prompt.contains("HAS_KNOWLEDGE"));
+    }
+
+    #[test]
+    fn test_claude_args_default() {
+        let client = ClaudeCliClient::default();
+        let args = client.args("sys", "msg");
+        assert_eq!(
+            args,
+            vec![
+                "-p",
+                "--output-format",
+                "json",
+                "--no-session-persistence",
+                "--system-prompt",
+                "sys",
+                "msg"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_claude_args_with_configured_model() {
+        let config: crate::types::Config =
+            toml::from_str("[llm]\nmodel = \"haiku\"\nextra_args = [\"--verbose\"]\n").unwrap();
+        let client = ClaudeCliClient::from_config(&config.llm);
+        let args = client.args("sys", "msg");
+
+        let model_pos = args.iter().position(|a| a == "--model").unwrap();
+        assert_eq!(args[model_pos + 1], "haiku");
+        assert!(args.contains(&"--verbose".to_string()));
+        // Prompt pair always comes last
+        assert_eq!(&args[args.len() - 3..], ["--system-prompt", "sys", "msg"]);
+    }
+
+    #[test]
+    fn test_claude_extra_args_merges_config_then_env() {
+        let config = vec!["--verbose".to_string()];
+        let args = claude_extra_args(&config, Some("  --max-turns 2 ")).unwrap();
+        assert_eq!(args, ["--verbose", "--max-turns", "2"]);
+        assert_eq!(claude_extra_args(&config, None).unwrap(), ["--verbose"]);
+    }
+
+    #[test]
+    fn test_claude_extra_args_rejects_reserved_flags() {
+        let err = claude_extra_args(&[], Some("--output-format=text")).unwrap_err();
+        assert!(err.contains("--output-format"));
+        let err =
+            claude_extra_args(&["--model".to_string(), "opus".to_string()], None).unwrap_err();
+        assert!(err.contains("[llm] model"));
+        assert!(claude_extra_args(&[], Some("-p")).is_err());
+        // Prefix matches only on `flag=`, not longer flag names
+        assert!(claude_extra_args(&[], Some("--print-debug")).is_ok());
+    }
+
+    #[test]
+    fn test_openai_request_body() {
+        let client = OpenAiClient {
+            api_key: "key".to_string(),
+            base_url: DEFAULT_OPENAI_BASE_URL.to_string(),
+            model: "test-model".to_string(),
+            timeout: Duration::from_secs(120),
+        };
+        let body = client.request_body("be terse", "hello");
+
+        assert_eq!(body["model"], "test-model");
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][0]["content"], "be terse");
+        assert_eq!(body["messages"][1]["role"], "user");
+        assert_eq!(body["messages"][1]["content"], "hello");
+    }
+
+    #[test]
+    fn test_only_timed_out_io_counts_as_timeout() {
+        let transport = |kind| match ureq::Error::from(std::io::Error::from(kind)) {
+            ureq::Error::Transport(t) => t,
+            other => panic!("expected a transport error, got {}", other),
+        };
+        assert!(is_timeout(&transport(std::io::ErrorKind::TimedOut)));
+        assert!(is_timeout(&transport(std::io::ErrorKind::WouldBlock)));
+        assert!(!is_timeout(&transport(std::io::ErrorKind::ConnectionReset)));
+    }
+
+    #[test]
+    fn test_parse_chat_completion() {
+        let body = serde_json::json!({
+            "choices": [{ "message": { "role": "assistant", "content": "HAS_KNOWLEDGE: NO" } }]
+        });
+        assert_eq!(parse_chat_completion(&body).unwrap(), "HAS_KNOWLEDGE: NO");
+
+        let result = parse_marker_response(&parse_chat_completion(&body).unwrap(), "HAS_KNOWLEDGE");
+        assert!(!result.is_positive);
+    }
+
+    #[test]
+    fn test_parse_chat_completion_missing_content() {
+        let body = serde_json::json!({ "choices": [] });
+        let err = parse_chat_completion(&body).unwrap_err();
+        assert!(err.contains("choices[0].message.content"));
+    }
+
     #[test]
     fn test_parse_marker_yes() {
         let text = "HAS_KNOWLEDGE: YES\n- First insight\n- Second insight";
@@ -0,0 +1,167 @@
+//! PID lock files under .wm/
+//!
+//! Hook-triggered commands (superego runs `wm extract &`) can overlap when the
+//! user types quickly. Both runs would read state.md, call the LLM and race on
+//! the state.md.tmp → state.md rename, silently dropping one extraction.
+//! A lock file created with `create_new` lets only one run proceed.
+//!
+//! AIDEV-NOTE: The lock holds the owner's PID. A lock whose PID is no longer
+//! alive (crashed or killed run) is stale and gets replaced, so a dead process
+//! never blocks extraction forever. Where liveness can't be checked (non-unix),
+//! a lock is stale only once it is older than UNREADABLE_LOCK_GRACE.
+
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Lock file for wm extract
+pub const EXTRACT_LOCK_FILE: &str = "extract.lock";
+
+/// Grace period for a lock without a readable PID (owner may be mid-write)
+const UNREADABLE_LOCK_GRACE: Duration = Duration::from_secs(60);
+
+/// A held lock; removed on drop
+#[derive(Debug)]
+pub struct LockFile {
+    path: PathBuf,
+}
+
+impl LockFile {
+    /// Try to take the lock at `path`
+    ///
+    /// Returns Ok(None) if another live process holds it.
+    pub fn acquire(path: &Path) -> Result<Option<LockFile>, String> {
+        if let Some(lock) = try_create(path)? {
+            return Ok(Some(lock));
+        }
+
+        let Some(stale) = fs::read_to_string(path).ok() else {
+            // Released in the meantime; take it if still free
+            return try_create(path);
+        };
+        if !is_stale(path) {
+            return Ok(None);
+        }
+
+        // Stale lock from a dead process: replace it, but only if it still
+        // holds the same PID. Another run may already have replaced it with
+        // its own live lock, which must not be removed. If another run beats
+        // us to it after the removal, create_new fails and we back off.
+        if fs::read_to_string(path).ok().as_deref() != Some(stale.as_str()) {
+            return Ok(None);
+        }
+        let _ = fs::remove_file(path);
+        try_create(path)
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Create the lock file exclusively and write our PID
+fn try_create(path: &Path) -> Result<Option<LockFile>, String> {
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(mut file) => {
+            let lock = LockFile {
+                path: path.to_path_buf(),
+            };
+            file.write_all(std::process::id().to_string().as_bytes())
+                .map_err(|e| format!("Failed to write lock file {:?}: {}", path, e))?;
+            Ok(Some(lock))
+        }
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(None),
+        Err(e) => Err(format!("Failed to create lock file {:?}: {}", path, e)),
+    }
+}
+
+/// Check whether an existing lock was left behind by a dead process
+pub fn is_stale(path: &Path) -> bool {
+    let pid = fs::read_to_string(path)
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok());
+
+    match pid.and_then(process_alive) {
+        Some(alive) => !alive,
+        None => {
+            // No PID yet (or garbage), or no way to check it: only stale once
+            // it has sat around a while
+            let age = fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| SystemTime::now().duration_since(t).ok());
+            age.is_some_and(|age| age > UNREADABLE_LOCK_GRACE)
+        }
+    }
+}
+
+/// Check whether a process with this PID is running; None if we can't tell
+#[cfg(unix)]
+fn process_alive(pid: u32) -> Option<bool> {
+    // `kill -0` checks existence without signalling
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .ok()
+}
+
+/// Check whether a process with this PID is running; None if we can't tell
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> Option<bool> {
+    // No cheap liveness check; is_stale falls back to the lock's age
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_held_lock_blocks_second_acquire() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(EXTRACT_LOCK_FILE);
+
+        let held = LockFile::acquire(&path).unwrap();
+        assert!(held.is_some());
+        assert!(LockFile::acquire(&path).unwrap().is_none());
+
+        drop(held);
+        assert!(!path.exists());
+        assert!(LockFile::acquire(&path).unwrap().is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stale_lock_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(EXTRACT_LOCK_FILE);
+
+        // PID of a process that has already exited
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+        fs::write(&path, dead_pid.to_string()).unwrap();
+
+        let lock = LockFile::acquire(&path).unwrap();
+        assert!(lock.is_some());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            std::process::id().to_string()
+        );
+    }
+
+    #[test]
+    fn test_fresh_unreadable_lock_is_held() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(EXTRACT_LOCK_FILE);
+        fs::write(&path, "").unwrap();
+
+        assert!(LockFile::acquire(&path).unwrap().is_none());
+    }
+}
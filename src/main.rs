@@ -1,44 +1,102 @@
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, Local, Utc};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
+use intent::IntentType;
+use source::{DistillSource, ExtractSource};
+
 mod codex;
+mod color;
 mod compile;
 mod compress;
+mod config;
+mod corrections;
+mod diff;
 mod distill;
 mod dive;
+mod doctor;
+mod editor;
+mod export;
 mod extract;
+mod gc;
+mod gemini;
+mod history;
+mod info;
 mod init;
+mod intent;
 mod llm;
+mod lock;
+mod mcp;
+mod merge;
+mod migrate;
 mod oh;
+mod pinned;
+mod prompts;
+mod provenance;
+mod redact;
+mod search;
 mod session;
+mod session_stat;
 mod show;
+mod source;
 mod state;
+mod stats;
+mod text;
 mod transcript;
 mod types;
+mod watch;
 
 #[derive(Parser)]
 #[command(name = "wm")]
 #[command(about = "Working memory for AI coding assistants")]
 #[command(version)]
+#[command(after_help = "Run without a command for an overview of this project's wm state.")]
 struct Cli {
+    /// Use this directory instead of .wm/ (same as WM_DIR)
+    #[arg(long, global = true, value_name = "DIR")]
+    wm_dir: Option<PathBuf>,
+
+    /// Disable colored output (same as NO_COLOR=1)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Omitted: print an overview (see run_overview)
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Initialize .wm/ in current project
-    Init,
+    Init {
+        /// Recreate missing files in an existing .wm/ (never overwrites)
+        #[arg(long)]
+        force: bool,
+
+        /// Move an existing .wm/ to .wm.bak/ and start fresh
+        #[arg(long, conflicts_with = "force")]
+        reset: bool,
+    },
 
     /// Run LLM extraction from transcript
     Extract {
-        /// Path to transcript file
+        /// Path to transcript file ("-" reads JSONL from stdin)
         #[arg(long)]
         transcript: Option<String>,
 
         /// Claude session ID (for session-scoped extraction)
         #[arg(long)]
         session_id: Option<String>,
+
+        /// Which agent's session to extract from (Gemini: use wm distill --source gemini)
+        #[arg(long, value_enum, default_value_t = ExtractSource::Claude)]
+        source: ExtractSource,
+
+        /// Print the would-be state.md without writing anything
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Compile working set for current state
@@ -46,10 +104,47 @@ enum Commands {
         /// User's current message (for intent detection)
         #[arg(long)]
         intent: Option<String>,
+
+        /// Exclude dive context from the compiled working set
+        #[arg(long)]
+        no_dive: bool,
+
+        /// Write to the per-session working set (as the hook does)
+        #[arg(long)]
+        session_id: Option<String>,
+
+        /// Rewrite the working set of every session in .wm/sessions/, each routed
+        /// by that session's last prompt (--intent covers sessions without one)
+        #[arg(long, conflicts_with_all = ["session_id", "save_as"])]
+        all_sessions: bool,
+
+        /// Also save the compiled result to .wm/working_sets/<name>.md
+        #[arg(long, value_name = "NAME")]
+        save_as: Option<String>,
+
+        /// Use a saved working set instead of compiling (the hook injects it until --unload)
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["save_as", "no_dive"])]
+        load: Option<String>,
+
+        /// Stop injecting the saved working set selected with --load
+        #[arg(long, conflicts_with_all = ["save_as", "load"])]
+        unload: bool,
+
+        /// Print the working set to stdout instead of writing any file
+        #[arg(long, conflicts_with_all = ["session_id", "all_sessions", "save_as", "load", "unload"])]
+        print: bool,
     },
 
     /// Compress state.md by synthesizing to higher-level abstractions
-    Compress,
+    Compress {
+        /// Maximum compression passes (stops early once nothing is left to compress)
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..=compress::MAX_PASSES as u64))]
+        passes: u64,
+
+        /// Repeat (up to 5 passes) until a pass removes under 5% of lines
+        #[arg(long, conflicts_with = "passes")]
+        until_stable: bool,
+    },
 
     /// Batch extract knowledge from all sessions (replaces per-turn extract)
     Distill {
@@ -69,13 +164,61 @@ enum Commands {
         #[arg(long)]
         context_id: Option<String>,
 
-        /// Filter to a specific project by name (substring match)
+        /// OH context ID for guardrails (defaults to --context-id)
         #[arg(long)]
-        project: Option<String>,
+        guardrails_context_id: Option<String>,
+
+        /// OH context ID for metis (defaults to --context-id)
+        #[arg(long)]
+        metis_context_id: Option<String>,
+
+        /// Push items again even if .wm/distill/pushed.json records them
+        #[arg(long, requires = "push_to_oh")]
+        force_push: bool,
 
-        /// Process Codex sessions instead of Claude Code sessions
+        /// Filter to a specific project by name (substring match; Gemini needs the project's path)
         #[arg(long)]
+        project: Option<String>,
+
+        /// Exact project directory to distill (overrides --project's substring match)
+        #[arg(long, value_name = "PATH")]
+        project_path: Option<PathBuf>,
+
+        /// Which agent's sessions to process (all = every agent, one Pass 2)
+        #[arg(long, value_enum, default_value_t = DistillSource::Claude)]
+        source: DistillSource,
+
+        /// Only sessions modified since then: RFC 3339, YYYY-MM-DD, or a span ago (7d, 12h)
+        #[arg(long, value_name = "WHEN", value_parser = |s: &str| parse_since(s, Utc::now()))]
+        since: Option<DateTime<Utc>>,
+
+        /// Only the newest N sessions (per agent with --source all)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        limit: Option<u64>,
+
+        /// Give up on a session after this many seconds of LLM calls and move on to the next
+        #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+        timeout_secs: Option<u64>,
+
+        /// Shorthand for --source codex
+        #[arg(long, conflicts_with = "source")]
         codex: bool,
+
+        /// Shorthand for --source gemini
+        #[arg(long, conflicts_with_all = ["source", "codex"])]
+        gemini: bool,
+
+        /// Re-extract only sessions that failed in a previous run (see errors.log)
+        #[arg(long, conflicts_with = "force")]
+        retry_failed: bool,
+
+        /// Keep bullets repeated across sessions instead of collapsing them before Pass 2
+        #[arg(long)]
+        no_dedup: bool,
+
+        /// With --dry-run, print the plan as JSON
+        #[arg(long, requires = "dry_run")]
+        json: bool,
     },
 
     /// Display state, working set, or sessions
@@ -87,6 +230,82 @@ enum Commands {
         /// Session ID (for session-specific working set)
         #[arg(long)]
         session_id: Option<String>,
+
+        /// Output as JSON (state, sessions)
+        #[arg(long)]
+        json: bool,
+
+        /// Show each bullet's source session and date (state)
+        #[arg(long)]
+        with_provenance: bool,
+
+        /// Include message count and time span per session (reads transcripts)
+        #[arg(long)]
+        detailed: bool,
+    },
+
+    /// Export knowledge captured since a date (requires provenance tags)
+    Export {
+        /// Cutoff date (YYYY-MM-DD, inclusive)
+        #[arg(long)]
+        since: String,
+    },
+
+    /// Search captured knowledge (state, distilled files, dive preps)
+    Search {
+        /// Text to look for (case-insensitive)
+        query: String,
+
+        /// Treat the query as a regular expression
+        #[arg(long)]
+        regex: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print binary path, version, build metadata and resolved wm paths
+    Info,
+
+    /// Check the claude CLI, .wm/, config, session dirs, locks and OH access
+    Doctor,
+
+    /// Remove .wm/sessions/ directories whose transcripts no longer exist
+    Gc {
+        /// List what would be removed without deleting
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Print a shell completion script (e.g. wm completions zsh > ~/.zfunc/_wm)
+    Completions {
+        /// Shell to generate for
+        shell: Shell,
+    },
+
+    /// Read or change .wm/config.toml settings
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Manage state.md
+    State {
+        #[command(subcommand)]
+        command: StateCommands,
+    },
+
+    /// Inspect Claude sessions for this project
+    Sessions {
+        #[command(subcommand)]
+        command: SessionsCommands,
+    },
+
+    /// Exchange guardrails and metis with Open Horizons
+    Oh {
+        #[command(subcommand)]
+        command: OhCommands,
     },
 
     /// Manage Open Horizons dive pack context
@@ -99,6 +318,10 @@ enum Commands {
     Pause {
         /// Operation to pause: extract, compile, or omit for both
         operation: Option<String>,
+
+        /// Resume automatically after this long (e.g. 30m, 1h, 2d, 1h30m)
+        #[arg(long = "for", value_name = "DURATION")]
+        duration: Option<String>,
     },
 
     /// Resume extract, compile, or both operations
@@ -110,6 +333,16 @@ enum Commands {
     /// Show current pause/resume status
     Status,
 
+    /// Serve wm state to MCP clients over stdio
+    Mcp,
+
+    /// Extract automatically whenever a session's transcript changes
+    Watch {
+        /// Session to watch (defaults to the newest session for this project)
+        #[arg(long)]
+        session_id: Option<String>,
+    },
+
     /// Hook entry points (called by Claude Code hooks)
     Hook {
         #[command(subcommand)]
@@ -121,6 +354,101 @@ enum Commands {
     DivePrep {
         /// Intent or context for the dive
         intent: Option<String>,
+
+        /// Intent type (classified from the intent text when omitted)
+        #[arg(long = "type", value_enum)]
+        intent_type: Option<IntentType>,
+
+        /// Skip git context (e.g. preparing in a non-repo docs folder)
+        #[arg(long)]
+        no_git: bool,
+
+        /// Print the assembled context instead of writing dive_context.md
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum StateCommands {
+    /// Undo the last compress by restoring .wm/state.md.backup
+    Restore,
+
+    /// List state.md snapshots in .wm/history/ (newest first)
+    History,
+
+    /// Print a state.md snapshot
+    Show {
+        /// Snapshot id from 'wm state history' (e.g. 20250115-103000)
+        snapshot: String,
+    },
+
+    /// Upgrade legacy .wm/ files (OH_context.md, checkpoint.json) to the current layout
+    Migrate,
+
+    /// Show what changed in state.md since the compress backup (or a snapshot)
+    Diff {
+        /// Compare against a history snapshot instead of state.md.backup
+        #[arg(long)]
+        against: Option<String>,
+    },
+
+    /// Open state.md in $EDITOR (the previous version is kept in history)
+    Edit,
+
+    /// Merge another project's state.md (and distilled guardrails) into this one
+    Merge {
+        /// The other project's .wm/ directory (or the project root containing it)
+        other: PathBuf,
+
+        /// Add the other state under a heading instead of an LLM merge
+        #[arg(long)]
+        append: bool,
+    },
+
+    /// Summarize accumulated knowledge: state.md size, dives, guardrails/metis, sessions
+    Stats {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print a setting (dotted key, e.g. operations.extract)
+    Get { key: String },
+
+    /// Change a setting (use "none" to unset optional keys)
+    Set { key: String, value: String },
+
+    /// Print every setting
+    List,
+}
+
+#[derive(Subcommand)]
+enum OhCommands {
+    /// Merge a context's approved guardrails/metis into .wm/distill/
+    Pull {
+        /// OH context to pull from
+        #[arg(long)]
+        context_id: String,
+    },
+
+    /// Check that the OH API is reachable with the configured key
+    Status,
+}
+
+#[derive(Subcommand)]
+enum SessionsCommands {
+    /// Detailed breakdown of a single session
+    Stat {
+        /// Claude session ID
+        session_id: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
     },
 }
 
@@ -133,6 +461,11 @@ enum DiveCommands {
     New {
         /// Name for the dive prep (kebab-case)
         name: String,
+
+        /// Seed from a template file ({{name}} and {{date}} are substituted)
+        /// [default: .wm/dive_template.md if present]
+        #[arg(long)]
+        template: Option<String>,
     },
 
     /// Switch to a named dive prep
@@ -147,6 +480,30 @@ enum DiveCommands {
         name: String,
     },
 
+    /// Open a dive prep in $EDITOR (created if it doesn't exist)
+    Edit {
+        /// Prep to edit (default: current)
+        name: Option<String>,
+    },
+
+    /// Rename a dive prep (stays current if it was)
+    Rename {
+        /// Existing prep name
+        old: String,
+
+        /// New name (kebab-case)
+        new: String,
+    },
+
+    /// Copy a dive prep to a new name
+    Copy {
+        /// Prep to copy
+        src: String,
+
+        /// Name for the copy (kebab-case)
+        dst: String,
+    },
+
     /// Save current dive_context.md as a named prep
     Save {
         /// Name to save as (kebab-case)
@@ -179,6 +536,18 @@ enum DiveCommands {
     Prep {
         /// Intent or context for the dive
         intent: Option<String>,
+
+        /// Intent type (classified from the intent text when omitted)
+        #[arg(long = "type", value_enum)]
+        intent_type: Option<IntentType>,
+
+        /// Skip git context (e.g. preparing in a non-repo docs folder)
+        #[arg(long)]
+        no_git: bool,
+
+        /// Print the assembled context instead of writing dive_context.md
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
@@ -191,6 +560,13 @@ enum HookCommands {
         session_id: String,
     },
 
+    /// Called by the SessionStart hook (injects the latest working set)
+    SessionStart {
+        /// Claude session ID
+        #[arg(long)]
+        session_id: String,
+    },
+
     /// Called by sg after clearing (or manually)
     Extract,
 }
@@ -203,52 +579,187 @@ fn main() -> ExitCode {
 
     let cli = Cli::parse();
 
-    let result = match cli.command {
-        Commands::Init => init::run(),
+    if let Some(dir) = &cli.wm_dir {
+        // SAFETY: Set before any threads start; child wm processes inherit it
+        unsafe { std::env::set_var(state::WM_DIR_ENV, dir) };
+    }
+    if cli.no_color {
+        // SAFETY: As above, before any threads start
+        unsafe { std::env::set_var(color::NO_COLOR_ENV, "1") };
+    }
+
+    // Bare `wm` lands on an overview rather than clap's usage error
+    let Some(command) = cli.command else {
+        return exit_code(run_overview());
+    };
+
+    let result = match command {
+        Commands::Init { force, reset } => init::run(force, reset),
         Commands::Extract {
             transcript,
             session_id,
-        } => extract::run(transcript, session_id),
-        Commands::Compile { intent } => compile::run(intent),
-        Commands::Compress => compress::run(),
+            source,
+            dry_run,
+        } => extract::run(transcript, session_id, source, dry_run),
+        Commands::Compile {
+            intent,
+            no_dive,
+            session_id,
+            all_sessions,
+            save_as,
+            load,
+            unload,
+            print,
+        } => compile::run(
+            intent,
+            no_dive,
+            if print {
+                compile::Output::Stdout
+            } else if all_sessions {
+                compile::Output::AllSessions
+            } else {
+                session_id.map_or(compile::Output::Project, compile::Output::Session)
+            },
+            save_as,
+            load,
+            unload,
+        ),
+        Commands::Compress {
+            passes,
+            until_stable,
+        } => compress::run(passes as usize, until_stable),
         Commands::Distill {
             dry_run,
             force,
             push_to_oh,
             context_id,
+            guardrails_context_id,
+            metis_context_id,
+            force_push,
             project,
+            project_path,
+            source,
+            since,
+            limit,
+            timeout_secs,
             codex,
+            gemini,
+            retry_failed,
+            no_dedup,
+            json,
         } => distill::run(distill::DistillOptions {
             dry_run,
             force,
             push_to_oh,
             context_id,
+            guardrails_context_id,
+            metis_context_id,
+            force_push,
             project,
-            codex,
+            project_path,
+            source: if codex {
+                DistillSource::Codex
+            } else if gemini {
+                DistillSource::Gemini
+            } else {
+                source
+            },
+            since,
+            limit: limit.map(|n| n as usize),
+            timeout_secs,
+            retry_failed,
+            dedup: !no_dedup,
+            json,
         }),
-        Commands::Show { what, session_id } => show::run(&what, session_id.as_deref()),
+        Commands::Show {
+            what,
+            session_id,
+            json,
+            with_provenance,
+            detailed,
+        } => show::run(
+            &what,
+            session_id.as_deref(),
+            json,
+            with_provenance,
+            detailed,
+        ),
+        Commands::Export { since } => export::run(&since),
+        Commands::Search { query, regex, json } => search::run(&query, regex, json),
+        Commands::Info => info::run(),
+        Commands::Doctor => doctor::run(),
+        Commands::Gc { dry_run } => gc::run(dry_run),
+        Commands::Completions { shell } => {
+            write_completions(shell, &mut std::io::stdout());
+            Ok(())
+        }
+        Commands::Config { command } => match command {
+            ConfigCommands::Get { key } => config::get(&key),
+            ConfigCommands::Set { key, value } => config::set(&key, &value),
+            ConfigCommands::List => config::list(),
+        },
+        Commands::State { command } => match command {
+            StateCommands::Restore => compress::restore(),
+            StateCommands::History => history::list(),
+            StateCommands::Show { snapshot } => history::show(&snapshot),
+            StateCommands::Migrate => migrate::run(),
+            StateCommands::Diff { against } => history::diff(against.as_deref()),
+            StateCommands::Stats { json } => stats::run(json),
+            StateCommands::Edit => editor::edit_state(),
+            StateCommands::Merge { other, append } => merge::run(&other, append),
+        },
+        Commands::Sessions { command } => match command {
+            SessionsCommands::Stat { session_id, json } => session_stat::run(&session_id, json),
+        },
+        Commands::Oh { command } => match command {
+            OhCommands::Pull { context_id } => oh::run_pull(&context_id),
+            OhCommands::Status => oh::run_status(),
+        },
         Commands::Dive { command } => match command {
             DiveCommands::List => dive::list(),
-            DiveCommands::New { name } => dive::new(&name, None),
+            DiveCommands::New { name, template } => dive::new(&name, None, template.as_deref()),
             DiveCommands::Switch { name } => dive::switch(&name),
             DiveCommands::Delete { name } => dive::delete(&name),
+            DiveCommands::Edit { name } => dive::edit(name.as_deref()),
+            DiveCommands::Rename { old, new } => dive::rename(&old, &new),
+            DiveCommands::Copy { src, dst } => dive::copy(&src, &dst),
             DiveCommands::Save { name } => dive::save(&name),
             DiveCommands::Current => dive::current(),
             DiveCommands::Show { name } => dive::show(name.as_deref()),
             DiveCommands::Load { pack_id, name } => dive::load(&pack_id, name.as_deref()),
             DiveCommands::Clear => dive::clear(),
-            DiveCommands::Prep { intent } => dive::prep(intent.as_deref()),
+            DiveCommands::Prep {
+                intent,
+                intent_type,
+                no_git,
+                dry_run,
+            } => dive::prep(intent.as_deref(), intent_type, no_git, dry_run),
         },
-        Commands::Pause { operation } => run_pause(operation),
+        Commands::Pause {
+            operation,
+            duration,
+        } => run_pause(operation, duration.as_deref()),
         Commands::Resume { operation } => run_resume(operation),
         Commands::Status => run_status(),
+        Commands::Mcp => mcp::run(),
+        Commands::Watch { session_id } => watch::run(session_id),
         Commands::Hook { command } => match command {
             HookCommands::Compile { session_id } => compile::run_hook(&session_id),
+            HookCommands::SessionStart { session_id } => compile::run_session_start(&session_id),
             HookCommands::Extract => extract::run_hook(),
         },
-        Commands::DivePrep { intent } => dive::prep(intent.as_deref()),
+        Commands::DivePrep {
+            intent,
+            intent_type,
+            no_git,
+            dry_run,
+        } => dive::prep(intent.as_deref(), intent_type, no_git, dry_run),
     };
 
+    exit_code(result)
+}
+
+fn exit_code(result: Result<(), String>) -> ExitCode {
     match result {
         Ok(_) => ExitCode::SUCCESS,
         Err(e) => {
@@ -258,36 +769,143 @@ fn main() -> ExitCode {
     }
 }
 
-fn run_pause(operation: Option<String>) -> Result<(), String> {
+/// Write the completion script for `shell`, generated from the Cli definition
+fn write_completions(shell: Shell, out: &mut dyn std::io::Write) {
+    clap_complete::generate(shell, &mut Cli::command(), "wm", out);
+}
+
+fn run_pause(operation: Option<String>, duration: Option<&str>) -> Result<(), String> {
     if !state::is_initialized() {
         return Err("Not initialized. Run 'wm init' first.".to_string());
     }
 
-    let mut config = state::read_config();
-
-    match operation.as_deref() {
-        Some("extract") => {
-            config.operations.extract = false;
-            println!("Paused: extract");
+    // A plain pause is indefinite and replaces any earlier deadline
+    let (until, suffix) = match duration {
+        Some(d) => {
+            let until = Utc::now() + parse_duration(d)?;
+            (
+                Some(until.to_rfc3339()),
+                format!(
+                    " until {}",
+                    until.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+                ),
+            )
         }
-        Some("compile") => {
-            config.operations.compile = false;
-            println!("Paused: compile");
+        None => (None, String::new()),
+    };
+
+    pause_in(
+        &state::wm_path(state::CONFIG_FILE),
+        operation.as_deref(),
+        until,
+        &suffix,
+    )
+}
+
+/// Record a pause in the config file at `config_path`
+fn pause_in(
+    config_path: &Path,
+    operation: Option<&str>,
+    until: Option<String>,
+    suffix: &str,
+) -> Result<(), String> {
+    state::update_config_in(config_path, |config| {
+        let ops = &mut config.operations;
+        match operation {
+            Some("extract") => {
+                ops.extract = false;
+                ops.extract_paused_until = until;
+                println!("Paused: extract{}", suffix);
+            }
+            Some("compile") => {
+                ops.compile = false;
+                ops.compile_paused_until = until;
+                println!("Paused: compile{}", suffix);
+            }
+            Some(op) => {
+                return Err(format!(
+                    "Unknown operation: {}. Use 'extract' or 'compile'.",
+                    op
+                ));
+            }
+            None => {
+                ops.extract = false;
+                ops.compile = false;
+                ops.extract_paused_until = until.clone();
+                ops.compile_paused_until = until;
+                println!("Paused: extract, compile{}", suffix);
+            }
         }
-        Some(op) => {
-            return Err(format!(
-                "Unknown operation: {}. Use 'extract' or 'compile'.",
-                op
-            ));
+        Ok(())
+    })
+}
+
+/// Parse a pause duration like `45s`, `30m`, `1h`, `2d` or `1h30m`
+fn parse_duration(input: &str) -> Result<chrono::Duration, String> {
+    let invalid = || {
+        format!(
+            "Invalid duration '{}'. Use a number with s, m, h or d (e.g. 30m, 1h30m).",
+            input
+        )
+    };
+
+    let mut total = chrono::Duration::zero();
+    let mut digits = String::new();
+    for c in input.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
         }
-        None => {
-            config.operations.extract = false;
-            config.operations.compile = false;
-            println!("Paused: extract, compile");
+        let n: i64 = digits.parse().map_err(|_| invalid())?;
+        digits.clear();
+        total += match c.to_ascii_lowercase() {
+            's' => chrono::Duration::try_seconds(n),
+            'm' => chrono::Duration::try_minutes(n),
+            'h' => chrono::Duration::try_hours(n),
+            'd' => chrono::Duration::try_days(n),
+            _ => None,
         }
+        .ok_or_else(invalid)?;
+    }
+
+    // Trailing digits without a unit, or nothing at all
+    if !digits.is_empty() || total <= chrono::Duration::zero() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+/// Parse a --since value: RFC 3339, YYYY-MM-DD (local midnight), or a
+/// duration before `now` (`7d`, `12h`, `1h30m`)
+fn parse_since(input: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let input = input.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Ok(time.with_timezone(&Utc));
     }
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        && let Some(midnight) = date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|t| t.and_local_timezone(Local).earliest())
+    {
+        return Ok(midnight.with_timezone(&Utc));
+    }
+    parse_duration(input).map(|ago| now - ago).map_err(|_| {
+        format!(
+            "Invalid --since '{}'. Use RFC 3339, YYYY-MM-DD, or a span like 7d or 12h.",
+            input
+        )
+    })
+}
 
-    state::write_config(&config).map_err(|e| format!("Failed to write config: {}", e))
+/// Format time left on a timed pause, e.g. `1h 5m` (minimum `<1m`)
+fn format_remaining(remaining: chrono::Duration) -> String {
+    let minutes = remaining.num_minutes();
+    match (minutes / (60 * 24), minutes / 60 % 24, minutes % 60) {
+        (0, 0, 0) => "<1m".to_string(),
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
 }
 
 fn run_resume(operation: Option<String>) -> Result<(), String> {
@@ -295,31 +913,39 @@ fn run_resume(operation: Option<String>) -> Result<(), String> {
         return Err("Not initialized. Run 'wm init' first.".to_string());
     }
 
-    let mut config = state::read_config();
+    resume_in(&state::wm_path(state::CONFIG_FILE), operation.as_deref())
+}
 
-    match operation.as_deref() {
-        Some("extract") => {
-            config.operations.extract = true;
-            println!("Resumed: extract");
+/// Clear pauses in the config file at `config_path`
+fn resume_in(config_path: &Path, operation: Option<&str>) -> Result<(), String> {
+    state::update_config_in(config_path, |config| {
+        match operation {
+            Some("extract") => {
+                config.operations.extract = true;
+                config.operations.extract_paused_until = None;
+                println!("Resumed: extract");
+            }
+            Some("compile") => {
+                config.operations.compile = true;
+                config.operations.compile_paused_until = None;
+                println!("Resumed: compile");
+            }
+            Some(op) => {
+                return Err(format!(
+                    "Unknown operation: {}. Use 'extract' or 'compile'.",
+                    op
+                ));
+            }
+            None => {
+                config.operations.extract = true;
+                config.operations.compile = true;
+                config.operations.extract_paused_until = None;
+                config.operations.compile_paused_until = None;
+                println!("Resumed: extract, compile");
+            }
         }
-        Some("compile") => {
-            config.operations.compile = true;
-            println!("Resumed: compile");
-        }
-        Some(op) => {
-            return Err(format!(
-                "Unknown operation: {}. Use 'extract' or 'compile'.",
-                op
-            ));
-        }
-        None => {
-            config.operations.extract = true;
-            config.operations.compile = true;
-            println!("Resumed: extract, compile");
-        }
-    }
-
-    state::write_config(&config).map_err(|e| format!("Failed to write config: {}", e))
+        Ok(())
+    })
 }
 
 fn run_status() -> Result<(), String> {
@@ -328,20 +954,253 @@ fn run_status() -> Result<(), String> {
     }
 
     let config = state::read_config();
+    let ops = &config.operations;
+    let now = Utc::now();
 
-    let extract_status = if config.operations.extract {
-        "running"
-    } else {
-        "paused"
+    let extract_status = operation_status(
+        ops.extract_enabled_at(now),
+        ops.extract_paused_until.as_deref(),
+        now,
+    );
+    let compile_status = operation_status(
+        ops.compile_enabled_at(now),
+        ops.compile_paused_until.as_deref(),
+        now,
+    );
+
+    println!("extract: {}", colored_status(&extract_status));
+    println!("compile: {}", colored_status(&compile_status));
+
+    Ok(())
+}
+
+/// Bare `wm`: where .wm/ is, the current dive, pause state and last distill
+/// Unlike `wm status`, an uninitialized project isn't an error here.
+fn run_overview() -> Result<(), String> {
+    let dir = state::wm_dir();
+    if !state::is_initialized() {
+        println!(
+            "wm {}: no {} here. Run 'wm init' to start, or 'wm --help' for all commands.",
+            env!("CARGO_PKG_VERSION"),
+            dir.display()
+        );
+        return Ok(());
+    }
+
+    let config = state::read_config();
+    let ops = &config.operations;
+    let now = Utc::now();
+    let overview = Overview {
+        wm_dir: dir,
+        dive: state::current_dive(),
+        extract: operation_status(
+            ops.extract_enabled_at(now),
+            ops.extract_paused_until.as_deref(),
+            now,
+        ),
+        compile: operation_status(
+            ops.compile_enabled_at(now),
+            ops.compile_paused_until.as_deref(),
+            now,
+        ),
+        last_extract: extract::last_extracted_at().map(|t| t.with_timezone(&Local)),
+        last_distill: distill::last_extracted_at().map(|t| t.with_timezone(&Local)),
     };
-    let compile_status = if config.operations.compile {
-        "running"
+    print!("{}", overview.render());
+    Ok(())
+}
+
+/// What bare `wm` reports
+struct Overview {
+    wm_dir: PathBuf,
+    dive: Option<String>,
+    extract: String,
+    compile: String,
+    last_extract: Option<DateTime<Local>>,
+    last_distill: Option<DateTime<Local>>,
+}
+
+impl Overview {
+    fn render(&self) -> String {
+        let dive = match &self.dive {
+            Some(name) => color::green(name),
+            None => "none (wm dive new <name>)".to_string(),
+        };
+        let when = |at: Option<DateTime<Local>>, never: &str| match at {
+            Some(at) => at.format("%Y-%m-%d %H:%M").to_string(),
+            None => never.to_string(),
+        };
+        format!(
+            "wm {}: {}\n\
+             dive:         {}\n\
+             extract:      {}\n\
+             compile:      {}\n\
+             last extract: {}\n\
+             last distill: {}\n\n\
+             Run 'wm --help' for all commands.\n",
+            env!("CARGO_PKG_VERSION"),
+            self.wm_dir.display(),
+            dive,
+            colored_status(&self.extract),
+            colored_status(&self.compile),
+            when(self.last_extract, "never"),
+            when(self.last_distill, "never (wm distill)")
+        )
+    }
+}
+
+/// Green for running, red for paused
+fn colored_status(status: &str) -> String {
+    if status == "running" {
+        color::green(status)
     } else {
-        "paused"
-    };
+        color::red(status)
+    }
+}
 
-    println!("extract: {}", extract_status);
-    println!("compile: {}", compile_status);
+/// `running`, `paused`, or `paused (resumes in 25m)` for wm status
+fn operation_status(enabled: bool, paused_until: Option<&str>, now: DateTime<Utc>) -> String {
+    if enabled {
+        return "running".to_string();
+    }
+    match paused_until.and_then(|ts| DateTime::parse_from_rfc3339(ts).ok()) {
+        Some(until) => format!(
+            "paused (resumes in {})",
+            format_remaining(until.with_timezone(&Utc) - now)
+        ),
+        None => "paused".to_string(),
+    }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_args_dispatches_to_overview() {
+        let cli = Cli::try_parse_from(["wm"]).unwrap();
+        assert!(cli.command.is_none());
+        // Subcommands and --help are unaffected
+        assert!(matches!(
+            Cli::try_parse_from(["wm", "status"]).unwrap().command,
+            Some(Commands::Status)
+        ));
+        let help = Cli::try_parse_from(["wm", "--help"]).err().unwrap();
+        assert_eq!(help.kind(), clap::error::ErrorKind::DisplayHelp);
+    }
+
+    #[test]
+    fn test_extract_rejects_gemini_source() {
+        let err = Cli::try_parse_from(["wm", "extract", "--source", "gemini"])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+        assert!(Cli::try_parse_from(["wm", "distill", "--source", "gemini"]).is_ok());
+    }
+
+    #[test]
+    fn test_pause_resume_leave_malformed_config_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(state::CONFIG_FILE);
+        let broken = "[dive]\ncurrent = \"auth\"\n[operations\nextract = false\n";
+        std::fs::write(&path, broken).unwrap();
+
+        let err = pause_in(&path, None, None, "").unwrap_err();
+        assert!(err.contains("left unchanged"), "{}", err);
+        assert!(resume_in(&path, Some("extract")).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), broken);
+
+        // A valid file is updated as before
+        std::fs::write(&path, "[dive]\ncurrent = \"auth\"\n").unwrap();
+        pause_in(&path, Some("compile"), None, "").unwrap();
+        let config = state::parse_config_from(&path).unwrap();
+        assert!(!config.operations.compile);
+        assert_eq!(config.dive.current.as_deref(), Some("auth"));
+    }
+
+    #[test]
+    fn test_overview_render() {
+        // Only lines that are never colored are asserted
+        let overview = Overview {
+            wm_dir: PathBuf::from("/p/.wm"),
+            dive: None,
+            extract: "running".to_string(),
+            compile: "paused".to_string(),
+            last_extract: None,
+            last_distill: None,
+        };
+        let text = overview.render();
+        assert!(text.starts_with("wm "));
+        assert!(text.contains(": /p/.wm\n"));
+        assert!(text.contains("dive:         none (wm dive new <name>)\n"));
+        assert!(text.contains("last extract: never\n"));
+        assert!(text.contains("last distill: never (wm distill)\n"));
+        assert!(text.ends_with("Run 'wm --help' for all commands.\n"));
+    }
+
+    #[test]
+    fn test_bash_completions_generate() {
+        let mut out = Vec::new();
+        write_completions(Shell::Bash, &mut out);
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("wm"));
+        assert!(script.contains("doctor"));
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = DateTime::parse_from_rfc3339("2025-01-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            parse_since("7d", now).unwrap(),
+            now - chrono::Duration::days(7)
+        );
+        assert_eq!(
+            parse_since("2025-01-10T08:30:00+01:00", now).unwrap(),
+            DateTime::parse_from_rfc3339("2025-01-10T07:30:00Z").unwrap()
+        );
+        let date = parse_since("2025-01-10", now).unwrap();
+        assert_eq!(
+            date.with_timezone(&Local).date_naive().to_string(),
+            "2025-01-10"
+        );
+        assert!(parse_since("last week", now).is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(
+            parse_duration("30m").unwrap(),
+            chrono::Duration::minutes(30)
+        );
+        assert_eq!(
+            parse_duration("1h30m").unwrap(),
+            chrono::Duration::minutes(90)
+        );
+        assert_eq!(parse_duration("2d").unwrap(), chrono::Duration::days(2));
+        assert_eq!(
+            parse_duration("45S").unwrap(),
+            chrono::Duration::seconds(45)
+        );
+        for bad in ["", "30", "m", "1x", "0m", "-5m", "1h 30m"] {
+            assert!(parse_duration(bad).is_err(), "{} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_operation_status_shows_remaining() {
+        let now = Utc::now();
+        let until =
+            (now + chrono::Duration::minutes(65) + chrono::Duration::seconds(5)).to_rfc3339();
+        assert_eq!(
+            operation_status(false, Some(&until), now),
+            "paused (resumes in 1h 5m)"
+        );
+        assert_eq!(operation_status(false, None, now), "paused");
+        assert_eq!(operation_status(true, Some(&until), now), "running");
+        assert_eq!(format_remaining(chrono::Duration::seconds(20)), "<1m");
+        assert_eq!(format_remaining(chrono::Duration::hours(50)), "2d 2h");
+    }
 }
@@ -0,0 +1,331 @@
+//! MCP server over stdio (`wm mcp`)
+//!
+//! Lets MCP-aware clients read wm state live instead of scraping CLI output.
+//! Exposes state.md, the latest working set and the dive context as
+//! resources, and a `wm_compile` tool that compiles a working set for an
+//! intent.
+//!
+//! AIDEV-NOTE: Minimal JSON-RPC 2.0, one message per line (the MCP stdio
+//! transport). Only request/response is implemented - no subscriptions,
+//! batching or server-initiated messages. stdout carries protocol messages
+//! only; diagnostics go to .wm/hook.log via state::log.
+
+use crate::compile;
+use crate::state;
+use serde_json::{Value, json};
+use std::io::{BufRead, Write};
+
+/// Protocol version answered when the client asks for one we don't know
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Protocol versions this server can speak (request/response subset)
+const SUPPORTED_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", "2025-06-18"];
+
+/// Tool name for compiling a working set
+/// (underscore rather than a dot: several clients reject dots in tool names)
+const COMPILE_TOOL: &str = "wm_compile";
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// MCP: resource not found
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// A readable wm resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resource {
+    State,
+    WorkingSet,
+    DiveContext,
+}
+
+impl Resource {
+    const ALL: [Resource; 3] = [Resource::State, Resource::WorkingSet, Resource::DiveContext];
+
+    fn uri(self) -> &'static str {
+        match self {
+            Resource::State => "wm://state",
+            Resource::WorkingSet => "wm://working-set",
+            Resource::DiveContext => "wm://dive-context",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Resource::State => "Accumulated project knowledge (.wm/state.md)",
+            Resource::WorkingSet => "Most recently compiled working set",
+            Resource::DiveContext => "Current dive prep context",
+        }
+    }
+
+    fn from_uri(uri: &str) -> Option<Resource> {
+        Resource::ALL.into_iter().find(|r| r.uri() == uri)
+    }
+}
+
+/// Where the server gets wm content (a trait so tests don't need a .wm/)
+trait Workspace {
+    /// Resource text; empty when it doesn't exist yet
+    fn read(&self, resource: Resource) -> String;
+
+    /// Working set for an intent, as the compile hook would build it
+    fn compile(&self, intent: Option<&str>) -> String;
+}
+
+/// The project's .wm/ directory
+struct WmWorkspace;
+
+impl Workspace for WmWorkspace {
+    fn read(&self, resource: Resource) -> String {
+        let path = match resource {
+            Resource::State => Some(state::wm_path("state.md")),
+            Resource::WorkingSet => state::newest_working_set(),
+            Resource::DiveContext => return compile::read_dive_context(),
+        };
+        path.and_then(|p| std::fs::read_to_string(p).ok())
+            .unwrap_or_default()
+    }
+
+    fn compile(&self, intent: Option<&str>) -> String {
+        compile::compile_working_set(intent)
+    }
+}
+
+/// Run wm mcp: serve requests from stdin until it closes
+pub fn run() -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+    state::log("mcp", "Server started");
+
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| format!("Failed to read stdin: {}", e))?;
+        if let Some(response) = handle_line(&WmWorkspace, &line) {
+            writeln!(stdout, "{}", response)
+                .and_then(|_| stdout.flush())
+                .map_err(|e| format!("Failed to write response: {}", e))?;
+        }
+    }
+
+    state::log("mcp", "Server stopped (stdin closed)");
+    Ok(())
+}
+
+/// Handle one line of input; None for notifications and blank lines
+fn handle_line(workspace: &dyn Workspace, line: &str) -> Option<String> {
+    if line.trim().is_empty() {
+        return None;
+    }
+
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(e) => {
+            return Some(error_response(&Value::Null, PARSE_ERROR, &e.to_string()).to_string());
+        }
+    };
+
+    // Notifications (no id) never get a response
+    let id = message.get("id")?.clone();
+
+    let Some(method) = message.get("method").and_then(|m| m.as_str()) else {
+        return Some(error_response(&id, INVALID_REQUEST, "Missing method").to_string());
+    };
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let response = match dispatch(workspace, method, &params) {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err((code, msg)) => error_response(&id, code, &msg),
+    };
+    Some(response.to_string())
+}
+
+fn error_response(id: &Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": code, "message": message},
+    })
+}
+
+/// Result for a request method, or a JSON-RPC error code and message
+fn dispatch(
+    workspace: &dyn Workspace,
+    method: &str,
+    params: &Value,
+) -> Result<Value, (i64, String)> {
+    match method {
+        "initialize" => Ok(initialize(params)),
+        "ping" => Ok(json!({})),
+        "resources/list" => Ok(json!({
+            "resources": Resource::ALL.iter().map(|r| json!({
+                "uri": r.uri(),
+                "name": r.uri().trim_start_matches("wm://"),
+                "description": r.description(),
+                "mimeType": "text/markdown",
+            })).collect::<Vec<_>>(),
+        })),
+        "resources/read" => {
+            let uri = params
+                .get("uri")
+                .and_then(|u| u.as_str())
+                .ok_or((INVALID_PARAMS, "Missing uri".to_string()))?;
+            let resource = Resource::from_uri(uri)
+                .ok_or((RESOURCE_NOT_FOUND, format!("Resource not found: {}", uri)))?;
+            Ok(json!({
+                "contents": [{
+                    "uri": uri,
+                    "mimeType": "text/markdown",
+                    "text": workspace.read(resource),
+                }],
+            }))
+        }
+        "tools/list" => Ok(json!({
+            "tools": [{
+                "name": COMPILE_TOOL,
+                "description": "Compile the wm working set (guardrails, dive context, metis) for an intent, as the prompt hook would inject it",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "intent": {
+                            "type": "string",
+                            "description": "What the user is about to do; selects [compile.routes] sections",
+                        },
+                    },
+                },
+            }],
+        })),
+        "tools/call" => {
+            let name = params.get("name").and_then(|n| n.as_str()).unwrap_or("");
+            if name != COMPILE_TOOL {
+                return Err((INVALID_PARAMS, format!("Unknown tool: {}", name)));
+            }
+            let intent = params
+                .get("arguments")
+                .and_then(|a| a.get("intent"))
+                .and_then(|i| i.as_str());
+            Ok(json!({
+                "content": [{"type": "text", "text": workspace.compile(intent)}],
+                "isError": false,
+            }))
+        }
+        _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+    }
+}
+
+/// initialize result: echo the client's protocol version when supported
+fn initialize(params: &Value) -> Value {
+    let version = params
+        .get("protocolVersion")
+        .and_then(|v| v.as_str())
+        .filter(|v| SUPPORTED_VERSIONS.contains(v))
+        .unwrap_or(PROTOCOL_VERSION);
+    json!({
+        "protocolVersion": version,
+        "capabilities": {"resources": {}, "tools": {}},
+        "serverInfo": {"name": "wm", "version": env!("CARGO_PKG_VERSION")},
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StubWorkspace;
+
+    impl Workspace for StubWorkspace {
+        fn read(&self, resource: Resource) -> String {
+            format!("content of {}", resource.uri())
+        }
+
+        fn compile(&self, intent: Option<&str>) -> String {
+            format!("compiled for {:?}", intent)
+        }
+    }
+
+    fn request(line: &str) -> Value {
+        serde_json::from_str(&handle_line(&StubWorkspace, line).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_resources_list_framing() {
+        let response = request(r#"{"jsonrpc":"2.0","id":1,"method":"resources/list"}"#);
+        assert_eq!(response["jsonrpc"], "2.0");
+        assert_eq!(response["id"], 1);
+        let uris: Vec<&str> = response["result"]["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["uri"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            uris,
+            ["wm://state", "wm://working-set", "wm://dive-context"]
+        );
+    }
+
+    #[test]
+    fn test_resources_read_framing() {
+        let response = request(
+            r#"{"jsonrpc":"2.0","id":"r-2","method":"resources/read","params":{"uri":"wm://state"}}"#,
+        );
+        assert_eq!(response["id"], "r-2");
+        let contents = &response["result"]["contents"][0];
+        assert_eq!(contents["uri"], "wm://state");
+        assert_eq!(contents["mimeType"], "text/markdown");
+        assert_eq!(contents["text"], "content of wm://state");
+
+        let missing = request(
+            r#"{"jsonrpc":"2.0","id":3,"method":"resources/read","params":{"uri":"wm://nope"}}"#,
+        );
+        assert_eq!(missing["error"]["code"], RESOURCE_NOT_FOUND);
+        assert!(missing.get("result").is_none());
+    }
+
+    #[test]
+    fn test_compile_tool_passes_intent() {
+        let response = request(
+            r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"wm_compile","arguments":{"intent":"deploy"}}}"#,
+        );
+        assert_eq!(
+            response["result"]["content"][0]["text"],
+            "compiled for Some(\"deploy\")"
+        );
+    }
+
+    #[test]
+    fn test_notifications_and_errors() {
+        assert_eq!(
+            handle_line(
+                &StubWorkspace,
+                r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#
+            ),
+            None
+        );
+        assert_eq!(handle_line(&StubWorkspace, "  "), None);
+
+        let parse = request("{not json");
+        assert_eq!(parse["error"]["code"], PARSE_ERROR);
+        assert_eq!(parse["id"], Value::Null);
+
+        let unknown = request(r#"{"jsonrpc":"2.0","id":5,"method":"sampling/createMessage"}"#);
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_initialize_negotiates_version() {
+        let known = request(
+            r#"{"jsonrpc":"2.0","id":0,"method":"initialize","params":{"protocolVersion":"2024-11-05"}}"#,
+        );
+        assert_eq!(known["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(known["result"]["serverInfo"]["name"], "wm");
+
+        let future = request(
+            r#"{"jsonrpc":"2.0","id":0,"method":"initialize","params":{"protocolVersion":"2099-01-01"}}"#,
+        );
+        assert_eq!(future["result"]["protocolVersion"], PROTOCOL_VERSION);
+    }
+}
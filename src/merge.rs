@@ -0,0 +1,240 @@
+//! `wm state merge <other>` - fold another project's knowledge into this one
+//!
+//! For repos being split or combined. The default path asks the LLM to merge
+//! the two state.md files into one, de-duplicating with the same synthesis
+//! guide compress uses. `--append` skips the LLM and adds the other state
+//! under a heading. The other project's distilled guardrails are merged into
+//! ours either way.
+//!
+//! AIDEV-NOTE: Pinned sections from both sides bypass the LLM and are
+//! restored verbatim (see pinned.rs), and the result is written like compress
+//! writes: history snapshot, state.md.backup, atomic rename - so
+//! `wm state restore` undoes a merge.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::compress;
+use crate::distill;
+use crate::history;
+use crate::llm::{self, LlmClient};
+use crate::pinned;
+use crate::provenance;
+use crate::state;
+
+/// Run wm state merge <other> [--append]
+/// `other` is the other project's .wm/ directory, or the project containing it.
+pub fn run(other: &Path, append: bool) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let other_dir = resolve_other(other)?;
+    if same_dir(&other_dir, &state::wm_dir()) {
+        return Err("Cannot merge a .wm/ directory into itself.".to_string());
+    }
+    let label = other_dir.display().to_string();
+
+    let theirs = fs::read_to_string(other_dir.join("state.md")).unwrap_or_default();
+
+    if theirs.trim().is_empty() {
+        println!("{} has no state.md content to merge.", label);
+    } else {
+        let state_path = state::wm_path("state.md");
+        let ours = fs::read_to_string(&state_path).unwrap_or_default();
+
+        let merged = if append {
+            append_states(&ours, &theirs, &label)
+        } else {
+            println!("Merging state.md from {}...", label);
+            let client = llm::compress_client()?;
+            synthesize(client.as_ref(), &ours, &theirs)?
+        };
+
+        history::snapshot_state();
+        compress::replace_with_backup(
+            &state_path,
+            &state::wm_path(compress::BACKUP_FILE),
+            &ours,
+            &merged,
+        )?;
+
+        let summary = format!(
+            "{} + {} → {} lines",
+            ours.lines().count(),
+            theirs.lines().count(),
+            merged.lines().count()
+        );
+        state::log(
+            "merge",
+            &format!("Merged state.md from {}: {}", label, summary),
+        );
+        println!("Merged state.md: {}", summary);
+        println!("Backup saved to .wm/state.md.backup (undo: wm state restore)");
+    }
+
+    // Only once state.md is in place, so a failed LLM merge changes nothing
+    let guardrails = merge_guardrails(&other_dir)?;
+    if guardrails > 0 {
+        println!(
+            "  {} new guardrail(s) added to .wm/distill/guardrails.md",
+            guardrails
+        );
+    }
+    Ok(())
+}
+
+/// The other .wm/ directory: `path` itself if it holds state.md, else `path/.wm`
+fn resolve_other(path: &Path) -> Result<PathBuf, String> {
+    if path.join("state.md").exists() {
+        return Ok(path.to_path_buf());
+    }
+    let nested = path.join(".wm");
+    if nested.join("state.md").exists() {
+        return Ok(nested);
+    }
+    Err(format!(
+        "No .wm/ state found at {} (pass the other project's .wm/ directory or its root)",
+        path.display()
+    ))
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Add the other project's distilled guardrails to ours; returns how many were new
+fn merge_guardrails(other_dir: &Path) -> Result<usize, String> {
+    let path = other_dir.join(distill::DISTILL_DIR).join("guardrails.md");
+    let items = distill::items(&fs::read_to_string(path).unwrap_or_default());
+    if items.is_empty() {
+        return Ok(0);
+    }
+    distill::import_items("guardrails.md", "Guardrails", &items)
+}
+
+/// `ours` followed by `theirs` under a heading naming where it came from
+fn append_states(ours: &str, theirs: &str, label: &str) -> String {
+    let ours = ours.trim_end();
+    let theirs = theirs.trim();
+    if ours.is_empty() {
+        return format!("{}\n", theirs);
+    }
+    format!("{}\n\n## Merged from {}\n\n{}\n", ours, label, theirs)
+}
+
+/// One state combining both, via the LLM; pinned blocks from either side are kept
+fn synthesize(llm: &dyn LlmClient, ours: &str, theirs: &str) -> Result<String, String> {
+    let (mut blocks, our_rest) = pinned::split(ours);
+    let (their_blocks, their_rest) = pinned::split(theirs);
+    for block in their_blocks {
+        if !blocks.iter().any(|ours| ours.text == block.text) {
+            blocks.push(block);
+        }
+    }
+
+    let merged = call_merge(
+        llm,
+        &provenance::strip_all(&our_rest),
+        &provenance::strip_all(&their_rest),
+    )?;
+    // Bullets keep the tag of whichever side they came from
+    let tagged_sources = format!("{}\n{}", our_rest, their_rest);
+    let merged = provenance::carry_forward(&tagged_sources, &merged);
+    Ok(pinned::restore(&blocks, &merged))
+}
+
+fn call_merge(llm: &dyn LlmClient, ours: &str, theirs: &str) -> Result<String, String> {
+    let system_prompt = format!(
+        r#"You are merging the accumulated tacit knowledge of two projects into one state.
+
+Every distinct insight from either side must survive. Where both sides say the same thing, keep it once. Where they conflict, keep both and note which project each applies to.
+
+{}
+
+RESPONSE FORMAT:
+WAS_COMPRESSED: YES
+
+<merged markdown content>"#,
+        compress::SYNTHESIS_GUIDE
+    );
+    let message = format!(
+        "CURRENT PROJECT STATE:\n\n{}\n\nOTHER PROJECT STATE:\n\n{}\n\nOUTPUT:",
+        ours, theirs
+    );
+
+    state::log("merge", &format!("Sending {} chars to LLM", message.len()));
+
+    let marker = state::read_config().markers.compress;
+    let response = llm::complete_with_marker(
+        llm,
+        &system_prompt,
+        &message,
+        llm::COMPRESSED_MARKER,
+        &marker,
+    )?;
+    // A merge always has an answer; an empty one would wipe state.md
+    if !response.is_positive || response.content.trim().is_empty() {
+        return Err("LLM returned no merged state; nothing was changed (try --append)".to_string());
+    }
+    Ok(response.content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MockLlmClient;
+
+    #[test]
+    fn test_append_states() {
+        assert_eq!(
+            append_states("- ours\n", "- theirs\n", "../api/.wm"),
+            "- ours\n\n## Merged from ../api/.wm\n\n- theirs\n"
+        );
+        assert_eq!(append_states("", "- theirs", "x"), "- theirs\n");
+    }
+
+    #[test]
+    fn test_synthesize_with_mock_keeps_pinned_from_both() {
+        let ours =
+            "<!-- wm:pinned -->\n- Never force-push main\n<!-- /wm:pinned -->\n\n- Uses sqlite\n";
+        let theirs = "<!-- wm:pinned -->\n- API is versioned under /v2\n<!-- /wm:pinned -->\n\n- Uses sqlite too\n";
+        let mock = MockLlmClient::with_texts(&["WAS_COMPRESSED: YES\n- Both projects use sqlite"]);
+
+        let merged = synthesize(&mock, ours, theirs).unwrap();
+        assert_eq!(
+            merged,
+            "<!-- wm:pinned -->\n- Never force-push main\n<!-- /wm:pinned -->\n\n\
+             <!-- wm:pinned -->\n- API is versioned under /v2\n<!-- /wm:pinned -->\n\n\
+             - Both projects use sqlite\n"
+        );
+
+        // Both unpinned sides reach the LLM; pinned text never does
+        let message = &mock.calls.borrow()[0].1;
+        assert!(message.contains("- Uses sqlite\n"));
+        assert!(message.contains("- Uses sqlite too"));
+        assert!(!message.contains("force-push"));
+    }
+
+    #[test]
+    fn test_synthesize_refuses_empty_answer() {
+        let mock = MockLlmClient::with_texts(&["WAS_COMPRESSED: NO"]);
+        let err = synthesize(&mock, "- a\n", "- b\n").unwrap_err();
+        assert!(err.contains("--append"));
+    }
+
+    #[test]
+    fn test_resolve_other_accepts_project_root() {
+        let root = tempfile::tempdir().unwrap();
+        let wm = root.path().join(".wm");
+        fs::create_dir_all(&wm).unwrap();
+        fs::write(wm.join("state.md"), "- x\n").unwrap();
+
+        assert_eq!(resolve_other(root.path()).unwrap(), wm);
+        assert_eq!(resolve_other(&wm).unwrap(), wm);
+        assert!(resolve_other(&root.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+//! Upgrade legacy .wm/ layouts
+//!
+//! Older versions of wm left files the current code no longer writes:
+//! - OH_context.md: dive context from before dive_context.md/named preps.
+//!   Folded into the current dive prep (or dive_context.md).
+//! - checkpoint.json ({"position": N}): byte-position extraction cursor,
+//!   replaced by timestamp-based extraction_state.json. Dropped.
+//!
+//! Migration is idempotent: once the legacy files are gone it reports nothing to do.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::state;
+
+const LEGACY_OH_CONTEXT: &str = "OH_context.md";
+const LEGACY_CHECKPOINT: &str = "checkpoint.json";
+
+/// Run wm state migrate
+pub fn run() -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let current_prep = state::current_dive().map(|name| state::dive_prep_path(&name));
+    let migrated = migrate(&state::wm_dir(), current_prep.as_deref())?;
+
+    if migrated.is_empty() {
+        println!("Nothing to migrate - .wm/ already uses the current layout.");
+        return Ok(());
+    }
+
+    for line in &migrated {
+        state::log("migrate", line);
+        println!("✓ {}", line);
+    }
+    Ok(())
+}
+
+/// Migrate legacy files in `wm_dir`, returning a description of each change
+fn migrate(wm_dir: &Path, current_prep: Option<&Path>) -> Result<Vec<String>, String> {
+    let mut migrated = Vec::new();
+
+    let oh_context = wm_dir.join(LEGACY_OH_CONTEXT);
+    if oh_context.exists() {
+        let target = fold_oh_context(&oh_context, wm_dir, current_prep)?;
+        migrated.push(format!("Folded {} into {}", LEGACY_OH_CONTEXT, target));
+    }
+
+    if remove_legacy_checkpoint(wm_dir)? {
+        migrated.push(format!(
+            "Removed obsolete {} (extraction now tracks timestamps in extraction_state.json)",
+            LEGACY_CHECKPOINT
+        ));
+    }
+
+    Ok(migrated)
+}
+
+/// Move OH_context.md content into the active dive context; returns the target's name
+/// AIDEV-NOTE: Appends rather than overwrites - the current prep may already hold
+/// curated context that must not be lost. The legacy file is removed only after
+/// the append succeeds.
+fn fold_oh_context(
+    oh_context: &Path,
+    wm_dir: &Path,
+    current_prep: Option<&Path>,
+) -> Result<String, String> {
+    let legacy = fs::read_to_string(oh_context)
+        .map_err(|e| format!("Failed to read {}: {}", LEGACY_OH_CONTEXT, e))?;
+
+    let target: PathBuf = match current_prep {
+        Some(prep) if prep.exists() => prep.to_path_buf(),
+        _ => wm_dir.join("dive_context.md"),
+    };
+
+    let existing = fs::read_to_string(&target).unwrap_or_default();
+    let combined = if existing.trim().is_empty() {
+        legacy
+    } else if legacy.trim().is_empty() {
+        existing
+    } else {
+        format!(
+            "{}\n\n## Open Horizons Context (migrated)\n\n{}\n",
+            existing.trim_end(),
+            legacy.trim()
+        )
+    };
+
+    fs::write(&target, combined)
+        .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+    fs::remove_file(oh_context)
+        .map_err(|e| format!("Failed to remove {}: {}", LEGACY_OH_CONTEXT, e))?;
+
+    Ok(target
+        .strip_prefix(wm_dir)
+        .unwrap_or(&target)
+        .display()
+        .to_string())
+}
+
+/// Legacy files still present in `wm_dir` (what `wm state migrate` would fix)
+pub fn legacy_files(wm_dir: &Path) -> Vec<&'static str> {
+    let mut found = Vec::new();
+    if wm_dir.join(LEGACY_OH_CONTEXT).exists() {
+        found.push(LEGACY_OH_CONTEXT);
+    }
+    if is_legacy_checkpoint(&wm_dir.join(LEGACY_CHECKPOINT)) {
+        found.push(LEGACY_CHECKPOINT);
+    }
+    found
+}
+
+/// Delete an old-format checkpoint.json; true if one was removed
+/// (also run by `wm init --force`, which repairs the layout in place)
+pub fn remove_legacy_checkpoint(wm_dir: &Path) -> Result<bool, String> {
+    let checkpoint = wm_dir.join(LEGACY_CHECKPOINT);
+    if !is_legacy_checkpoint(&checkpoint) {
+        return Ok(false);
+    }
+    fs::remove_file(&checkpoint)
+        .map_err(|e| format!("Failed to remove {}: {}", LEGACY_CHECKPOINT, e))?;
+    Ok(true)
+}
+
+/// A checkpoint.json in the old {"position": N} format
+fn is_legacy_checkpoint(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .is_some_and(|v| v.get("position").is_some_and(|p| p.is_u64()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_folds_oh_context_into_current_prep() {
+        let wm = tempfile::tempdir().unwrap();
+        let dives = wm.path().join("dives");
+        fs::create_dir_all(&dives).unwrap();
+        let prep = dives.join("auth.md");
+        fs::write(&prep, "# Dive: auth\n").unwrap();
+        fs::write(wm.path().join(LEGACY_OH_CONTEXT), "Mission: ship login\n").unwrap();
+        fs::write(wm.path().join(LEGACY_CHECKPOINT), "{\"position\": 0}").unwrap();
+        assert_eq!(
+            legacy_files(wm.path()),
+            [LEGACY_OH_CONTEXT, LEGACY_CHECKPOINT]
+        );
+
+        let migrated = migrate(wm.path(), Some(&prep)).unwrap();
+        assert_eq!(migrated.len(), 2);
+        assert!(migrated[0].contains("dives/auth.md"));
+
+        let content = fs::read_to_string(&prep).unwrap();
+        assert!(content.starts_with("# Dive: auth"));
+        assert!(content.contains("Mission: ship login"));
+        assert!(!wm.path().join(LEGACY_OH_CONTEXT).exists());
+        assert!(!wm.path().join(LEGACY_CHECKPOINT).exists());
+        assert!(legacy_files(wm.path()).is_empty());
+
+        // Idempotent
+        assert!(migrate(wm.path(), Some(&prep)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_without_prep_uses_dive_context() {
+        let wm = tempfile::tempdir().unwrap();
+        fs::write(wm.path().join(LEGACY_OH_CONTEXT), "Mission\n").unwrap();
+
+        let migrated = migrate(wm.path(), None).unwrap();
+        assert_eq!(migrated, ["Folded OH_context.md into dive_context.md"]);
+        assert_eq!(
+            fs::read_to_string(wm.path().join("dive_context.md")).unwrap(),
+            "Mission\n"
+        );
+    }
+
+    #[test]
+    fn test_non_legacy_checkpoint_is_kept() {
+        let wm = tempfile::tempdir().unwrap();
+        fs::write(wm.path().join(LEGACY_CHECKPOINT), "{\"cursor\": \"x\"}").unwrap();
+
+        assert!(migrate(wm.path(), None).unwrap().is_empty());
+        assert!(wm.path().join(LEGACY_CHECKPOINT).exists());
+    }
+}
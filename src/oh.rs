@@ -1,17 +1,32 @@
-//! Open Horizons integration for pushing candidates
+//! Open Horizons integration for pushing candidates and pulling approved items
 //!
-//! Pushes distilled guardrails and metis candidates to OH API.
+//! Pushes distilled guardrails and metis candidates to OH API, and pulls a
+//! context's approved guardrails/metis back into .wm/distill/.
 //! Uses direct HTTP calls since wm runs outside Claude Code's MCP context.
 //!
 //! AIDEV-NOTE: This module talks directly to the OH API, not via MCP.
-//! Requires OH_API_KEY env var. OH_API_URL defaults to https://app.openhorizons.me
+//! Credentials come from OH_API_KEY / OH_API_URL, then `[oh]` in
+//! .wm/config.toml, then ~/.config/openhorizons/config.json. The URL
+//! defaults to https://app.openhorizons.me
 
+use crate::distill::{self, CategorizationResult};
 use crate::state;
+use crate::text;
+use crate::types::{LogLevel, OhConfig};
 use serde::{Deserialize, Serialize};
 
 /// Default OH API URL
 const DEFAULT_OH_API_URL: &str = "https://app.openhorizons.me";
 
+/// Timeout for the connectivity probe (status, doctor)
+const CHECK_TIMEOUT_SECS: u64 = 10;
+
+/// Give up on `wm oh pull` if the API hasn't answered in this many seconds
+const PULL_TIMEOUT_SECS: u64 = 30;
+
+/// Error when no API key is configured
+pub const MISSING_KEY: &str = "OH API key not found. Set OH_API_KEY, `wm config set oh.api_key <key>`, or api_key in ~/.config/openhorizons/config.json";
+
 /// Request body for creating a candidate
 #[derive(Debug, Serialize)]
 struct CreateCandidateRequest<'a> {
@@ -23,63 +38,92 @@ struct CreateCandidateRequest<'a> {
 }
 
 /// Response from creating a candidate
+/// AIDEV-NOTE: Some OH API versions return `id` instead of `candidate_id`.
 #[derive(Debug, Deserialize)]
 struct CreateCandidateResponse {
+    #[serde(alias = "id")]
     candidate_id: String,
 }
 
+/// One item of a context, as returned by `GET /api/contexts/<id>/items`
+/// AIDEV-NOTE: Items without a status are treated as approved (older API
+/// versions only return approved items from this endpoint).
+#[derive(Debug, Deserialize)]
+struct ContextItem {
+    #[serde(rename = "type", alias = "candidate_type")]
+    item_type: String,
+    content: String,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+/// Items list, either bare or wrapped in `{"items": [...]}`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ContextItemsResponse {
+    Bare(Vec<ContextItem>),
+    Wrapped { items: Vec<ContextItem> },
+}
+
+/// Max characters of a response body quoted in parse errors
+const ERROR_BODY_CHARS: usize = 500;
+
 /// Result of pushing candidates to OH
 #[derive(Debug)]
 pub struct PushResult {
-    /// Number of guardrails successfully pushed
-    pub guardrails_pushed: usize,
-    /// Number of metis items successfully pushed
-    pub metis_pushed: usize,
+    /// Guardrails successfully pushed
+    pub guardrails_pushed: Vec<String>,
+    /// Metis items successfully pushed
+    pub metis_pushed: Vec<String>,
     /// Errors encountered (item content, error message)
     pub errors: Vec<(String, String)>,
 }
 
 /// Push guardrails and metis candidates to Open Horizons
 ///
-/// Returns the number of items successfully pushed and any errors.
+/// Each category goes to its own context (the two ids may be the same).
+/// Returns the items successfully pushed and any errors.
 pub fn push_candidates(
-    context_id: &str,
+    guardrails_context_id: &str,
     guardrails: &[String],
+    metis_context_id: &str,
     metis: &[String],
 ) -> Result<PushResult, String> {
-    let api_key = std::env::var("OH_API_KEY")
-        .map_err(|_| "OH_API_KEY environment variable not set".to_string())?;
-
-    let api_url = std::env::var("OH_API_URL").unwrap_or_else(|_| DEFAULT_OH_API_URL.to_string());
+    let (api_url, api_key) = credentials().ok_or_else(|| MISSING_KEY.to_string())?;
 
     state::log(
         "oh",
         &format!(
-            "Pushing {} guardrails and {} metis to OH context {}",
+            "Pushing {} guardrails to OH context {} and {} metis to OH context {}",
             guardrails.len(),
+            guardrails_context_id,
             metis.len(),
-            context_id
+            metis_context_id
         ),
     );
 
     let mut result = PushResult {
-        guardrails_pushed: 0,
-        metis_pushed: 0,
+        guardrails_pushed: Vec::new(),
+        metis_pushed: Vec::new(),
         errors: Vec::new(),
     };
 
     // Push guardrails
     for item in guardrails {
-        match push_single_candidate(&api_url, &api_key, context_id, "guardrail", item) {
+        match push_single_candidate(&api_url, &api_key, guardrails_context_id, "guardrail", item) {
             Ok(candidate_id) => {
                 state::log(
                     "oh",
                     &format!("Created guardrail candidate: {}", candidate_id),
                 );
-                result.guardrails_pushed += 1;
+                result.guardrails_pushed.push(item.clone());
             }
             Err(e) => {
-                state::log("oh", &format!("Failed to push guardrail: {}", e));
+                state::log_at(
+                    LogLevel::Warn,
+                    "oh",
+                    &format!("Failed to push guardrail: {}", e),
+                );
                 result.errors.push((truncate_for_error(item), e));
             }
         }
@@ -87,13 +131,17 @@ pub fn push_candidates(
 
     // Push metis
     for item in metis {
-        match push_single_candidate(&api_url, &api_key, context_id, "metis", item) {
+        match push_single_candidate(&api_url, &api_key, metis_context_id, "metis", item) {
             Ok(candidate_id) => {
                 state::log("oh", &format!("Created metis candidate: {}", candidate_id));
-                result.metis_pushed += 1;
+                result.metis_pushed.push(item.clone());
             }
             Err(e) => {
-                state::log("oh", &format!("Failed to push metis: {}", e));
+                state::log_at(
+                    LogLevel::Warn,
+                    "oh",
+                    &format!("Failed to push metis: {}", e),
+                );
                 result.errors.push((truncate_for_error(item), e));
             }
         }
@@ -102,6 +150,230 @@ pub fn push_candidates(
     Ok(result)
 }
 
+/// Run wm oh pull --context-id <id>
+pub fn run_pull(context_id: &str) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let result = pull_context(context_id)?;
+    let guardrails = distill::import_items("guardrails.md", "Guardrails", &result.guardrails)?;
+    let metis = distill::import_items("metis.md", "Metis", &result.metis)?;
+
+    println!(
+        "Pulled {} guardrail(s) and {} metis item(s) from context {}",
+        result.guardrails.len(),
+        result.metis.len(),
+        context_id
+    );
+    println!(
+        "  {} new guardrail(s) added to .wm/distill/guardrails.md",
+        guardrails
+    );
+    println!(
+        "  {} new metis item(s) added to .wm/distill/metis.md",
+        metis
+    );
+    Ok(())
+}
+
+/// Run wm oh status: report the resolved URL, whether a key is set, and
+/// whether the API accepts it. Errors (non-zero exit) unless reachable.
+pub fn run_status() -> Result<(), String> {
+    let (api_url, api_key) = settings();
+    println!("API URL: {}", api_url);
+    let Some(api_key) = api_key else {
+        println!("API key: not configured");
+        return Err(MISSING_KEY.to_string());
+    };
+    println!("API key: configured");
+
+    match check_connection(&api_url, &api_key) {
+        Ok(()) => {
+            println!("Status:  reachable");
+            Ok(())
+        }
+        Err(e) => {
+            println!("Status:  unreachable");
+            Err(e)
+        }
+    }
+}
+
+/// Fetch a context's approved guardrails and metis from Open Horizons
+pub fn pull_context(context_id: &str) -> Result<CategorizationResult, String> {
+    let (api_url, api_key) = credentials().ok_or_else(|| MISSING_KEY.to_string())?;
+    let url = format!(
+        "{}/api/contexts/{}/items",
+        api_url.trim_end_matches('/'),
+        encode_path_segment(context_id)
+    );
+
+    state::log(
+        "oh",
+        &format!("Pulling items from OH context {}", context_id),
+    );
+
+    let response = ureq::get(&url)
+        .set("Authorization", &format!("Bearer {}", api_key))
+        .timeout(std::time::Duration::from_secs(PULL_TIMEOUT_SECS))
+        .call()
+        .map_err(|e| match e {
+            ureq::Error::Status(code, response) => {
+                let body = response.into_string().unwrap_or_default();
+                format!("HTTP {} - {}", code, body)
+            }
+            other => format!("Request failed: {}", other),
+        })?;
+
+    let body = response
+        .into_string()
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+
+    parse_context_items(&body)
+}
+
+/// Percent-encode a URL path segment (RFC 3986 unreserved characters pass through)
+fn encode_path_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Split an items response into approved guardrails and metis
+fn parse_context_items(body: &str) -> Result<CategorizationResult, String> {
+    let items = match serde_json::from_str::<ContextItemsResponse>(body) {
+        Ok(ContextItemsResponse::Bare(items) | ContextItemsResponse::Wrapped { items }) => items,
+        Err(e) => {
+            return Err(format!(
+                "Failed to parse response: {} (body: {})",
+                e,
+                text::truncate_chars(body.trim(), ERROR_BODY_CHARS)
+            ));
+        }
+    };
+
+    let mut result = CategorizationResult {
+        guardrails: Vec::new(),
+        metis: Vec::new(),
+    };
+    for item in items {
+        let approved = item
+            .status
+            .as_deref()
+            .is_none_or(|s| s.eq_ignore_ascii_case("approved"));
+        let content = item.content.trim();
+        if !approved || content.is_empty() {
+            continue;
+        }
+        match item.item_type.to_ascii_lowercase().as_str() {
+            "guardrail" | "guardrails" => result.guardrails.push(content.to_string()),
+            "metis" => result.metis.push(content.to_string()),
+            _ => {}
+        }
+    }
+    Ok(result)
+}
+
+/// OH API url and key, if a key is configured anywhere
+/// AIDEV-NOTE: Each value resolves independently: env var, then `[oh]` in
+/// .wm/config.toml, then the shared ~/.config/openhorizons/config.json that
+/// the OH CLI writes. Empty values count as unset.
+pub fn credentials() -> Option<(String, String)> {
+    let (api_url, api_key) = settings();
+    api_key.map(|key| (api_url, key))
+}
+
+/// Resolved API url (defaulted) and key (None if not configured)
+fn settings() -> (String, Option<String>) {
+    let config = state::read_config().oh;
+    resolve_credentials(
+        |name| std::env::var(name).ok(),
+        &config,
+        &load_shared_config(),
+    )
+}
+
+/// Resolve url and key from env lookup, wm config, and the shared OH config
+fn resolve_credentials(
+    env: impl Fn(&str) -> Option<String>,
+    config: &OhConfig,
+    shared: &serde_json::Value,
+) -> (String, Option<String>) {
+    let set = |v: &String| !v.trim().is_empty();
+    let resolve = |env_name: &str, configured: &Option<String>, shared_key: &str| {
+        env(env_name)
+            .filter(set)
+            .or_else(|| configured.clone().filter(set))
+            .or_else(|| {
+                shared
+                    .get(shared_key)
+                    .and_then(|v| v.as_str())
+                    .map(String::from)
+                    .filter(set)
+            })
+    };
+    let api_key = resolve("OH_API_KEY", &config.api_key, "api_key");
+    let api_url = resolve("OH_API_URL", &config.api_url, "api_url")
+        .unwrap_or_else(|| DEFAULT_OH_API_URL.to_string());
+    (api_url, api_key)
+}
+
+/// Read ~/.config/openhorizons/config.json (missing or invalid = empty)
+fn load_shared_config() -> serde_json::Value {
+    std::env::var("HOME")
+        .ok()
+        .map(|home| {
+            std::path::PathBuf::from(home)
+                .join(".config")
+                .join("openhorizons")
+                .join("config.json")
+        })
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or(serde_json::Value::Null)
+}
+
+/// Check that the OH API is reachable and accepts the key
+/// AIDEV-NOTE: Any HTTP response other than 401/403 counts as reachable - the
+/// probe only needs to prove the network path and the credentials.
+pub fn check_connection(api_url: &str, api_key: &str) -> Result<(), String> {
+    let url = format!("{}/api/candidates", api_url.trim_end_matches('/'));
+    match ureq::get(&url)
+        .set("Authorization", &format!("Bearer {}", api_key))
+        .timeout(std::time::Duration::from_secs(CHECK_TIMEOUT_SECS))
+        .call()
+    {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code @ (401 | 403), _)) => {
+            Err(format!("HTTP {} - the OH API key was rejected", code))
+        }
+        Err(ureq::Error::Status(_, _)) => Ok(()),
+        Err(ureq::Error::Transport(t)) => Err(describe_transport_error(&t)),
+    }
+}
+
+/// Diagnostic for a probe that never got an HTTP response
+fn describe_transport_error(t: &ureq::Transport) -> String {
+    match t.kind() {
+        // ureq reports read timeouts as I/O transport errors
+        ureq::ErrorKind::Io => format!(
+            "No response within {} seconds - check the API URL and your network",
+            CHECK_TIMEOUT_SECS
+        ),
+        ureq::ErrorKind::Dns | ureq::ErrorKind::ConnectionFailed => {
+            format!("Could not connect - check the API URL ({})", t)
+        }
+        _ => format!("Request failed: {}", t),
+    }
+}
+
 /// Push a single candidate to OH API
 fn push_single_candidate(
     api_url: &str,
@@ -131,28 +403,134 @@ fn push_single_candidate(
             other => format!("Request failed: {}", other),
         })?;
 
-    let response_body: CreateCandidateResponse = response
-        .into_json()
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    let body = response
+        .into_string()
+        .map_err(|e| format!("Failed to read response: {}", e))?;
 
-    Ok(response_body.candidate_id)
+    parse_candidate_id(&body)
+}
+
+/// Extract the candidate id from a create-candidate response body
+/// On failure the raw body is included so API mismatches are debuggable.
+fn parse_candidate_id(body: &str) -> Result<String, String> {
+    serde_json::from_str::<CreateCandidateResponse>(body)
+        .map(|r| r.candidate_id)
+        .map_err(|e| {
+            format!(
+                "Failed to parse response: {} (body: {})",
+                e,
+                text::truncate_chars(body.trim(), ERROR_BODY_CHARS)
+            )
+        })
 }
 
 /// Truncate content for error messages (first 50 chars)
-/// Uses char boundary to avoid UTF-8 panic on multi-byte characters.
 fn truncate_for_error(content: &str) -> String {
-    let truncated: String = content.chars().take(50).collect();
-    if truncated.len() < content.len() {
-        format!("{}...", truncated)
-    } else {
-        truncated
-    }
+    text::truncate_chars(content, 50)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_encode_path_segment_escapes_reserved_characters() {
+        assert_eq!(encode_path_segment("ctx-1_a.b~"), "ctx-1_a.b~");
+        assert_eq!(encode_path_segment("team/ctx 1?x"), "team%2Fctx%201%3Fx");
+    }
+
+    #[test]
+    fn test_parse_candidate_id_accepts_id_field() {
+        assert_eq!(
+            parse_candidate_id(r#"{"candidate_id":"c-1"}"#).unwrap(),
+            "c-1"
+        );
+        assert_eq!(
+            parse_candidate_id(r#"{"id":"c-2","status":"pending"}"#).unwrap(),
+            "c-2"
+        );
+    }
+
+    #[test]
+    fn test_parse_candidate_id_error_includes_body() {
+        let err = parse_candidate_id(r#"{"error":"context not found"}"#).unwrap_err();
+        assert!(err.contains("Failed to parse response"));
+        assert!(err.contains("context not found"));
+    }
+
+    #[test]
+    fn test_parse_context_items_keeps_approved() {
+        let body = r#"{"items":[
+            {"type":"guardrail","content":"Never push to main","status":"approved"},
+            {"type":"guardrail","content":"Maybe lint","status":"pending"},
+            {"type":"metis","content":"Prefer small PRs"},
+            {"type":"aim","content":"Ship v2","status":"approved"}
+        ]}"#;
+        let result = parse_context_items(body).unwrap();
+        assert_eq!(result.guardrails, vec!["Never push to main"]);
+        assert_eq!(result.metis, vec!["Prefer small PRs"]);
+
+        let bare = parse_context_items(r#"[{"candidate_type":"metis","content":"x"}]"#).unwrap();
+        assert_eq!(bare.metis, vec!["x"]);
+
+        let err = parse_context_items(r#"{"error":"not found"}"#).unwrap_err();
+        assert!(err.contains("not found"));
+    }
+
+    #[test]
+    fn test_credentials_fall_back_to_config_when_env_unset() {
+        let config = OhConfig {
+            api_key: Some("cfg-key".to_string()),
+            api_url: Some("https://oh.example".to_string()),
+        };
+        let no_env = |_: &str| None;
+        assert_eq!(
+            resolve_credentials(no_env, &config, &serde_json::Value::Null),
+            (
+                "https://oh.example".to_string(),
+                Some("cfg-key".to_string())
+            )
+        );
+
+        // Shared OH config fills what wm config leaves unset
+        let shared = serde_json::json!({"api_key": "shared-key", "api_url": "https://shared"});
+        assert_eq!(
+            resolve_credentials(no_env, &OhConfig::default(), &shared),
+            ("https://shared".to_string(), Some("shared-key".to_string()))
+        );
+        assert_eq!(
+            resolve_credentials(no_env, &OhConfig::default(), &serde_json::Value::Null),
+            (DEFAULT_OH_API_URL.to_string(), None)
+        );
+    }
+
+    #[test]
+    fn test_credentials_env_takes_precedence() {
+        let config = OhConfig {
+            api_key: Some("cfg-key".to_string()),
+            api_url: None,
+        };
+        let env = |name: &str| (name == "OH_API_KEY").then(|| "env-key".to_string());
+        assert_eq!(
+            resolve_credentials(env, &config, &serde_json::Value::Null),
+            (DEFAULT_OH_API_URL.to_string(), Some("env-key".to_string()))
+        );
+
+        // An empty env var doesn't mask the configured key
+        let empty_env = |_: &str| Some(String::new());
+        assert_eq!(
+            resolve_credentials(empty_env, &config, &serde_json::Value::Null).1,
+            Some("cfg-key".to_string())
+        );
+    }
+
+    #[test]
+    fn test_check_connection_reports_unreachable_host() {
+        // Port 9 on localhost: nothing listens, so the connect fails fast
+        let err = check_connection("http://127.0.0.1:9", "key").unwrap_err();
+        assert!(err.starts_with("Could not connect"), "{}", err);
+    }
+
     #[test]
     fn test_truncate_for_error_short() {
         assert_eq!(truncate_for_error("short"), "short");
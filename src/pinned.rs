@@ -0,0 +1,218 @@
+//! Pinned sections of state.md
+//!
+//! Text between `<!-- wm:pinned -->` and `<!-- /wm:pinned -->` lines is
+//! hand-curated. Extract and compress take it out before the LLM rewrites
+//! state.md and put it back verbatim afterwards, so the generative pass can't
+//! reorder, reword or drop it.
+//!
+//! AIDEV-NOTE: The LLM never sees pinned blocks, so each one remembers where
+//! it sat: the heading above it, and whether it came straight after that
+//! heading or after the section's content. It goes back to the same spot in
+//! the regenerated text; if the LLM dropped or renamed the heading, the block
+//! goes to the end. An opening marker without a close pins everything after
+//! it rather than risk losing hand-written text.
+
+/// Line that opens a pinned block
+pub const OPEN: &str = "<!-- wm:pinned -->";
+
+/// Line that closes a pinned block
+pub const CLOSE: &str = "<!-- /wm:pinned -->";
+
+/// A pinned block (markers included) and where it goes back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub text: String,
+
+    /// Nearest heading above the block (None: before any heading)
+    heading: Option<String>,
+
+    /// Directly under the heading, before any of the section's content
+    at_start: bool,
+}
+
+/// Split content into pinned blocks and everything else
+pub fn split(content: &str) -> (Vec<Block>, String) {
+    let mut blocks = Vec::new();
+    let mut rest = Vec::new();
+    let mut block: Option<Vec<&str>> = None;
+    let mut heading: Option<&str> = None;
+    let mut section_has_content = false;
+    let mut in_fence = false;
+
+    for line in content.lines() {
+        match block.as_mut() {
+            Some(lines) => {
+                lines.push(line);
+                if line.trim() == CLOSE {
+                    blocks.push(Block {
+                        text: lines.join("\n"),
+                        heading: heading.map(String::from),
+                        at_start: !section_has_content,
+                    });
+                    block = None;
+                }
+            }
+            None if line.trim() == OPEN => block = Some(vec![line]),
+            None => {
+                if is_fence(line) {
+                    in_fence = !in_fence;
+                }
+                if !in_fence && line.starts_with('#') {
+                    heading = Some(line);
+                    section_has_content = false;
+                } else if !line.trim().is_empty() {
+                    section_has_content = true;
+                }
+                rest.push(line);
+            }
+        }
+    }
+    if let Some(lines) = block {
+        blocks.push(Block {
+            text: lines.join("\n"),
+            heading: heading.map(String::from),
+            at_start: !section_has_content,
+        });
+    }
+
+    if blocks.is_empty() {
+        return (blocks, content.to_string());
+    }
+    (blocks, rest.join("\n").trim().to_string() + "\n")
+}
+
+/// Put pinned blocks back into regenerated content where they were
+/// Pinned blocks the generated text contains anyway are dropped from it, so
+/// they never appear twice.
+pub fn restore(blocks: &[Block], generated: &str) -> String {
+    if blocks.is_empty() {
+        return generated.to_string();
+    }
+    let (_, generated) = split(generated);
+    let lines: Vec<&str> = generated.trim().lines().collect();
+    let headings = heading_flags(&lines);
+
+    // Blocks to insert before each line (the last slot is the end)
+    let mut slots: Vec<Vec<&str>> = vec![Vec::new(); lines.len() + 1];
+    for block in blocks {
+        slots[insert_at(block, &lines, &headings)].push(&block.text);
+    }
+
+    let mut out: Vec<&str> = Vec::new();
+    let mut need_blank = false;
+    for (i, slot) in slots.iter().enumerate() {
+        for text in slot {
+            if out.last().is_some_and(|line| !line.trim().is_empty()) {
+                out.push("");
+            }
+            out.push(text);
+            need_blank = true;
+        }
+        if let Some(line) = lines.get(i) {
+            if need_blank && !line.trim().is_empty() {
+                out.push("");
+            }
+            need_blank = false;
+            out.push(line);
+        }
+    }
+    out.join("\n").trim().to_string() + "\n"
+}
+
+/// Index of the line `block` goes before in `lines`
+fn insert_at(block: &Block, lines: &[&str], headings: &[bool]) -> usize {
+    let section_start = match &block.heading {
+        None => 0,
+        Some(heading) => match (0..lines.len()).find(|&i| headings[i] && lines[i] == heading) {
+            Some(i) => i + 1,
+            None => return lines.len(),
+        },
+    };
+    if block.at_start {
+        return section_start;
+    }
+    (section_start..lines.len())
+        .find(|&i| headings[i])
+        .unwrap_or(lines.len())
+}
+
+/// Which lines are markdown headings (not `#` lines inside code fences)
+fn heading_flags(lines: &[&str]) -> Vec<bool> {
+    let mut in_fence = false;
+    lines
+        .iter()
+        .map(|line| {
+            if is_fence(line) {
+                in_fence = !in_fence;
+            }
+            !in_fence && line.starts_with('#')
+        })
+        .collect()
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_restore_round_trip() {
+        let state = "# State\n\n<!-- wm:pinned -->\n## Team rules\n- Squash merges only\n<!-- /wm:pinned -->\n\n- Uses sqlite\n";
+        let (blocks, rest) = split(state);
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(
+            blocks[0].text,
+            "<!-- wm:pinned -->\n## Team rules\n- Squash merges only\n<!-- /wm:pinned -->"
+        );
+        assert_eq!(rest, "# State\n\n\n- Uses sqlite\n");
+        assert_eq!(
+            restore(&blocks, "# State\n\n- Uses postgres"),
+            "# State\n\n<!-- wm:pinned -->\n## Team rules\n- Squash merges only\n<!-- /wm:pinned -->\n\n- Uses postgres\n"
+        );
+    }
+
+    #[test]
+    fn test_no_markers_is_untouched() {
+        let state = "- a\n- b";
+        assert_eq!(split(state), (Vec::new(), state.to_string()));
+        assert_eq!(restore(&[], "- c"), "- c");
+    }
+
+    #[test]
+    fn test_unterminated_block_pins_the_rest() {
+        let (blocks, rest) = split("- a\n<!-- wm:pinned -->\n- keep me\n");
+        assert_eq!(blocks[0].text, "<!-- wm:pinned -->\n- keep me");
+        assert_eq!(rest, "- a\n");
+    }
+
+    #[test]
+    fn test_pinned_block_mid_document_stays_in_its_section() {
+        let pinned = "<!-- wm:pinned -->\n- Squash merges only\n<!-- /wm:pinned -->";
+        let state = format!(
+            "# State\n\n## Workflow\n- Small PRs\n\n{}\n\n## Stack\n- Uses sqlite\n",
+            pinned
+        );
+        let (blocks, _) = split(&state);
+
+        let regenerated =
+            "# State\n\n## Workflow\n- Small PRs\n- Review same day\n\n## Stack\n- Uses postgres";
+        assert_eq!(
+            restore(&blocks, regenerated),
+            format!(
+                "# State\n\n## Workflow\n- Small PRs\n- Review same day\n\n{}\n\n## Stack\n- Uses postgres\n",
+                pinned
+            )
+        );
+
+        // Heading gone from the regenerated text: the block goes last
+        assert_eq!(
+            restore(&blocks, "# State\n\n- Uses postgres"),
+            format!("# State\n\n- Uses postgres\n\n{}\n", pinned)
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! System prompt overrides from .wm/prompts/
+//!
+//! Each LLM operation has a built-in system prompt. Dropping a markdown file
+//! into .wm/prompts/ replaces it for this project, so teams can tune capture
+//! conventions without rebuilding wm:
+//!
+//! - `extract.md`    - per-turn `wm extract` (answers with the state to keep)
+//! - `distill.md`    - `wm distill` Pass 1, one session at a time
+//! - `categorize.md` - `wm distill` Pass 2 (GUARDRAILS:/METIS: sections)
+//! - `compress.md`   - `wm compress`
+//!
+//! AIDEV-NOTE: An override replaces the whole prompt, so it has to ask for
+//! the same response format the built-in one does (the marker line, or the
+//! GUARDRAILS:/METIS: sections). Markers are still rewritten to the
+//! configured `[markers]` names. `wm compile` makes no LLM call and has no
+//! prompt to override.
+
+use crate::state;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Directory under .wm/ holding prompt overrides
+pub const PROMPTS_DIR: &str = "prompts";
+
+/// An operation whose system prompt can be overridden
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prompt {
+    Extract,
+    Distill,
+    Categorize,
+    Compress,
+}
+
+impl Prompt {
+    /// Override file name under PROMPTS_DIR
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Prompt::Extract => "extract.md",
+            Prompt::Distill => "distill.md",
+            Prompt::Categorize => "categorize.md",
+            Prompt::Compress => "compress.md",
+        }
+    }
+}
+
+/// System prompt for `prompt`: the project's override when present, else `default`
+pub fn system_prompt(prompt: Prompt, default: &str) -> String {
+    system_prompt_in(&state::wm_path(PROMPTS_DIR), prompt, default)
+}
+
+/// Like `system_prompt`, reading overrides from an explicit directory
+pub fn system_prompt_in(dir: &Path, prompt: Prompt, default: &str) -> String {
+    read_cached(&dir.join(prompt.file_name())).unwrap_or_else(|| default.to_string())
+}
+
+/// Read an override once per process (distill asks for every session)
+/// Missing, unreadable and blank files all mean "use the default".
+fn read_cached(path: &Path) -> Option<String> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, Option<String>>>> = OnceLock::new();
+    let mut cache = CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    cache
+        .entry(path.to_path_buf())
+        .or_insert_with(|| {
+            std::fs::read_to_string(path)
+                .ok()
+                .filter(|text| !text.trim().is_empty())
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_prompt_file_preferred_over_default() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("categorize.md"),
+            "Sort into GUARDRAILS: and METIS: per our wiki.\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("compress.md"), "  \n").unwrap();
+
+        assert_eq!(
+            system_prompt_in(dir.path(), Prompt::Categorize, "built-in"),
+            "Sort into GUARDRAILS: and METIS: per our wiki.\n"
+        );
+        // Missing and blank overrides fall back to the built-in prompt
+        assert_eq!(
+            system_prompt_in(dir.path(), Prompt::Extract, "built-in"),
+            "built-in"
+        );
+        assert_eq!(
+            system_prompt_in(dir.path(), Prompt::Compress, "built-in"),
+            "built-in"
+        );
+    }
+}
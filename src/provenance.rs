@@ -0,0 +1,214 @@
+//! Provenance tags on captured knowledge
+//!
+//! Bullets in state.md can carry a trailing HTML comment recording where they
+//! came from: `- Insight text <!-- session:<id> date:<YYYY-MM-DD> -->`.
+//! The comment is invisible in rendered markdown.
+//!
+//! AIDEV-NOTE: Tags are stripped before state.md goes into any LLM prompt
+//! (extract, compress) and re-attached afterwards by `carry_forward`, so the
+//! model never sees or mangles them. Bullets the LLM merged inherit the most
+//! recent tag of the old bullets they absorbed.
+
+use chrono::NaiveDate;
+use std::collections::HashSet;
+
+const TAG_OPEN: &str = "<!-- session:";
+const TAG_CLOSE: &str = "-->";
+
+/// Where and when a bullet was captured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub session_id: String,
+    pub date: NaiveDate,
+}
+
+/// Parse the provenance tag from a line, if present and well-formed
+pub fn parse(line: &str) -> Option<Provenance> {
+    let start = line.rfind(TAG_OPEN)?;
+    let rest = &line[start + TAG_OPEN.len()..];
+    let body = &rest[..rest.find(TAG_CLOSE)?];
+
+    let mut parts = body.split_whitespace();
+    let session_id = parts.next()?.to_string();
+    let date = parts.next()?.strip_prefix("date:")?;
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+
+    Some(Provenance { session_id, date })
+}
+
+/// Remove a provenance tag from a line (returns the line unchanged if untagged)
+pub fn strip(line: &str) -> &str {
+    match line.rfind(TAG_OPEN) {
+        Some(start) if parse(line).is_some() => line[..start].trim_end(),
+        _ => line,
+    }
+}
+
+/// Share of an old bullet's words that must be exceeded for it to count as merged
+const MERGE_OVERLAP: f64 = 0.5;
+
+impl Provenance {
+    /// The trailing HTML comment for this provenance
+    pub fn tag(&self) -> String {
+        format!(
+            "{}{} date:{} {}",
+            TAG_OPEN,
+            self.session_id,
+            self.date.format("%Y-%m-%d"),
+            TAG_CLOSE
+        )
+    }
+}
+
+/// Remove provenance tags from every line (for text sent to an LLM)
+pub fn strip_all(text: &str) -> String {
+    let stripped: Vec<&str> = text.lines().map(strip).collect();
+    let mut output = stripped.join("\n");
+    if text.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+/// Re-attach tags from `old` to the untagged bullets of `new`
+///
+/// An unchanged bullet gets its old tag back. A rewritten or merged bullet gets
+/// the most recent tag among the old bullets whose words it mostly contains.
+/// Bullets with no ancestor stay untagged (see `tag_untagged`).
+pub fn carry_forward(old: &str, new: &str) -> String {
+    let ancestors: Vec<(HashSet<String>, Provenance)> = old
+        .lines()
+        .filter(|line| is_bullet(line))
+        .filter_map(|line| parse(line).map(|p| (words(strip(line)), p)))
+        .collect();
+
+    map_untagged_bullets(new, |line| {
+        let line_words = words(line);
+        ancestors
+            .iter()
+            .filter(|(old_words, _)| overlap(old_words, &line_words) > MERGE_OVERLAP)
+            .map(|(_, p)| p)
+            .max_by_key(|p| p.date)
+            .cloned()
+    })
+}
+
+/// Tag every bullet that has no provenance yet with `provenance`
+pub fn tag_untagged(text: &str, provenance: &Provenance) -> String {
+    map_untagged_bullets(text, |_| Some(provenance.clone()))
+}
+
+/// Apply `assign` to each untagged bullet line, appending the tag it returns
+fn map_untagged_bullets(text: &str, assign: impl Fn(&str) -> Option<Provenance>) -> String {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            if !is_bullet(line) || parse(line).is_some() {
+                return line.to_string();
+            }
+            match assign(line) {
+                Some(p) => format!("{} {}", line.trim_end(), p.tag()),
+                None => line.to_string(),
+            }
+        })
+        .collect();
+
+    let mut output = lines.join("\n");
+    if text.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+/// Markdown list item with content
+fn is_bullet(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    (trimmed.starts_with("- ") || trimmed.starts_with("* ")) && trimmed.len() > 2
+}
+
+/// Lowercased words of 3+ characters (ignores bullet markers and punctuation)
+fn words(line: &str) -> HashSet<String> {
+    line.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Fraction of `old` words that appear in `new`
+fn overlap(old: &HashSet<String>, new: &HashSet<String>) -> f64 {
+    if old.is_empty() {
+        return 0.0;
+    }
+    old.intersection(new).count() as f64 / old.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tag() {
+        let line = "- Prefers small commits <!-- session:abc-123 date:2025-01-15 -->";
+        let p = parse(line).unwrap();
+        assert_eq!(p.session_id, "abc-123");
+        assert_eq!(p.date, NaiveDate::from_ymd_opt(2025, 1, 15).unwrap());
+        assert_eq!(strip(line), "- Prefers small commits");
+    }
+
+    #[test]
+    fn test_untagged_and_malformed_lines() {
+        assert!(parse("- Plain bullet").is_none());
+        assert!(parse("- Bad <!-- session:abc date:yesterday -->").is_none());
+        assert_eq!(strip("- Plain bullet"), "- Plain bullet");
+        // Malformed tags are left alone rather than silently dropped
+        assert_eq!(
+            strip("- Bad <!-- session:abc date:yesterday -->"),
+            "- Bad <!-- session:abc date:yesterday -->"
+        );
+    }
+
+    fn prov(session: &str, date: &str) -> Provenance {
+        Provenance {
+            session_id: session.to_string(),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_tag_round_trips() {
+        let p = prov("abc-123", "2025-01-15");
+        let line = format!("- Use tabs {}", p.tag());
+        assert_eq!(parse(&line), Some(p));
+        assert_eq!(
+            strip_all(&format!("# State\n{}\n", line)),
+            "# State\n- Use tabs\n"
+        );
+    }
+
+    #[test]
+    fn test_carry_forward_keeps_and_merges_tags() {
+        let old = "# State\n\
+- Run cargo fmt before commits <!-- session:s1 date:2025-01-10 -->\n\
+- Run clippy before commits <!-- session:s2 date:2025-02-01 -->\n\
+- Prefer tabs <!-- session:s3 date:2025-01-05 -->\n";
+        let new = "# State\n- Prefer tabs\n- Run cargo fmt and clippy before commits\n- Brand new insight\n";
+
+        let carried = carry_forward(old, new);
+        let lines: Vec<&str> = carried.lines().collect();
+        assert_eq!(parse(lines[1]), Some(prov("s3", "2025-01-05")));
+        // Merged bullet keeps the most recent of its sources
+        assert_eq!(parse(lines[2]), Some(prov("s2", "2025-02-01")));
+        assert_eq!(lines[3], "- Brand new insight");
+
+        let tagged = tag_untagged(&carried, &prov("s4", "2025-03-01"));
+        assert_eq!(
+            parse(tagged.lines().nth(3).unwrap()),
+            Some(prov("s4", "2025-03-01"))
+        );
+        assert_eq!(
+            parse(tagged.lines().nth(1).unwrap()),
+            Some(prov("s3", "2025-01-05"))
+        );
+        assert!(!tagged.lines().next().unwrap().contains("<!--"));
+    }
+}
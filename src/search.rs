@@ -0,0 +1,147 @@
+//! Search captured knowledge
+//!
+//! Scans state.md, distill/guardrails.md, distill/metis.md, and named dive preps
+//! for matching lines. Case-insensitive substring by default, regex on request.
+
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::state;
+
+/// A single matching line
+#[derive(Debug, Serialize)]
+pub struct SearchMatch {
+    /// Path relative to .wm/ (e.g. "distill/metis.md")
+    pub file: String,
+    /// 1-based line number
+    pub line: usize,
+    pub text: String,
+}
+
+/// How a line is tested against the query
+enum Matcher {
+    Substring(String),
+    Pattern(Regex),
+}
+
+impl Matcher {
+    fn new(query: &str, regex: bool) -> Result<Self, String> {
+        if regex {
+            RegexBuilder::new(query)
+                .case_insensitive(true)
+                .build()
+                .map(Matcher::Pattern)
+                .map_err(|e| format!("Invalid regex: {}", e))
+        } else {
+            Ok(Matcher::Substring(query.to_lowercase()))
+        }
+    }
+
+    fn is_match(&self, line: &str) -> bool {
+        match self {
+            Matcher::Substring(needle) => line.to_lowercase().contains(needle),
+            Matcher::Pattern(re) => re.is_match(line),
+        }
+    }
+}
+
+/// Run wm search <query> [--regex] [--json]
+pub fn run(query: &str, regex: bool, json: bool) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let matcher = Matcher::new(query, regex)?;
+    let mut matches = Vec::new();
+    for (label, path) in knowledge_files() {
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            matches.extend(search_content(&label, &content, &matcher));
+        }
+    }
+
+    if json {
+        let out = serde_json::to_string_pretty(&matches)
+            .map_err(|e| format!("Failed to serialize matches: {}", e))?;
+        println!("{}", out);
+        return Ok(());
+    }
+
+    if matches.is_empty() {
+        println!("No matches for '{}'.", query);
+        return Ok(());
+    }
+
+    for m in &matches {
+        println!("{}:{}: {}", m.file, m.line, m.text);
+    }
+
+    Ok(())
+}
+
+/// Files to search, as (label relative to .wm/, full path)
+fn knowledge_files() -> Vec<(String, PathBuf)> {
+    let mut files: Vec<(String, PathBuf)> =
+        ["state.md", "distill/guardrails.md", "distill/metis.md"]
+            .iter()
+            .map(|name| (name.to_string(), state::wm_path(name)))
+            .collect();
+
+    let mut preps = state::list_dive_preps().unwrap_or_default();
+    preps.sort();
+    for name in preps {
+        files.push((format!("dives/{}.md", name), state::dive_prep_path(&name)));
+    }
+
+    files
+}
+
+/// Collect matching lines from one file's content
+fn search_content(label: &str, content: &str, matcher: &Matcher) -> Vec<SearchMatch> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| matcher.is_match(line))
+        .map(|(i, line)| SearchMatch {
+            file: label.to_string(),
+            line: i + 1,
+            text: line.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str =
+        "# Metis\n- Prefer small commits\n- Run Tests before pushing\n- Avoid mocks";
+
+    #[test]
+    fn test_substring_is_case_insensitive() {
+        let matcher = Matcher::new("tests", false).unwrap();
+        let matches = search_content("distill/metis.md", CONTENT, &matcher);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line, 3);
+        assert_eq!(matches[0].file, "distill/metis.md");
+    }
+
+    #[test]
+    fn test_regex_matching() {
+        let matcher = Matcher::new(r"^- (prefer|avoid)", true).unwrap();
+        let matches = search_content("state.md", CONTENT, &matcher);
+        let lines: Vec<usize> = matches.iter().map(|m| m.line).collect();
+        assert_eq!(lines, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_substring_treats_regex_chars_literally() {
+        let matcher = Matcher::new("(", false).unwrap();
+        assert!(search_content("state.md", CONTENT, &matcher).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_regex_errors() {
+        assert!(Matcher::new("(", true).is_err());
+    }
+}
@@ -6,6 +6,7 @@
 //! AIDEV-NOTE: This module is foundational for the distill command which needs to process
 //! all sessions in batch. Used by yz-yb9q (distill CLI).
 
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
 // Re-export SessionInfo for backward compatibility
@@ -13,29 +14,78 @@ pub use crate::types::SessionInfo;
 use crate::types::system_time_to_datetime;
 
 /// Compute project-id from a project path
-/// Converts absolute path to Claude's project-id format: slashes become dashes
+/// Converts absolute path to Claude's project-id format: separators become dashes
 ///
 /// Example: /Users/drazen/playground/ai-omnibus/wm -> -Users-drazen-playground-ai-omnibus-wm
+/// Example: C:\Users\me\proj -> C--Users-me-proj
 pub fn compute_project_id(project_path: &Path) -> String {
     // Get absolute path
     let abs_path = project_path
         .canonicalize()
         .unwrap_or_else(|_| project_path.to_path_buf());
 
-    // Convert to string and replace / with -
-    let path_str = abs_path.to_string_lossy();
-    path_str.replace('/', "-")
+    project_id_from_path(&abs_path.to_string_lossy())
 }
 
-/// Get the Claude projects directory (~/.claude/projects/)
+/// Map an absolute path string to a project id
+/// AIDEV-NOTE: On Windows, canonicalize() yields a verbatim `\\?\C:\...` path.
+/// Claude Code names the directory from the plain path with `\` and the drive
+/// colon replaced, so strip the prefix and map all three. Unix paths only
+/// contain `/`, so their ids are unchanged.
+fn project_id_from_path(path: &str) -> String {
+    let path = path.strip_prefix(r"\\?\").unwrap_or(path);
+    path.replace(['/', '\\', ':'], "-")
+}
+
+/// Env var that overrides the Claude projects directory
+pub const CLAUDE_PROJECTS_DIR_VAR: &str = "WM_CLAUDE_PROJECTS_DIR";
+
+/// Get the Claude projects directory (~/.claude/projects/, or WM_CLAUDE_PROJECTS_DIR)
 pub fn claude_projects_dir() -> Option<PathBuf> {
-    dirs::home_dir().map(|home| home.join(".claude").join("projects"))
+    resolve_user_dir(CLAUDE_PROJECTS_DIR_VAR, &[".claude", "projects"])
+}
+
+/// Resolve a directory under the user's home, honoring an env var override
+/// AIDEV-NOTE: `dirs::home_dir()` is None in minimal containers or with HOME unset.
+/// The override lets CI point discovery at a mounted directory; when neither is
+/// available, a warning is logged so empty discovery isn't silent.
+pub fn resolve_user_dir(override_var: &str, relative: &[&str]) -> Option<PathBuf> {
+    let resolved = dir_from(std::env::var(override_var).ok(), dirs::home_dir(), relative);
+    if resolved.is_none() {
+        crate::state::log("session", &home_unavailable_message(override_var));
+    }
+    resolved
+}
+
+/// Error text when neither HOME nor the override is available
+pub fn home_unavailable_message(override_var: &str) -> String {
+    format!(
+        "Could not determine home directory. Set HOME or {} to locate sessions.",
+        override_var
+    )
+}
+
+/// Pure resolution: a non-empty override wins, otherwise home joined with `relative`
+fn dir_from(
+    override_value: Option<String>,
+    home: Option<PathBuf>,
+    relative: &[&str],
+) -> Option<PathBuf> {
+    if let Some(dir) = override_value.filter(|d| !d.trim().is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    home.map(|home| relative.iter().fold(home, |path, part| path.join(part)))
 }
 
 /// Get the project directory for a given project path
 /// Returns None if the directory doesn't exist
 pub fn get_project_dir(project_path: &Path) -> Option<PathBuf> {
-    let projects_dir = claude_projects_dir()?;
+    project_dir_in(&claude_projects_dir()?, project_path)
+}
+
+/// Exact project directory for `project_path` under an explicit projects root
+/// Unlike find_projects_by_filter, `/x/wm` never matches `/x/swarm`.
+pub fn project_dir_in(projects_dir: &Path, project_path: &Path) -> Option<PathBuf> {
     let project_id = compute_project_id(project_path);
     let project_dir = projects_dir.join(&project_id);
 
@@ -50,6 +100,9 @@ pub fn get_project_dir(project_path: &Path) -> Option<PathBuf> {
 ///
 /// Returns sessions sorted by modification time (newest first)
 pub fn discover_sessions(project_path: &Path) -> Result<Vec<SessionInfo>, String> {
+    if claude_projects_dir().is_none() {
+        return Err(home_unavailable_message(CLAUDE_PROJECTS_DIR_VAR));
+    }
     let project_dir = get_project_dir(project_path)
         .ok_or_else(|| format!("No Claude project directory found for {:?}", project_path))?;
 
@@ -66,13 +119,8 @@ pub fn discover_sessions_in_dir(project_dir: &Path) -> Result<Vec<SessionInfo>,
         .filter_map(|entry| {
             let path = entry.path();
 
-            // Only consider .jsonl files
-            if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
-                return None;
-            }
-
-            // Session ID is the filename without extension
-            let session_id = path.file_stem()?.to_str()?.to_string();
+            // Only consider .jsonl (or archived .jsonl.gz) files
+            let session_id = transcript_session_id(&path)?.to_string();
 
             // Get metadata for timestamps and size
             let metadata = std::fs::metadata(&path).ok()?;
@@ -91,12 +139,32 @@ pub fn discover_sessions_in_dir(project_dir: &Path) -> Result<Vec<SessionInfo>,
         })
         .collect();
 
+    // A session both live and archived is read from the live file
+    let live: HashSet<String> = sessions
+        .iter()
+        .filter(|s| !is_gzipped(&s.transcript_path))
+        .map(|s| s.session_id.clone())
+        .collect();
+    sessions.retain(|s| !is_gzipped(&s.transcript_path) || !live.contains(&s.session_id));
+
     // Sort by modification time, newest first
-    sessions.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.modified_at));
 
     Ok(sessions)
 }
 
+/// Session id of a transcript file: its name minus `.jsonl` or `.jsonl.gz`
+/// None for anything else in the project directory.
+fn transcript_session_id(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    let id = name.strip_suffix(".gz").unwrap_or(name);
+    id.strip_suffix(".jsonl").filter(|id| !id.is_empty())
+}
+
+fn is_gzipped(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
 /// Get the current project path
 /// Uses CLAUDE_PROJECT_DIR if set, otherwise current working directory
 pub fn current_project_path() -> PathBuf {
@@ -124,8 +192,8 @@ pub struct ProjectInfo {
 ///
 /// Returns projects sorted alphabetically by project_id
 pub fn list_all_projects() -> Result<Vec<ProjectInfo>, String> {
-    let projects_dir = claude_projects_dir()
-        .ok_or_else(|| "Could not determine Claude projects directory".to_string())?;
+    let projects_dir =
+        claude_projects_dir().ok_or_else(|| home_unavailable_message(CLAUDE_PROJECTS_DIR_VAR))?;
 
     if !projects_dir.exists() {
         return Ok(Vec::new());
@@ -146,12 +214,13 @@ pub fn list_all_projects() -> Result<Vec<ProjectInfo>, String> {
 
             let project_id = path.file_name()?.to_str()?.to_string();
 
-            // Count .jsonl files (sessions)
+            // Count sessions (live .jsonl and archived .jsonl.gz, once each)
             let session_count = std::fs::read_dir(&path)
                 .ok()?
                 .filter_map(|e| e.ok())
-                .filter(|e| e.path().extension().and_then(|ext| ext.to_str()) == Some("jsonl"))
-                .count();
+                .filter_map(|e| transcript_session_id(&e.path()).map(String::from))
+                .collect::<HashSet<_>>()
+                .len();
 
             Some(ProjectInfo {
                 project_id,
@@ -182,6 +251,76 @@ pub fn find_projects_by_filter(filter: &str) -> Result<Vec<ProjectInfo>, String>
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_project_dir_in_matches_exact_path_only() {
+        let projects = tempfile::tempdir().unwrap();
+        for path in ["/nonexistent/work/wm", "/nonexistent/work/swarm"] {
+            std::fs::create_dir(projects.path().join(compute_project_id(Path::new(path)))).unwrap();
+        }
+
+        assert_eq!(
+            project_dir_in(projects.path(), Path::new("/nonexistent/work/wm")),
+            Some(projects.path().join("-nonexistent-work-wm"))
+        );
+        // A substring filter for "work-arm" would hit swarm; an exact path doesn't
+        assert_eq!(
+            project_dir_in(projects.path(), Path::new("/nonexistent/work/arm")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_discover_includes_archived_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "live.jsonl",
+            "old.jsonl.gz",
+            "both.jsonl",
+            "both.jsonl.gz",
+            "notes.txt",
+        ] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let mut found: Vec<(String, String)> = discover_sessions_in_dir(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|s| {
+                let file = s.transcript_path.file_name().unwrap().to_string_lossy();
+                (s.session_id, file.into_owned())
+            })
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                ("both".to_string(), "both.jsonl".to_string()),
+                ("live".to_string(), "live.jsonl".to_string()),
+                ("old".to_string(), "old.jsonl.gz".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_project_id_windows_paths() {
+        assert_eq!(
+            project_id_from_path(r"C:\Users\me\proj"),
+            "C--Users-me-proj"
+        );
+        assert_eq!(
+            project_id_from_path(r"\\?\D:\work\my-app"),
+            "D--work-my-app"
+        );
+    }
+
+    #[test]
+    fn test_project_id_unix_unchanged() {
+        assert_eq!(
+            project_id_from_path("/Users/drazen/playground/ai-omnibus/wm"),
+            "-Users-drazen-playground-ai-omnibus-wm"
+        );
+    }
+
     #[test]
     fn test_compute_project_id() {
         let path = Path::new("/Users/drazen/playground/ai-omnibus/wm");
@@ -199,4 +338,29 @@ mod tests {
         let path = dir.unwrap();
         assert!(path.ends_with(".claude/projects") || path.to_string_lossy().contains(".claude"));
     }
+
+    #[test]
+    fn test_dir_from_prefers_override() {
+        let dir = dir_from(
+            Some("/mnt/claude".to_string()),
+            Some(PathBuf::from("/home/u")),
+            &[".claude", "projects"],
+        );
+        assert_eq!(dir, Some(PathBuf::from("/mnt/claude")));
+    }
+
+    #[test]
+    fn test_dir_from_falls_back_to_home() {
+        let dir = dir_from(
+            Some("  ".to_string()),
+            Some(PathBuf::from("/home/u")),
+            &[".claude", "projects"],
+        );
+        assert_eq!(dir, Some(PathBuf::from("/home/u/.claude/projects")));
+    }
+
+    #[test]
+    fn test_dir_from_without_home_or_override() {
+        assert_eq!(dir_from(None, None, &[".claude", "projects"]), None);
+    }
 }
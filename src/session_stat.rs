@@ -0,0 +1,384 @@
+//! Per-session diagnostics for `wm sessions stat <session-id>`
+//!
+//! Parses a single Claude transcript and reports message counts by role,
+//! a tool usage histogram, first/last timestamps, size, and extraction status.
+//! Useful when deciding whether a session is worth distilling.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::distill;
+use crate::session;
+use crate::show;
+use crate::state;
+use crate::transcript::types::TranscriptEntry;
+use crate::transcript::{read_transcript, read_transcript_since};
+use crate::types::SessionInfo;
+
+/// Cache of session summaries under .wm/, keyed by session id
+const SUMMARY_CACHE_FILE: &str = "session_summaries.json";
+
+/// Computed breakdown of a single session
+#[derive(Debug, Default, Serialize)]
+pub struct SessionStats {
+    pub session_id: String,
+    pub transcript_path: PathBuf,
+    pub size_bytes: u64,
+
+    /// Block counts keyed by role (user, assistant, thinking, tool_use, tool_result, summary)
+    pub messages: BTreeMap<String, usize>,
+
+    /// Tool invocations keyed by tool name
+    pub tools: BTreeMap<String, usize>,
+
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+
+    /// Whether `wm distill` has this session in its cache
+    pub distilled: bool,
+
+    /// Whether hook-driven `wm extract` has processed this session
+    pub extracted: bool,
+}
+
+/// Run wm sessions stat <session-id> [--json]
+pub fn run(session_id: &str, json: bool) -> Result<(), String> {
+    let project_path = session::current_project_path();
+    let info = session::discover_sessions(&project_path)?
+        .into_iter()
+        .find(|s| s.session_id == session_id)
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+
+    let entries = read_transcript(&info.transcript_path)
+        .map_err(|e| format!("Failed to read transcript: {}", e))?;
+
+    let mut stats = compute_stats(&entries, session_id);
+    stats.transcript_path = info.transcript_path;
+    stats.size_bytes = info.size_bytes;
+    stats.distilled = distill::is_distilled(session_id);
+    stats.extracted = state::session_dir(session_id)
+        .join("extraction_state.json")
+        .exists();
+
+    if json {
+        let out = serde_json::to_string_pretty(&stats)
+            .map_err(|e| format!("Failed to serialize stats: {}", e))?;
+        println!("{}", out);
+    } else {
+        print_stats(&stats);
+    }
+
+    Ok(())
+}
+
+/// Compute counts and timestamps from parsed entries
+/// AIDEV-NOTE: Transcripts can contain entries from other sessions (resumed/forked
+/// conversations), so messages are filtered by session_id like distill does.
+/// Summaries carry no session_id and are always counted.
+fn compute_stats(entries: &[TranscriptEntry], session_id: &str) -> SessionStats {
+    let mut stats = SessionStats {
+        session_id: session_id.to_string(),
+        ..Default::default()
+    };
+
+    for entry in entries {
+        if entry.is_summary() {
+            bump(&mut stats.messages, "summary", 1);
+            continue;
+        }
+        if !entry.is_message() || entry.session_id() != Some(session_id) {
+            continue;
+        }
+
+        if let Some(ts) = entry.timestamp() {
+            if stats.first_timestamp.is_none() {
+                stats.first_timestamp = Some(ts.to_string());
+            }
+            stats.last_timestamp = Some(ts.to_string());
+        }
+
+        if entry.is_user() {
+            bump(
+                &mut stats.messages,
+                "user",
+                usize::from(entry.user_text().is_some()),
+            );
+            bump(
+                &mut stats.messages,
+                "tool_result",
+                entry.tool_results().len(),
+            );
+        } else {
+            bump(
+                &mut stats.messages,
+                "assistant",
+                usize::from(entry.assistant_text().is_some()),
+            );
+            bump(
+                &mut stats.messages,
+                "thinking",
+                usize::from(entry.assistant_thinking().is_some()),
+            );
+            let tool_uses = entry.tool_uses();
+            bump(&mut stats.messages, "tool_use", tool_uses.len());
+            for (name, _) in tool_uses {
+                bump(&mut stats.tools, name, 1);
+            }
+        }
+    }
+
+    stats
+}
+
+/// Message count and time span of one session, for `wm show sessions --detailed`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub message_count: usize,
+    pub first_timestamp: Option<DateTime<Utc>>,
+    pub last_timestamp: Option<DateTime<Utc>>,
+}
+
+impl SessionSummary {
+    /// Time between the first and last message
+    pub fn span(&self) -> Option<chrono::Duration> {
+        Some(self.last_timestamp? - self.first_timestamp?)
+    }
+}
+
+/// A cached summary and the transcript size/mtime it was computed from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedSummary {
+    size_bytes: u64,
+    modified_at: DateTime<Utc>,
+    summary: SessionSummary,
+}
+
+/// Summarize each session, reusing cached results for unchanged transcripts
+/// AIDEV-NOTE: A transcript whose size and mtime match the cache isn't re-read.
+/// Unreadable transcripts get an empty summary and aren't cached.
+pub fn summarize_sessions(sessions: &[SessionInfo]) -> Vec<SessionSummary> {
+    let cache_path = state::wm_path(SUMMARY_CACHE_FILE);
+    let mut cache = load_summary_cache(&cache_path);
+    let mut changed = false;
+
+    let summaries = sessions
+        .iter()
+        .map(|info| {
+            if let Some(summary) = cached_summary(&cache, info) {
+                return summary;
+            }
+            match read_transcript_since(&info.transcript_path, None, Some(&info.session_id)) {
+                Ok(entries) => {
+                    let summary = summarize_entries(&entries, &info.session_id);
+                    cache.insert(
+                        info.session_id.clone(),
+                        CachedSummary {
+                            size_bytes: info.size_bytes,
+                            modified_at: info.modified_at,
+                            summary: summary.clone(),
+                        },
+                    );
+                    changed = true;
+                    summary
+                }
+                Err(_) => SessionSummary::default(),
+            }
+        })
+        .collect();
+
+    // Best effort: a missing .wm/ just means nothing is cached
+    if changed
+        && state::is_initialized()
+        && let Ok(json) = serde_json::to_string(&cache)
+    {
+        let _ = std::fs::write(&cache_path, json);
+    }
+    summaries
+}
+
+fn load_summary_cache(path: &Path) -> HashMap<String, CachedSummary> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// The cached summary for a session, if its transcript hasn't changed since
+fn cached_summary(
+    cache: &HashMap<String, CachedSummary>,
+    info: &SessionInfo,
+) -> Option<SessionSummary> {
+    cache
+        .get(&info.session_id)
+        .filter(|c| c.size_bytes == info.size_bytes && c.modified_at == info.modified_at)
+        .map(|c| c.summary.clone())
+}
+
+/// Count this session's messages and find the earliest and latest timestamps
+fn summarize_entries(entries: &[TranscriptEntry], session_id: &str) -> SessionSummary {
+    let mut summary = SessionSummary::default();
+    for entry in entries {
+        if !entry.is_message() || entry.session_id() != Some(session_id) {
+            continue;
+        }
+        summary.message_count += 1;
+
+        let Some(ts) = entry
+            .timestamp()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&Utc))
+        else {
+            continue;
+        };
+        summary.first_timestamp = Some(summary.first_timestamp.map_or(ts, |f| f.min(ts)));
+        summary.last_timestamp = Some(summary.last_timestamp.map_or(ts, |l| l.max(ts)));
+    }
+    summary
+}
+
+/// Add n to a counter, skipping zero so absent kinds don't show up
+fn bump(map: &mut BTreeMap<String, usize>, key: &str, n: usize) {
+    if n > 0 {
+        *map.entry(key.to_string()).or_insert(0) += n;
+    }
+}
+
+fn print_stats(stats: &SessionStats) {
+    println!("# Session {}", stats.session_id);
+    println!();
+    println!("Transcript: {}", stats.transcript_path.display());
+    println!("Size:       {}", show::format_size(stats.size_bytes));
+    println!(
+        "First:      {}",
+        stats.first_timestamp.as_deref().unwrap_or("-")
+    );
+    println!(
+        "Last:       {}",
+        stats.last_timestamp.as_deref().unwrap_or("-")
+    );
+    println!("Distilled:  {}", if stats.distilled { "yes" } else { "no" });
+    println!("Extracted:  {}", if stats.extracted { "yes" } else { "no" });
+
+    println!();
+    println!("## Messages");
+    for (role, count) in &stats.messages {
+        println!("  {:<12} {}", role, count);
+    }
+
+    println!();
+    println!("## Tools");
+    if stats.tools.is_empty() {
+        println!("  (none)");
+    }
+    let mut tools: Vec<_> = stats.tools.iter().collect();
+    tools.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+    for (name, count) in tools {
+        println!("  {:<12} {}", name, count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(lines: &[&str]) -> Vec<TranscriptEntry> {
+        lines
+            .iter()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    fn sample() -> Vec<TranscriptEntry> {
+        entries(&[
+            r#"{"type":"summary","summary":"Earlier work"}"#,
+            r#"{"type":"user","uuid":"a","sessionId":"s1","timestamp":"2025-01-15T10:00:00Z","message":{"role":"user","content":"Fix it"}}"#,
+            r#"{"type":"user","uuid":"x","sessionId":"s2","timestamp":"2025-01-14T09:00:00Z","message":{"role":"user","content":"Other"}}"#,
+            r#"{"type":"assistant","uuid":"d","sessionId":"s1","timestamp":"2025-01-15T12:05:00Z","message":{"role":"assistant","content":[{"type":"text","text":"Done"}]}}"#,
+            r#"{"type":"assistant","uuid":"b","sessionId":"s1","timestamp":"2025-01-15T10:00:01Z","message":{"role":"assistant","content":[{"type":"text","text":"On it"}]}}"#,
+        ])
+    }
+
+    #[test]
+    fn test_summarize_entries_counts_and_span() {
+        let summary = summarize_entries(&sample(), "s1");
+        assert_eq!(summary.message_count, 3);
+        assert_eq!(
+            summary.first_timestamp.unwrap().to_rfc3339(),
+            "2025-01-15T10:00:00+00:00"
+        );
+        assert_eq!(summary.span().unwrap().num_minutes(), 125);
+        assert_eq!(summarize_entries(&sample(), "none").span(), None);
+    }
+
+    #[test]
+    fn test_cached_summary_requires_unchanged_transcript() {
+        let info = SessionInfo {
+            session_id: "s1".to_string(),
+            transcript_path: PathBuf::from("s1.jsonl"),
+            modified_at: Utc::now(),
+            size_bytes: 100,
+        };
+        let summary = summarize_entries(&sample(), "s1");
+        let cache = HashMap::from([(
+            "s1".to_string(),
+            CachedSummary {
+                size_bytes: 100,
+                modified_at: info.modified_at,
+                summary: summary.clone(),
+            },
+        )]);
+
+        assert_eq!(cached_summary(&cache, &info), Some(summary));
+        let grown = SessionInfo {
+            size_bytes: 200,
+            ..info.clone()
+        };
+        assert_eq!(cached_summary(&cache, &grown), None);
+    }
+
+    #[test]
+    fn test_compute_stats_counts_roles_and_tools() {
+        let entries = entries(&[
+            r#"{"type":"summary","summary":"Earlier work"}"#,
+            r#"{"type":"user","uuid":"a","sessionId":"s1","timestamp":"2025-01-15T10:00:00Z","message":{"role":"user","content":"Fix it"}}"#,
+            r#"{"type":"assistant","uuid":"b","sessionId":"s1","timestamp":"2025-01-15T10:00:01Z","message":{"role":"assistant","content":[{"type":"thinking","thinking":"hmm"},{"type":"tool_use","name":"Read","input":{}},{"type":"tool_use","name":"Bash","input":{}},{"type":"tool_use","name":"Read","input":{}}]}}"#,
+            r#"{"type":"user","uuid":"c","sessionId":"s1","timestamp":"2025-01-15T10:00:02Z","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"}]}}"#,
+            r#"{"type":"assistant","uuid":"d","sessionId":"s1","timestamp":"2025-01-15T10:05:00Z","message":{"role":"assistant","content":[{"type":"text","text":"Done"}]}}"#,
+        ]);
+
+        let stats = compute_stats(&entries, "s1");
+        assert_eq!(stats.messages["summary"], 1);
+        assert_eq!(stats.messages["user"], 1);
+        assert_eq!(stats.messages["assistant"], 1);
+        assert_eq!(stats.messages["thinking"], 1);
+        assert_eq!(stats.messages["tool_use"], 3);
+        assert_eq!(stats.messages["tool_result"], 1);
+        assert_eq!(stats.tools["Read"], 2);
+        assert_eq!(stats.tools["Bash"], 1);
+        assert_eq!(
+            stats.first_timestamp.as_deref(),
+            Some("2025-01-15T10:00:00Z")
+        );
+        assert_eq!(
+            stats.last_timestamp.as_deref(),
+            Some("2025-01-15T10:05:00Z")
+        );
+    }
+
+    #[test]
+    fn test_compute_stats_ignores_other_sessions() {
+        let entries = entries(&[
+            r#"{"type":"user","uuid":"a","sessionId":"s1","timestamp":"2025-01-15T10:00:00Z","message":{"role":"user","content":"mine"}}"#,
+            r#"{"type":"user","uuid":"b","sessionId":"s2","timestamp":"2025-01-16T10:00:00Z","message":{"role":"user","content":"theirs"}}"#,
+        ]);
+
+        let stats = compute_stats(&entries, "s1");
+        assert_eq!(stats.messages["user"], 1);
+        assert_eq!(
+            stats.last_timestamp.as_deref(),
+            Some("2025-01-15T10:00:00Z")
+        );
+    }
+}
@@ -1,14 +1,53 @@
 //! Display commands for state and working set
 
+use serde::Serialize;
+
+use crate::color;
+use crate::provenance;
 use crate::session;
+use crate::session_stat::{self, SessionSummary};
 use crate::state;
+use crate::types::SessionInfo;
+
+/// JSON view of state.md for `wm show state --json`
+#[derive(Debug, Serialize)]
+struct StateView {
+    exists: bool,
+    bytes: usize,
+    lines: usize,
+    content: String,
+}
 
-/// Run wm show <what> [--session-id ID]
-pub fn run(what: &str, session_id: Option<&str>) -> Result<(), String> {
+/// JSON view of one session for `wm show sessions --json`
+#[derive(Debug, Serialize)]
+struct SessionView {
+    session_id: String,
+    size_bytes: u64,
+    modified_at: String,
+    has_wm_state: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_timestamp: Option<String>,
+}
+
+/// Run wm show <what> [--session-id ID] [--json] [--with-provenance] [--detailed]
+pub fn run(
+    what: &str,
+    session_id: Option<&str>,
+    json: bool,
+    with_provenance: bool,
+    detailed: bool,
+) -> Result<(), String> {
     match what {
-        "state" => show_state(),
+        "sessions" => show_sessions(json, detailed),
+        _ if detailed => Err("--detailed is supported for: sessions".to_string()),
+        "state" => show_state(json, with_provenance),
+        _ if with_provenance => Err("--with-provenance is supported for: state".to_string()),
+        "working" if json => Err("--json is supported for: state, sessions".to_string()),
         "working" => show_working(session_id),
-        "sessions" => show_sessions(),
         _ => Err(format!(
             "Unknown target: {}. Use: state, working, sessions",
             what
@@ -16,19 +55,36 @@ pub fn run(what: &str, session_id: Option<&str>) -> Result<(), String> {
     }
 }
 
-fn show_state() -> Result<(), String> {
+/// Print a serializable view as pretty JSON
+fn print_json<T: Serialize>(value: &T) -> Result<(), String> {
+    let out = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize output: {}", e))?;
+    println!("{}", out);
+    Ok(())
+}
+
+fn show_state(json: bool, with_provenance: bool) -> Result<(), String> {
     if !state::is_initialized() {
         return Err("Not initialized. Run 'wm init' first.".to_string());
     }
 
     let path = state::wm_path("state.md");
+    if json {
+        let view = match std::fs::read_to_string(&path) {
+            Ok(content) => state_view(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => state_view(None),
+            Err(e) => return Err(format!("Failed to read state.md: {}", e)),
+        };
+        return print_json(&view);
+    }
+
     match std::fs::read_to_string(&path) {
         Ok(content) if content.trim().is_empty() => {
             println!("_No knowledge captured yet. Run 'wm extract' after some conversations._");
             Ok(())
         }
         Ok(content) => {
-            println!("{}", content);
+            println!("{}", render_state(&content, with_provenance));
             Ok(())
         }
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
@@ -87,10 +143,69 @@ fn show_working(session_id: Option<&str>) -> Result<(), String> {
     Ok(())
 }
 
-fn show_sessions() -> Result<(), String> {
+fn state_view(content: Option<String>) -> StateView {
+    match content {
+        Some(content) => StateView {
+            exists: true,
+            bytes: content.len(),
+            lines: content.lines().count(),
+            content,
+        },
+        None => StateView {
+            exists: false,
+            bytes: 0,
+            lines: 0,
+            content: String::new(),
+        },
+    }
+}
+
+/// Whether wm has per-session extraction state for this session
+fn has_wm_state(session_id: &str) -> bool {
+    state::session_dir(session_id)
+        .join("extraction_state.json")
+        .exists()
+}
+
+fn session_view(s: &SessionInfo, summary: Option<&SessionSummary>) -> SessionView {
+    SessionView {
+        session_id: s.session_id.clone(),
+        size_bytes: s.size_bytes,
+        modified_at: s.modified_at.to_rfc3339(),
+        has_wm_state: has_wm_state(&s.session_id),
+        message_count: summary.map(|m| m.message_count),
+        first_timestamp: summary
+            .and_then(|m| m.first_timestamp)
+            .map(|t| t.to_rfc3339()),
+        last_timestamp: summary
+            .and_then(|m| m.last_timestamp)
+            .map(|t| t.to_rfc3339()),
+    }
+}
+
+fn show_sessions(json: bool, detailed: bool) -> Result<(), String> {
     let project_path = session::current_project_path();
     let sessions = session::discover_sessions(&project_path)?;
 
+    // AIDEV-NOTE: Opt-in because it reads every transcript not already cached
+    let summaries: Vec<Option<SessionSummary>> = if detailed {
+        session_stat::summarize_sessions(&sessions)
+            .into_iter()
+            .map(Some)
+            .collect()
+    } else {
+        vec![None; sessions.len()]
+    };
+
+    if json {
+        let views: Vec<SessionView> = sessions
+            .iter()
+            .zip(&summaries)
+            .map(|(s, summary)| session_view(s, summary.as_ref()))
+            .collect();
+        return print_json(&views);
+    }
+
     if sessions.is_empty() {
         println!("_No Claude sessions found for this project._");
         return Ok(());
@@ -99,12 +214,13 @@ fn show_sessions() -> Result<(), String> {
     println!("# Claude Sessions ({})", sessions.len());
     println!();
 
-    for s in &sessions {
+    for (s, summary) in sessions.iter().zip(&summaries) {
         // Check if we have local state for this session
-        let has_local_state = state::session_dir(&s.session_id)
-            .join("extraction_state.json")
-            .exists();
-        let marker = if has_local_state { "●" } else { "○" };
+        let marker = if has_wm_state(&s.session_id) {
+            color::green("●")
+        } else {
+            color::dim("○")
+        };
 
         // Format size in human-readable form
         let size = format_size(s.size_bytes);
@@ -112,7 +228,16 @@ fn show_sessions() -> Result<(), String> {
         // Format timestamp
         let time = s.modified_at.format("%Y-%m-%d %H:%M");
 
-        println!("{} {} ({}, {})", marker, s.session_id, size, time);
+        match summary {
+            Some(summary) => println!(
+                "{} {} ({}) {}",
+                marker,
+                s.session_id,
+                session_details(summary, s.size_bytes),
+                time
+            ),
+            None => println!("{} {} ({}, {})", marker, s.session_id, size, time),
+        }
     }
 
     println!();
@@ -121,8 +246,36 @@ fn show_sessions() -> Result<(), String> {
     Ok(())
 }
 
+/// `42 msgs, 3.1 KB, 2h span` (span omitted without timestamps)
+fn session_details(summary: &SessionSummary, size_bytes: u64) -> String {
+    let mut details = format!(
+        "{} msg{}, {}",
+        summary.message_count,
+        if summary.message_count == 1 { "" } else { "s" },
+        format_size(size_bytes)
+    );
+    if let Some(span) = summary.span() {
+        details.push_str(&format!(", {} span", format_span(span)));
+    }
+    details
+}
+
+/// Coarse duration: `45s`, `12m`, `2h`, `3d`
+fn format_span(span: chrono::Duration) -> String {
+    let secs = span.num_seconds().max(0);
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h", secs / 3600)
+    } else {
+        format!("{}d", secs / 86400)
+    }
+}
+
 /// Format bytes in human-readable form
-fn format_size(bytes: u64) -> String {
+pub fn format_size(bytes: u64) -> String {
     if bytes < 1024 {
         format!("{} B", bytes)
     } else if bytes < 1024 * 1024 {
@@ -131,3 +284,92 @@ fn format_size(bytes: u64) -> String {
         format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
     }
 }
+
+/// state.md for display: provenance tags hidden, or spelled out inline
+fn render_state(content: &str, with_provenance: bool) -> String {
+    content
+        .lines()
+        .map(|line| match provenance::parse(line) {
+            Some(p) if with_provenance => format!(
+                "{}  [{} · session {}]",
+                provenance::strip(line),
+                p.date,
+                p.session_id
+            ),
+            _ => provenance::strip(line).to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_state_view_counts() {
+        let view = state_view(Some("# State\n- one\n".to_string()));
+        assert!(view.exists);
+        assert_eq!(view.bytes, 14);
+        assert_eq!(view.lines, 2);
+
+        let json = serde_json::to_value(state_view(None)).unwrap();
+        assert_eq!(json["exists"], false);
+        assert_eq!(json["content"], "");
+    }
+
+    #[test]
+    fn test_session_view_json_shape() {
+        let info = SessionInfo {
+            session_id: "abc".to_string(),
+            transcript_path: std::path::PathBuf::from("abc.jsonl"),
+            modified_at: Utc.with_ymd_and_hms(2025, 1, 15, 10, 0, 0).unwrap(),
+            size_bytes: 2048,
+        };
+        let json = serde_json::to_value(session_view(&info, None)).unwrap();
+        assert_eq!(json["session_id"], "abc");
+        assert_eq!(json["size_bytes"], 2048);
+        assert_eq!(json["modified_at"], "2025-01-15T10:00:00+00:00");
+        assert!(json["has_wm_state"].is_boolean());
+        assert!(json.get("message_count").is_none());
+
+        let summary = SessionSummary {
+            message_count: 42,
+            first_timestamp: Some(Utc.with_ymd_and_hms(2025, 1, 15, 8, 0, 0).unwrap()),
+            last_timestamp: Some(Utc.with_ymd_and_hms(2025, 1, 15, 10, 6, 0).unwrap()),
+        };
+        let json = serde_json::to_value(session_view(&info, Some(&summary))).unwrap();
+        assert_eq!(json["message_count"], 42);
+        assert_eq!(json["last_timestamp"], "2025-01-15T10:06:00+00:00");
+    }
+
+    #[test]
+    fn test_session_details() {
+        let summary = SessionSummary {
+            message_count: 42,
+            first_timestamp: Some(Utc.with_ymd_and_hms(2025, 1, 15, 8, 0, 0).unwrap()),
+            last_timestamp: Some(Utc.with_ymd_and_hms(2025, 1, 15, 10, 6, 0).unwrap()),
+        };
+        assert_eq!(session_details(&summary, 3174), "42 msgs, 3.1 KB, 2h span");
+        assert_eq!(
+            session_details(&SessionSummary::default(), 10),
+            "0 msgs, 10 B"
+        );
+        assert_eq!(format_span(chrono::Duration::seconds(45)), "45s");
+        assert_eq!(format_span(chrono::Duration::days(3)), "3d");
+    }
+
+    #[test]
+    fn test_render_state_provenance() {
+        let content = "# State\n- Use tabs <!-- session:abc date:2025-01-15 -->\n- Untagged";
+        assert_eq!(
+            render_state(content, false),
+            "# State\n- Use tabs\n- Untagged"
+        );
+        assert_eq!(
+            render_state(content, true),
+            "# State\n- Use tabs  [2025-01-15 · session abc]\n- Untagged"
+        );
+    }
+}
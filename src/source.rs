@@ -0,0 +1,320 @@
+//! Transcript sources - the coding agents whose sessions wm can distill
+//!
+//! Each agent stores sessions in its own layout and format. `TranscriptSource`
+//! captures the parts distill needs (discover, read, filter, format) so that
+//! supporting a new agent is one impl here rather than new call sites in distill.
+//!
+//! AIDEV-NOTE: The trait uses associated types and consts rather than trait
+//! objects - callers are generic (`source_pass1::<ClaudeSource>`, which hands
+//! its sessions to `run_pass1_generic`), and dispatch on `SourceKind` happens
+//! once at the CLI boundary.
+
+use clap::ValueEnum;
+use std::path::Path;
+
+use crate::codex::{self, types::CodexEntry};
+use crate::gemini::{self, types::GeminiEntry};
+use crate::session;
+use crate::transcript::{self, types::TranscriptEntry};
+use crate::types::{
+    CodexSessionInfo, Config, GeminiSessionInfo, ReadError, SessionInfo, SessionLike,
+};
+
+/// Which agent's sessions to process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SourceKind {
+    #[default]
+    Claude,
+    Codex,
+    Gemini,
+}
+
+/// Which agent's session `wm extract` reads
+/// AIDEV-NOTE: Gemini sessions are distill-only (no incremental extract path),
+/// so it isn't offered here; `--source gemini` is rejected by clap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ExtractSource {
+    #[default]
+    Claude,
+    Codex,
+}
+
+/// Which sessions `wm distill` reads: one agent's, or every agent's
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DistillSource {
+    #[default]
+    Claude,
+    Codex,
+    Gemini,
+    All,
+}
+
+impl DistillSource {
+    /// Agents to distill, in processing order
+    pub fn kinds(self) -> Vec<SourceKind> {
+        match self {
+            DistillSource::Claude => vec![SourceKind::Claude],
+            DistillSource::Codex => vec![SourceKind::Codex],
+            DistillSource::Gemini => vec![SourceKind::Gemini],
+            DistillSource::All => vec![SourceKind::Claude, SourceKind::Codex, SourceKind::Gemini],
+        }
+    }
+}
+
+/// A session format that distill can extract knowledge from
+pub trait TranscriptSource {
+    /// Discovered session metadata (shared with distill's reader threads)
+    type Session: SessionLike + Sync;
+
+    /// One parsed line/message of a session file
+    type Entry;
+
+    /// Agent name for user-facing messages
+    const NAME: &'static str;
+
+    /// Pass 1 cache file under .wm/distill/
+    const CACHE_FILE: &'static str;
+
+    /// Find sessions, optionally narrowed by a project filter
+    ///
+    /// Returns sessions sorted by modification time (newest first).
+    fn discover(project_filter: Option<&str>) -> Result<Vec<Self::Session>, String>;
+
+    /// Discover sessions for exactly one project directory (no substring matching)
+    fn discover_path(project_path: &Path) -> Result<Vec<Self::Session>, String>;
+
+    /// Parse a session file, skipping malformed lines
+    fn read(path: &Path) -> Result<Vec<Self::Entry>, ReadError>;
+
+    /// Entries that belong to `session` and carry conversational content
+    fn relevant<'a>(session: &Self::Session, entries: &'a [Self::Entry]) -> Vec<&'a Self::Entry>;
+
+    /// Format entries as USER:/ASSISTANT:/... text for the extraction LLM
+    /// `config` is read once per run by the caller.
+    fn format_context(entries: &[&Self::Entry], config: &Config) -> String;
+}
+
+/// Claude Code sessions (~/.claude/projects/<project-id>/*.jsonl)
+pub struct ClaudeSource;
+
+impl TranscriptSource for ClaudeSource {
+    type Session = SessionInfo;
+    type Entry = TranscriptEntry;
+
+    const NAME: &'static str = "Claude";
+    const CACHE_FILE: &'static str = "cache.json";
+
+    fn discover(project_filter: Option<&str>) -> Result<Vec<SessionInfo>, String> {
+        match project_filter {
+            Some(filter) => discover_claude_by_project_filter(filter),
+            // Default: current project only
+            None => session::discover_sessions(&session::current_project_path()),
+        }
+    }
+
+    fn discover_path(project_path: &Path) -> Result<Vec<SessionInfo>, String> {
+        match session::get_project_dir(project_path) {
+            Some(dir) => session::discover_sessions_in_dir(&dir),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn read(path: &Path) -> Result<Vec<TranscriptEntry>, ReadError> {
+        transcript::read_transcript(path)
+    }
+
+    fn relevant<'a>(
+        session: &SessionInfo,
+        entries: &'a [TranscriptEntry],
+    ) -> Vec<&'a TranscriptEntry> {
+        // AIDEV-NOTE: Use .as_str() for proper Option<&str> comparison
+        entries
+            .iter()
+            .filter(|e| e.session_id() == Some(session.session_id.as_str()))
+            .filter(|e| e.is_message() || e.is_summary())
+            .collect()
+    }
+
+    fn format_context(entries: &[&TranscriptEntry], config: &Config) -> String {
+        transcript::format_context(
+            entries,
+            &config.extract.roles,
+            config.extract.tool_output_max_bytes,
+        )
+    }
+}
+
+/// Codex CLI sessions (~/.codex/sessions/YYYY/MM/DD/rollout-*.jsonl)
+pub struct CodexSource;
+
+impl TranscriptSource for CodexSource {
+    type Session = CodexSessionInfo;
+    type Entry = CodexEntry;
+
+    const NAME: &'static str = "Codex";
+    const CACHE_FILE: &'static str = "codex_cache.json";
+
+    fn discover(project_filter: Option<&str>) -> Result<Vec<CodexSessionInfo>, String> {
+        codex::discover_sessions(project_filter)
+    }
+
+    /// AIDEV-NOTE: Codex records the session's cwd, so an exact path is an
+    /// equality check on it rather than the `contains` the filter uses.
+    fn discover_path(project_path: &Path) -> Result<Vec<CodexSessionInfo>, String> {
+        let project_path = project_path
+            .canonicalize()
+            .unwrap_or_else(|_| project_path.to_path_buf());
+        Ok(codex::discover_sessions(None)?
+            .into_iter()
+            .filter(|s| s.cwd.as_deref().map(Path::new) == Some(project_path.as_path()))
+            .collect())
+    }
+
+    fn read(path: &Path) -> Result<Vec<CodexEntry>, ReadError> {
+        codex::read_codex_session(path)
+    }
+
+    fn relevant<'a>(_session: &CodexSessionInfo, entries: &'a [CodexEntry]) -> Vec<&'a CodexEntry> {
+        entries.iter().filter(|e| e.is_relevant()).collect()
+    }
+
+    fn format_context(entries: &[&CodexEntry], config: &Config) -> String {
+        codex::format_context(
+            entries,
+            &config.extract.roles,
+            config.codex.tool_output_max_bytes,
+        )
+    }
+}
+
+/// Gemini CLI sessions (~/.gemini/tmp/<project-hash>/chats/session-*.json)
+pub struct GeminiSource;
+
+impl TranscriptSource for GeminiSource {
+    type Session = GeminiSessionInfo;
+    type Entry = GeminiEntry;
+
+    const NAME: &'static str = "Gemini";
+    const CACHE_FILE: &'static str = "gemini_cache.json";
+
+    // Gemini keys sessions by project path hash, so the filter is a path
+    fn discover(project_filter: Option<&str>) -> Result<Vec<GeminiSessionInfo>, String> {
+        gemini::discover_sessions(project_filter)
+    }
+
+    fn discover_path(project_path: &Path) -> Result<Vec<GeminiSessionInfo>, String> {
+        // Gemini's filter is already a path (hashed, so exact)
+        gemini::discover_sessions(Some(&project_path.to_string_lossy()))
+    }
+
+    fn read(path: &Path) -> Result<Vec<GeminiEntry>, ReadError> {
+        gemini::read_gemini_session(path)
+    }
+
+    fn relevant<'a>(
+        _session: &GeminiSessionInfo,
+        entries: &'a [GeminiEntry],
+    ) -> Vec<&'a GeminiEntry> {
+        entries.iter().filter(|e| e.is_relevant()).collect()
+    }
+
+    fn format_context(entries: &[&GeminiEntry], config: &Config) -> String {
+        gemini::format_context(entries, &config.extract.roles)
+    }
+}
+
+/// Discover Claude sessions across all projects matching a filter
+fn discover_claude_by_project_filter(filter: &str) -> Result<Vec<SessionInfo>, String> {
+    if filter.trim().is_empty() {
+        return Err("Project filter cannot be empty".to_string());
+    }
+
+    let matching_projects = session::find_projects_by_filter(filter)?;
+
+    if matching_projects.is_empty() {
+        return Err(format!(
+            "No projects found matching '{}'. Use 'wm show sessions' to list available projects.",
+            filter
+        ));
+    }
+
+    // If multiple matches, show which projects we're processing
+    if matching_projects.len() > 1 {
+        eprintln!("Matched {} projects:", matching_projects.len());
+        for p in &matching_projects {
+            eprintln!("  {} ({} sessions)", p.project_id, p.session_count);
+        }
+        eprintln!();
+    } else {
+        eprintln!("Project: {}", matching_projects[0].project_id);
+    }
+
+    // Collect sessions from all matching projects
+    let mut all_sessions = Vec::new();
+    for project in matching_projects {
+        let sessions = session::discover_sessions_in_dir(&project.project_dir)?;
+        all_sessions.extend(sessions);
+    }
+
+    // Sort by modification time, newest first
+    all_sessions.sort_by_key(|s| std::cmp::Reverse(s.modified_at));
+
+    Ok(all_sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codex_source_reads_and_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rollout.jsonl");
+        std::fs::write(
+            &path,
+            r#"{"timestamp":"2025-01-01T00:00:00Z","type":"session_meta","payload":{"id":"abc","cwd":"/tmp"}}
+{"timestamp":"2025-01-01T00:00:01Z","type":"event_msg","payload":{"type":"user_message","message":"Use tabs"}}
+"#,
+        )
+        .unwrap();
+
+        let entries = CodexSource::read(&path).unwrap();
+        let session = CodexSessionInfo {
+            session_id: "abc".to_string(),
+            session_path: path.clone(),
+            cwd: None,
+            modified_at: chrono::Utc::now(),
+            size_bytes: 0,
+        };
+        let relevant = CodexSource::relevant(&session, &entries);
+        let formatted = CodexSource::format_context(&relevant, &Config::default());
+        assert!(formatted.contains("USER: Use tabs"));
+    }
+
+    #[test]
+    fn test_claude_source_drops_other_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("s1.jsonl");
+        std::fs::write(
+            &path,
+            r#"{"type":"user","uuid":"a","sessionId":"s1","timestamp":"2025-01-15T10:00:00Z","message":{"role":"user","content":"mine"}}
+{"type":"user","uuid":"b","sessionId":"s2","timestamp":"2025-01-15T10:00:00Z","message":{"role":"user","content":"theirs"}}
+"#,
+        )
+        .unwrap();
+
+        let entries = ClaudeSource::read(&path).unwrap();
+        let session = SessionInfo {
+            session_id: "s1".to_string(),
+            transcript_path: path.clone(),
+            modified_at: chrono::Utc::now(),
+            size_bytes: 0,
+        };
+        let formatted = ClaudeSource::format_context(
+            &ClaudeSource::relevant(&session, &entries),
+            &Config::default(),
+        );
+        assert!(formatted.contains("mine"));
+        assert!(!formatted.contains("theirs"));
+    }
+}
@@ -1,37 +1,128 @@
 //! State management - file I/O helpers for .wm/
 
-use crate::types::Config;
-use chrono::Local;
+use crate::types::{Config, LogConfig, LogLevel};
+use chrono::{Local, Utc};
 use std::fs::{self, OpenOptions};
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Once, OnceLock};
+use std::time::SystemTime;
 
 const WM_DIR: &str = ".wm";
-const WORKING_SET_FILE: &str = "working_set.md";
+
+/// Env var that replaces the whole .wm directory path (set by --wm-dir too)
+pub const WM_DIR_ENV: &str = "WM_DIR";
+pub const WORKING_SET_FILE: &str = "working_set.md";
 const HOOK_LOG_FILE: &str = "hook.log";
-const CONFIG_FILE: &str = "config.toml";
+pub const CONFIG_FILE: &str = "config.toml";
 
-/// Log a message to .wm/hook.log
+/// Log a message to .wm/hook.log at Info level
 pub fn log(context: &str, message: &str) {
+    log_at(LogLevel::Info, context, message);
+}
+
+/// Log a message to .wm/hook.log if `level` meets the configured threshold
+/// The first write of a process rotates an oversized log (see rotate_log).
+pub fn log_at(level: LogLevel, context: &str, message: &str) {
+    let settings = log_settings();
+    if level < settings.level {
+        return;
+    }
+
     let path = wm_path(HOOK_LOG_FILE);
+    static ROTATE: Once = Once::new();
+    ROTATE.call_once(|| {
+        let _ = rotate_log(&path, settings.max_bytes, settings.keep);
+    });
+    append_log(&path, settings.level, level, context, message);
+}
+
+/// `[log]` config with WM_LOG_LEVEL applied
+/// AIDEV-NOTE: Resolved once per process - a hook run logs dozens of lines and
+/// shouldn't re-read config.toml for each. An unparseable WM_LOG_LEVEL falls
+/// back to config rather than failing the hook.
+fn log_settings() -> &'static LogConfig {
+    static SETTINGS: OnceLock<LogConfig> = OnceLock::new();
+    SETTINGS.get_or_init(|| {
+        let mut settings = read_config().log;
+        if let Some(level) = std::env::var("WM_LOG_LEVEL")
+            .ok()
+            .and_then(|level| level.parse().ok())
+        {
+            settings.level = level;
+        }
+        settings
+    })
+}
+
+/// Rotate `path` to `path.1` (shifting older ones up to `path.<keep>`) when it
+/// exceeds `max_bytes`. Returns whether it rotated. `max_bytes` 0 disables;
+/// `keep` 0 just removes the oversized log.
+fn rotate_log(path: &Path, max_bytes: u64, keep: usize) -> io::Result<bool> {
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if max_bytes == 0 || size <= max_bytes {
+        return Ok(false);
+    }
+
+    let numbered = |n: usize| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    };
+    if keep == 0 {
+        fs::remove_file(path)?;
+        return Ok(true);
+    }
+    for n in (1..keep).rev() {
+        let older = numbered(n);
+        if older.exists() {
+            fs::rename(older, numbered(n + 1))?;
+        }
+    }
+    fs::rename(path, numbered(1))?;
+    Ok(true)
+}
+
+fn append_log(path: &Path, threshold: LogLevel, level: LogLevel, context: &str, message: &str) {
+    if level < threshold {
+        return;
+    }
     let timestamp = Local::now().format("%H:%M:%S");
-    let line = format!("[{}] [{}] {}\n", timestamp, context, message);
+    let line = match level {
+        LogLevel::Info => format!("[{}] [{}] {}\n", timestamp, context, message),
+        LogLevel::Debug => format!("[{}] [{}] DEBUG {}\n", timestamp, context, message),
+        LogLevel::Warn => format!("[{}] [{}] WARN {}\n", timestamp, context, message),
+    };
 
     // Append to log file, ignore errors (logging should never fail the operation)
     let _ = OpenOptions::new()
         .create(true)
         .append(true)
-        .open(&path)
+        .open(path)
         .and_then(|mut f| f.write_all(line.as_bytes()));
 }
 
 /// Get the .wm directory path for the current project
-/// Uses CLAUDE_PROJECT_DIR if set (from hooks), otherwise falls back to cwd
+/// WM_DIR (or --wm-dir) names the directory outright; otherwise it is .wm/
+/// under CLAUDE_PROJECT_DIR if set (from hooks), falling back to cwd.
 pub fn wm_dir() -> PathBuf {
-    if let Ok(project_dir) = std::env::var("CLAUDE_PROJECT_DIR") {
-        PathBuf::from(project_dir).join(WM_DIR)
-    } else {
-        PathBuf::from(WM_DIR)
+    resolve_wm_dir(
+        std::env::var(WM_DIR_ENV).ok(),
+        std::env::var("CLAUDE_PROJECT_DIR").ok(),
+    )
+}
+
+/// AIDEV-NOTE: Every .wm/ path goes through wm_dir, so an override here
+/// relocates all of working memory (out-of-tree storage, read-only project
+/// roots). Empty values count as unset.
+fn resolve_wm_dir(wm_dir_env: Option<String>, project_dir: Option<String>) -> PathBuf {
+    match (
+        wm_dir_env.filter(|d| !d.is_empty()),
+        project_dir.filter(|d| !d.is_empty()),
+    ) {
+        (Some(dir), _) => PathBuf::from(dir),
+        (None, Some(project_dir)) => PathBuf::from(project_dir).join(WM_DIR),
+        (None, None) => PathBuf::from(WM_DIR),
     }
 }
 
@@ -50,50 +141,235 @@ pub fn read_working_set() -> io::Result<String> {
     fs::read_to_string(wm_path(WORKING_SET_FILE))
 }
 
-/// Write the compiled working set (legacy global path)
-pub fn write_working_set(content: &str) -> io::Result<()> {
-    fs::write(wm_path(WORKING_SET_FILE), content)
-}
-
 /// Get session-specific directory path
 pub fn session_dir(session_id: &str) -> PathBuf {
     wm_path(&format!("sessions/{}", session_id))
 }
 
+/// Session IDs with a directory under `wm_dir`/sessions/ (sorted)
+pub fn list_session_ids_in(wm_dir: &Path) -> io::Result<Vec<String>> {
+    let dir = wm_dir.join("sessions");
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut ids = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir()
+            && let Some(name) = path.file_name()
+        {
+            ids.push(name.to_string_lossy().to_string());
+        }
+    }
+    ids.sort();
+    Ok(ids)
+}
+
+/// Prune old session directories when sessions.max_session_dirs is set
+/// AIDEV-NOTE: Runs opportunistically from extract/compile hooks, so failures are
+/// logged rather than returned. The active session is never pruned.
+pub fn gc_session_dirs(active_session_id: &str) {
+    gc_session_dirs_in(&wm_dir(), active_session_id);
+}
+
+/// gc_session_dirs for the .wm directory at `wm_dir`
+pub fn gc_session_dirs_in(wm_dir: &Path, active_session_id: &str) {
+    let Some(max) = read_config().sessions.max_session_dirs else {
+        return;
+    };
+
+    match prune_session_dirs(&wm_dir.join("sessions"), max, active_session_id) {
+        Ok(pruned) => {
+            for session_id in pruned {
+                log("gc", &format!("Pruned session directory {}", session_id));
+            }
+        }
+        Err(e) => log("gc", &format!("Failed to prune session directories: {}", e)),
+    }
+}
+
+/// Remove all but the `max` most recent session directories (by working_set mtime)
+///
+/// The active session always survives and counts toward `max`.
+/// Returns the IDs of pruned sessions.
+fn prune_session_dirs(
+    sessions_dir: &Path,
+    max: usize,
+    active_session_id: &str,
+) -> io::Result<Vec<String>> {
+    if !sessions_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut candidates = Vec::new();
+    let mut has_active = false;
+    for entry in fs::read_dir(sessions_dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let session_id = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if session_id == active_session_id {
+            has_active = true;
+            continue;
+        }
+        candidates.push((session_last_used(&path), session_id, path));
+    }
+
+    // Newest first; everything past the budget goes
+    candidates.sort_by_key(|(mtime, _, _)| std::cmp::Reverse(*mtime));
+    let keep = max.saturating_sub(usize::from(has_active));
+
+    let mut pruned = Vec::new();
+    for (_, session_id, path) in candidates.into_iter().skip(keep) {
+        fs::remove_dir_all(&path)?;
+        pruned.push(session_id);
+    }
+    Ok(pruned)
+}
+
+/// When a session directory was last used: its working_set mtime, else the dir's
+fn session_last_used(dir: &Path) -> SystemTime {
+    fs::metadata(dir.join(WORKING_SET_FILE))
+        .or_else(|_| fs::metadata(dir))
+        .and_then(|m| m.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// Most relevant existing working set for a session
+///
+/// The session's own working_set.md if present (resumed/cleared session),
+/// otherwise the most recently written one: global or any other session's.
+pub fn latest_working_set(session_id: &str) -> Option<PathBuf> {
+    latest_working_set_in(&wm_dir(), session_id)
+}
+
+fn latest_working_set_in(wm_dir: &Path, session_id: &str) -> Option<PathBuf> {
+    let own = wm_dir
+        .join("sessions")
+        .join(session_id)
+        .join(WORKING_SET_FILE);
+    if own.is_file() {
+        return Some(own);
+    }
+    newest_working_set_in(wm_dir)
+}
+
+/// Most recently written working set: global or any session's
+pub fn newest_working_set() -> Option<PathBuf> {
+    newest_working_set_in(&wm_dir())
+}
+
+fn newest_working_set_in(wm_dir: &Path) -> Option<PathBuf> {
+    let sessions_dir = wm_dir.join("sessions");
+    let mut candidates = vec![wm_dir.join(WORKING_SET_FILE)];
+    if let Ok(entries) = fs::read_dir(&sessions_dir) {
+        candidates.extend(entries.flatten().map(|e| e.path().join(WORKING_SET_FILE)));
+    }
+    candidates
+        .into_iter()
+        .filter_map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((modified, path))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
 /// Write working set to session-specific path
 /// AIDEV-NOTE: Per-session working_set prevents race conditions when
 /// multiple sessions compile concurrently in the same project folder.
 pub fn write_working_set_for_session(session_id: &str, content: &str) -> io::Result<()> {
-    let dir = session_dir(session_id);
+    write_working_set_in(&wm_dir(), Some(session_id), content).map(|_| ())
+}
+
+/// Write a working set under `wm_dir`: the session's when given, else the
+/// global one. Returns the path written.
+pub fn write_working_set_in(
+    wm_dir: &Path,
+    session_id: Option<&str>,
+    content: &str,
+) -> io::Result<PathBuf> {
+    let dir = match session_id {
+        Some(id) => wm_dir.join("sessions").join(id),
+        None => wm_dir.to_path_buf(),
+    };
     fs::create_dir_all(&dir)?;
-    fs::write(dir.join(WORKING_SET_FILE), content)
+    let path = dir.join(WORKING_SET_FILE);
+    fs::write(&path, content)?;
+    Ok(path)
 }
 
 /// Read project-level config, returns default if not found
+/// A config.toml that doesn't parse also yields defaults, with a warning on
+/// stderr naming the problem (see read_config_from).
 pub fn read_config() -> Config {
-    let path = wm_path(CONFIG_FILE);
-    match fs::read_to_string(&path) {
-        Ok(content) => toml::from_str(&content).unwrap_or_default(),
-        Err(_) => Config::default(),
+    read_config_from(&wm_path(CONFIG_FILE))
+}
+
+/// Parse a config file (missing = defaults)
+pub fn parse_config_from(path: &Path) -> Result<Config, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => toml::from_str(&content)
+            .map_err(|e| format!("Invalid {}: {}", path.display(), e.to_string().trim_end())),
+        Err(_) => Ok(Config::default()),
     }
 }
 
-/// Write project-level config
-pub fn write_config(config: &Config) -> io::Result<()> {
-    let path = wm_path(CONFIG_FILE);
+/// AIDEV-NOTE: Readers keep working on a broken config.toml (hooks must not
+/// fail), but say so once per process rather than silently running on
+/// defaults. Can't go through `log` - it reads the config itself.
+fn read_config_from(path: &Path) -> Config {
+    parse_config_from(path).unwrap_or_else(|e| {
+        static WARNED: std::sync::Once = std::sync::Once::new();
+        WARNED.call_once(|| eprintln!("wm: {} - using defaults", e));
+        Config::default()
+    })
+}
+
+/// Read-modify-write config.toml
+pub fn update_config(change: impl FnOnce(&mut Config) -> Result<(), String>) -> Result<(), String> {
+    update_config_in(&wm_path(CONFIG_FILE), change)
+}
+
+/// Read-modify-write the config file at `path`
+/// AIDEV-NOTE: Refuses to touch a file that doesn't parse - writing
+/// read_config's defaults back would wipe pauses, the current dive and
+/// unknown sections. `change` returning Err also leaves the file alone.
+pub fn update_config_in(
+    path: &Path,
+    change: impl FnOnce(&mut Config) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut config = parse_config_from(path)
+        .map_err(|e| format!("{}. Fix it first; config.toml was left unchanged.", e))?;
+    change(&mut config)?;
+    write_config_to(path, &config).map_err(|e| format!("Failed to write config: {}", e))
+}
+
+/// AIDEV-NOTE: Write to a .tmp file then rename (same as state.md in extract)
+/// so a crash mid-write never leaves a truncated config.toml behind.
+fn write_config_to(path: &Path, config: &Config) -> io::Result<()> {
     let content = toml::to_string_pretty(config)
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-    fs::write(path, content)
+    let tmp_path = path.with_extension("toml.tmp");
+    fs::write(&tmp_path, content)?;
+    fs::rename(&tmp_path, path)
 }
 
 /// Check if extract operation is enabled
+/// A timed pause (`wm pause --for`) counts as enabled once it expires.
 pub fn is_extract_enabled() -> bool {
-    read_config().operations.extract
+    read_config().operations.extract_enabled_at(Utc::now())
 }
 
 /// Check if compile operation is enabled
+/// A timed pause (`wm pause --for`) counts as enabled once it expires.
 pub fn is_compile_enabled() -> bool {
-    read_config().operations.compile
+    read_config().operations.compile_enabled_at(Utc::now())
 }
 
 // ============================================================================
@@ -109,12 +385,22 @@ pub fn dive_dir() -> PathBuf {
 
 /// Get path to a named dive prep (.wm/dives/{name}.md)
 pub fn dive_prep_path(name: &str) -> PathBuf {
-    dive_dir().join(format!("{}.md", name))
+    dive_prep_path_in(&wm_dir(), name)
+}
+
+/// Path to a named dive prep under `wm_dir`
+pub fn dive_prep_path_in(wm_dir: &Path, name: &str) -> PathBuf {
+    wm_dir.join(DIVES_DIR).join(format!("{}.md", name))
 }
 
 /// List all named dive preps (returns names without .md extension)
 pub fn list_dive_preps() -> io::Result<Vec<String>> {
-    let dir = dive_dir();
+    list_dive_preps_in(&wm_dir())
+}
+
+/// Named dive preps under `wm_dir` (sorted)
+pub fn list_dive_preps_in(wm_dir: &Path) -> io::Result<Vec<String>> {
+    let dir = wm_dir.join(DIVES_DIR);
     if !dir.exists() {
         return Ok(Vec::new());
     }
@@ -135,17 +421,214 @@ pub fn list_dive_preps() -> io::Result<Vec<String>> {
 
 /// Get the currently active dive prep name (None = use legacy fallback)
 pub fn current_dive() -> Option<String> {
-    read_config().dive.current
+    current_dive_in(&wm_dir())
+}
+
+/// Current dive prep recorded in `wm_dir`/config.toml
+pub fn current_dive_in(wm_dir: &Path) -> Option<String> {
+    read_config_from(&wm_dir.join(CONFIG_FILE)).dive.current
 }
 
 /// Set the current dive prep (None to clear)
-pub fn set_current_dive(name: Option<&str>) -> io::Result<()> {
-    let mut config = read_config();
-    config.dive.current = name.map(String::from);
-    write_config(&config)
+pub fn set_current_dive(name: Option<&str>) -> Result<(), String> {
+    set_current_dive_in(&wm_dir(), name)
+}
+
+/// Set the current dive prep in `wm_dir`/config.toml (None to clear)
+pub fn set_current_dive_in(wm_dir: &Path, name: Option<&str>) -> Result<(), String> {
+    update_config_in(&wm_dir.join(CONFIG_FILE), |config| {
+        config.dive.current = name.map(String::from);
+        Ok(())
+    })
 }
 
 /// Ensure the dives directory exists
 pub fn ensure_dive_dir() -> io::Result<()> {
     fs::create_dir_all(dive_dir())
 }
+
+// ============================================================================
+// Saved working sets
+// ============================================================================
+
+const WORKING_SETS_DIR: &str = "working_sets";
+
+/// Get the saved working sets directory path (.wm/working_sets/)
+pub fn working_sets_dir() -> PathBuf {
+    wm_path(WORKING_SETS_DIR)
+}
+
+/// Get path to a saved working set (.wm/working_sets/{name}.md)
+pub fn saved_working_set_path(name: &str) -> PathBuf {
+    working_sets_dir().join(format!("{}.md", name))
+}
+
+/// Set the saved working set the hook injects (None to clear)
+pub fn set_saved_working_set(name: Option<&str>) -> Result<(), String> {
+    update_config(|config| {
+        config.compile.saved = name.map(String::from);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_wm_dir_override_relocates_files() {
+        let store = tempfile::tempdir().unwrap();
+        let custom = store.path().join("wm-data");
+        let env = Some(custom.display().to_string());
+
+        // WM_DIR wins over CLAUDE_PROJECT_DIR and is used as-is (no .wm appended)
+        let dir = resolve_wm_dir(env.clone(), Some("/project".to_string()));
+        assert_eq!(dir, custom);
+        assert_eq!(
+            resolve_wm_dir(None, Some("/project".to_string())),
+            PathBuf::from("/project/.wm")
+        );
+        assert_eq!(
+            resolve_wm_dir(Some(String::new()), None),
+            PathBuf::from(".wm")
+        );
+
+        fs::create_dir_all(&dir).unwrap();
+        let path = write_working_set_in(&dir, Some("s1"), "ws").unwrap();
+        assert!(path.starts_with(&custom));
+        assert_eq!(fs::read_to_string(path).unwrap(), "ws");
+    }
+
+    fn make_session(root: &Path, id: &str, age_secs: u64) {
+        let dir = root.join(id);
+        fs::create_dir_all(&dir).unwrap();
+        let file = fs::File::create(dir.join(WORKING_SET_FILE)).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(age_secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_config_write_preserves_unknown_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        fs::write(
+            &path,
+            "[operations]\nextract = false\n\n[custom]\nteam = \"infra\"\nlimit = 3\n",
+        )
+        .unwrap();
+
+        let mut config = read_config_from(&path);
+        assert!(!config.operations.extract);
+        config.operations.compile = false;
+        write_config_to(&path, &config).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("[custom]"));
+        assert!(content.contains("team = \"infra\""));
+        let reread = read_config_from(&path);
+        assert!(!reread.operations.compile);
+        assert_eq!(reread.extra["custom"]["limit"].as_integer(), Some(3));
+        assert!(!dir.path().join("config.toml.tmp").exists());
+    }
+
+    #[test]
+    fn test_prune_keeps_most_recent_and_active() {
+        let root = tempfile::tempdir().unwrap();
+        make_session(root.path(), "newest", 10);
+        make_session(root.path(), "middle", 100);
+        make_session(root.path(), "oldest", 1000);
+        make_session(root.path(), "active-but-old", 5000);
+
+        let mut pruned = prune_session_dirs(root.path(), 2, "active-but-old").unwrap();
+        pruned.sort();
+
+        assert_eq!(pruned, vec!["middle".to_string(), "oldest".to_string()]);
+        assert!(root.path().join("newest").exists());
+        assert!(root.path().join("active-but-old").exists());
+    }
+
+    #[test]
+    fn test_prune_never_removes_active_even_at_zero() {
+        let root = tempfile::tempdir().unwrap();
+        make_session(root.path(), "active", 1000);
+        make_session(root.path(), "other", 10);
+
+        let pruned = prune_session_dirs(root.path(), 0, "active").unwrap();
+        assert_eq!(pruned, vec!["other".to_string()]);
+        assert!(root.path().join("active").exists());
+    }
+
+    #[test]
+    fn test_log_threshold_suppresses_lower_levels() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HOOK_LOG_FILE);
+
+        append_log(&path, LogLevel::Warn, LogLevel::Info, "extract", "progress");
+        append_log(&path, LogLevel::Warn, LogLevel::Debug, "extract", "preview");
+        assert!(!path.exists());
+
+        append_log(&path, LogLevel::Warn, LogLevel::Warn, "llm", "retrying");
+        append_log(&path, LogLevel::Info, LogLevel::Info, "extract", "progress");
+        let log = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("[llm] WARN retrying"));
+        assert!(lines[1].ends_with("[extract] progress"));
+    }
+
+    #[test]
+    fn test_rotate_log_past_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join(HOOK_LOG_FILE);
+        let rotated = |n: usize| dir.path().join(format!("{}.{}", HOOK_LOG_FILE, n));
+
+        fs::write(&log, "0123456789").unwrap();
+        assert!(
+            !rotate_log(&log, 10, 2).unwrap(),
+            "at the threshold: keep appending"
+        );
+        assert!(!rotate_log(&log, 0, 2).unwrap(), "0 disables rotation");
+
+        fs::write(&log, "first log!!").unwrap();
+        assert!(rotate_log(&log, 10, 2).unwrap());
+        assert!(!log.exists());
+        assert_eq!(fs::read_to_string(rotated(1)).unwrap(), "first log!!");
+
+        // Older rotations shift up; the oldest beyond `keep` is overwritten
+        fs::write(&log, "second log!").unwrap();
+        rotate_log(&log, 10, 2).unwrap();
+        fs::write(&log, "third log!!").unwrap();
+        rotate_log(&log, 10, 2).unwrap();
+        assert_eq!(fs::read_to_string(rotated(1)).unwrap(), "third log!!");
+        assert_eq!(fs::read_to_string(rotated(2)).unwrap(), "second log!");
+        assert!(!rotated(3).exists());
+    }
+
+    #[test]
+    fn test_latest_working_set_prefers_own_then_newest() {
+        let root = tempfile::tempdir().unwrap();
+        let sessions = root.path().join("sessions");
+        assert_eq!(latest_working_set_in(root.path(), "new"), None);
+
+        make_session(&sessions, "old", 1000);
+        make_session(&sessions, "recent", 10);
+        fs::write(root.path().join(WORKING_SET_FILE), "global").unwrap();
+        let global = fs::File::options()
+            .write(true)
+            .open(root.path().join(WORKING_SET_FILE))
+            .unwrap();
+        global
+            .set_modified(SystemTime::now() - Duration::from_secs(100))
+            .unwrap();
+
+        assert_eq!(
+            latest_working_set_in(root.path(), "new"),
+            Some(sessions.join("recent").join(WORKING_SET_FILE))
+        );
+        assert_eq!(
+            latest_working_set_in(root.path(), "old"),
+            Some(sessions.join("old").join(WORKING_SET_FILE))
+        );
+    }
+}
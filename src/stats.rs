@@ -0,0 +1,204 @@
+//! `wm state stats` - how much knowledge wm has accumulated
+//!
+//! Sizes state.md (with a rough token estimate, to judge whether it's time to
+//! compress), counts dive preps and distilled guardrails/metis, and lists
+//! which sessions have a compiled working set.
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::distill;
+use crate::state;
+
+/// Rough chars-per-token ratio for English prose and code
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Size of a text file
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TextStats {
+    pub lines: usize,
+    pub words: usize,
+    pub chars: usize,
+    /// chars / 4, good enough to compare against a context budget
+    pub tokens_estimate: usize,
+}
+
+impl TextStats {
+    fn of(content: &str) -> Self {
+        let chars = content.chars().count();
+        Self {
+            lines: content.lines().count(),
+            words: content.split_whitespace().count(),
+            chars,
+            tokens_estimate: chars.div_ceil(CHARS_PER_TOKEN),
+        }
+    }
+}
+
+/// A session directory and whether it holds a compiled working set
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct SessionWorkingSet {
+    pub session_id: String,
+    pub has_working_set: bool,
+}
+
+/// Everything `wm state stats` reports
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct WmStats {
+    pub state: TextStats,
+    pub dive_preps: usize,
+    pub guardrails: usize,
+    pub metis: usize,
+    pub sessions: Vec<SessionWorkingSet>,
+}
+
+/// Run wm state stats [--json]
+pub fn run(json: bool) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let stats = compute(&state::wm_dir())?;
+    if json {
+        let out = serde_json::to_string_pretty(&stats)
+            .map_err(|e| format!("Failed to serialize stats: {}", e))?;
+        println!("{}", out);
+    } else {
+        print!("{}", render(&stats));
+    }
+    Ok(())
+}
+
+/// Gather stats from a .wm/ directory (missing files count as empty)
+fn compute(wm_dir: &Path) -> Result<WmStats, String> {
+    let read = |path: &Path| fs::read_to_string(path).unwrap_or_default();
+    let distill_dir = wm_dir.join(distill::DISTILL_DIR);
+
+    let sessions = state::list_session_ids_in(wm_dir)
+        .map_err(|e| format!("Failed to list sessions: {}", e))?
+        .into_iter()
+        .map(|session_id| SessionWorkingSet {
+            has_working_set: wm_dir
+                .join("sessions")
+                .join(&session_id)
+                .join(state::WORKING_SET_FILE)
+                .exists(),
+            session_id,
+        })
+        .collect();
+
+    Ok(WmStats {
+        state: TextStats::of(&read(&wm_dir.join("state.md"))),
+        dive_preps: state::list_dive_preps_in(wm_dir)
+            .map_err(|e| format!("Failed to list dive preps: {}", e))?
+            .len(),
+        guardrails: distill::count_items(&read(&distill_dir.join("guardrails.md"))),
+        metis: distill::count_items(&read(&distill_dir.join("metis.md"))),
+        sessions,
+    })
+}
+
+/// Human-readable report
+fn render(stats: &WmStats) -> String {
+    let s = &stats.state;
+    let with_working_set = stats.sessions.iter().filter(|s| s.has_working_set).count();
+
+    let mut out = format!(
+        "state.md:    {} lines, {} words, {} chars (~{} tokens)\n\
+         dive preps:  {}\n\
+         guardrails:  {}\n\
+         metis:       {}\n\
+         sessions:    {} ({} with a working set)\n",
+        s.lines,
+        s.words,
+        s.chars,
+        s.tokens_estimate,
+        stats.dive_preps,
+        stats.guardrails,
+        stats.metis,
+        stats.sessions.len(),
+        with_working_set,
+    );
+    for session in &stats.sessions {
+        out.push_str(&format!(
+            "  {}  {}\n",
+            session.session_id,
+            if session.has_working_set {
+                "working set"
+            } else {
+                "-"
+            }
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_over_fixture_wm_dir() {
+        let wm = tempfile::tempdir().unwrap();
+        let dir = wm.path();
+        fs::write(
+            dir.join("state.md"),
+            "# State\n\n- Uses sqlite for local state\n",
+        )
+        .unwrap();
+        fs::create_dir_all(dir.join("dives")).unwrap();
+        fs::write(dir.join("dives/auth.md"), "# Dive").unwrap();
+        fs::write(dir.join("dives/notes.txt"), "not a prep").unwrap();
+        fs::create_dir_all(dir.join("distill")).unwrap();
+        fs::write(
+            dir.join("distill/guardrails.md"),
+            "# Guardrails\n\n- a\n- b\n",
+        )
+        .unwrap();
+        fs::write(dir.join("distill/metis.md"), "# Metis\n\n## testing\n- c\n").unwrap();
+        fs::create_dir_all(dir.join("sessions/s1")).unwrap();
+        fs::write(dir.join("sessions/s1/working_set.md"), "ws").unwrap();
+        fs::create_dir_all(dir.join("sessions/s2")).unwrap();
+
+        let stats = compute(dir).unwrap();
+        assert_eq!(
+            stats.state,
+            TextStats {
+                lines: 3,
+                words: 8,
+                chars: 39,
+                tokens_estimate: 10,
+            }
+        );
+        assert_eq!(stats.dive_preps, 1);
+        assert_eq!(stats.guardrails, 2);
+        assert_eq!(stats.metis, 1);
+        assert_eq!(
+            stats.sessions,
+            vec![
+                SessionWorkingSet {
+                    session_id: "s1".to_string(),
+                    has_working_set: true,
+                },
+                SessionWorkingSet {
+                    session_id: "s2".to_string(),
+                    has_working_set: false,
+                },
+            ]
+        );
+
+        let out = render(&stats);
+        assert!(out.contains("~10 tokens"));
+        assert!(out.contains("sessions:    2 (1 with a working set)"));
+    }
+
+    #[test]
+    fn test_compute_empty_wm_dir() {
+        let wm = tempfile::tempdir().unwrap();
+        let stats = compute(wm.path()).unwrap();
+        assert_eq!(stats.state, TextStats::default());
+        assert_eq!((stats.dive_preps, stats.guardrails, stats.metis), (0, 0, 0));
+        assert!(stats.sessions.is_empty());
+    }
+}
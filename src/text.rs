@@ -0,0 +1,160 @@
+//! Text helpers for user-facing and LLM-facing output
+//!
+//! AIDEV-NOTE: All truncation goes through here. Slicing a `&str` at an arbitrary
+//! byte index panics on multi-byte characters (CJK, emoji), so every helper cuts
+//! on char boundaries.
+
+/// Ellipsis appended by `truncate_chars` when content is cut
+const ELLIPSIS: &str = "...";
+
+/// Truncate to at most `max_chars` characters, appending "..." if anything was cut
+pub fn truncate_chars(s: &str, max_chars: usize) -> String {
+    match s.char_indices().nth(max_chars) {
+        Some((byte_idx, _)) => format!("{}{}", &s[..byte_idx], ELLIPSIS),
+        None => s.to_string(),
+    }
+}
+
+/// Longest prefix of `s` that fits in `max_bytes` without splitting a character
+pub fn truncate_bytes(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let mut end = max_bytes;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// Tool output cut to `max_bytes` with a `...[truncated]` marker (0 = no limit)
+pub fn truncate_tool_output(s: &str, max_bytes: usize) -> String {
+    if max_bytes == 0 || s.len() <= max_bytes {
+        return s.to_string();
+    }
+    format!("{}...[truncated]", truncate_bytes(s, max_bytes))
+}
+
+/// Line prefixes that start a new message in formatted transcript context
+const MESSAGE_PREFIXES: &[&str] = &[
+    "USER:",
+    "ASSISTANT:",
+    "THINKING:",
+    "TOOLS:",
+    "TOOL:",
+    "TOOL_RESULT:",
+    "SUMMARY:",
+    "CORRECTION:",
+];
+
+/// Split formatted transcript context into chunks of at most `max_bytes`
+///
+/// Cuts only where a line starts a new message (USER:, ASSISTANT:, ...), so a
+/// message is never split across chunks. A single message larger than the
+/// budget is the exception: it is cut on char boundaries.
+pub fn chunk_messages(text: &str, max_bytes: usize) -> Vec<String> {
+    if max_bytes == 0 || text.len() <= max_bytes {
+        return vec![text.to_string()];
+    }
+
+    // Byte offsets where messages begin
+    let mut starts = vec![0];
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if offset > 0 && MESSAGE_PREFIXES.iter().any(|p| line.starts_with(p)) {
+            starts.push(offset);
+        }
+        offset += line.len();
+    }
+    starts.push(text.len());
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for bounds in starts.windows(2) {
+        let mut message = &text[bounds[0]..bounds[1]];
+        if !current.is_empty() && current.len() + message.len() > max_bytes {
+            chunks.push(std::mem::take(&mut current));
+        }
+        // Oversized single message: hard-split it
+        while message.len() > max_bytes {
+            let mut head = truncate_bytes(message, max_bytes);
+            if head.is_empty() {
+                // Budget smaller than one character: take the character anyway
+                head = &message[..message.chars().next().map_or(0, char::len_utf8)];
+            }
+            chunks.push(head.to_string());
+            message = &message[head.len()..];
+        }
+        current.push_str(message);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_chars_short_unchanged() {
+        assert_eq!(truncate_chars("short", 10), "short");
+        assert_eq!(truncate_chars("exact", 5), "exact");
+    }
+
+    #[test]
+    fn test_truncate_chars_ascii() {
+        assert_eq!(truncate_chars("abcdefgh", 3), "abc...");
+    }
+
+    #[test]
+    fn test_truncate_chars_cjk() {
+        let text = "知識管理システムのテスト";
+        assert_eq!(truncate_chars(text, 4), "知識管理...");
+    }
+
+    #[test]
+    fn test_truncate_chars_emoji() {
+        let text = "🎉".repeat(10);
+        let truncated = truncate_chars(&text, 3);
+        assert_eq!(truncated, "🎉🎉🎉...");
+        assert_eq!(truncated.chars().count(), 6);
+    }
+
+    #[test]
+    fn test_truncate_bytes_respects_char_boundary() {
+        // Each emoji is 4 bytes; 6 bytes must round down to one emoji
+        assert_eq!(truncate_bytes("🎉🎉🎉", 6), "🎉");
+        // CJK characters are 3 bytes each
+        assert_eq!(truncate_bytes("日本語", 7), "日本");
+        assert_eq!(truncate_bytes("ascii", 10), "ascii");
+        assert_eq!(truncate_bytes("ascii", 0), "");
+    }
+
+    #[test]
+    fn test_chunk_messages_cuts_at_message_boundaries() {
+        let text = "USER: one\n\nASSISTANT: two\nstill two\n\nUSER: three\n\n";
+        assert_eq!(chunk_messages(text, 1000), vec![text.to_string()]);
+        assert_eq!(chunk_messages(text, 0), vec![text.to_string()]);
+
+        let chunks = chunk_messages(text, 30);
+        assert_eq!(
+            chunks,
+            vec![
+                "USER: one\n\n".to_string(),
+                "ASSISTANT: two\nstill two\n\n".to_string(),
+                "USER: three\n\n".to_string(),
+            ]
+        );
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn test_chunk_messages_splits_oversized_message() {
+        let text = format!("USER: {}\n\nUSER: ok\n", "日".repeat(10));
+        let chunks = chunk_messages(&text, 16);
+        assert!(chunks.iter().all(|c| c.len() <= 16));
+        assert_eq!(chunks.concat(), text);
+    }
+}
@@ -1,4 +1,7 @@
 pub mod reader;
 pub mod types;
 
-pub use reader::{format_context, get_messages_in_window, get_messages_since, read_transcript};
+pub use reader::{
+    STDIN_PATH, format_context, get_messages_in_window, get_messages_since, keep_active_thread,
+    read_transcript, read_transcript_since,
+};
@@ -1,17 +1,69 @@
 use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 
+use crate::redact;
+use crate::text;
 use crate::transcript::types::TranscriptEntry;
-use crate::types::{strip_xml_tags, ReadError};
+use crate::types::{MessageRole, ReadError, strip_xml_tags};
+
+/// Transcript path meaning "read JSONL from stdin" (`wm extract --transcript -`)
+pub const STDIN_PATH: &str = "-";
 
 /// Read and parse a transcript JSONL file
 ///
 /// Skips malformed lines rather than failing entirely
 pub fn read_transcript(path: &Path) -> Result<Vec<TranscriptEntry>, ReadError> {
+    read_entries(path, |_| true)
+}
+
+/// Read only the entries extraction can use: messages and summaries for
+/// `session_id`, timestamped after `since` (untimestamped entries are kept)
+///
+/// AIDEV-NOTE: Filters while parsing so a long transcript is never fully
+/// materialized. Keeps a superset of what `get_messages_since(since)` and
+/// `get_messages_in_window` with a start >= `since` select, so those can run
+/// on the result unchanged. Callers that need carryover pass the window start.
+pub fn read_transcript_since(
+    path: &Path,
+    since: Option<DateTime<Utc>>,
+    session_id: Option<&str>,
+) -> Result<Vec<TranscriptEntry>, ReadError> {
+    read_entries(path, |entry| {
+        (entry.is_message() || entry.is_summary())
+            && session_id.is_none_or(|sid| entry.session_id() == Some(sid))
+            && match (since, parsed_timestamp(entry)) {
+                (Some(cutoff), Some(ts)) => ts >= cutoff,
+                _ => true,
+            }
+    })
+}
+
+/// Parse a transcript file (or stdin for `-`), keeping entries that pass `keep`
+fn read_entries(
+    path: &Path,
+    keep: impl FnMut(&TranscriptEntry) -> bool,
+) -> Result<Vec<TranscriptEntry>, ReadError> {
+    if path == Path::new(STDIN_PATH) {
+        return parse_entries(std::io::stdin().lock(), keep);
+    }
     let file = File::open(path)?;
-    let reader = BufReader::new(file);
+    // Archived sessions (<id>.jsonl.gz) are decompressed as they're read
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        return parse_entries(BufReader::new(GzDecoder::new(file)), keep);
+    }
+    parse_entries(BufReader::new(file), keep)
+}
+
+/// Parse JSONL line by line, keeping entries that pass `keep`
+fn parse_entries(
+    reader: impl BufRead,
+    mut keep: impl FnMut(&TranscriptEntry) -> bool,
+) -> Result<Vec<TranscriptEntry>, ReadError> {
     let mut entries = Vec::new();
 
     for (line_num, line_result) in reader.lines().enumerate() {
@@ -21,7 +73,8 @@ pub fn read_transcript(path: &Path) -> Result<Vec<TranscriptEntry>, ReadError> {
         }
 
         match serde_json::from_str::<TranscriptEntry>(&line) {
-            Ok(entry) => entries.push(entry),
+            Ok(entry) if keep(&entry) => entries.push(entry),
+            Ok(_) => {}
             Err(e) => {
                 // Log warning but continue - don't fail on malformed lines
                 eprintln!(
@@ -52,17 +105,18 @@ pub fn get_messages_in_window<'a>(
 
     let content_filter = |e: &&TranscriptEntry| e.is_message() || e.is_summary();
 
-    entries
-        .iter()
-        .filter(content_filter)
-        .filter(session_filter)
-        .filter(|e| {
-            e.timestamp()
-                .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
-                .map(|ts| ts >= start && ts < end)
-                .unwrap_or(false)
-        })
-        .collect()
+    sort_chronologically(
+        entries
+            .iter()
+            .filter(content_filter)
+            .filter(session_filter)
+            .filter(|e| {
+                parsed_timestamp(e)
+                    .map(|ts| ts >= start && ts < end)
+                    .unwrap_or(false)
+            })
+            .collect(),
+    )
 }
 
 /// Get messages since a given timestamp, optionally filtered by session
@@ -84,7 +138,7 @@ pub fn get_messages_since<'a>(
     // Include messages AND summaries (summaries provide context after compaction)
     let content_filter = |e: &&TranscriptEntry| e.is_message() || e.is_summary();
 
-    match since {
+    let selected = match since {
         Some(cutoff) => {
             entries
                 .iter()
@@ -93,10 +147,7 @@ pub fn get_messages_since<'a>(
                 .filter(|e| {
                     // Include if timestamp is after cutoff (or if no timestamp)
                     // Summaries don't have timestamps, so they pass through
-                    e.timestamp()
-                        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
-                        .map(|ts| ts > cutoff)
-                        .unwrap_or(true)
+                    parsed_timestamp(e).map(|ts| ts > cutoff).unwrap_or(true)
                 })
                 .collect()
         }
@@ -108,7 +159,156 @@ pub fn get_messages_since<'a>(
                 .filter(session_filter)
                 .collect()
         }
+    };
+
+    sort_chronologically(selected)
+}
+
+/// Drop messages on abandoned branches, keeping the thread that leads to the
+/// latest message
+///
+/// AIDEV-NOTE: Editing a prompt or retrying forks the `parentUuid` chain; the
+/// old branch stays in the file and interleaves with the live one by time.
+/// The active path is walked back from the newest message. A side branch off
+/// that path is abandoned - and dropped - only when it starts with a user
+/// prompt: that's what an edit or retry leaves behind. Parallel tool calls
+/// also fork (each tool_use/tool_result pair hangs off the assistant entry)
+/// and those branches are real output, so they're kept. Messages whose
+/// ancestry never joins the path (parents before the read cutoff, chains
+/// broken by compaction, other sessions) are kept, as are summaries.
+pub fn keep_active_thread(entries: Vec<TranscriptEntry>) -> Vec<TranscriptEntry> {
+    let parents: HashMap<&str, Option<&str>> = entries
+        .iter()
+        .filter_map(|e| Some((e.uuid()?, e.parent_uuid())))
+        .collect();
+
+    // Newest message; equal timestamps resolve to the later line
+    let Some(leaf) = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.uuid().is_some())
+        .max_by_key(|(i, e)| (parsed_timestamp(e), *i))
+        .and_then(|(_, e)| e.uuid())
+    else {
+        return entries;
+    };
+
+    let mut active = HashSet::new();
+    let mut cursor = Some(leaf);
+    while let Some(uuid) = cursor {
+        if !active.insert(uuid) {
+            break; // cycle guard
+        }
+        cursor = parents.get(uuid).copied().flatten();
     }
+
+    let by_uuid: HashMap<&str, &TranscriptEntry> = entries
+        .iter()
+        .filter_map(|e| Some((e.uuid()?, e)))
+        .collect();
+    let mut joins: HashMap<&str, bool> = active.iter().map(|uuid| (*uuid, true)).collect();
+    let abandoned: HashSet<String> = parents
+        .keys()
+        .filter(|uuid| !active.contains(*uuid) && joins_path(uuid, &parents, &mut joins))
+        .filter(|uuid| {
+            branch_root(uuid, &parents, &active)
+                .and_then(|root| by_uuid.get(root))
+                .is_some_and(|entry| is_prompt(entry))
+        })
+        .map(|uuid| uuid.to_string())
+        .collect();
+    if abandoned.is_empty() {
+        return entries;
+    }
+
+    entries
+        .into_iter()
+        .filter(|e| e.uuid().is_none_or(|uuid| !abandoned.contains(uuid)))
+        .collect()
+}
+
+/// First message of the side branch holding `uuid`: the ancestor (or `uuid`
+/// itself) whose parent is on the active path
+fn branch_root<'a>(
+    uuid: &'a str,
+    parents: &HashMap<&'a str, Option<&'a str>>,
+    active: &HashSet<&'a str>,
+) -> Option<&'a str> {
+    let mut seen = HashSet::new();
+    let mut current = uuid;
+    loop {
+        let parent = parents.get(current).copied().flatten()?;
+        if active.contains(parent) {
+            return Some(current);
+        }
+        if !seen.insert(parent) {
+            return None; // cycle guard
+        }
+        current = parent;
+    }
+}
+
+/// A prompt the user typed, as opposed to a tool result sent back to Claude
+fn is_prompt(entry: &TranscriptEntry) -> bool {
+    entry.user_text().is_some() && entry.tool_results().is_empty()
+}
+
+/// Whether following parents from `uuid` reaches the active path
+/// `joins` memoizes the answer for every message visited (active ones start true).
+fn joins_path<'a>(
+    uuid: &'a str,
+    parents: &HashMap<&'a str, Option<&'a str>>,
+    joins: &mut HashMap<&'a str, bool>,
+) -> bool {
+    let mut chain = HashSet::new();
+    let mut cursor = parents.get(uuid).copied().flatten();
+    let result = loop {
+        let Some(parent) = cursor else { break false };
+        if let Some(&known) = joins.get(parent) {
+            break known;
+        }
+        if !chain.insert(parent) {
+            break false; // cycle guard
+        }
+        cursor = parents.get(parent).copied().flatten();
+    };
+    for visited in chain {
+        joins.insert(visited, result);
+    }
+    result
+}
+
+/// Parse an entry's RFC3339 timestamp, if it has one
+fn parsed_timestamp(entry: &TranscriptEntry) -> Option<DateTime<Utc>> {
+    entry
+        .timestamp()
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|ts| ts.with_timezone(&Utc))
+}
+
+/// Stable-sort entries by timestamp
+/// AIDEV-NOTE: File order isn't always chronological (compaction can write out
+/// of order). Entries without a timestamp (summaries) travel with the next
+/// timestamped entry so a summary stays in front of the message it precedes;
+/// untimestamped entries at the end stay at the end. Equal timestamps keep
+/// file order.
+fn sort_chronologically(entries: Vec<&TranscriptEntry>) -> Vec<&TranscriptEntry> {
+    let mut blocks: Vec<(DateTime<Utc>, Vec<&TranscriptEntry>)> = Vec::new();
+    let mut pending = Vec::new();
+
+    for entry in entries {
+        pending.push(entry);
+        if let Some(ts) = parsed_timestamp(entry) {
+            blocks.push((ts, std::mem::take(&mut pending)));
+        }
+    }
+
+    blocks.sort_by_key(|(ts, _)| *ts);
+    blocks
+        .into_iter()
+        .flat_map(|(_, block)| block)
+        .chain(pending)
+        .collect()
 }
 
 /// Strip ALL <system-reminder>...</system-reminder> blocks
@@ -145,49 +345,60 @@ fn tool_summary(name: &str, input: Option<&serde_json::Value>) -> String {
 }
 
 /// Format messages for context (for sending to extraction LLM)
-pub fn format_context(messages: &[&TranscriptEntry]) -> String {
-    let mut output = String::new();
+/// Only blocks whose kind is in `roles` are emitted; summaries always are.
+/// Tool results are cut to `tool_output_max_bytes` (0 = no limit).
+pub fn format_context(
+    messages: &[&TranscriptEntry],
+    roles: &[MessageRole],
+    tool_output_max_bytes: usize,
+) -> String {
+    let mut blocks = Blocks::default();
+    let include = |role: MessageRole| roles.contains(&role);
 
     for entry in messages {
         match entry {
             TranscriptEntry::Summary { .. } => {
                 if let Some(text) = entry.summary_text() {
-                    output.push_str("SUMMARY: ");
-                    output.push_str(text);
-                    output.push_str("\n\n");
+                    blocks.push("SUMMARY: ", text);
                 }
             }
             TranscriptEntry::User { .. } => {
                 // Include tool results (what Claude read/executed)
                 let tool_results = entry.tool_results();
-                if !tool_results.is_empty() {
+                if include(MessageRole::ToolResult) && !tool_results.is_empty() {
                     for (_id, content) in &tool_results {
-                        output.push_str("TOOL_RESULT: ");
-                        output.push_str(content);
-                        output.push_str("\n\n");
+                        blocks.push(
+                            "TOOL_RESULT: ",
+                            &text::truncate_tool_output(content, tool_output_max_bytes),
+                        );
                     }
                 }
 
-                if let Some(text) = entry.user_text() {
+                if let Some(text) = entry.user_text().filter(|_| include(MessageRole::User)) {
                     let cleaned = strip_system_reminders(&text);
                     if !cleaned.is_empty() {
-                        output.push_str("USER: ");
-                        output.push_str(&cleaned);
-                        output.push_str("\n\n");
+                        blocks.push("USER: ", &cleaned);
                     }
                 }
             }
             TranscriptEntry::Assistant { .. } => {
-                let tool_uses = entry.tool_uses();
+                let assistant = include(MessageRole::Assistant);
+                let tool_uses = if assistant {
+                    entry.tool_uses()
+                } else {
+                    Vec::new()
+                };
 
                 // Include thinking if present (shows Claude's reasoning)
-                if let Some(thinking) = entry.assistant_thinking() {
-                    output.push_str("THINKING: ");
-                    output.push_str(&thinking);
-                    output.push_str("\n\n");
+                if let Some(thinking) = entry
+                    .assistant_thinking()
+                    .filter(|_| include(MessageRole::Thinking))
+                {
+                    blocks.push("THINKING: ", &thinking);
                 }
 
                 if !tool_uses.is_empty() {
+                    let output = &mut blocks.output;
                     output.push_str("TOOLS: ");
                     for (name, input) in &tool_uses {
                         output.push_str(name);
@@ -202,19 +413,48 @@ pub fn format_context(messages: &[&TranscriptEntry]) -> String {
                     output.push('\n');
                 }
 
-                if let Some(text) = entry.assistant_text() {
-                    output.push_str("ASSISTANT: ");
-                    output.push_str(&text);
-                    output.push_str("\n\n");
+                if let Some(text) = entry.assistant_text().filter(|_| assistant) {
+                    blocks.push("ASSISTANT: ", &text);
                 } else if !tool_uses.is_empty() {
-                    output.push('\n');
+                    blocks.output.push('\n');
                 }
             }
             _ => {}
         }
     }
 
-    output
+    // Mask API keys, tokens, etc. before the text leaves for the LLM
+    redact::scrub(&blocks.output)
+}
+
+/// Blocks shorter than this are always emitted, even when repeated
+const MIN_DEDUP_BYTES: usize = 80;
+
+/// format_context output, skipping blocks already emitted verbatim
+/// AIDEV-NOTE: CLAUDE.md-style boilerplate recurs across turns (re-read files,
+/// pasted instructions, summaries) even after reminders are stripped; only the
+/// first copy carries signal. Short blocks ("yes", "continue") are exempt:
+/// repeating them is part of the conversation, not noise.
+#[derive(Default)]
+struct Blocks {
+    output: String,
+    seen: HashSet<u64>,
+}
+
+impl Blocks {
+    /// Append `label` + `text` unless the same text was emitted earlier
+    fn push(&mut self, label: &str, text: &str) {
+        if text.len() >= MIN_DEDUP_BYTES {
+            let mut hasher = DefaultHasher::new();
+            text.hash(&mut hasher);
+            if !self.seen.insert(hasher.finish()) {
+                return;
+            }
+        }
+        self.output.push_str(label);
+        self.output.push_str(text);
+        self.output.push_str("\n\n");
+    }
 }
 
 #[cfg(test)]
@@ -238,6 +478,265 @@ mod tests {
         assert_eq!(entry.assistant_text(), Some("hi there".to_string()));
     }
 
+    fn user_at(text: &str, ts: &str) -> TranscriptEntry {
+        serde_json::from_str(&format!(
+            r#"{{"type":"user","uuid":"{text}","sessionId":"s1","timestamp":"{ts}","message":{{"role":"user","content":"{text}"}}}}"#
+        ))
+        .unwrap()
+    }
+
+    fn summary(text: &str) -> TranscriptEntry {
+        serde_json::from_str(&format!(r#"{{"type":"summary","summary":"{text}"}}"#)).unwrap()
+    }
+
+    fn order(entries: &[&TranscriptEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .map(|e| {
+                e.user_text()
+                    .or_else(|| e.summary_text().map(String::from))
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_messages_since_sorted_by_timestamp() {
+        let entries = vec![
+            user_at("third", "2025-01-15T10:00:03Z"),
+            summary("recap"),
+            user_at("first", "2025-01-15T10:00:01Z"),
+            user_at("second", "2025-01-15T10:00:02+00:00"),
+            summary("trailing"),
+        ];
+
+        let selected = get_messages_since(&entries, None, None);
+        assert_eq!(
+            order(&selected),
+            vec!["recap", "first", "second", "third", "trailing"]
+        );
+
+        let start = "2025-01-15T10:00:00Z".parse().unwrap();
+        let end = "2025-01-15T10:00:10Z".parse().unwrap();
+        let windowed = get_messages_in_window(&entries, start, end, None);
+        assert_eq!(order(&windowed), vec!["first", "second", "third"]);
+    }
+
+    #[test]
+    fn test_read_gzipped_transcript_matches_plain() {
+        use flate2::{Compression, write::GzEncoder};
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let content = r#"{"type":"user","uuid":"a","sessionId":"s1","timestamp":"2025-01-15T10:00:00Z","message":{"role":"user","content":"hello"}}
+{"type":"summary","summary":"compacted"}
+{"type":"assistant","uuid":"b","sessionId":"s1","timestamp":"2025-01-15T10:00:01Z","message":{"role":"assistant","content":[{"type":"text","text":"hi"}]}}
+"#;
+        let plain = dir.path().join("s1.jsonl");
+        std::fs::write(&plain, content).unwrap();
+        let gzipped = dir.path().join("s1.jsonl.gz");
+        let mut encoder = GzEncoder::new(File::create(&gzipped).unwrap(), Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let debug = |entries: Vec<TranscriptEntry>| format!("{:?}", entries);
+        let from_plain = read_transcript(&plain).unwrap();
+        assert_eq!(from_plain.len(), 3);
+        assert_eq!(debug(read_transcript(&gzipped).unwrap()), debug(from_plain));
+    }
+
+    /// Peak resident set size of this process so far (KiB)
+    #[cfg(target_os = "linux")]
+    fn peak_rss_kib() -> u64 {
+        std::fs::read_to_string("/proc/self/status")
+            .unwrap()
+            .lines()
+            .find_map(|line| line.strip_prefix("VmHWM:"))
+            .and_then(|rest| rest.trim().trim_end_matches("kB").trim().parse().ok())
+            .unwrap()
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    #[ignore = "writes a ~100MB transcript; run alone with --ignored"]
+    fn test_read_transcript_since_memory_stays_bounded_on_100mb() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("big.jsonl");
+        let start = DateTime::parse_from_rfc3339("2025-01-15T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let padding = "x".repeat(2000);
+        let count: i64 = 100 * 1024 * 1024 / 2100;
+        {
+            let mut file = std::io::BufWriter::new(File::create(&path).unwrap());
+            for i in 0..count {
+                writeln!(
+                    file,
+                    r#"{{"type":"user","uuid":"u{i}","sessionId":"s1","timestamp":"{}","message":{{"role":"user","content":"{padding} {i}"}}}}"#,
+                    (start + chrono::Duration::seconds(i)).to_rfc3339()
+                )
+                .unwrap();
+            }
+        }
+        assert!(std::fs::metadata(&path).unwrap().len() >= 100 * 1024 * 1024);
+
+        let before = peak_rss_kib();
+        let cutoff = start + chrono::Duration::seconds(count - 500);
+        let entries = read_transcript_since(&path, Some(cutoff), Some("s1")).unwrap();
+        let grown = peak_rss_kib().saturating_sub(before);
+
+        // The kept tail is ~1MB; parsing everything would need far more than 100MB
+        assert_eq!(entries.len(), 500);
+        assert!(grown < 32 * 1024, "peak RSS grew by {} KiB", grown);
+    }
+
+    #[test]
+    fn test_read_transcript_since_matches_eager_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("t.jsonl");
+        std::fs::write(
+            &path,
+            r#"{"type":"user","uuid":"a","sessionId":"s1","timestamp":"2025-01-15T10:00:00Z","message":{"role":"user","content":"old"}}
+{"type":"summary","summary":"compacted"}
+{"type":"user","uuid":"b","sessionId":"s2","timestamp":"2025-01-15T10:05:00Z","message":{"role":"user","content":"other session"}}
+not json
+{"type":"assistant","uuid":"c","sessionId":"s1","timestamp":"2025-01-15T10:04:00Z","message":{"role":"assistant","content":[{"type":"text","text":"carryover"}]}}
+{"type":"user","uuid":"d","sessionId":"s1","timestamp":"2025-01-15T10:06:00Z","message":{"role":"user","content":"new"}}
+{"type":"file-history-snapshot","sessionId":"s1","timestamp":"2025-01-15T10:07:00Z"}
+"#,
+        )
+        .unwrap();
+
+        let ts = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let cutoff = Some(ts("2025-01-15T10:05:00Z"));
+        let window = (ts("2025-01-15T10:03:00Z"), ts("2025-01-15T10:05:00Z"));
+
+        let text = |m: &[&TranscriptEntry]| format_context(m, &MessageRole::ALL, 0);
+
+        let eager = read_transcript(&path).unwrap();
+        let streamed = read_transcript_since(&path, Some(window.0), Some("s1")).unwrap();
+        assert!(streamed.len() < eager.len());
+
+        assert_eq!(
+            text(&get_messages_since(&streamed, cutoff, Some("s1"))),
+            text(&get_messages_since(&eager, cutoff, Some("s1")))
+        );
+        assert_eq!(
+            text(&get_messages_in_window(
+                &streamed,
+                window.0,
+                window.1,
+                Some("s1")
+            )),
+            text(&get_messages_in_window(
+                &eager,
+                window.0,
+                window.1,
+                Some("s1")
+            ))
+        );
+
+        let all = read_transcript_since(&path, None, None).unwrap();
+        assert_eq!(
+            text(&get_messages_since(&all, None, None)),
+            text(&get_messages_since(&eager, None, None))
+        );
+    }
+
+    #[test]
+    fn test_parse_entries_from_stdin_stream() {
+        // Same path `--transcript -` takes, with a cursor standing in for stdin
+        let input = r#"{"type":"user","uuid":"a","sessionId":"s1","timestamp":"2025-01-15T10:00:00Z","message":{"role":"user","content":"Use tabs"}}
+not json
+{"type":"user","uuid":"b","sessionId":"s2","timestamp":"2025-01-15T10:00:01Z","message":{"role":"user","content":"other session"}}
+"#;
+        let entries = parse_entries(std::io::Cursor::new(input), |e| {
+            e.session_id() == Some("s1")
+        })
+        .unwrap();
+
+        let messages = get_messages_since(&entries, None, Some("s1"));
+        assert_eq!(order(&messages), ["Use tabs"]);
+    }
+
+    #[test]
+    fn test_keep_active_thread_drops_abandoned_branch() {
+        let msg = |uuid: &str, parent: Option<&str>, ts: &str| -> TranscriptEntry {
+            serde_json::from_value(serde_json::json!({
+                "type": "user", "uuid": uuid, "parentUuid": parent, "sessionId": "s1",
+                "timestamp": format!("2025-01-15T10:00:{ts}Z"),
+                "message": {"role": "user", "content": uuid}
+            }))
+            .unwrap()
+        };
+        // a -> b -> c (abandoned retry) ; b -> d -> e (live). x is a disconnected
+        // segment (parent before the read cutoff) and must survive.
+        let entries = vec![
+            msg("x", Some("before-cutoff"), "00"),
+            msg("a", None, "01"),
+            msg("b", Some("a"), "02"),
+            msg("c", Some("b"), "03"),
+            summary("recap"),
+            msg("d", Some("b"), "04"),
+            msg("e", Some("d"), "05"),
+        ];
+
+        let kept = keep_active_thread(entries);
+        let refs: Vec<&TranscriptEntry> = kept.iter().collect();
+        assert_eq!(order(&refs), ["x", "a", "b", "recap", "d", "e"]);
+
+        let linear = vec![msg("a", None, "01"), msg("b", Some("a"), "02")];
+        assert_eq!(keep_active_thread(linear).len(), 2);
+    }
+
+    #[test]
+    fn test_keep_active_thread_keeps_parallel_tool_results() {
+        let entry = |value: serde_json::Value| -> TranscriptEntry {
+            serde_json::from_value(value).unwrap()
+        };
+        let prompt = entry(serde_json::json!({
+            "type": "user", "uuid": "p", "parentUuid": null, "sessionId": "s1",
+            "timestamp": "2025-01-15T10:00:00Z",
+            "message": {"role": "user", "content": "read both files"}
+        }));
+        let tool_use = entry(serde_json::json!({
+            "type": "assistant", "uuid": "a", "parentUuid": "p", "sessionId": "s1",
+            "timestamp": "2025-01-15T10:00:01Z",
+            "message": {"role": "assistant", "content": [
+                {"type": "tool_use", "id": "t1", "name": "Read", "input": {}},
+                {"type": "tool_use", "id": "t2", "name": "Read", "input": {}}
+            ]}
+        }));
+        // Both results hang off the assistant entry: sibling branches
+        let result = |uuid: &str, id: &str, ts: &str| {
+            entry(serde_json::json!({
+                "type": "user", "uuid": uuid, "parentUuid": "a", "sessionId": "s1",
+                "timestamp": format!("2025-01-15T10:00:{ts}Z"),
+                "message": {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": id, "content": format!("output {id}")}
+                ]}
+            }))
+        };
+        let answer = entry(serde_json::json!({
+            "type": "assistant", "uuid": "z", "parentUuid": "r2", "sessionId": "s1",
+            "timestamp": "2025-01-15T10:00:04Z",
+            "message": {"role": "assistant", "content": [{"type": "text", "text": "done"}]}
+        }));
+        let entries = vec![
+            prompt,
+            tool_use,
+            result("r1", "t1", "02"),
+            result("r2", "t2", "03"),
+            answer,
+        ];
+
+        let kept = keep_active_thread(entries);
+        let uuids: Vec<&str> = kept.iter().filter_map(|e| e.uuid()).collect();
+        assert_eq!(uuids, ["p", "a", "r1", "r2", "z"]);
+    }
+
     #[test]
     fn test_parse_unknown_type() {
         let json = r#"{"type":"some-new-type","data":"whatever"}"#;
@@ -287,4 +786,150 @@ mod tests {
         let result = get_messages_since(&entries, None, None);
         assert_eq!(result.len(), 2);
     }
+
+    /// One entry of each kind: user text, tool result, assistant with thinking + tool use
+    fn role_fixture() -> Vec<TranscriptEntry> {
+        [
+            r#"{"type":"user","uuid":"a","sessionId":"s1","timestamp":"2025-01-15T10:00:00Z","message":{"role":"user","content":"Fix the bug"}}"#,
+            r#"{"type":"assistant","uuid":"b","sessionId":"s1","timestamp":"2025-01-15T10:00:01Z","message":{"role":"assistant","content":[{"type":"thinking","thinking":"Check the parser"},{"type":"tool_use","name":"Read","input":{"file_path":"/src/parse.rs"}},{"type":"text","text":"Reading it"}]}}"#,
+            r#"{"type":"user","uuid":"c","sessionId":"s1","timestamp":"2025-01-15T10:00:02Z","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"fn parse() {}"}]}}"#,
+        ]
+        .iter()
+        .map(|json| serde_json::from_str(json).unwrap())
+        .collect()
+    }
+
+    fn format_with(roles: &[MessageRole]) -> String {
+        let entries = role_fixture();
+        let refs: Vec<&TranscriptEntry> = entries.iter().collect();
+        format_context(&refs, roles, 0)
+    }
+
+    #[test]
+    fn test_format_context_skips_repeated_blocks() {
+        let boilerplate = "Project rules: always run cargo fmt, never push to main, keep commits small and focused.";
+        let user = |uuid: &str, content: serde_json::Value| -> TranscriptEntry {
+            serde_json::from_value(serde_json::json!({
+                "type": "user", "uuid": uuid, "sessionId": "s1",
+                "timestamp": "2025-01-15T10:00:00Z",
+                "message": {"role": "user", "content": content},
+            }))
+            .unwrap()
+        };
+        let entries = [
+            user(
+                "a",
+                format!("<system-reminder>x</system-reminder>{}", boilerplate).into(),
+            ),
+            user("b", "yes".into()),
+            user(
+                "c",
+                serde_json::json!([{"type": "tool_result", "tool_use_id": "t1", "content": boilerplate}]),
+            ),
+            user("d", "yes".into()),
+            user("e", boilerplate.into()),
+        ];
+        let refs: Vec<&TranscriptEntry> = entries.iter().collect();
+
+        let out = format_context(&refs, &MessageRole::ALL, 0);
+        // First occurrence kept, later identical blocks dropped
+        assert_eq!(out.matches(boilerplate).count(), 1);
+        assert!(out.starts_with(&format!("USER: {}", boilerplate)));
+        // Short replies are conversation, not boilerplate
+        assert_eq!(out.matches("USER: yes\n").count(), 2);
+    }
+
+    #[test]
+    fn test_format_context_tool_result_limit() {
+        let entries = role_fixture();
+        let refs: Vec<&TranscriptEntry> = entries.iter().collect();
+
+        let under = format_context(&refs, &MessageRole::ALL, 100);
+        assert!(under.contains("TOOL_RESULT: fn parse() {}\n"));
+
+        let over = format_context(&refs, &MessageRole::ALL, 8);
+        assert!(over.contains("TOOL_RESULT: fn parse...[truncated]\n"));
+        assert!(!over.contains("fn parse() {}"));
+    }
+
+    #[test]
+    fn test_format_context_truncates_large_read_by_default() {
+        let big = "line of file content\n".repeat(1000);
+        let json = serde_json::json!({
+            "type": "user", "uuid": "c", "sessionId": "s1",
+            "timestamp": "2025-01-15T10:00:02Z",
+            "message": {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "t1", "content": big}
+            ]}
+        });
+        let entry: TranscriptEntry = serde_json::from_value(json).unwrap();
+        let max = crate::types::ExtractConfig::default().tool_output_max_bytes;
+
+        let formatted = format_context(&[&entry], &MessageRole::ALL, max);
+        assert!(formatted.contains("...[truncated]"));
+        assert!(formatted.len() < max + 100);
+    }
+
+    #[test]
+    fn test_format_context_all_roles() {
+        let formatted = format_with(&MessageRole::ALL);
+        assert!(formatted.contains("USER: Fix the bug"));
+        assert!(formatted.contains("THINKING: Check the parser"));
+        assert!(formatted.contains("TOOLS: Read(/src/parse.rs)"));
+        assert!(formatted.contains("ASSISTANT: Reading it"));
+        assert!(formatted.contains("TOOL_RESULT: fn parse() {}"));
+    }
+
+    #[test]
+    fn test_format_context_user_only() {
+        let formatted = format_with(&[MessageRole::User]);
+        assert!(formatted.contains("USER: Fix the bug"));
+        assert!(!formatted.contains("THINKING:"));
+        assert!(!formatted.contains("TOOLS:"));
+        assert!(!formatted.contains("ASSISTANT:"));
+        assert!(!formatted.contains("TOOL_RESULT:"));
+    }
+
+    #[test]
+    fn test_format_context_assistant_only() {
+        let formatted = format_with(&[MessageRole::Assistant]);
+        assert!(!formatted.contains("USER:"));
+        assert!(!formatted.contains("THINKING:"));
+        assert!(formatted.contains("TOOLS: Read"));
+        assert!(formatted.contains("ASSISTANT: Reading it"));
+        assert!(!formatted.contains("TOOL_RESULT:"));
+    }
+
+    #[test]
+    fn test_format_context_excludes_thinking_and_tool_results() {
+        let formatted = format_with(&[MessageRole::User, MessageRole::Assistant]);
+        assert!(formatted.contains("USER: Fix the bug"));
+        assert!(formatted.contains("ASSISTANT: Reading it"));
+        assert!(!formatted.contains("THINKING:"));
+        assert!(!formatted.contains("TOOL_RESULT:"));
+    }
+
+    #[test]
+    fn test_format_context_thinking_and_tool_results_only() {
+        let formatted = format_with(&[MessageRole::Thinking, MessageRole::ToolResult]);
+        assert!(formatted.contains("THINKING: Check the parser"));
+        assert!(formatted.contains("TOOL_RESULT: fn parse() {}"));
+        assert!(!formatted.contains("USER:"));
+        assert!(!formatted.contains("ASSISTANT:"));
+    }
+
+    #[test]
+    fn test_format_context_no_roles() {
+        assert!(format_with(&[]).trim().is_empty());
+    }
+
+    #[test]
+    fn test_roles_config_parsing() {
+        let config: crate::types::Config =
+            toml::from_str("[extract]\nroles = [\"user\", \"tool_result\"]\n").unwrap();
+        assert_eq!(
+            config.extract.roles,
+            vec![MessageRole::User, MessageRole::ToolResult]
+        );
+    }
 }
@@ -99,6 +99,24 @@ impl TranscriptEntry {
         }
     }
 
+    /// Get the message uuid if available
+    pub fn uuid(&self) -> Option<&str> {
+        match self {
+            TranscriptEntry::User { uuid, .. } => Some(uuid),
+            TranscriptEntry::Assistant { uuid, .. } => Some(uuid),
+            _ => None,
+        }
+    }
+
+    /// Get the parent message uuid if available
+    pub fn parent_uuid(&self) -> Option<&str> {
+        match self {
+            TranscriptEntry::User { parent_uuid, .. } => parent_uuid.as_deref(),
+            TranscriptEntry::Assistant { parent_uuid, .. } => parent_uuid.as_deref(),
+            _ => None,
+        }
+    }
+
     /// Get the timestamp if available
     pub fn timestamp(&self) -> Option<&str> {
         match self {
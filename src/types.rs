@@ -2,6 +2,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::time::SystemTime;
 
@@ -82,6 +83,8 @@ use std::path::Path;
 pub trait SessionLike {
     fn session_id(&self) -> &str;
     fn size_bytes(&self) -> u64;
+    fn path(&self) -> &Path;
+    fn modified_at(&self) -> DateTime<Utc>;
 
     /// Format session info for display (used in dry-run output)
     fn display_info(&self) -> String;
@@ -103,6 +106,12 @@ impl SessionLike for SessionInfo {
     fn size_bytes(&self) -> u64 {
         self.size_bytes
     }
+    fn path(&self) -> &Path {
+        &self.transcript_path
+    }
+    fn modified_at(&self) -> DateTime<Utc> {
+        self.modified_at
+    }
     fn display_info(&self) -> String {
         let size_kb = self.size_bytes / 1024;
         format!(
@@ -131,6 +140,12 @@ impl SessionLike for CodexSessionInfo {
     fn size_bytes(&self) -> u64 {
         self.size_bytes
     }
+    fn path(&self) -> &Path {
+        &self.session_path
+    }
+    fn modified_at(&self) -> DateTime<Utc> {
+        self.modified_at
+    }
     fn display_info(&self) -> String {
         let size_kb = self.size_bytes / 1024;
         let cwd_display = self
@@ -148,6 +163,39 @@ impl SessionLike for CodexSessionInfo {
     }
 }
 
+/// Gemini CLI session info
+#[derive(Debug, Clone)]
+pub struct GeminiSessionInfo {
+    pub session_id: String,
+    pub session_path: PathBuf,
+    pub modified_at: DateTime<Utc>,
+    pub size_bytes: u64,
+}
+
+impl SessionLike for GeminiSessionInfo {
+    fn session_id(&self) -> &str {
+        &self.session_id
+    }
+    fn size_bytes(&self) -> u64 {
+        self.size_bytes
+    }
+    fn path(&self) -> &Path {
+        &self.session_path
+    }
+    fn modified_at(&self) -> DateTime<Utc> {
+        self.modified_at
+    }
+    fn display_info(&self) -> String {
+        let size_kb = self.size_bytes / 1024;
+        format!(
+            "{} ({} KB, {})",
+            self.session_id,
+            size_kb,
+            self.modified_at.format("%Y-%m-%d %H:%M")
+        )
+    }
+}
+
 /// Hook-specific output for UserPromptSubmit hooks
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -176,6 +224,39 @@ pub struct Config {
 
     #[serde(default)]
     pub dive: DiveConfig,
+
+    #[serde(default)]
+    pub compile: CompileConfig,
+
+    #[serde(default)]
+    pub extract: ExtractConfig,
+
+    #[serde(default)]
+    pub llm: LlmConfig,
+
+    #[serde(default)]
+    pub markers: MarkersConfig,
+
+    #[serde(default)]
+    pub sessions: SessionsConfig,
+
+    #[serde(default)]
+    pub history: HistoryConfig,
+
+    #[serde(default)]
+    pub codex: CodexConfig,
+
+    #[serde(default)]
+    pub oh: OhConfig,
+
+    #[serde(default)]
+    pub log: LogConfig,
+
+    /// Sections wm doesn't model (user keys, newer wm versions)
+    /// AIDEV-NOTE: Kept so a read-modify-write (pause, dive switch, wm config)
+    /// doesn't silently drop them.
+    #[serde(flatten)]
+    pub extra: toml::Table,
 }
 
 /// Configuration for named dive preps
@@ -185,6 +266,376 @@ pub struct DiveConfig {
     pub current: Option<String>,
 }
 
+/// Configuration for working set compilation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompileConfig {
+    /// Include the current dive prep in the compiled working set
+    #[serde(default = "default_true")]
+    pub include_dive: bool,
+
+    /// Saved working set the hook injects instead of compiling
+    /// (set by `wm compile --load`, cleared by `wm compile --unload`)
+    pub saved: Option<String>,
+
+    /// Prepend a "compiled at / state last updated" comment to injected context
+    #[serde(default = "default_true")]
+    pub freshness_header: bool,
+
+    /// Intent keyword -> `## ` sections of guardrails/metis to keep
+    #[serde(default)]
+    pub routes: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for CompileConfig {
+    fn default() -> Self {
+        Self {
+            include_dive: true,
+            saved: None,
+            freshness_header: true,
+            routes: BTreeMap::new(),
+        }
+    }
+}
+
+/// Configuration for per-session directories under .wm/sessions/
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionsConfig {
+    /// Keep at most this many session directories, pruning the oldest
+    /// during extract/compile (None = keep everything)
+    pub max_session_dirs: Option<usize>,
+}
+
+/// Configuration for state.md snapshots under .wm/history/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Number of snapshots to keep (0 disables snapshots)
+    #[serde(default = "default_history_keep")]
+    pub keep: usize,
+}
+
+fn default_history_keep() -> usize {
+    10
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            keep: default_history_keep(),
+        }
+    }
+}
+
+/// Configuration for .wm/hook.log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogConfig {
+    /// Lowest level written (WM_LOG_LEVEL env takes precedence)
+    #[serde(default)]
+    pub level: LogLevel,
+
+    /// Rotate hook.log to hook.log.1 once it exceeds this size (0 disables)
+    #[serde(default = "default_log_max_bytes")]
+    pub max_bytes: u64,
+
+    /// Rotated files to keep (hook.log.1 .. hook.log.N)
+    #[serde(default = "default_log_keep")]
+    pub keep: usize,
+}
+
+fn default_log_max_bytes() -> u64 {
+    5 * 1024 * 1024
+}
+
+fn default_log_keep() -> usize {
+    3
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: LogLevel::default(),
+            max_bytes: default_log_max_bytes(),
+            keep: default_log_keep(),
+        }
+    }
+}
+
+/// Severity of a hook.log line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    /// Payload sizes and previews, for debugging prompts
+    Debug,
+    /// Normal progress of hooks and commands (default)
+    #[default]
+    Info,
+    /// Recoverable failures
+    Warn,
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warn" => Ok(LogLevel::Warn),
+            other => Err(format!(
+                "Unknown log level: {}. Use 'debug', 'info' or 'warn'.",
+                other
+            )),
+        }
+    }
+}
+
+/// Open Horizons API credentials
+/// OH_API_KEY / OH_API_URL take precedence; see oh::credentials
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OhConfig {
+    pub api_key: Option<String>,
+    pub api_url: Option<String>,
+}
+
+/// Configuration for reading Codex sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodexConfig {
+    /// Tool output longer than this is truncated before extraction (0 = no limit)
+    #[serde(default = "default_codex_tool_output_max_bytes")]
+    pub tool_output_max_bytes: usize,
+}
+
+fn default_codex_tool_output_max_bytes() -> usize {
+    500
+}
+
+impl Default for CodexConfig {
+    fn default() -> Self {
+        Self {
+            tool_output_max_bytes: default_codex_tool_output_max_bytes(),
+        }
+    }
+}
+
+/// Configuration for knowledge extraction (extract + distill)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractConfig {
+    /// Relabel user corrections as CORRECTION: turns before extraction
+    #[serde(default)]
+    pub flag_corrections: bool,
+
+    /// Transcript blocks fed to extraction (default: all)
+    #[serde(default = "default_roles")]
+    pub roles: Vec<MessageRole>,
+
+    /// Minutes before the last extraction re-read as carryover context (0 disables)
+    /// AIDEV-NOTE: Default 5 matches sg. Negative values fail to parse with an
+    /// error naming the key; wm doctor reports it and config writers refuse
+    /// to overwrite the file (see state::update_config_in).
+    #[serde(
+        default = "default_carryover_window_minutes",
+        deserialize_with = "deserialize_window_minutes"
+    )]
+    pub carryover_window_minutes: u32,
+
+    /// Hook-triggered extracts within this many seconds of the last attempt
+    /// are skipped unless enough new messages have arrived (0 disables)
+    #[serde(default = "default_min_interval_secs")]
+    pub min_interval_secs: u64,
+
+    /// Formatted transcript larger than this is extracted in chunks (0 disables)
+    #[serde(default = "default_max_context_bytes")]
+    pub max_context_bytes: usize,
+
+    /// Claude TOOL_RESULT blocks longer than this are truncated (0 = no limit)
+    /// AIDEV-NOTE: A single large Read or test run otherwise dominates the
+    /// extraction context. Default matches [codex] tool_output_max_bytes.
+    #[serde(default = "default_tool_output_max_bytes")]
+    pub tool_output_max_bytes: usize,
+
+    /// Drop messages on abandoned edit/retry branches (Claude `parentUuid` threading)
+    #[serde(default = "default_true")]
+    pub active_thread_only: bool,
+}
+
+impl Default for ExtractConfig {
+    fn default() -> Self {
+        Self {
+            flag_corrections: false,
+            roles: default_roles(),
+            carryover_window_minutes: default_carryover_window_minutes(),
+            min_interval_secs: default_min_interval_secs(),
+            max_context_bytes: default_max_context_bytes(),
+            tool_output_max_bytes: default_tool_output_max_bytes(),
+            active_thread_only: true,
+        }
+    }
+}
+
+fn default_tool_output_max_bytes() -> usize {
+    500
+}
+
+fn default_carryover_window_minutes() -> u32 {
+    5
+}
+
+fn deserialize_window_minutes<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u32, D::Error> {
+    let minutes = i64::deserialize(deserializer)?;
+    u32::try_from(minutes).map_err(|_| {
+        serde::de::Error::custom(format!(
+            "extract.carryover_window_minutes must be 0 (off) or a positive number of minutes, got {}",
+            minutes
+        ))
+    })
+}
+
+fn default_min_interval_secs() -> u64 {
+    30
+}
+
+/// ~100k tokens of transcript, leaving room for the prompt and current state
+fn default_max_context_bytes() -> usize {
+    400_000
+}
+
+fn default_roles() -> Vec<MessageRole> {
+    MessageRole::ALL.to_vec()
+}
+
+/// Kind of transcript block that `format_context` can emit
+/// AIDEV-NOTE: TOOLS:/TOOL: lines (tool invocations) travel with `assistant`;
+/// summaries are always included since they carry compacted history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageRole {
+    User,
+    Assistant,
+    Thinking,
+    ToolResult,
+}
+
+impl MessageRole {
+    pub const ALL: [MessageRole; 4] = [
+        MessageRole::User,
+        MessageRole::Assistant,
+        MessageRole::Thinking,
+        MessageRole::ToolResult,
+    ];
+}
+
+/// LLM backend used for extraction, categorization, and compression
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LlmBackend {
+    /// Shell out to the `claude` CLI (default)
+    #[default]
+    Claude,
+    /// POST to an OpenAI-compatible /v1/chat/completions endpoint
+    OpenAi,
+}
+
+impl std::str::FromStr for LlmBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "claude" => Ok(LlmBackend::Claude),
+            "openai" => Ok(LlmBackend::OpenAi),
+            other => Err(format!(
+                "Unknown LLM backend: {}. Use 'claude' or 'openai'.",
+                other
+            )),
+        }
+    }
+}
+
+/// Configuration for LLM calls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmConfig {
+    /// Which backend to call (WM_LLM_BACKEND env takes precedence)
+    #[serde(default)]
+    pub backend: LlmBackend,
+
+    /// Model to request (claude: --model; openai: request model)
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Model override for compress (e.g. a stronger model than extraction uses)
+    #[serde(default)]
+    pub compress_model: Option<String>,
+
+    /// Extra arguments appended to the claude CLI invocation
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+
+    /// Retries after a transient failure (rate limit, overload, spawn error)
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Seconds to wait for a single LLM call before killing it
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            backend: LlmBackend::default(),
+            model: None,
+            compress_model: None,
+            extra_args: Vec::new(),
+            max_retries: default_max_retries(),
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_timeout_secs() -> u64 {
+    120
+}
+
+/// Marker names the LLM answers with, per operation
+/// AIDEV-NOTE: Built-in prompts are rewritten to ask for the configured marker,
+/// so custom prompts can use their own convention without code changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkersConfig {
+    /// Marker for per-turn `wm extract`
+    #[serde(default = "default_knowledge_marker")]
+    pub extract: String,
+
+    /// Marker for `wm distill` session extraction
+    #[serde(default = "default_knowledge_marker")]
+    pub distill: String,
+
+    /// Marker for `wm compress`
+    #[serde(default = "default_compress_marker")]
+    pub compress: String,
+}
+
+impl Default for MarkersConfig {
+    fn default() -> Self {
+        Self {
+            extract: default_knowledge_marker(),
+            distill: default_knowledge_marker(),
+            compress: default_compress_marker(),
+        }
+    }
+}
+
+fn default_knowledge_marker() -> String {
+    "HAS_KNOWLEDGE".to_string()
+}
+
+fn default_compress_marker() -> String {
+    "WAS_COMPRESSED".to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OperationsConfig {
     #[serde(default = "default_true")]
@@ -192,6 +643,35 @@ pub struct OperationsConfig {
 
     #[serde(default = "default_true")]
     pub compile: bool,
+
+    /// RFC3339 time a `wm pause extract --for` expires (None = indefinite)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extract_paused_until: Option<String>,
+
+    /// RFC3339 time a `wm pause compile --for` expires (None = indefinite)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compile_paused_until: Option<String>,
+}
+
+impl OperationsConfig {
+    /// Whether extract runs at `now` (paused with an expired deadline counts as running)
+    pub fn extract_enabled_at(&self, now: DateTime<Utc>) -> bool {
+        self.extract || pause_expired(self.extract_paused_until.as_deref(), now)
+    }
+
+    /// Whether compile runs at `now` (paused with an expired deadline counts as running)
+    pub fn compile_enabled_at(&self, now: DateTime<Utc>) -> bool {
+        self.compile || pause_expired(self.compile_paused_until.as_deref(), now)
+    }
+}
+
+/// Whether a timed pause is over
+/// AIDEV-NOTE: An unparseable deadline is treated as no deadline (stays paused),
+/// so a hand-edited typo never silently re-enables extraction.
+fn pause_expired(paused_until: Option<&str>, now: DateTime<Utc>) -> bool {
+    paused_until
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .is_some_and(|until| now >= until)
 }
 
 fn default_true() -> bool {
@@ -203,6 +683,95 @@ impl Default for OperationsConfig {
         Self {
             extract: true,
             compile: true,
+            extract_paused_until: None,
+            compile_paused_until: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_defaults_when_sections_missing() {
+        let config: Config = toml::from_str("").unwrap();
+        assert!(config.operations.extract);
+        assert!(config.operations.compile);
+        assert!(config.compile.include_dive);
+        assert!(!config.extract.flag_corrections);
+        assert_eq!(config.extract.roles, MessageRole::ALL.to_vec());
+        assert_eq!(config.markers.extract, "HAS_KNOWLEDGE");
+        assert_eq!(config.markers.distill, "HAS_KNOWLEDGE");
+        assert_eq!(config.markers.compress, "WAS_COMPRESSED");
+    }
+
+    #[test]
+    fn test_log_level_parses_and_orders() {
+        assert_eq!("WARN".parse::<LogLevel>(), Ok(LogLevel::Warn));
+        assert!("verbose".parse::<LogLevel>().is_err());
+        assert!(LogLevel::Debug < LogLevel::Info && LogLevel::Info < LogLevel::Warn);
+        assert_eq!(Config::default().log.level, LogLevel::Info);
+    }
+
+    #[test]
+    fn test_timed_pause_expires() {
+        let now = Utc::now();
+        let mut ops = OperationsConfig {
+            extract: false,
+            compile: false,
+            extract_paused_until: Some((now + chrono::Duration::minutes(5)).to_rfc3339()),
+            compile_paused_until: Some((now - chrono::Duration::minutes(5)).to_rfc3339()),
+        };
+        assert!(!ops.extract_enabled_at(now));
+        assert!(ops.compile_enabled_at(now));
+        assert!(ops.extract_enabled_at(now + chrono::Duration::minutes(6)));
+
+        // Indefinite and malformed deadlines stay paused
+        ops.extract_paused_until = None;
+        assert!(!ops.extract_enabled_at(now + chrono::Duration::days(365)));
+        ops.extract_paused_until = Some("tomorrow".to_string());
+        assert!(!ops.extract_enabled_at(now));
+    }
+
+    #[test]
+    fn test_markers_override() {
+        let config: Config = toml::from_str("[markers]\ncompress = \"SHRUNK\"\n").unwrap();
+        assert_eq!(config.markers.compress, "SHRUNK");
+        assert_eq!(config.markers.extract, "HAS_KNOWLEDGE");
+    }
+
+    #[test]
+    fn test_llm_backend_parsing() {
+        let config: Config = toml::from_str("[llm]\nbackend = \"openai\"\n").unwrap();
+        assert_eq!(config.llm.backend, LlmBackend::OpenAi);
+        assert_eq!(Config::default().llm.backend, LlmBackend::Claude);
+
+        assert_eq!("Claude".parse::<LlmBackend>(), Ok(LlmBackend::Claude));
+        assert_eq!("openai".parse::<LlmBackend>(), Ok(LlmBackend::OpenAi));
+        assert!("gemini".parse::<LlmBackend>().is_err());
+    }
+
+    #[test]
+    fn test_carryover_window_parsing() {
+        assert_eq!(Config::default().extract.carryover_window_minutes, 5);
+        let config: Config = toml::from_str("[extract]\ncarryover_window_minutes = 0\n").unwrap();
+        assert_eq!(config.extract.carryover_window_minutes, 0);
+        let err = toml::from_str::<Config>("[extract]\ncarryover_window_minutes = -1\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("extract.carryover_window_minutes"), "{}", err);
+    }
+
+    #[test]
+    fn test_compile_include_dive_toggle() {
+        let config: Config = toml::from_str("[compile]\ninclude_dive = false\n").unwrap();
+        assert!(!config.compile.include_dive);
+        assert!(config.compile.freshness_header);
+        assert!(config.compile.routes.is_empty());
+
+        let config: Config =
+            toml::from_str("[compile.routes]\ndeploy = [\"Infra\", \"Deployment\"]\n").unwrap();
+        assert_eq!(config.compile.routes["deploy"], vec!["Infra", "Deployment"]);
+    }
+}
@@ -0,0 +1,173 @@
+//! wm watch - extract automatically as a session's transcript grows
+//!
+//! Watches the active Claude transcript and runs the same extraction the
+//! hook does once writes settle, for setups without the sg hook installed.
+//!
+//! AIDEV-NOTE: Claude appends several lines per turn (tool calls, results,
+//! the reply), so extraction waits for QUIET_PERIOD without writes. A long
+//! agentic turn never goes quiet; MAX_WAIT bounds how long changes can pile
+//! up. Ctrl-C ends the process; a lock left by an interrupted extraction is
+//! stale (dead PID) and gets replaced by the next run.
+
+use crate::extract::{self, WatchExtract};
+use crate::session;
+use crate::state;
+use crate::types::LogLevel;
+use chrono::Local;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// Writes must stop for this long before extraction runs
+const QUIET_PERIOD: Duration = Duration::from_secs(10);
+
+/// Extract anyway once changes have been pending this long
+const MAX_WAIT: Duration = Duration::from_secs(120);
+
+/// How often to re-check the debouncer while no events arrive
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Decides when pending transcript changes should trigger an extraction
+#[derive(Debug)]
+struct Debouncer {
+    quiet: Duration,
+    max_wait: Duration,
+    /// First change since the last extraction
+    pending_since: Option<Instant>,
+    /// Most recent change
+    last_change: Option<Instant>,
+}
+
+impl Debouncer {
+    fn new(quiet: Duration, max_wait: Duration) -> Self {
+        Self {
+            quiet,
+            max_wait,
+            pending_since: None,
+            last_change: None,
+        }
+    }
+
+    /// Record a transcript change at `now`
+    fn record(&mut self, now: Instant) {
+        self.pending_since.get_or_insert(now);
+        self.last_change = Some(now);
+    }
+
+    /// True (and resets) when pending changes have gone quiet or waited too long
+    fn take_due(&mut self, now: Instant) -> bool {
+        let (Some(since), Some(last)) = (self.pending_since, self.last_change) else {
+            return false;
+        };
+        let due =
+            now.duration_since(last) >= self.quiet || now.duration_since(since) >= self.max_wait;
+        if due {
+            self.pending_since = None;
+            self.last_change = None;
+        }
+        due
+    }
+}
+
+/// Run wm watch [--session-id <id>]
+/// Watches the given session's transcript (default: the newest one for this
+/// project) until interrupted.
+pub fn run(session_id: Option<String>) -> Result<(), String> {
+    if !state::is_initialized() {
+        return Err("Not initialized. Run 'wm init' first.".to_string());
+    }
+
+    let sessions = session::discover_sessions(&session::current_project_path())?;
+    let target = match session_id.as_deref() {
+        Some(id) => sessions
+            .iter()
+            .find(|s| s.session_id == id)
+            .ok_or_else(|| format!("Session not found: {}", id))?,
+        None => sessions
+            .first()
+            .ok_or_else(|| "No sessions found for this project".to_string())?,
+    };
+    let transcript = target.transcript_path.display().to_string();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to start file watcher: {}", e))?;
+    watcher
+        .watch(&target.transcript_path, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", transcript, e))?;
+
+    println!(
+        "Watching session {} ({}). Press Ctrl-C to stop.",
+        target.session_id, transcript
+    );
+    state::log("watch", &format!("Watching {}", transcript));
+
+    let mut debouncer = Debouncer::new(QUIET_PERIOD, MAX_WAIT);
+    loop {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(Ok(event)) => {
+                if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                    debouncer.record(Instant::now());
+                }
+            }
+            Ok(Err(e)) => state::log_at(LogLevel::Warn, "watch", &format!("Watch error: {}", e)),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err("File watcher stopped unexpectedly".to_string());
+            }
+        }
+
+        if debouncer.take_due(Instant::now()) {
+            let outcome = match extract::run_watch(&transcript, &target.session_id) {
+                Ok(WatchExtract::Extracted) => "extraction complete".to_string(),
+                Ok(WatchExtract::Paused) => "skipped (extract is paused)".to_string(),
+                Ok(WatchExtract::Locked) => "skipped (extract already running)".to_string(),
+                Err(e) => format!("failed: {}", e),
+            };
+            println!("[{}] {}", Local::now().format("%H:%M:%S"), outcome);
+            state::log("watch", &outcome);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debouncer_waits_for_quiet_period() {
+        let start = Instant::now();
+        let secs = Duration::from_secs;
+        let mut d = Debouncer::new(secs(10), secs(120));
+
+        // Nothing pending: never fires
+        assert!(!d.take_due(start + secs(60)));
+
+        d.record(start);
+        d.record(start + secs(5));
+        assert!(!d.take_due(start + secs(14)));
+        assert!(d.take_due(start + secs(15)));
+
+        // Fired once; no new changes, no second trigger
+        assert!(!d.take_due(start + secs(30)));
+    }
+
+    #[test]
+    fn test_debouncer_fires_after_max_wait_despite_activity() {
+        let start = Instant::now();
+        let secs = Duration::from_secs;
+        let mut d = Debouncer::new(secs(10), secs(30));
+
+        // A change every 5s never goes quiet
+        for t in (0..30).step_by(5) {
+            d.record(start + secs(t));
+            assert!(!d.take_due(start + secs(t)));
+        }
+        d.record(start + secs(30));
+        assert!(d.take_due(start + secs(30)));
+
+        // The window restarts from the next change
+        d.record(start + secs(35));
+        assert!(!d.take_due(start + secs(40)));
+    }
+}